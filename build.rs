@@ -0,0 +1,17 @@
+fn main() {
+    println!("cargo:rustc-check-cfg=cfg(libpostal_missing)");
+    #[cfg(feature = "libpostal")]
+    probe_libpostal();
+}
+
+/// Link libpostal, found with pkg-config
+///
+/// Without libpostal, the crate still builds, and the functions of the `libpostal` module
+/// return an error, so that e.g. `cargo test --all-features` works on any machine.
+#[cfg(feature = "libpostal")]
+fn probe_libpostal() {
+    if pkg_config::Config::new().probe("libpostal").is_err() {
+        println!("cargo:warning=libpostal wasn't found, its functions will return an error");
+        println!("cargo:rustc-cfg=libpostal_missing");
+    }
+}
@@ -0,0 +1,276 @@
+//! Country-specific formatting of addresses.
+//!
+//! Renders an [`Address`](../struct.Address.html) as a one-line or multi-line string, with its
+//! components in the order used in the address's country, following the
+//! [OpenCage address-formatting templates](https://github.com/OpenCageData/address-formatting).
+//! Since `Address` can be converted from the results of several providers, this gives
+//! consistently formatted addresses regardless of the provider which produced them.
+//!
+//! The templates of common address layouts are implemented: countries without a dedicated
+//! template use the generic `road house_number, postcode city` layout.
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::address_format::{format, format_one_line};
+//! use geocoding::Address;
+//!
+//! let address = Address {
+//!     house_number: Some("1600".to_string()),
+//!     road: Some("Pennsylvania Avenue NW".to_string()),
+//!     city: Some("Washington".to_string()),
+//!     state_code: Some("DC".to_string()),
+//!     postcode: Some("20500".to_string()),
+//!     country: Some("United States of America".to_string()),
+//!     country_code: Some("US".to_string()),
+//!     ..Default::default()
+//! };
+//! assert_eq!(
+//!     format_one_line(&address),
+//!     "1600 Pennsylvania Avenue NW, Washington, DC 20500, United States of America"
+//! );
+//! assert_eq!(
+//!     format(&address),
+//!     "1600 Pennsylvania Avenue NW\nWashington, DC 20500\nUnited States of America"
+//! );
+//! ```
+use crate::Address;
+
+/// An address component used in templates
+#[derive(Clone, Copy, Debug)]
+enum Component {
+    HouseNumber,
+    Road,
+    Suburb,
+    City,
+    // The suburb, or the city if there is no suburb
+    Locality,
+    Postcode,
+    State,
+    // The state code, or the state name if there is no code
+    StateCode,
+    Country,
+}
+
+/// A template line: components, each preceded by the separator used when it
+/// follows another component of the line
+type Line = &'static [(Component, &'static str)];
+
+use Component::*;
+
+/// Road before house number, postcode before city (most of continental Europe)
+const GENERIC: &[Line] = &[
+    &[(Road, ""), (HouseNumber, " ")],
+    &[(Postcode, ""), (City, " ")],
+    &[(Country, "")],
+];
+
+/// House number before road, city before state and postcode (e.g. the United States)
+const NORTH_AMERICA: &[Line] = &[
+    &[(HouseNumber, ""), (Road, " ")],
+    &[(City, ""), (StateCode, ", "), (Postcode, " ")],
+    &[(Country, "")],
+];
+
+/// House number before road, city, state and postcode on one line without a comma
+const AUSTRALIA: &[Line] = &[
+    &[(HouseNumber, ""), (Road, " ")],
+    &[(Locality, ""), (StateCode, " "), (Postcode, " ")],
+    &[(Country, "")],
+];
+
+/// House number before road, city and postcode on separate lines
+const BRITISH: &[Line] = &[
+    &[(HouseNumber, ""), (Road, " ")],
+    &[(Suburb, "")],
+    &[(City, "")],
+    &[(Postcode, "")],
+    &[(Country, "")],
+];
+
+/// House number before road, postcode before city
+const FRENCH: &[Line] = &[
+    &[(HouseNumber, ""), (Road, " ")],
+    &[(Postcode, ""), (City, " ")],
+    &[(Country, "")],
+];
+
+/// Road before house number, postcode, city and province code
+const ITALIAN: &[Line] = &[
+    &[(Road, ""), (HouseNumber, " ")],
+    &[(Postcode, ""), (City, " "), (StateCode, " ")],
+    &[(Country, "")],
+];
+
+/// Road before house number, postcode, city and state
+const BRAZILIAN: &[Line] = &[
+    &[(Road, ""), (HouseNumber, ", ")],
+    &[(Suburb, "")],
+    &[(City, ""), (StateCode, " - ")],
+    &[(Postcode, "")],
+    &[(Country, "")],
+];
+
+/// Postcode and country first, then from the largest to the smallest area
+const JAPANESE: &[Line] = &[
+    &[(Country, "")],
+    &[(Postcode, "")],
+    &[(State, ""), (City, " "), (Suburb, " ")],
+    &[(Road, ""), (HouseNumber, " ")],
+];
+
+/// The template used in a country, given its ISO 3166-1 alpha-2 code
+fn template(country_code: Option<&str>) -> &'static [Line] {
+    let code = country_code.map(|code| code.to_ascii_uppercase());
+    match code.as_deref() {
+        Some("US") | Some("CA") | Some("PR") | Some("GU") | Some("VI") | Some("AS")
+        | Some("MP") | Some("PH") => NORTH_AMERICA,
+        Some("AU") | Some("NZ") => AUSTRALIA,
+        Some("GB") | Some("IE") | Some("IM") | Some("JE") | Some("GG") => BRITISH,
+        Some("FR") | Some("LU") | Some("MC") | Some("RE") | Some("GP") | Some("MQ")
+        | Some("GF") | Some("YT") | Some("NC") | Some("PF") => FRENCH,
+        Some("IT") | Some("SM") | Some("VA") => ITALIAN,
+        Some("BR") => BRAZILIAN,
+        Some("JP") => JAPANESE,
+        _ => GENERIC,
+    }
+}
+
+fn component(address: &Address, component: Component) -> Option<&str> {
+    let value = match component {
+        HouseNumber => &address.house_number,
+        Road => &address.road,
+        Suburb => &address.suburb,
+        City => &address.city,
+        Locality => {
+            if address.suburb.is_some() {
+                &address.suburb
+            } else {
+                &address.city
+            }
+        }
+        Postcode => &address.postcode,
+        State => &address.state,
+        StateCode => {
+            if address.state_code.is_some() {
+                &address.state_code
+            } else {
+                &address.state
+            }
+        }
+        Country => &address.country,
+    };
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+/// Render the lines of an address, skipping missing components and empty lines
+fn lines(address: &Address) -> Vec<String> {
+    template(address.country_code.as_deref())
+        .iter()
+        .filter_map(|line| {
+            let mut rendered = String::new();
+            for &(c, separator) in line.iter() {
+                if let Some(value) = component(address, c) {
+                    if !rendered.is_empty() {
+                        rendered.push_str(separator);
+                    }
+                    rendered.push_str(value);
+                }
+            }
+            Some(rendered).filter(|rendered| !rendered.is_empty())
+        })
+        .collect()
+}
+
+/// Format an address on multiple lines, separated by `\n`
+pub fn format(address: &Address) -> String {
+    lines(address).join("\n")
+}
+
+/// Format an address on a single line, with its lines separated by `, `
+pub fn format_one_line(address: &Address) -> String {
+    lines(address).join(", ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn address(country_code: &str) -> Address {
+        Address {
+            house_number: Some("68".to_string()),
+            road: Some("Carrer de Calatrava".to_string()),
+            suburb: Some("Sant Gervasi".to_string()),
+            city: Some("Barcelona".to_string()),
+            postcode: Some("08017".to_string()),
+            state: Some("Catalonia".to_string()),
+            state_code: Some("CT".to_string()),
+            country: Some("Spain".to_string()),
+            country_code: Some(country_code.to_string()),
+        }
+    }
+
+    #[test]
+    fn generic_test() {
+        assert_eq!(
+            format(&address("ES")),
+            "Carrer de Calatrava 68\n08017 Barcelona\nSpain"
+        );
+        // Countries without a dedicated template, and addresses without a country code
+        assert_eq!(
+            format_one_line(&address("DE")),
+            "Carrer de Calatrava 68, 08017 Barcelona, Spain"
+        );
+        let mut no_country = address("ES");
+        no_country.country_code = None;
+        assert_eq!(format(&no_country), format(&address("ES")));
+    }
+
+    #[test]
+    fn country_templates_test() {
+        assert_eq!(
+            format_one_line(&address("us")),
+            "68 Carrer de Calatrava, Barcelona, CT 08017, Spain"
+        );
+        assert_eq!(
+            format(&address("GB")),
+            "68 Carrer de Calatrava\nSant Gervasi\nBarcelona\n08017\nSpain"
+        );
+        assert_eq!(
+            format_one_line(&address("FR")),
+            "68 Carrer de Calatrava, 08017 Barcelona, Spain"
+        );
+        assert_eq!(
+            format_one_line(&address("IT")),
+            "Carrer de Calatrava 68, 08017 Barcelona CT, Spain"
+        );
+        assert_eq!(
+            format_one_line(&address("AU")),
+            "68 Carrer de Calatrava, Sant Gervasi CT 08017, Spain"
+        );
+        assert_eq!(
+            format_one_line(&address("JP")),
+            "Spain, 08017, Catalonia Barcelona Sant Gervasi, Carrer de Calatrava 68"
+        );
+    }
+
+    #[test]
+    fn missing_components_test() {
+        let address = Address {
+            road: Some("Bahnhofstrasse".to_string()),
+            city: Some(" Zürich ".to_string()),
+            postcode: Some("".to_string()),
+            country_code: Some("CH".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(format(&address), "Bahnhofstrasse\nZürich");
+        let address = Address {
+            city: Some("Springfield".to_string()),
+            state: Some("Illinois".to_string()),
+            country_code: Some("US".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(format_one_line(&address), "Springfield, Illinois");
+        assert_eq!(format(&Address::default()), "");
+    }
+}
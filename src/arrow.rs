@@ -0,0 +1,250 @@
+//! Arrow and Parquet output of batch results, enabled by the `arrow` feature.
+//!
+//! Batch records are converted to Arrow `RecordBatch`es with one row per address, and the
+//! columns `index`, `input`, `lon`, `lat`, `formatted`, `confidence`, `provider` and `error`.
+//! [`BatchForward::write_parquet`](../batch/struct.BatchForward.html#method.write_parquet)
+//! writes them to a Parquet file as they are produced, which can be loaded directly by
+//! data warehouses and dataframe libraries.
+//!
+//! ### Example
+//!
+//! ```no_run
+//! use geocoding::batch::BatchForward;
+//! use geocoding::Openstreetmap;
+//! use std::fs::File;
+//!
+//! let osm = Openstreetmap::new();
+//! let addresses = vec!["Schwabing, München".to_string()];
+//! let file = File::create("geocoded.parquet").unwrap();
+//! BatchForward::new(&osm)
+//!     .write_parquet::<f64, _, _>(addresses, file)
+//!     .unwrap();
+//! ```
+use crate::batch::{BatchForward, BatchRecord, BatchSummary};
+use crate::validate::Validation;
+use crate::{ForwardLabels, GeocodingError};
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use num_traits::Float;
+use parquet::arrow::ArrowWriter;
+use std::fmt::Debug;
+use std::io::Write;
+use std::sync::Arc;
+
+/// The number of rows of the record batches written by `write_parquet`
+pub const RECORD_BATCH_SIZE: usize = 8192;
+
+/// The schema of batch record batches
+pub fn schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("index", DataType::UInt64, false),
+        Field::new("input", DataType::Utf8, false),
+        Field::new("lon", DataType::Float64, true),
+        Field::new("lat", DataType::Float64, true),
+        Field::new("formatted", DataType::Utf8, true),
+        Field::new("confidence", DataType::Float64, true),
+        Field::new("provider", DataType::Utf8, false),
+        Field::new("error", DataType::Utf8, true),
+        Field::new("validation", DataType::Utf8, true),
+    ]))
+}
+
+/// Convert batch records to a `RecordBatch` with the [`schema`](fn.schema.html) of this module
+pub fn to_record_batch<T>(records: &[BatchRecord<T>]) -> Result<RecordBatch, GeocodingError>
+where
+    T: Float + Debug,
+{
+    let coordinate = |c: Option<T>| c.and_then(|c| c.to_f64());
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(UInt64Array::from_iter_values(
+            records.iter().map(|r| r.index as u64),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            records.iter().map(|r| &r.input),
+        )),
+        Arc::new(
+            records
+                .iter()
+                .map(|r| coordinate(r.lon))
+                .collect::<Float64Array>(),
+        ),
+        Arc::new(
+            records
+                .iter()
+                .map(|r| coordinate(r.lat))
+                .collect::<Float64Array>(),
+        ),
+        Arc::new(
+            records
+                .iter()
+                .map(|r| r.formatted.as_deref())
+                .collect::<StringArray>(),
+        ),
+        Arc::new(
+            records
+                .iter()
+                .map(|r| r.confidence)
+                .collect::<Float64Array>(),
+        ),
+        Arc::new(StringArray::from_iter_values(
+            records.iter().map(|r| &r.provider),
+        )),
+        Arc::new(
+            records
+                .iter()
+                .map(|r| r.error.as_deref())
+                .collect::<StringArray>(),
+        ),
+        Arc::new(
+            records
+                .iter()
+                .map(|r| r.validation.map(validation_name))
+                .collect::<StringArray>(),
+        ),
+    ];
+    Ok(RecordBatch::try_new(schema(), columns)?)
+}
+
+/// The name of a validation outcome, as serialized in JSON
+fn validation_name(validation: Validation) -> &'static str {
+    match validation {
+        Validation::Accepted => "accepted",
+        Validation::NeedsReview => "needs_review",
+        Validation::Rejected => "rejected",
+    }
+}
+
+/// A record with only what its journal entry needs, to hold many of them until they're journaled
+fn without_result<T>(record: BatchRecord<T>) -> BatchRecord<()> {
+    BatchRecord {
+        index: record.index,
+        input: record.input,
+        lon: None,
+        lat: None,
+        formatted: None,
+        confidence: None,
+        provider: String::new(),
+        error: record.error,
+        validation: None,
+    }
+}
+
+impl<'a, P> BatchForward<'a, P> {
+    /// Geocode `inputs`, writing the records to `writer` as a Parquet file
+    ///
+    /// Records are written in batches of [`RECORD_BATCH_SIZE`](../arrow/constant.RECORD_BATCH_SIZE.html)
+    /// rows, so memory use doesn't grow with the number of addresses.
+    /// Only write errors stop the batch: geocoding errors are written in the records.
+    /// With a [`Journal`](../batch/struct.Journal.html), the records are journaled once the
+    /// file is complete, since an incomplete Parquet file can't be read.
+    pub fn write_parquet<T, I, W>(
+        &self,
+        inputs: I,
+        writer: W,
+    ) -> Result<BatchSummary, GeocodingError>
+    where
+        T: Float + Debug,
+        P: ForwardLabels<T>,
+        I: IntoIterator<Item = String>,
+        I::IntoIter: 'a,
+        W: Write + Send,
+    {
+        let mut writer = ArrowWriter::try_new(writer, schema(), None)?;
+        let mut summary = BatchSummary::default();
+        let mut records = Vec::with_capacity(RECORD_BATCH_SIZE);
+        let mut written = vec![];
+        for record in self.pending::<T, I>(inputs) {
+            summary.add(&record);
+            records.push(record);
+            if records.len() == RECORD_BATCH_SIZE {
+                writer.write(&to_record_batch(&records)?)?;
+                written.extend(records.drain(..).map(without_result));
+            }
+        }
+        if !records.is_empty() {
+            writer.write(&to_record_batch(&records)?)?;
+            written.extend(records.drain(..).map(without_result));
+        }
+        writer.close()?;
+        for record in &written {
+            self.journal(record);
+        }
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Point;
+    use arrow_array::Array;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    struct Stub;
+
+    impl ForwardLabels<f32> for Stub {
+        fn forward_with_labels(
+            &self,
+            address: &str,
+        ) -> Result<Vec<(Point<f32>, String)>, GeocodingError> {
+            match address {
+                "error" => Err(GeocodingError::Forward),
+                _ => Ok(vec![(Point::new(2.5, 41.5), address.to_string())]),
+            }
+        }
+    }
+
+    #[test]
+    fn to_record_batch_test() {
+        let records: Vec<BatchRecord<f32>> = BatchForward::new(&Stub)
+            .records(vec!["Barcelona".to_string(), "error".to_string()])
+            .collect();
+        let batch = to_record_batch(&records).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.schema(), schema());
+        let lon = batch
+            .column_by_name("lon")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(lon.value(0), 2.5);
+        assert!(lon.is_null(1));
+        let error = batch
+            .column_by_name("error")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert!(error.is_null(0));
+        assert_eq!(error.value(1), "Forward geocoding failed");
+    }
+
+    #[test]
+    fn write_parquet_test() {
+        let inputs: Vec<String> = (0..RECORD_BATCH_SIZE + 1)
+            .map(|i| {
+                if i == 1 {
+                    "error".to_string()
+                } else {
+                    i.to_string()
+                }
+            })
+            .collect();
+        let path = std::env::temp_dir().join("geocoding-write-parquet-test.parquet");
+        let summary = BatchForward::new(&Stub)
+            .write_parquet::<f32, _, _>(inputs, std::fs::File::create(&path).unwrap())
+            .unwrap();
+        assert_eq!(summary.total, RECORD_BATCH_SIZE + 1);
+        assert_eq!(summary.failed, 1);
+
+        let file = std::fs::File::open(&path).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap();
+        let rows: usize = reader.map(|batch| batch.unwrap().num_rows()).sum();
+        assert_eq!(rows, RECORD_BATCH_SIZE + 1);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,1204 @@
+//! Batch forward geocoding of many addresses.
+//!
+//! [`BatchForward`](struct.BatchForward.html) geocodes a sequence of addresses with any
+//! provider implementing [`ForwardLabels`](../trait.ForwardLabels.html), producing a
+//! [`BatchRecord`](struct.BatchRecord.html) for each address: its best result, the
+//! [match score](../score/index.html) of that result as its confidence, or the error which
+//! occurred. Failures don't stop the batch.
+//!
+//! Records are produced lazily, one address at a time, so that batches of any size run in
+//! constant memory. [`write_ndjson`](struct.BatchForward.html#method.write_ndjson) writes
+//! each record as a line of JSON as soon as it is available.
+//!
+//! With the `rayon` feature, [`par_batch_forward`](struct.BatchForward.html#method.par_batch_forward)
+//! geocodes addresses on several threads, for providers which allow concurrent requests such
+//! as self-hosted Nominatim instances. A shared [`Throttle`](../throttle/struct.Throttle.html)
+//! set with [`with_throttle`](struct.BatchForward.html#method.with_throttle) limits the rate
+//! of requests of all threads together.
+//!
+//! A [`QuotaPolicy`](enum.QuotaPolicy.html) decides what happens when the provider's quota is
+//! exhausted, such as OpenCage's daily quota: by default the remaining addresses fail, but the
+//! batch can also pause until the quota is reset, or switch to a fallback provider.
+//!
+//! [`plan`](struct.BatchForward.html#method.plan) reports how many requests a batch will
+//! send, how long it will take under its throttle, and whether it will exceed the known
+//! quota, without sending any request.
+//!
+//! [Validators](../validate/index.html) set with
+//! [`with_validator`](struct.BatchForward.html#method.with_validator) mark the results as
+//! accepted, rejected or needing review, e.g. when they are far from the expected region.
+//!
+//! A [`Journal`](struct.Journal.html) set with
+//! [`with_journal`](struct.BatchForward.html#method.with_journal) records the inputs which
+//! were geocoded in an append-only file, so that an interrupted job resumes where it left off
+//! when run again, skipping these inputs even without a response cache.
+//!
+//! [`BatchReverse`](struct.BatchReverse.html) reverse-geocodes a sequence of points, such as
+//! a GPS trace. With [`with_cluster_radius`](struct.BatchReverse.html#method.with_cluster_radius),
+//! consecutive points close to each other share a single lookup, which saves most of the
+//! requests of dense traces when meter-level precision isn't needed.
+//!
+//! ### Example
+//!
+//! ```no_run
+//! use geocoding::batch::BatchForward;
+//! use geocoding::Openstreetmap;
+//! use std::io::{stdout, BufRead};
+//!
+//! let osm = Openstreetmap::new();
+//! let addresses = std::io::stdin().lock().lines().map(Result::unwrap);
+//! let summary = BatchForward::new(&osm)
+//!     .write_ndjson::<f64, _, _>(addresses, stdout().lock())
+//!     .unwrap();
+//! eprintln!("{} of {} addresses failed", summary.failed, summary.total);
+//! ```
+use crate::score::match_score;
+use crate::throttle::Throttle;
+use crate::validate::{self, Candidate, Validation, Validator};
+use crate::{haversine_distance, ForwardLabels, GeocodingError, Point, Reverse};
+use crate::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use num_traits::Float;
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::Debug;
+use std::fs::{File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+/// The outcome of forward-geocoding one address of a batch
+///
+/// If the address was geocoded, `lon`, `lat`, `formatted` and `confidence` describe its best
+/// result, and are `None` if there was no result. If the request failed, `error` describes
+/// the failure.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BatchRecord<T> {
+    /// The position of the address in the batch, starting at 0
+    pub index: usize,
+    pub input: String,
+    pub lon: Option<T>,
+    pub lat: Option<T>,
+    pub formatted: Option<String>,
+    /// The [match score](../score/fn.match_score.html) of the result against the input
+    pub confidence: Option<f64>,
+    pub provider: String,
+    pub error: Option<String>,
+    /// The strictest outcome of the batch's validators for the result, if there is a
+    /// result and the batch has validators
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validation: Option<Validation>,
+}
+
+/// The number of addresses processed by a batch
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BatchSummary {
+    pub total: usize,
+    /// Addresses with at least one result
+    pub matched: usize,
+    /// Addresses without results
+    pub unmatched: usize,
+    pub failed: usize,
+    /// Results accepted by the validators
+    pub accepted: usize,
+    /// Results which need to be reviewed according to the validators
+    pub needs_review: usize,
+    /// Results rejected by the validators
+    pub rejected: usize,
+}
+
+impl BatchSummary {
+    pub(crate) fn add<T>(&mut self, record: &BatchRecord<T>) {
+        self.total += 1;
+        if record.error.is_some() {
+            self.failed += 1;
+        } else if record.formatted.is_some() {
+            self.matched += 1;
+        } else {
+            self.unmatched += 1;
+        }
+        match record.validation {
+            Some(Validation::Accepted) => self.accepted += 1,
+            Some(Validation::NeedsReview) => self.needs_review += 1,
+            Some(Validation::Rejected) => self.rejected += 1,
+            None => {}
+        }
+    }
+}
+
+/// The requests a batch will send, estimated by [`plan`](struct.BatchForward.html#method.plan)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BatchPlan {
+    /// The number of inputs
+    pub inputs: usize,
+    /// Inputs skipped because they are in the journal
+    pub skipped: usize,
+    /// The number of requests to the provider, one per input which isn't skipped
+    pub requests: usize,
+    /// The time the requests will take at least, under the current interval of the batch's
+    /// throttle, excluding the response times
+    pub duration: Duration,
+    /// The remaining quota set with
+    /// [`with_remaining_quota`](struct.BatchForward.html#method.with_remaining_quota)
+    pub remaining_quota: Option<u64>,
+}
+
+impl BatchPlan {
+    /// Whether the requests will exceed the remaining quota, if it is known
+    pub fn exceeds_quota(&self) -> bool {
+        self.remaining_quota
+            .is_some_and(|remaining| self.requests as u64 > remaining)
+    }
+}
+
+/// What a batch does when the provider reports that its quota is exhausted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QuotaPolicy {
+    /// Record the error, like any other failure
+    #[default]
+    Fail,
+    /// Sleep until the quota is reset, then resume; fail if the reset time isn't known
+    WaitUntilReset,
+    /// Geocode the remaining addresses with the fallback provider set with
+    /// [`with_fallback`](struct.BatchForward.html#method.with_fallback); fail if there is none
+    SwitchToFallback,
+}
+
+/// An append-only journal of the inputs geocoded by batches, for resuming interrupted jobs
+///
+/// Each input whose record has no error is appended to the file as a line of JSON with its
+/// index, once its record is yielded or written. When a batch is run again with the same
+/// journal, inputs found in it at the same index are skipped, so only the remaining inputs
+/// are geocoded, and the records and summaries only cover them. Failed inputs are geocoded
+/// again.
+///
+/// Write errors are ignored, so that journaling never fails a batch: an input whose entry
+/// couldn't be written is geocoded again when resuming. A last line truncated by an
+/// interruption is ignored as well.
+///
+/// # Examples
+///
+/// ```no_run
+/// use geocoding::batch::{BatchForward, Journal};
+/// use geocoding::Openstreetmap;
+/// use std::fs::OpenOptions;
+///
+/// let osm = Openstreetmap::new();
+/// let journal = Journal::open("addresses.journal").unwrap();
+/// let addresses = vec!["Carrer de Calatrava 68, Barcelona".to_string()];
+/// // Append the records of this run to those of the interrupted runs
+/// let out = OpenOptions::new().create(true).append(true).open("addresses.ndjson").unwrap();
+/// BatchForward::new(&osm)
+///     .with_journal(&journal)
+///     .build()
+///     .write_ndjson::<f64, _, _>(addresses, out)
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Journal {
+    file: Mutex<File>,
+    // The hashes of the index and input of the completed inputs
+    completed: HashSet<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JournalEntry<'a> {
+    index: usize,
+    #[serde(borrow)]
+    input: Cow<'a, str>,
+}
+
+impl Journal {
+    /// Open the journal at `path`, creating it if it doesn't exist
+    pub fn open<Q>(path: Q) -> Result<Journal, GeocodingError>
+    where
+        Q: AsRef<Path>,
+    {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+        let mut completed = HashSet::new();
+        let mut truncated = false;
+        for line in BufReader::new(&file).lines() {
+            let line = line?;
+            match serde_json::from_str::<JournalEntry>(&line) {
+                Ok(entry) => {
+                    completed.insert(entry_hash(entry.index, &entry.input));
+                    truncated = false;
+                }
+                Err(_) => truncated = !line.is_empty(),
+            }
+        }
+        // Start the next entry on a line of its own
+        if truncated {
+            file.write_all(b"\n")?;
+        }
+        Ok(Journal {
+            file: Mutex::new(file),
+            completed,
+        })
+    }
+
+    /// Whether the input at `index` was geocoded by a previous run
+    pub fn contains(&self, index: usize, input: &str) -> bool {
+        self.completed.contains(&entry_hash(index, input))
+    }
+
+    /// The number of inputs geocoded by previous runs
+    pub fn len(&self) -> usize {
+        self.completed.len()
+    }
+
+    /// Whether no input was geocoded by previous runs
+    pub fn is_empty(&self) -> bool {
+        self.completed.is_empty()
+    }
+
+    /// Append the input of `record` to the journal, unless the record has an error
+    pub(crate) fn record<T>(&self, record: &BatchRecord<T>) {
+        if record.error.is_some() {
+            return;
+        }
+        let entry = JournalEntry {
+            index: record.index,
+            input: Cow::Borrowed(&record.input),
+        };
+        if let Ok(mut line) = serde_json::to_vec(&entry) {
+            line.push(b'\n');
+            let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+            let _ = file.write_all(&line).and_then(|_| file.flush());
+        }
+    }
+}
+
+fn entry_hash(index: usize, input: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (index, input).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A batch forward-geocoding job
+pub struct BatchForward<'a, P> {
+    provider: &'a P,
+    provider_name: &'a str,
+    throttle: Option<&'a Throttle>,
+    quota_policy: QuotaPolicy,
+    fallback: Option<(&'a P, &'a str)>,
+    journal: Option<&'a Journal>,
+    remaining_quota: Option<u64>,
+    validators: Vec<&'a dyn Validator>,
+    // Whether the quota of the provider is exhausted, and the fallback is used
+    switched: AtomicBool,
+}
+
+impl<'a, P> BatchForward<'a, P> {
+    /// Create a batch job using `provider`
+    ///
+    /// The provider is named after its type in the records, e.g. `"Openstreetmap"`.
+    pub fn new(provider: &'a P) -> BatchForward<'a, P> {
+        BatchForward {
+            provider,
+            provider_name: type_name::<P>(),
+            throttle: None,
+            quota_policy: QuotaPolicy::default(),
+            fallback: None,
+            journal: None,
+            remaining_quota: None,
+            validators: vec![],
+            switched: AtomicBool::new(false),
+        }
+    }
+
+    /// Set the name of the provider in the records
+    pub fn with_provider_name(&mut self, provider_name: &'a str) -> &mut Self {
+        self.provider_name = provider_name;
+        self
+    }
+
+    /// Wait for `throttle` before each request, keyed by the provider name
+    ///
+    /// Unlike a throttle set on the provider, which only limits the requests to each host,
+    /// this limits the rate of the batch's requests, whatever their host.
+    pub fn with_throttle(&mut self, throttle: &'a Throttle) -> &mut Self {
+        self.throttle = Some(throttle);
+        self
+    }
+
+    /// Set what happens when the provider's quota is exhausted, `QuotaPolicy::Fail` by default
+    pub fn with_quota_policy(&mut self, quota_policy: QuotaPolicy) -> &mut Self {
+        self.quota_policy = quota_policy;
+        self
+    }
+
+    /// Set the provider used by `QuotaPolicy::SwitchToFallback`, named `provider_name` in
+    /// the records
+    ///
+    /// The fallback has the type of the main provider: use
+    /// [`GeocodingService`](../service/enum.GeocodingService.html) to fall back to another kind
+    /// of provider.
+    pub fn with_fallback(&mut self, fallback: &'a P, provider_name: &'a str) -> &mut Self {
+        self.fallback = Some((fallback, provider_name));
+        self
+    }
+
+    /// Skip the inputs recorded in `journal`, and record the inputs geocoded by this batch
+    pub fn with_journal(&mut self, journal: &'a Journal) -> &mut Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Set the number of requests the provider's quota still allows, for
+    /// [`plan`](#method.plan), e.g. from
+    /// [`Opencage::remaining_calls`](../opencage/struct.Opencage.html#method.remaining_calls)
+    pub fn with_remaining_quota(&mut self, remaining_quota: u64) -> &mut Self {
+        self.remaining_quota = Some(remaining_quota);
+        self
+    }
+
+    /// Validate the result of each address with `validator`, in addition to the previously
+    /// set validators
+    pub fn with_validator(&mut self, validator: &'a dyn Validator) -> &mut Self {
+        self.validators.push(validator);
+        self
+    }
+
+    /// Build and return an instance of BatchForward
+    pub fn build(&self) -> BatchForward<'a, P> {
+        BatchForward {
+            provider: self.provider,
+            provider_name: self.provider_name,
+            throttle: self.throttle,
+            quota_policy: self.quota_policy,
+            fallback: self.fallback,
+            journal: self.journal,
+            remaining_quota: self.remaining_quota,
+            validators: self.validators.clone(),
+            switched: AtomicBool::new(self.switched.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Geocode `input` following the quota policy, returning the name of the provider used
+    /// along with the results
+    #[allow(clippy::type_complexity)]
+    fn lookup<T>(&self, input: &str) -> (&'a str, Result<Vec<(Point<T>, String)>, GeocodingError>)
+    where
+        T: Float + Debug,
+        P: ForwardLabels<T>,
+    {
+        if let (true, Some((fallback, name))) =
+            (self.switched.load(Ordering::Relaxed), self.fallback)
+        {
+            return (name, fallback.forward_with_labels(input));
+        }
+        let mut waited: Option<DateTime<Utc>> = None;
+        loop {
+            let result = self.provider.forward_with_labels(input);
+            let reset = match &result {
+                Err(GeocodingError::QuotaExceeded { reset }) => *reset,
+                _ => return (self.provider_name, result),
+            };
+            match (self.quota_policy, reset, self.fallback) {
+                // Only retry if the quota is now reset later than the last time, so that a
+                // provider reporting a past reset time isn't queried in a loop
+                (QuotaPolicy::WaitUntilReset, Some(reset), _)
+                    if waited.map_or(true, |w| reset > w) =>
+                {
+                    if let Ok(duration) = (reset - Utc::now()).to_std() {
+                        thread::sleep(duration);
+                    }
+                    waited = Some(reset);
+                }
+                (QuotaPolicy::SwitchToFallback, _, Some((fallback, name))) => {
+                    self.switched.store(true, Ordering::Relaxed);
+                    return (name, fallback.forward_with_labels(input));
+                }
+                _ => return (self.provider_name, result),
+            }
+        }
+    }
+
+    /// Geocode the address at `index` of the batch
+    pub(crate) fn record<T>(&self, index: usize, input: String) -> BatchRecord<T>
+    where
+        T: Float + Debug,
+        P: ForwardLabels<T>,
+    {
+        if let Some(throttle) = self.throttle {
+            throttle.wait(self.provider_name);
+        }
+        let (provider_name, result) = self.lookup(&input);
+        let mut record = BatchRecord {
+            index,
+            input,
+            lon: None,
+            lat: None,
+            formatted: None,
+            confidence: None,
+            provider: provider_name.to_string(),
+            error: None,
+            validation: None,
+        };
+        match result {
+            Ok(results) => {
+                if let Some((point, label)) = results.into_iter().next() {
+                    let confidence = match_score(&record.input, &label, None);
+                    if !self.validators.is_empty() {
+                        let candidate = Candidate {
+                            input: &record.input,
+                            point: Point::new(
+                                point.x().to_f64().unwrap_or(f64::NAN),
+                                point.y().to_f64().unwrap_or(f64::NAN),
+                            ),
+                            formatted: &label,
+                            confidence,
+                        };
+                        record.validation = Some(validate::validate(&self.validators, &candidate));
+                    }
+                    record.lon = Some(point.x());
+                    record.lat = Some(point.y());
+                    record.confidence = Some(confidence);
+                    record.formatted = Some(label);
+                }
+            }
+            Err(e) => record.error = Some(error_details(&e)),
+        }
+        record
+    }
+
+    /// Estimate the requests geocoding `inputs` will send, without sending any
+    ///
+    /// Each input which isn't in the journal takes a request. The duration only accounts
+    /// for the throttle set with [`with_throttle`](#method.with_throttle), which spaces the
+    /// requests of all threads together.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::batch::BatchForward;
+    /// use geocoding::throttle::Throttle;
+    /// use geocoding::Openstreetmap;
+    /// use std::time::Duration;
+    ///
+    /// let osm = Openstreetmap::new();
+    /// let throttle = Throttle::new(Duration::from_secs(1));
+    /// let addresses = vec!["Berlin", "Bern", "Barcelona"];
+    /// let plan = BatchForward::new(&osm)
+    ///     .with_throttle(&throttle)
+    ///     .with_remaining_quota(2)
+    ///     .build()
+    ///     .plan(&addresses);
+    /// assert_eq!(plan.requests, 3);
+    /// assert_eq!(plan.duration, Duration::from_secs(2));
+    /// assert!(plan.exceeds_quota());
+    /// ```
+    pub fn plan<I>(&self, inputs: I) -> BatchPlan
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut plan = BatchPlan {
+            remaining_quota: self.remaining_quota,
+            ..BatchPlan::default()
+        };
+        for (index, input) in inputs.into_iter().enumerate() {
+            plan.inputs += 1;
+            if self.is_journaled(index, input.as_ref()) {
+                plan.skipped += 1;
+            } else {
+                plan.requests += 1;
+            }
+        }
+        if let Some(throttle) = self.throttle {
+            // The first request is sent without waiting
+            let waits = plan.requests.saturating_sub(1) as u32;
+            plan.duration = throttle.effective_interval(self.provider_name) * waits;
+        }
+        plan
+    }
+
+    /// Geocode `inputs` one after the other, lazily
+    ///
+    /// Each address is only geocoded when its record is requested from the iterator.
+    /// With a [`Journal`](struct.Journal.html), the inputs it contains are skipped, and the
+    /// others are journaled as their records are yielded.
+    pub fn records<T, I>(&self, inputs: I) -> impl Iterator<Item = BatchRecord<T>> + '_
+    where
+        T: Float + Debug,
+        P: ForwardLabels<T>,
+        I: IntoIterator<Item = String>,
+        I::IntoIter: 'a,
+    {
+        self.pending(inputs)
+            .inspect(move |record| self.journal(record))
+    }
+
+    /// Geocode the `inputs` which aren't journaled, without journaling them
+    pub(crate) fn pending<T, I>(&self, inputs: I) -> impl Iterator<Item = BatchRecord<T>> + '_
+    where
+        T: Float + Debug,
+        P: ForwardLabels<T>,
+        I: IntoIterator<Item = String>,
+        I::IntoIter: 'a,
+    {
+        inputs
+            .into_iter()
+            .enumerate()
+            .filter(move |(index, input)| !self.is_journaled(*index, input))
+            .map(move |(index, input)| self.record(index, input))
+    }
+
+    /// Whether the input at `index` is in the journal
+    fn is_journaled(&self, index: usize, input: &str) -> bool {
+        self.journal
+            .is_some_and(|journal| journal.contains(index, input))
+    }
+
+    /// Record the input of `record` in the journal, if any
+    pub(crate) fn journal<T>(&self, record: &BatchRecord<T>) {
+        if let Some(journal) = self.journal {
+            journal.record(record);
+        }
+    }
+
+    /// Geocode `inputs`, writing each record to `writer` as a line of JSON as soon as
+    /// it is available
+    ///
+    /// Only write errors stop the batch: geocoding errors are written in the records.
+    /// `writer` isn't buffered, so wrap it in a `BufWriter` if needed: it is flushed once
+    /// the batch is done, or after each record with a [`Journal`](struct.Journal.html), so
+    /// that only written records are journaled.
+    pub fn write_ndjson<T, I, W>(
+        &self,
+        inputs: I,
+        mut writer: W,
+    ) -> Result<BatchSummary, GeocodingError>
+    where
+        T: Float + Debug + Serialize,
+        P: ForwardLabels<T>,
+        I: IntoIterator<Item = String>,
+        I::IntoIter: 'a,
+        W: Write,
+    {
+        let mut summary = BatchSummary::default();
+        for record in self.pending::<T, I>(inputs) {
+            write_ndjson_record(&mut writer, &record)?;
+            if self.journal.is_some() {
+                // Journal the record once it's written, so that it's written again if the
+                // batch is interrupted in between
+                writer.flush()?;
+                self.journal(&record);
+            }
+            summary.add(&record);
+        }
+        writer.flush()?;
+        Ok(summary)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, P> BatchForward<'a, P>
+where
+    P: Sync,
+{
+    /// Geocode `inputs` on `threads` threads, returning the records in input order
+    ///
+    /// Set a throttle with [`with_throttle`](#method.with_throttle), or on the provider, to
+    /// respect the provider's rate limit: threads share it. Journaled inputs are skipped.
+    pub fn par_batch_forward<T>(
+        &self,
+        inputs: Vec<String>,
+        threads: usize,
+    ) -> Result<Vec<BatchRecord<T>>, GeocodingError>
+    where
+        T: Float + Debug + Send,
+        P: ForwardLabels<T>,
+    {
+        use rayon::prelude::*;
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()?;
+        Ok(pool.install(|| {
+            inputs
+                .into_par_iter()
+                .enumerate()
+                .filter(|(index, input)| !self.is_journaled(*index, input))
+                .map(|(index, input)| self.record(index, input))
+                .inspect(|record| self.journal(record))
+                .collect()
+        }))
+    }
+}
+
+/// The outcome of reverse-geocoding one point of a batch
+///
+/// If the point was looked up, `formatted` is its address, and is `None` if there was no
+/// result. If the request failed, `error` describes the failure.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReverseRecord<T> {
+    /// The position of the point in the batch, starting at 0
+    pub index: usize,
+    pub lon: T,
+    pub lat: T,
+    pub formatted: Option<String>,
+    /// The index of the point whose lookup this point shares, its own index unless it was
+    /// clustered with an earlier point
+    pub cluster: usize,
+    pub provider: String,
+    pub error: Option<String>,
+}
+
+/// A batch reverse-geocoding job
+pub struct BatchReverse<'a, P> {
+    provider: &'a P,
+    provider_name: &'a str,
+    throttle: Option<&'a Throttle>,
+    cluster_radius: Option<f64>,
+}
+
+impl<'a, P> BatchReverse<'a, P> {
+    /// Create a batch job using `provider`
+    ///
+    /// The provider is named after its type in the records, e.g. `"Openstreetmap"`.
+    pub fn new(provider: &'a P) -> BatchReverse<'a, P> {
+        BatchReverse {
+            provider,
+            provider_name: type_name::<P>(),
+            throttle: None,
+            cluster_radius: None,
+        }
+    }
+
+    /// Set the name of the provider in the records
+    pub fn with_provider_name(&mut self, provider_name: &'a str) -> &mut Self {
+        self.provider_name = provider_name;
+        self
+    }
+
+    /// Wait for `throttle` before each request, keyed by the provider name
+    pub fn with_throttle(&mut self, throttle: &'a Throttle) -> &mut Self {
+        self.throttle = Some(throttle);
+        self
+    }
+
+    /// Share the lookup of a point with the following points within `radius` meters of it
+    ///
+    /// A point further away is looked up, and starts a new cluster. Clusters are formed in
+    /// input order, so that traces are processed in constant memory: a place visited twice
+    /// is looked up once per visit. Points following a failed lookup are looked up again.
+    pub fn with_cluster_radius(&mut self, radius: f64) -> &mut Self {
+        self.cluster_radius = Some(radius);
+        self
+    }
+
+    /// Build and return an instance of BatchReverse
+    pub fn build(&self) -> BatchReverse<'a, P> {
+        BatchReverse {
+            provider: self.provider,
+            provider_name: self.provider_name,
+            throttle: self.throttle,
+            cluster_radius: self.cluster_radius,
+        }
+    }
+
+    /// Look up the point at `index` of the batch
+    fn record<T>(&self, index: usize, point: &Point<T>) -> ReverseRecord<T>
+    where
+        T: Float + Debug,
+        P: Reverse<T>,
+    {
+        if let Some(throttle) = self.throttle {
+            throttle.wait(self.provider_name);
+        }
+        let (formatted, error) = match self.provider.reverse(point) {
+            Ok(formatted) => (formatted, None),
+            Err(e) => (None, Some(error_details(&e))),
+        };
+        ReverseRecord {
+            index,
+            lon: point.x(),
+            lat: point.y(),
+            formatted,
+            cluster: index,
+            provider: self.provider_name.to_string(),
+            error,
+        }
+    }
+
+    /// Reverse-geocode `points` one after the other, lazily
+    ///
+    /// Each point is only looked up when its record is requested from the iterator.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use geocoding::batch::BatchReverse;
+    /// use geocoding::{Openstreetmap, Point};
+    ///
+    /// let osm = Openstreetmap::new();
+    /// let trace = vec![
+    ///     Point::new(2.128701, 41.401392),
+    ///     Point::new(2.128745, 41.401421),
+    ///     Point::new(2.131, 41.403),
+    /// ];
+    /// let records: Vec<_> = BatchReverse::new(&osm)
+    ///     .with_cluster_radius(25.0)
+    ///     .build()
+    ///     .records(trace)
+    ///     .collect();
+    /// // The second point shares the lookup of the first
+    /// assert_eq!(records[1].cluster, 0);
+    /// ```
+    pub fn records<'s, T, I>(&'s self, points: I) -> impl Iterator<Item = ReverseRecord<T>> + 's
+    where
+        T: Float + Debug + 's,
+        P: Reverse<T>,
+        I: IntoIterator<Item = Point<T>>,
+        I::IntoIter: 'a,
+    {
+        // The point which was looked up last, with its record
+        let mut center: Option<(Point<T>, ReverseRecord<T>)> = None;
+        points.into_iter().enumerate().map(move |(index, point)| {
+            if let (Some(radius), Some((center, shared))) = (self.cluster_radius, &center) {
+                if shared.error.is_none() && haversine_distance(center, &point) <= radius {
+                    return ReverseRecord {
+                        index,
+                        lon: point.x(),
+                        lat: point.y(),
+                        ..shared.clone()
+                    };
+                }
+            }
+            let record = self.record(index, &point);
+            center = Some((point, record.clone()));
+            record
+        })
+    }
+}
+
+/// The name of a type, without its module path
+pub(crate) fn type_name<P>() -> &'static str {
+    let type_name = std::any::type_name::<P>();
+    // Strip the module path, but not from generic parameters
+    let end = type_name.find('<').unwrap_or(type_name.len());
+    let start = type_name[..end].rfind("::").map_or(0, |i| i + 2);
+    &type_name[start..]
+}
+
+/// Write a record as a line of JSON
+pub(crate) fn write_ndjson_record<T, W>(
+    writer: &mut W,
+    record: &BatchRecord<T>,
+) -> Result<(), GeocodingError>
+where
+    T: Serialize,
+    W: Write,
+{
+    serde_json::to_writer(&mut *writer, record).map_err(std::io::Error::from)?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+/// An error message including the messages of the errors which caused it
+pub(crate) fn error_details(error: &GeocodingError) -> String {
+    let mut details = error.to_string();
+    let mut source = error.source();
+    while let Some(e) = source {
+        details.push_str(": ");
+        details.push_str(&e.to_string());
+        source = e.source();
+    }
+    details
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Point;
+
+    /// Returns a result for each address containing "Calatrava", and fails on "error"
+    struct Stub;
+
+    impl ForwardLabels<f64> for Stub {
+        fn forward_with_labels(
+            &self,
+            address: &str,
+        ) -> Result<Vec<(Point<f64>, String)>, GeocodingError> {
+            if address == "error" {
+                return Err(GeocodingError::InvalidOsmId("X1".to_string()));
+            }
+            if !address.contains("Calatrava") {
+                return Ok(vec![]);
+            }
+            Ok(vec![(
+                Point::new(2.12870, 41.40139),
+                "Carrer de Calatrava, 68, 08017 Barcelona, Spain".to_string(),
+            )])
+        }
+    }
+
+    /// Like `Stub`, but reports an exhausted quota for the calls in `exhausted`
+    struct Quota {
+        calls: std::sync::atomic::AtomicUsize,
+        exhausted: std::ops::Range<usize>,
+        reset: Option<DateTime<Utc>>,
+    }
+
+    impl Quota {
+        fn new(exhausted: std::ops::Range<usize>, reset: Option<DateTime<Utc>>) -> Self {
+            Quota {
+                calls: Default::default(),
+                exhausted,
+                reset,
+            }
+        }
+    }
+
+    impl ForwardLabels<f64> for Quota {
+        fn forward_with_labels(
+            &self,
+            address: &str,
+        ) -> Result<Vec<(Point<f64>, String)>, GeocodingError> {
+            let call = self.calls.fetch_add(1, Ordering::Relaxed);
+            if self.exhausted.contains(&call) {
+                return Err(GeocodingError::QuotaExceeded { reset: self.reset });
+            }
+            Stub.forward_with_labels(address)
+        }
+    }
+
+    fn inputs() -> Vec<String> {
+        vec![
+            "Carrer de Calatrava 68".to_string(),
+            "error".to_string(),
+            "Atlantis".to_string(),
+        ]
+    }
+
+    #[test]
+    fn records_test() {
+        let batch = BatchForward::new(&Stub);
+        let records: Vec<BatchRecord<f64>> = batch.records(inputs()).collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].index, 0);
+        assert_eq!(records[0].lon, Some(2.12870));
+        assert_eq!(records[0].lat, Some(41.40139));
+        assert_eq!(records[0].confidence, Some(1.0));
+        assert_eq!(records[0].provider, "Stub");
+        assert_eq!(records[1].error.as_deref(), Some("Invalid OSM id: X1"));
+        assert_eq!(records[1].lon, None);
+        assert_eq!(records[2].formatted, None);
+        assert_eq!(records[2].error, None);
+
+        let batch = BatchForward::new(&Stub).with_provider_name("stub").build();
+        let record: BatchRecord<f64> = batch.records(inputs()).next().unwrap();
+        assert_eq!(record.provider, "stub");
+    }
+
+    #[test]
+    fn with_throttle_test() {
+        let throttle = Throttle::new(std::time::Duration::from_millis(50));
+        let batch = BatchForward::new(&Stub).with_throttle(&throttle).build();
+        let start = std::time::Instant::now();
+        let records: Vec<BatchRecord<f64>> = batch.records(inputs()).collect();
+        assert_eq!(records.len(), 3);
+        assert!(start.elapsed() >= std::time::Duration::from_millis(100));
+    }
+
+    #[test]
+    fn quota_policy_test() {
+        let inputs = || vec!["Carrer de Calatrava 68".to_string(); 3];
+        // Fail
+        let provider = Quota::new(1..usize::MAX, None);
+        let records: Vec<BatchRecord<f64>> =
+            BatchForward::new(&provider).records(inputs()).collect();
+        assert!(records[0].error.is_none());
+        assert_eq!(records[1].error.as_deref(), Some("Request quota exhausted"));
+        assert_eq!(records[2].error.as_deref(), Some("Request quota exhausted"));
+
+        // WaitUntilReset
+        let reset = Utc::now() + chrono::Duration::milliseconds(100);
+        let provider = Quota::new(1..2, Some(reset));
+        let records: Vec<BatchRecord<f64>> = BatchForward::new(&provider)
+            .with_quota_policy(QuotaPolicy::WaitUntilReset)
+            .build()
+            .records(inputs())
+            .collect();
+        assert!(Utc::now() >= reset);
+        assert!(records.iter().all(|record| record.error.is_none()));
+        // A reset time which doesn't move isn't waited for again
+        let provider = Quota::new(0..usize::MAX, Some(Utc::now()));
+        let record: BatchRecord<f64> = BatchForward::new(&provider)
+            .with_quota_policy(QuotaPolicy::WaitUntilReset)
+            .build()
+            .record(0, "Carrer de Calatrava 68".to_string());
+        assert!(record.error.is_some());
+        assert_eq!(provider.calls.load(Ordering::Relaxed), 2);
+
+        // SwitchToFallback
+        let provider = Quota::new(1..usize::MAX, None);
+        let fallback = Quota::new(0..0, None);
+        let records: Vec<BatchRecord<f64>> = BatchForward::new(&provider)
+            .with_quota_policy(QuotaPolicy::SwitchToFallback)
+            .with_fallback(&fallback, "fallback")
+            .build()
+            .records(inputs())
+            .collect();
+        assert!(records.iter().all(|record| record.error.is_none()));
+        assert_eq!(records[0].provider, "Quota");
+        assert_eq!(records[1].provider, "fallback");
+        assert_eq!(records[2].provider, "fallback");
+        // The exhausted provider isn't queried again
+        assert_eq!(provider.calls.load(Ordering::Relaxed), 2);
+        assert_eq!(fallback.calls.load(Ordering::Relaxed), 2);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_batch_forward_test() {
+        let inputs: Vec<String> = (0..100)
+            .map(|i| format!("Carrer de Calatrava {}", i))
+            .collect();
+        let records: Vec<BatchRecord<f64>> = BatchForward::new(&Stub)
+            .par_batch_forward(inputs.clone(), 4)
+            .unwrap();
+        assert_eq!(records.len(), 100);
+        for (i, record) in records.iter().enumerate() {
+            assert_eq!(record.index, i);
+            assert_eq!(record.input, inputs[i]);
+        }
+
+        // Threads share the throttle
+        let throttle = Throttle::new(std::time::Duration::from_millis(20));
+        let start = std::time::Instant::now();
+        BatchForward::new(&Stub)
+            .with_throttle(&throttle)
+            .par_batch_forward::<f64>(inputs[..6].to_vec(), 3)
+            .unwrap();
+        assert!(start.elapsed() >= std::time::Duration::from_millis(100));
+    }
+
+    #[test]
+    fn write_ndjson_test() {
+        let mut out = vec![];
+        let summary = BatchForward::new(&Stub)
+            .write_ndjson::<f64, _, _>(inputs(), &mut out)
+            .unwrap();
+        assert_eq!(
+            summary,
+            BatchSummary {
+                total: 3,
+                matched: 1,
+                unmatched: 1,
+                failed: 1,
+                ..Default::default()
+            }
+        );
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 3);
+        let record: BatchRecord<f64> = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(record.input, "error");
+        assert_eq!(record.error.as_deref(), Some("Invalid OSM id: X1"));
+        assert!(lines[2].contains(r#""lon":null"#));
+        assert!(!lines[0].contains("validation"));
+
+        /// Counts its flushes
+        #[derive(Default)]
+        struct Flushes(Vec<u8>, usize);
+
+        impl Write for Flushes {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                self.1 += 1;
+                Ok(())
+            }
+        }
+
+        // Without a journal, the writer is only flushed at the end
+        let mut out = Flushes::default();
+        BatchForward::new(&Stub)
+            .write_ndjson::<f64, _, _>(inputs(), &mut out)
+            .unwrap();
+        assert_eq!(out.0.iter().filter(|b| **b == b'\n').count(), 3);
+        assert_eq!(out.1, 1);
+    }
+
+    #[test]
+    fn with_validator_test() {
+        let madrid = validate::WithinDistance::new(Point::new(-3.70, 40.42), 10.0);
+        let inputs = vec![
+            "Carrer de Calatrava 68".to_string(),
+            "Calatrava, Madrid".to_string(),
+            "Atlantis".to_string(),
+        ];
+        let batch = BatchForward::new(&Stub)
+            .with_validator(&validate::MinConfidence(0.8))
+            .build();
+        let records: Vec<BatchRecord<f64>> = batch.records(inputs.clone()).collect();
+        assert_eq!(records[0].validation, Some(Validation::Accepted));
+        assert_eq!(records[1].validation, Some(Validation::NeedsReview));
+        assert_eq!(records[2].validation, None);
+
+        let mut out = vec![];
+        let summary = BatchForward::new(&Stub)
+            .with_validator(&validate::MinConfidence(0.8))
+            .with_validator(&madrid)
+            .build()
+            .write_ndjson::<f64, _, _>(inputs, &mut out)
+            .unwrap();
+        assert_eq!(
+            (summary.accepted, summary.needs_review, summary.rejected),
+            (0, 0, 2)
+        );
+        let out = String::from_utf8(out).unwrap();
+        assert!(out
+            .lines()
+            .next()
+            .unwrap()
+            .contains(r#""validation":"rejected""#));
+    }
+
+    #[test]
+    fn plan_test() {
+        let plan = BatchForward::new(&Stub).plan(inputs());
+        assert_eq!(plan.inputs, 3);
+        assert_eq!(plan.requests, 3);
+        assert_eq!(plan.duration, Duration::ZERO);
+        assert!(!plan.exceeds_quota());
+
+        let throttle = Throttle::new(Duration::from_millis(500));
+        let plan = BatchForward::new(&Stub)
+            .with_throttle(&throttle)
+            .with_remaining_quota(3)
+            .build()
+            .plan(inputs());
+        assert_eq!(plan.duration, Duration::from_secs(1));
+        assert_eq!(plan.remaining_quota, Some(3));
+        assert!(!plan.exceeds_quota());
+        let plan = BatchForward::new(&Stub)
+            .with_remaining_quota(2)
+            .build()
+            .plan(inputs());
+        assert!(plan.exceeds_quota());
+    }
+
+    #[test]
+    fn with_journal_test() {
+        let path =
+            std::env::temp_dir().join(format!("geocoding-journal-{}.ndjson", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let journal = Journal::open(&path).unwrap();
+        assert!(journal.is_empty());
+        // The batch is interrupted after its first two records
+        let batch = BatchForward::new(&Stub).with_journal(&journal).build();
+        let records: Vec<BatchRecord<f64>> = batch.records(inputs()).take(2).collect();
+        assert_eq!(records.len(), 2);
+        drop(journal);
+        // An entry truncated by the interruption
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"index": 2, "inp"#).unwrap();
+
+        // Only the failed and remaining inputs are geocoded when resuming
+        let journal = Journal::open(&path).unwrap();
+        assert_eq!(journal.len(), 1);
+        assert!(journal.contains(0, "Carrer de Calatrava 68"));
+        assert!(!journal.contains(0, "Atlantis"));
+        // Inputs with escaped characters
+        journal.record(&BatchRecord::<f64> {
+            index: 3,
+            input: "\"Calatrava\" 68".to_string(),
+            lon: None,
+            lat: None,
+            formatted: None,
+            confidence: None,
+            provider: "Stub".to_string(),
+            error: None,
+            validation: None,
+        });
+        assert!(Journal::open(&path)
+            .unwrap()
+            .contains(3, "\"Calatrava\" 68"));
+        let plan = BatchForward::new(&Stub)
+            .with_journal(&journal)
+            .build()
+            .plan(inputs());
+        assert_eq!((plan.skipped, plan.requests), (1, 2));
+        let mut out = vec![];
+        let summary = BatchForward::new(&Stub)
+            .with_journal(&journal)
+            .build()
+            .write_ndjson::<f64, _, _>(inputs(), &mut out)
+            .unwrap();
+        assert_eq!(summary.total, 2);
+        assert_eq!(summary.failed, 1);
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.lines().all(|line| !line.contains("Calatrava")));
+        drop(journal);
+
+        let journal = Journal::open(&path).unwrap();
+        assert_eq!(journal.len(), 3);
+        assert!(journal.contains(2, "Atlantis"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn batch_reverse_test() {
+        /// Counts its lookups, and fails at (0, 0)
+        #[derive(Default)]
+        struct Counter {
+            calls: std::sync::atomic::AtomicUsize,
+        }
+
+        impl Reverse<f64> for Counter {
+            fn reverse(&self, point: &Point<f64>) -> Result<Option<String>, GeocodingError> {
+                self.calls.fetch_add(1, Ordering::Relaxed);
+                if point.x() == 0.0 {
+                    return Err(GeocodingError::Reverse);
+                }
+                Ok(Some(format!("{:?}", point.x_y())))
+            }
+        }
+
+        let trace = vec![
+            Point::new(2.128701, 41.401392),
+            // About 5 m and 30 m away from the first point
+            Point::new(2.128745, 41.401421),
+            Point::new(2.129, 41.4015),
+            Point::new(2.2, 41.4),
+            Point::new(0.0, 0.0),
+            Point::new(0.0, 0.0),
+        ];
+        let counter = Counter::default();
+        let records: Vec<ReverseRecord<f64>> = BatchReverse::new(&counter)
+            .with_cluster_radius(25.0)
+            .build()
+            .records(trace.clone())
+            .collect();
+        let clusters: Vec<usize> = records.iter().map(|record| record.cluster).collect();
+        assert_eq!(clusters, vec![0, 0, 2, 3, 4, 5]);
+        assert_eq!(records[1].formatted, records[0].formatted);
+        assert_eq!(records[1].lon, 2.128745);
+        assert_eq!(records[1].index, 1);
+        assert_eq!(records[0].provider, "Counter");
+        assert!(records[5].error.is_some());
+        assert_eq!(counter.calls.load(Ordering::Relaxed), 5);
+
+        let counter = Counter::default();
+        let records: Vec<ReverseRecord<f64>> =
+            BatchReverse::new(&counter).build().records(trace).collect();
+        assert_eq!(records.len(), 6);
+        assert_eq!(counter.calls.load(Ordering::Relaxed), 6);
+    }
+}
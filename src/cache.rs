@@ -0,0 +1,529 @@
+//! In-memory caching of provider results.
+//!
+//! [`Cached`](struct.Cached.html) wraps a provider and remembers the results of its
+//! `Forward`, `ForwardLabels` and `Reverse` lookups, so that repeated queries don't hit the
+//! network or count against a quota. Failed lookups aren't cached.
+//!
+//! Forward queries are keyed by their trimmed text. Reverse queries are keyed by their point,
+//! which can be snapped with a [`ReverseCacheKey`](enum.ReverseCacheKey.html) so that
+//! nearby points share a cache entry: GPS fixes of a stopped vehicle jitter by a few meters,
+//! and would otherwise each miss the cache. Rounding to 4 decimals groups points within about
+//! 10 m, and the `h3` feature allows snapping to H3 cells, whose size doesn't depend on
+//! the latitude.
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::cache::{Cached, ReverseCacheKey};
+//! use geocoding::{Openstreetmap, Point, Reverse};
+//!
+//! let osm = Cached::new(Openstreetmap::new())
+//!     .with_reverse_key(ReverseCacheKey::Decimals(4))
+//!     .with_capacity(10_000);
+//! let first = osm.reverse(&Point::new(2.128701, 41.401392)).unwrap();
+//! // Served from the cache
+//! let second = osm.reverse(&Point::new(2.128698, 41.401388)).unwrap();
+//! assert_eq!(first, second);
+//! assert_eq!(osm.stats().hits, 1);
+//! ```
+//!
+//! ### Warming
+//!
+//! [`Cached::warm`](struct.Cached.html#method.warm) reverse-geocodes a list of points ahead
+//! of time, e.g. the depots and stops of a fleet or a [`grid`](fn.grid.html) over its area,
+//! at a controlled rate, so that interactive lookups during the day are served from the cache:
+//!
+//! ```no_run
+//! use geocoding::cache::{grid, Cached, ReverseCacheKey};
+//! use geocoding::{InputBounds, Openstreetmap};
+//! use std::time::Duration;
+//!
+//! let osm = Cached::new(Openstreetmap::new()).with_reverse_key(ReverseCacheKey::Decimals(3));
+//! let area = InputBounds::new((2.10, 41.37), (2.20, 41.42));
+//! let summary = osm.warm(grid(&area, 0.001), Duration::from_secs(1));
+//! println!("{} points fetched, {} failed", summary.fetched, summary.failed);
+//! ```
+use crate::{
+    format_coordinate, Forward, ForwardLabels, GeocodingError, InputBounds, LanguageTag, Point,
+    Reverse,
+};
+use num_traits::Float;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How the points of reverse queries are turned into cache keys
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReverseCacheKey {
+    /// Only identical points share a cache entry
+    #[default]
+    Exact,
+    /// Points equal once rounded to this many decimals share a cache entry
+    Decimals(u8),
+    /// Points with the same geohash of this length share a cache entry
+    Geohash(usize),
+    /// Points in the same H3 cell at this resolution share a cache entry
+    #[cfg(feature = "h3")]
+    H3Cell(u8),
+}
+
+impl ReverseCacheKey {
+    /// The cache key of `point`
+    ///
+    /// Points which can't be snapped, e.g. out of range ones, are keyed exactly.
+    pub fn key<T>(&self, point: &Point<T>) -> String
+    where
+        T: Float + Debug,
+    {
+        let snapped = match *self {
+            ReverseCacheKey::Exact => None,
+            ReverseCacheKey::Decimals(decimals) => Some(format!(
+                "{},{}",
+                format_coordinate(point.x(), Some(decimals)),
+                format_coordinate(point.y(), Some(decimals))
+            )),
+            ReverseCacheKey::Geohash(len) => crate::geohash::encode(point, len),
+            #[cfg(feature = "h3")]
+            ReverseCacheKey::H3Cell(resolution) => {
+                crate::h3::cell(point, resolution).map(|cell| format!("{:x}", cell))
+            }
+        };
+        snapped.unwrap_or_else(|| {
+            format!(
+                "{},{}",
+                format_coordinate(point.x(), None),
+                format_coordinate(point.y(), None)
+            )
+        })
+    }
+}
+
+/// The number of lookups served from, and missing, the cache
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// The number of points processed by [`Cached::warm`](struct.Cached.html#method.warm)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WarmSummary {
+    pub total: usize,
+    /// Points whose cache key was already cached, which weren't looked up
+    pub cached: usize,
+    /// Points looked up and cached
+    pub fetched: usize,
+    /// Points whose lookup failed
+    pub failed: usize,
+}
+
+/// The points of a grid covering `bounds`, every `step` degrees from its south-west corner
+///
+/// Rows go from south to north and points from west to east. A step matching the
+/// [`ReverseCacheKey`](enum.ReverseCacheKey.html) of the cache, e.g. `0.001` for 3 decimals,
+/// warms one entry per point. A step which isn't positive yields no points.
+pub fn grid<T>(bounds: &InputBounds<T>, step: T) -> Vec<Point<T>>
+where
+    T: Float + Debug,
+{
+    let (min, max) = (bounds.minimum_lonlat, bounds.maximum_lonlat);
+    if step.is_nan() || step <= T::zero() {
+        return vec![];
+    }
+    let count = |from: T, to: T| ((to - from) / step).floor().to_usize().map(|n| n + 1);
+    let (columns, rows) = match (count(min.x(), max.x()), count(min.y(), max.y())) {
+        (Some(columns), Some(rows)) => (columns, rows),
+        _ => return vec![],
+    };
+    (0..rows)
+        .flat_map(|row| {
+            (0..columns).map(move |column| {
+                Point::new(
+                    min.x() + step * T::from(column).unwrap(),
+                    min.y() + step * T::from(row).unwrap(),
+                )
+            })
+        })
+        .collect()
+}
+
+/// A map evicting its oldest entries beyond a capacity
+struct Store<V> {
+    entries: HashMap<String, V>,
+    order: VecDeque<String>,
+}
+
+impl<V> Store<V>
+where
+    V: Clone,
+{
+    fn new() -> Self {
+        Store {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<V> {
+        self.entries.get(key).cloned()
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    fn insert(&mut self, key: String, value: V, capacity: Option<usize>) {
+        if self.entries.insert(key.clone(), value).is_none() {
+            self.order.push_back(key);
+        }
+        if let Some(capacity) = capacity {
+            while self.entries.len() > capacity {
+                match self.order.pop_front() {
+                    Some(oldest) => self.entries.remove(&oldest),
+                    None => break,
+                };
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+/// A provider whose results are cached in memory
+pub struct Cached<P> {
+    provider: P,
+    reverse_key: ReverseCacheKey,
+    capacity: Option<usize>,
+    forward: Mutex<Store<Vec<(f64, f64)>>>,
+    labels: Mutex<Store<Vec<(f64, f64, String)>>>,
+    reverse: Mutex<Store<Option<String>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<P> Cached<P> {
+    /// Cache the results of `provider`, without a capacity limit, keying reverse queries
+    /// by their exact point
+    pub fn new(provider: P) -> Self {
+        Cached {
+            provider,
+            reverse_key: ReverseCacheKey::default(),
+            capacity: None,
+            forward: Mutex::new(Store::new()),
+            labels: Mutex::new(Store::new()),
+            reverse: Mutex::new(Store::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Set how the points of reverse queries are snapped to cache keys
+    pub fn with_reverse_key(mut self, reverse_key: ReverseCacheKey) -> Self {
+        self.reverse_key = reverse_key;
+        self
+    }
+
+    /// Keep at most `capacity` entries of each kind of lookup, evicting the oldest ones
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// The wrapped provider
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    /// The number of lookups served from, and missing, the cache so far
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Remove all the cached results
+    pub fn clear(&self) {
+        lock(&self.forward).clear();
+        lock(&self.labels).clear();
+        lock(&self.reverse).clear();
+    }
+
+    /// Fill the cache with the reverse lookups of `points`, waiting at least `interval`
+    /// between lookups
+    ///
+    /// Points whose cache key is already cached, including earlier points snapped to the
+    /// same key, aren't looked up. Failed lookups are counted and skipped. The lookups don't
+    /// count in the [`stats`](#method.stats), and the throttle of the provider, if any, still
+    /// applies.
+    pub fn warm<T, I>(&self, points: I, interval: Duration) -> WarmSummary
+    where
+        T: Float + Debug,
+        P: Reverse<T>,
+        I: IntoIterator<Item = Point<T>>,
+    {
+        let mut summary = WarmSummary::default();
+        let mut last: Option<Instant> = None;
+        for point in points {
+            summary.total += 1;
+            let key = self.reverse_key.key(&point);
+            if lock(&self.reverse).contains(&key) {
+                summary.cached += 1;
+                continue;
+            }
+            if let Some(last) = last {
+                thread::sleep(interval.saturating_sub(last.elapsed()));
+            }
+            last = Some(Instant::now());
+            match self.provider.reverse(&point) {
+                Ok(value) => {
+                    lock(&self.reverse).insert(key, value, self.capacity);
+                    summary.fetched += 1;
+                }
+                Err(_) => summary.failed += 1,
+            }
+        }
+        summary
+    }
+
+    /// Look `key` up in `store`, or compute and cache its value with `lookup`
+    fn cached<V, F>(
+        &self,
+        store: &Mutex<Store<V>>,
+        key: String,
+        lookup: F,
+    ) -> Result<V, GeocodingError>
+    where
+        V: Clone,
+        F: FnOnce() -> Result<V, GeocodingError>,
+    {
+        if let Some(value) = lock(store).get(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(value);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        // The store isn't locked during the lookup, so that concurrent lookups don't wait
+        let value = lookup()?;
+        lock(store).insert(key, value.clone(), self.capacity);
+        Ok(value)
+    }
+}
+
+fn lock<V>(store: &Mutex<Store<V>>) -> std::sync::MutexGuard<'_, Store<V>> {
+    store.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn to_f64<T>(point: &Point<T>) -> (f64, f64)
+where
+    T: Float + Debug,
+{
+    (
+        point.x().to_f64().unwrap_or(f64::NAN),
+        point.y().to_f64().unwrap_or(f64::NAN),
+    )
+}
+
+fn from_f64<T>(x: f64, y: f64) -> Point<T>
+where
+    T: Float + Debug,
+{
+    Point::new(
+        T::from(x).unwrap_or_else(T::nan),
+        T::from(y).unwrap_or_else(T::nan),
+    )
+}
+
+impl<T, P> Forward<T> for Cached<P>
+where
+    T: Float + Debug,
+    P: Forward<T>,
+{
+    fn forward(&self, address: &str) -> Result<Vec<Point<T>>, GeocodingError> {
+        let points = self.cached(&self.forward, address.trim().to_string(), || {
+            Ok(self.provider.forward(address)?.iter().map(to_f64).collect())
+        })?;
+        Ok(points.into_iter().map(|(x, y)| from_f64(x, y)).collect())
+    }
+}
+
+impl<T, P> ForwardLabels<T> for Cached<P>
+where
+    T: Float + Debug,
+    P: ForwardLabels<T>,
+{
+    fn forward_with_labels(
+        &self,
+        address: &str,
+    ) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        let results = self.cached(&self.labels, address.trim().to_string(), || {
+            Ok(self
+                .provider
+                .forward_with_labels(address)?
+                .into_iter()
+                .map(|(point, label)| {
+                    let (x, y) = to_f64(&point);
+                    (x, y, label)
+                })
+                .collect())
+        })?;
+        Ok(results
+            .into_iter()
+            .map(|(x, y, label)| (from_f64(x, y), label))
+            .collect())
+    }
+
+    /// Localized lookups aren't cached, as the cache is keyed by address only
+    fn forward_localized(
+        &self,
+        address: &str,
+        language: &LanguageTag,
+    ) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        self.provider.forward_localized(address, language)
+    }
+}
+
+impl<T, P> Reverse<T> for Cached<P>
+where
+    T: Float + Debug,
+    P: Reverse<T>,
+{
+    /// A reverse lookup of a point, shared by all the points with the same
+    /// [`ReverseCacheKey`](enum.ReverseCacheKey.html)
+    fn reverse(&self, point: &Point<T>) -> Result<Option<String>, GeocodingError> {
+        self.cached(&self.reverse, self.reverse_key.key(point), || {
+            self.provider.reverse(point)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Counts its lookups, and fails on "error"
+    #[derive(Default)]
+    struct Counter {
+        calls: AtomicUsize,
+    }
+
+    impl Forward<f64> for Counter {
+        fn forward(&self, address: &str) -> Result<Vec<Point<f64>>, GeocodingError> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            match address {
+                "error" => Err(GeocodingError::Forward),
+                _ => Ok(vec![Point::new(2.12870, 41.40139)]),
+            }
+        }
+    }
+
+    impl Reverse<f64> for Counter {
+        fn reverse(&self, point: &Point<f64>) -> Result<Option<String>, GeocodingError> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            Ok(Some(format!("{:?}", point.x_y())))
+        }
+    }
+
+    #[test]
+    fn reverse_cache_key_test() {
+        let a = Point::new(2.128701, 41.401392);
+        let b = Point::new(2.128698, 41.401388);
+        assert_ne!(
+            ReverseCacheKey::Exact.key(&a),
+            ReverseCacheKey::Exact.key(&b)
+        );
+        let decimals = ReverseCacheKey::Decimals(4);
+        assert_eq!(decimals.key(&a), "2.1287,41.4014");
+        assert_eq!(decimals.key(&a), decimals.key(&b));
+        assert_ne!(decimals.key(&a), decimals.key(&Point::new(2.1290, 41.4014)));
+        let geohash = ReverseCacheKey::Geohash(8);
+        assert_eq!(geohash.key(&a), geohash.key(&b));
+        // Points which can't be snapped are keyed exactly
+        assert_eq!(geohash.key(&Point::new(0.0, 91.0)), "0,91");
+    }
+
+    #[cfg(feature = "h3")]
+    #[test]
+    fn h3_cell_key_test() {
+        let key = ReverseCacheKey::H3Cell(12);
+        assert_eq!(
+            key.key(&Point::new(2.128701, 41.401392)),
+            key.key(&Point::new(2.128698, 41.401388))
+        );
+    }
+
+    #[test]
+    fn reverse_test() {
+        let cached = Cached::new(Counter::default()).with_reverse_key(ReverseCacheKey::Decimals(4));
+        let first = cached.reverse(&Point::new(2.128701, 41.401392)).unwrap();
+        let second = cached.reverse(&Point::new(2.128698, 41.401388)).unwrap();
+        assert_eq!(first, second);
+        cached.reverse(&Point::new(2.2, 41.4)).unwrap();
+        assert_eq!(cached.provider().calls.load(Ordering::Relaxed), 2);
+        assert_eq!(cached.stats(), CacheStats { hits: 1, misses: 2 });
+    }
+
+    #[test]
+    fn forward_test() {
+        let cached = Cached::new(Counter::default()).with_capacity(2);
+        let res: Vec<Point<f64>> = cached.forward("Calatrava 68").unwrap();
+        assert_eq!(res, vec![Point::new(2.12870, 41.40139)]);
+        cached.forward(" Calatrava 68 ").unwrap();
+        assert_eq!(cached.provider().calls.load(Ordering::Relaxed), 1);
+        // Errors aren't cached
+        assert!(cached.forward("error").is_err());
+        assert!(cached.forward("error").is_err());
+        assert_eq!(cached.provider().calls.load(Ordering::Relaxed), 3);
+        // The oldest entry is evicted beyond the capacity
+        cached.forward("a").unwrap();
+        cached.forward("b").unwrap();
+        cached.forward("Calatrava 68").unwrap();
+        assert_eq!(cached.provider().calls.load(Ordering::Relaxed), 6);
+        cached.clear();
+        cached.forward("b").unwrap();
+        assert_eq!(cached.provider().calls.load(Ordering::Relaxed), 7);
+    }
+
+    #[test]
+    fn grid_test() {
+        let area = InputBounds::new((2.0, 41.0), (2.25, 41.1));
+        let points = grid(&area, 0.1);
+        assert_eq!(points.len(), 6);
+        assert_eq!(points[0], Point::new(2.0, 41.0));
+        assert_eq!(points[2], Point::new(2.2, 41.0));
+        assert_eq!(points[3], Point::new(2.0, 41.1));
+        assert!(grid(&area, 0.0).is_empty());
+        assert!(grid(&InputBounds::new((2.0, 41.0), (1.0, 41.1)), 0.1).is_empty());
+    }
+
+    #[test]
+    fn warm_test() {
+        let cached = Cached::new(Counter::default()).with_reverse_key(ReverseCacheKey::Decimals(4));
+        cached.reverse(&Point::new(2.1287, 41.4014)).unwrap();
+        let points = vec![
+            Point::new(2.128701, 41.401392),
+            Point::new(2.2, 41.4),
+            Point::new(2.200001, 41.4),
+            Point::new(f64::NAN, 41.4),
+        ];
+        let summary = cached.warm(points, Duration::from_millis(1));
+        assert_eq!(
+            summary,
+            WarmSummary {
+                total: 4,
+                cached: 2,
+                fetched: 2,
+                failed: 0,
+            }
+        );
+        assert_eq!(cached.provider().calls.load(Ordering::Relaxed), 3);
+        cached.reverse(&Point::new(2.2, 41.4)).unwrap();
+        assert_eq!(cached.stats(), CacheStats { hits: 1, misses: 1 });
+    }
+}
@@ -0,0 +1,291 @@
+//! Comparison of the results of two providers, e.g. when migrating from one to the other.
+//!
+//! [`compare`] geocodes the same inputs with a baseline and a candidate
+//! [`BatchForward`](../batch/struct.BatchForward.html) job, and [`compare_golden`] geocodes the
+//! inputs of a golden dataset, such as the records of a previous batch with the provider being
+//! replaced, with a candidate job. Both produce a [`DiffRecord`] for each input: whether each
+//! side found a result, the distance between the best results, and how similar their
+//! formatted addresses are. A [`CompareSummary`] counts the records of a comparison.
+//!
+//! Records are produced lazily, one input at a time, like the records of a batch.
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::batch::BatchForward;
+//! use geocoding::compare::{compare, CompareSummary, DiffOutcome};
+//! use geocoding::{GeoAdmin, Openstreetmap};
+//!
+//! let osm = Openstreetmap::new();
+//! let geoadmin = GeoAdmin::new();
+//! let baseline = BatchForward::new(&osm).build();
+//! let candidate = BatchForward::new(&geoadmin).build();
+//! let inputs = vec!["Bundesplatz 3, Bern".to_string()];
+//! let mut summary = CompareSummary::default();
+//! for record in compare::<f64, _, _, _>(&baseline, &candidate, inputs) {
+//!     summary.add(&record);
+//!     if record.outcome == DiffOutcome::Matched {
+//!         println!("{}: {:.0} m apart", record.input, record.distance.unwrap());
+//!     }
+//! }
+//! assert_eq!(summary.total, 1);
+//! ```
+use crate::batch::{BatchForward, BatchRecord};
+use crate::score::match_score;
+use crate::{haversine_distance, ForwardLabels, Point};
+use crate::{Deserialize, Serialize};
+use num_traits::Float;
+use std::fmt::Debug;
+
+/// Whether both sides of a comparison found a result for an input
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffOutcome {
+    /// Both sides found a result
+    Matched,
+    /// Only the baseline found a result: the candidate misses a match
+    BaselineOnly,
+    /// Only the candidate found a result
+    CandidateOnly,
+    /// Neither side found a result
+    Unmatched,
+    /// The lookup of either side failed
+    Failed,
+}
+
+/// The comparison of the best results of both sides for an input
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DiffRecord {
+    /// The position of the input in the comparison, starting at 0
+    pub index: usize,
+    pub input: String,
+    pub outcome: DiffOutcome,
+    /// The formatted address of the best result of the baseline
+    pub baseline: Option<String>,
+    /// The formatted address of the best result of the candidate
+    pub candidate: Option<String>,
+    /// The distance between the best results in meters, if both sides found one
+    pub distance: Option<f64>,
+    /// The smaller of the [match scores](../score/fn.match_score.html) of each formatted
+    /// address against the other, if both sides found a result
+    pub label_similarity: Option<f64>,
+    /// The errors of the failed lookups, prefixed by their side
+    pub error: Option<String>,
+}
+
+/// The number of inputs of a comparison by outcome, and the distances between their results
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CompareSummary {
+    pub total: usize,
+    pub matched: usize,
+    pub baseline_only: usize,
+    pub candidate_only: usize,
+    pub unmatched: usize,
+    pub failed: usize,
+    /// The mean distance between the results of matched inputs in meters
+    pub mean_distance: Option<f64>,
+    /// The largest distance between the results of matched inputs in meters
+    pub max_distance: Option<f64>,
+}
+
+impl CompareSummary {
+    /// Count a record
+    pub fn add(&mut self, record: &DiffRecord) {
+        self.total += 1;
+        match record.outcome {
+            DiffOutcome::Matched => self.matched += 1,
+            DiffOutcome::BaselineOnly => self.baseline_only += 1,
+            DiffOutcome::CandidateOnly => self.candidate_only += 1,
+            DiffOutcome::Unmatched => self.unmatched += 1,
+            DiffOutcome::Failed => self.failed += 1,
+        }
+        if let Some(distance) = record.distance {
+            // The records with a distance are the matched ones
+            let mean = self.mean_distance.unwrap_or(0.0);
+            self.mean_distance = Some(mean + (distance - mean) / self.matched as f64);
+            self.max_distance = Some(self.max_distance.unwrap_or(0.0).max(distance));
+        }
+    }
+}
+
+/// Geocode `inputs` with both `baseline` and `candidate`, comparing their best results
+///
+/// Inputs are geocoded like the records of the jobs, following their throttles, quota
+/// policies and validators, but aren't journaled.
+pub fn compare<'s, T, A, B, I>(
+    baseline: &'s BatchForward<'_, A>,
+    candidate: &'s BatchForward<'_, B>,
+    inputs: I,
+) -> impl Iterator<Item = DiffRecord> + 's
+where
+    T: Float + Debug,
+    A: ForwardLabels<T>,
+    B: ForwardLabels<T>,
+    I: IntoIterator<Item = String>,
+    I::IntoIter: 's,
+{
+    inputs.into_iter().enumerate().map(move |(index, input)| {
+        let expected = baseline.record(index, input.clone());
+        diff(&expected, &candidate.record(index, input))
+    })
+}
+
+/// Geocode the inputs of the `golden` records with `candidate`, comparing its best results
+/// with the golden ones
+///
+/// The golden records keep their index, and can be read from the output of
+/// [`write_ndjson`](../batch/struct.BatchForward.html#method.write_ndjson), or converted from
+/// the export of another system.
+pub fn compare_golden<'s, T, P, G>(
+    golden: G,
+    candidate: &'s BatchForward<'_, P>,
+) -> impl Iterator<Item = DiffRecord> + 's
+where
+    T: Float + Debug,
+    P: ForwardLabels<T>,
+    G: IntoIterator<Item = BatchRecord<T>>,
+    G::IntoIter: 's,
+{
+    golden.into_iter().map(move |expected| {
+        let actual = candidate.record(expected.index, expected.input.clone());
+        diff(&expected, &actual)
+    })
+}
+
+/// Compare the records of both sides for the same input
+pub fn diff<T>(baseline: &BatchRecord<T>, candidate: &BatchRecord<T>) -> DiffRecord
+where
+    T: Float + Debug,
+{
+    let point = |record: &BatchRecord<T>| Some(Point::new(record.lon?, record.lat?));
+    let errors: Vec<String> = [("baseline", baseline), ("candidate", candidate)]
+        .iter()
+        .filter_map(|(side, record)| Some(format!("{}: {}", side, record.error.as_ref()?)))
+        .collect();
+    let found = (point(baseline), point(candidate));
+    let outcome = match found {
+        _ if !errors.is_empty() => DiffOutcome::Failed,
+        (Some(_), Some(_)) => DiffOutcome::Matched,
+        (Some(_), None) => DiffOutcome::BaselineOnly,
+        (None, Some(_)) => DiffOutcome::CandidateOnly,
+        (None, None) => DiffOutcome::Unmatched,
+    };
+    let distance = match found {
+        (Some(a), Some(b)) if outcome == DiffOutcome::Matched => Some(haversine_distance(&a, &b)),
+        _ => None,
+    };
+    let label_similarity = match (&baseline.formatted, &candidate.formatted) {
+        (Some(a), Some(b)) if outcome == DiffOutcome::Matched => {
+            Some(match_score(a, b, None).min(match_score(b, a, None)))
+        }
+        _ => None,
+    };
+    DiffRecord {
+        index: baseline.index,
+        input: baseline.input.clone(),
+        outcome,
+        baseline: baseline.formatted.clone(),
+        candidate: candidate.formatted.clone(),
+        distance,
+        label_similarity,
+        error: (!errors.is_empty()).then(|| errors.join("; ")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::GeocodingError;
+
+    /// Finds the addresses of `0` in Barcelona, at an offset of `1` degrees of longitude
+    struct Stub(&'static [&'static str], f64);
+
+    impl ForwardLabels<f64> for Stub {
+        fn forward_with_labels(
+            &self,
+            address: &str,
+        ) -> Result<Vec<(Point<f64>, String)>, GeocodingError> {
+            if address == "error" {
+                return Err(GeocodingError::Config("unavailable".to_string()));
+            }
+            if !self.0.contains(&address) {
+                return Ok(vec![]);
+            }
+            let label = format!("{}, Barcelona", address);
+            Ok(vec![(Point::new(2.1287 + self.1, 41.40139), label)])
+        }
+    }
+
+    #[test]
+    fn compare_test() {
+        let google = Stub(&["Calatrava 68", "Balmes 1"], 0.0);
+        let opencage = Stub(&["Calatrava 68", "Muntaner 2"], 0.001);
+        let baseline = BatchForward::new(&google).build();
+        let candidate = BatchForward::new(&opencage).build();
+        let inputs = [
+            "Calatrava 68",
+            "Balmes 1",
+            "Muntaner 2",
+            "Aribau 3",
+            "error",
+        ];
+        let records: Vec<DiffRecord> = compare(
+            &baseline,
+            &candidate,
+            inputs.iter().map(|input| input.to_string()),
+        )
+        .collect();
+        let outcomes: Vec<DiffOutcome> = records.iter().map(|record| record.outcome).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                DiffOutcome::Matched,
+                DiffOutcome::BaselineOnly,
+                DiffOutcome::CandidateOnly,
+                DiffOutcome::Unmatched,
+                DiffOutcome::Failed,
+            ]
+        );
+        // A thousandth of a degree of longitude is about 83 m at this latitude
+        let distance = records[0].distance.unwrap();
+        assert!((distance - 83.5).abs() < 1.0, "{}", distance);
+        assert_eq!(records[0].label_similarity, Some(1.0));
+        assert_eq!(records[1].baseline.as_deref(), Some("Balmes 1, Barcelona"));
+        assert_eq!(records[1].distance, None);
+        assert!(records[4]
+            .error
+            .as_ref()
+            .unwrap()
+            .starts_with("baseline: Invalid provider configuration"));
+        let mut summary = CompareSummary::default();
+        records.iter().for_each(|record| summary.add(record));
+        assert_eq!(summary.total, 5);
+        assert_eq!(summary.matched, 1);
+        assert_eq!(summary.baseline_only, 1);
+        assert_eq!(summary.failed, 1);
+        assert_eq!(summary.max_distance, Some(distance));
+    }
+
+    #[test]
+    fn compare_golden_test() {
+        let golden = BatchRecord {
+            index: 7,
+            input: "Calatrava 68".to_string(),
+            lon: Some(2.1287),
+            lat: Some(41.40139),
+            formatted: Some("Carrer de Calatrava, 68, Barcelona".to_string()),
+            confidence: None,
+            provider: "Google".to_string(),
+            error: None,
+            validation: None,
+        };
+        let opencage = Stub(&["Calatrava 68"], 0.0);
+        let candidate = BatchForward::new(&opencage).build();
+        let records: Vec<DiffRecord> = compare_golden(vec![golden], &candidate).collect();
+        assert_eq!(records[0].index, 7);
+        assert_eq!(records[0].outcome, DiffOutcome::Matched);
+        assert_eq!(records[0].distance, Some(0.0));
+        // "Carrer" and "de" are missing from the candidate's address
+        assert_eq!(records[0].label_similarity, Some(0.6));
+    }
+}
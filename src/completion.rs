@@ -0,0 +1,258 @@
+//! Completion of partial addresses.
+//!
+//! Addresses entered by users or imported from other systems often lack some components,
+//! such as the postcode or the region. [`complete_address`] geocodes a partial address and
+//! fills its missing components from the address of the best match, recording the source of
+//! each component in a [`Provenance`], so that filled components can be reviewed.
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::completion::{complete_address, Source};
+//! use geocoding::{Address, Openstreetmap};
+//!
+//! let osm = Openstreetmap::new();
+//! let partial = Address {
+//!     house_number: Some("68".to_string()),
+//!     road: Some("Carrer de Calatrava".to_string()),
+//!     city: Some("Barcelona".to_string()),
+//!     ..Default::default()
+//! };
+//! let completed = complete_address::<f64, _>(&osm, partial).unwrap();
+//! assert_eq!(completed.address.postcode.as_deref(), Some("08017"));
+//! assert_eq!(completed.provenance.postcode, Source::Provider);
+//! ```
+use crate::{Address, ForwardStructured, GeocodingError};
+use num_traits::Float;
+use std::fmt::Debug;
+
+/// Where a component of a completed address comes from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Source {
+    /// The component was part of the partial address
+    Input,
+    /// The component was filled from the address of the best match
+    Provider,
+    /// Neither the partial address nor the best match have the component
+    #[default]
+    Missing,
+}
+
+/// The source of each component of a completed address
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Provenance {
+    pub house_number: Source,
+    pub road: Source,
+    pub suburb: Source,
+    pub city: Source,
+    pub postcode: Source,
+    pub state: Source,
+    pub state_code: Source,
+    pub country: Source,
+    pub country_code: Source,
+}
+
+impl Provenance {
+    /// Whether any component was filled from the best match
+    pub fn is_completed(&self) -> bool {
+        [
+            self.suburb,
+            self.city,
+            self.postcode,
+            self.state,
+            self.state_code,
+            self.country,
+            self.country_code,
+        ]
+        .contains(&Source::Provider)
+    }
+}
+
+/// An address completed by [`complete_address`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CompletedAddress {
+    /// The partial address, with its missing components filled
+    pub address: Address,
+    /// The source of each component of `address`
+    pub provenance: Provenance,
+}
+
+/// Geocode a partial address, and fill its missing components from the best match
+///
+/// The address is looked up with
+/// [`forward_structured_results`](../trait.ForwardStructured.html#method.forward_structured_results),
+/// and missing areas, postcodes and countries are filled from the address of the first
+/// result, while the components of the partial address are kept as they are. The house
+/// number and the road aren't filled, since the best match of an address without them is an
+/// area rather than a building.
+///
+/// The address is returned as it is when there is no match, when the provider doesn't return
+/// the address of its results, or when the best match is in another country than the
+/// `country_code` of the partial address.
+pub fn complete_address<T, P>(
+    provider: &P,
+    partial: Address,
+) -> Result<CompletedAddress, GeocodingError>
+where
+    T: Float + Debug,
+    P: ForwardStructured<T> + ?Sized,
+{
+    let found = provider
+        .forward_structured_results(&partial)?
+        .into_iter()
+        .next()
+        .and_then(|res| res.address);
+    let same_country = |found: &Address| match (&partial.country_code, &found.country_code) {
+        (Some(partial), Some(found)) => partial.eq_ignore_ascii_case(found),
+        _ => true,
+    };
+    let found = found.filter(same_country).unwrap_or_default();
+    Ok(merge(partial, found))
+}
+
+/// `partial`, with its missing components other than the house number and the road taken
+/// from `found`
+fn merge(partial: Address, found: Address) -> CompletedAddress {
+    let kept = |component: Option<String>| {
+        let source = match component {
+            Some(_) => Source::Input,
+            None => Source::Missing,
+        };
+        (component, source)
+    };
+    let filled = |component: Option<String>, found: Option<String>| match (component, found) {
+        (Some(component), _) => (Some(component), Source::Input),
+        (None, Some(found)) => (Some(found), Source::Provider),
+        (None, None) => (None, Source::Missing),
+    };
+    let (house_number, house_number_source) = kept(partial.house_number);
+    let (road, road_source) = kept(partial.road);
+    let (suburb, suburb_source) = filled(partial.suburb, found.suburb);
+    let (city, city_source) = filled(partial.city, found.city);
+    let (postcode, postcode_source) = filled(partial.postcode, found.postcode);
+    let (state, state_source) = filled(partial.state, found.state);
+    let (state_code, state_code_source) = filled(partial.state_code, found.state_code);
+    let (country, country_source) = filled(partial.country, found.country);
+    let (country_code, country_code_source) = filled(partial.country_code, found.country_code);
+    CompletedAddress {
+        address: Address {
+            house_number,
+            road,
+            suburb,
+            city,
+            postcode,
+            state,
+            state_code,
+            country,
+            country_code,
+        },
+        provenance: Provenance {
+            house_number: house_number_source,
+            road: road_source,
+            suburb: suburb_source,
+            city: city_source,
+            postcode: postcode_source,
+            state: state_source,
+            state_code: state_code_source,
+            country: country_source,
+            country_code: country_code_source,
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ForwardLabels, GeocodeResult, Point};
+
+    /// Finds every address in Barcelona, with the address of the street
+    struct Barcelona;
+
+    impl ForwardLabels<f64> for Barcelona {
+        fn forward_with_labels(
+            &self,
+            _: &str,
+        ) -> Result<Vec<(Point<f64>, String)>, GeocodingError> {
+            Ok(vec![(Point::new(2.1287, 41.4014), String::new())])
+        }
+    }
+
+    impl ForwardStructured<f64> for Barcelona {
+        fn forward_structured_results(
+            &self,
+            _: &Address,
+        ) -> Result<Vec<GeocodeResult<f64>>, GeocodingError> {
+            let point = Point::new(2.1287, 41.4014);
+            let mut res = GeocodeResult::new(point, "Carrer de Calatrava".to_string());
+            res.address = Some(Address {
+                road: Some("Carrer de Calatrava".to_string()),
+                city: Some("Barcelona".to_string()),
+                postcode: Some("08017".to_string()),
+                state: Some("Catalunya".to_string()),
+                country: Some("España".to_string()),
+                country_code: Some("ES".to_string()),
+                ..Default::default()
+            });
+            Ok(vec![res])
+        }
+    }
+
+    /// Finds every address at the same point, without its address
+    struct Labels;
+
+    impl ForwardLabels<f64> for Labels {
+        fn forward_with_labels(
+            &self,
+            _: &str,
+        ) -> Result<Vec<(Point<f64>, String)>, GeocodingError> {
+            Ok(vec![(Point::new(2.1287, 41.4014), "Barcelona".to_string())])
+        }
+    }
+
+    impl ForwardStructured<f64> for Labels {}
+
+    #[test]
+    fn complete_address_test() {
+        let partial = Address {
+            road: Some("Carrer de Calatrava".to_string()),
+            city: Some("BCN".to_string()),
+            ..Default::default()
+        };
+        let completed = complete_address(&Barcelona, partial).unwrap();
+        let address = completed.address;
+        assert_eq!(address.house_number, None);
+        assert_eq!(address.city.as_deref(), Some("BCN"));
+        assert_eq!(address.postcode.as_deref(), Some("08017"));
+        assert_eq!(address.country_code.as_deref(), Some("ES"));
+        let provenance = completed.provenance;
+        assert_eq!(provenance.house_number, Source::Missing);
+        assert_eq!(provenance.road, Source::Input);
+        assert_eq!(provenance.city, Source::Input);
+        assert_eq!(provenance.postcode, Source::Provider);
+        assert_eq!(provenance.state_code, Source::Missing);
+        assert!(provenance.is_completed());
+    }
+
+    #[test]
+    fn other_country_test() {
+        let partial = Address {
+            city: Some("Barcelona".to_string()),
+            country_code: Some("VE".to_string()),
+            ..Default::default()
+        };
+        let completed = complete_address(&Barcelona, partial.clone()).unwrap();
+        assert_eq!(completed.address, partial);
+        assert!(!completed.provenance.is_completed());
+    }
+
+    #[test]
+    fn without_address_test() {
+        let partial = Address {
+            city: Some("Barcelona".to_string()),
+            ..Default::default()
+        };
+        let completed = complete_address(&Labels, partial.clone()).unwrap();
+        assert_eq!(completed.address, partial);
+        assert!(!completed.provenance.is_completed());
+    }
+}
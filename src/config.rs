@@ -0,0 +1,199 @@
+//! Setup of a geocoding stack from a configuration file.
+//!
+//! A [`GeocoderConfig`] describes a provider along with its endpoint, the environment
+//! variable holding its API key, its rate limit and its cache. It can be deserialized from
+//! any format supported by serde, such as YAML or TOML, and
+//! [`build`](struct.GeocoderConfig.html#method.build) returns a ready
+//! [`Geocoder`](../trait.Geocoder.html), so that applications can wire the whole stack
+//! from their configuration:
+//!
+//! ```toml
+//! provider = "opencage"
+//! api_key_env = "OPENCAGE_API_KEY"
+//! requests_per_second = 1.0
+//!
+//! [cache]
+//! capacity = 10000
+//! reverse_decimals = 4
+//! ```
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::config::GeocoderConfig;
+//! use geocoding::Point;
+//!
+//! let config: GeocoderConfig = serde_json::from_str(
+//!     r#"{"provider": "nominatim", "requests_per_second": 1.0, "cache": {"capacity": 1000}}"#,
+//! )
+//! .unwrap();
+//! let geocoder = config.build().unwrap();
+//! let res: Vec<Point<f64>> = geocoder.forward("Schwabing, München").unwrap();
+//! ```
+use crate::cache::{Cached, ReverseCacheKey};
+use crate::geoadmin::GeoAdminBuilder;
+use crate::service::{GeocodingService, ServiceConfig, ServiceKind};
+use crate::throttle::Throttle;
+use crate::{Deserialize, Serialize};
+use crate::{Geocoder, GeocodingError, SecretString};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The configuration of a geocoding stack
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeocoderConfig {
+    /// The provider to use
+    pub provider: ServiceKind,
+    /// A custom endpoint, instead of the default endpoint of the provider
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// The environment variable holding the API key, so that keys stay out of the file
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+    /// The maximum number of requests per second to the provider's host, unlimited if unset
+    #[serde(default)]
+    pub requests_per_second: Option<f64>,
+    /// The in-memory cache of results, disabled if unset
+    #[serde(default)]
+    pub cache: Option<CacheConfig>,
+}
+
+/// The configuration of the cache of a [`GeocoderConfig`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheConfig {
+    /// The maximum number of entries of each kind of lookup, unlimited if unset
+    #[serde(default)]
+    pub capacity: Option<usize>,
+    /// The number of decimals reverse queries are rounded to, exact points if unset
+    #[serde(default)]
+    pub reverse_decimals: Option<u8>,
+}
+
+impl GeocoderConfig {
+    /// Create a configuration of `provider` with its default endpoint, no API key, rate
+    /// limit or cache
+    pub fn new(provider: ServiceKind) -> Self {
+        GeocoderConfig {
+            provider,
+            endpoint: None,
+            api_key_env: None,
+            requests_per_second: None,
+            cache: None,
+        }
+    }
+
+    /// The configuration of the provider, with the API key read from `api_key_env`
+    ///
+    /// Fails if the environment variable isn't set.
+    pub fn service_config(&self) -> Result<ServiceConfig, GeocodingError> {
+        let api_key = match &self.api_key_env {
+            Some(name) => Some(std::env::var(name).map_err(|_| {
+                GeocodingError::Config(format!("environment variable {} is not set", name))
+            })?),
+            None => None,
+        };
+        Ok(ServiceConfig {
+            provider: self.provider,
+            api_key: api_key.map(SecretString::from),
+            endpoint: self.endpoint.clone(),
+        })
+    }
+
+    /// Build the provider, throttled to the rate limit, without its cache
+    pub fn service(&self) -> Result<GeocodingService, GeocodingError> {
+        let service = GeocodingService::new(&self.service_config()?)?;
+        let rate = match self.requests_per_second {
+            Some(rate) if rate > 0.0 && rate.is_finite() => rate,
+            Some(rate) => {
+                return Err(GeocodingError::Config(format!(
+                    "invalid requests_per_second: {}",
+                    rate
+                )))
+            }
+            None => return Ok(service),
+        };
+        let throttle = Arc::new(Throttle::new(Duration::from_secs_f64(1.0 / rate)));
+        Ok(match service {
+            GeocodingService::Opencage(oc) => {
+                GeocodingService::Opencage(oc.with_throttle(throttle))
+            }
+            GeocodingService::Openstreetmap(osm) => {
+                GeocodingService::Openstreetmap(osm.with_throttle(throttle))
+            }
+            GeocodingService::GeoAdmin(_) => {
+                // GeoAdmin instances are configured by their builder
+                let mut builder = GeoAdminBuilder::new();
+                if let Some(endpoint) = &self.endpoint {
+                    builder.with_endpoint(endpoint);
+                }
+                GeocodingService::GeoAdmin(builder.with_throttle(throttle).build())
+            }
+        })
+    }
+
+    /// Build the geocoding stack: the provider, throttled to the rate limit and cached
+    pub fn build(&self) -> Result<Box<dyn Geocoder>, GeocodingError> {
+        let service = self.service()?;
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => return Ok(Box::new(service)),
+        };
+        let mut cached = Cached::new(service);
+        if let Some(capacity) = cache.capacity {
+            cached = cached.with_capacity(capacity);
+        }
+        if let Some(decimals) = cache.reverse_decimals {
+            cached = cached.with_reverse_key(ReverseCacheKey::Decimals(decimals));
+        }
+        Ok(Box::new(cached))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_server::{Response, Server};
+    use crate::{ExposeSecret, Point};
+
+    #[test]
+    fn service_config_test() {
+        let config: GeocoderConfig = serde_json::from_str(
+            r#"{"provider": "opencage", "api_key_env": "GEOCODING_CONFIG_TEST_KEY"}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            config.service_config(),
+            Err(GeocodingError::Config(_))
+        ));
+        std::env::set_var("GEOCODING_CONFIG_TEST_KEY", "0123");
+        let service = config.service_config().unwrap();
+        assert_eq!(service.api_key.unwrap().expose_secret(), "0123");
+        assert_eq!(config.service().unwrap().kind(), ServiceKind::Opencage);
+
+        let mut config = GeocoderConfig::new(ServiceKind::GeoAdmin);
+        config.requests_per_second = Some(0.0);
+        assert!(matches!(config.build(), Err(GeocodingError::Config(_))));
+    }
+
+    #[test]
+    fn build_test() {
+        let feature = r#"{"type": "Feature", "properties": {"place_id": 1, "osm_type": "node", "osm_id": 1, "display_name": "Carrer de Calatrava", "place_rank": 26, "category": "highway", "type": "residential", "importance": 0.1}, "bbox": [2.1, 41.4, 2.1, 41.4], "geometry": {"type": "Point", "coordinates": [2.1, 41.4]}}"#;
+        let server = Server::single(Response::json(format!(
+            r#"{{"type": "FeatureCollection", "licence": "ODbL", "features": [{}]}}"#,
+            feature
+        )));
+        let config: GeocoderConfig = serde_json::from_value(serde_json::json!({
+            "provider": "osm",
+            "endpoint": server.endpoint(),
+            "requests_per_second": 2.0,
+            "cache": {"capacity": 10},
+        }))
+        .unwrap();
+        let geocoder = config.build().unwrap();
+        let res = geocoder.forward("Calatrava").unwrap();
+        server.requests();
+        // Served from the cache, the server only accepting a single request
+        assert_eq!(geocoder.forward("Calatrava").unwrap(), res);
+        assert_eq!(res, vec![Point::new(2.1, 41.4)]);
+    }
+}
@@ -0,0 +1,649 @@
+//! Parsing of coordinates typed into search boxes.
+//!
+//! [`parse`](fn.parse.html) recognises decimal coordinate pairs (`"41.40139, 2.12870"`),
+//! degrees, minutes and seconds (`"41°24'5.06\"N 2°7'43.4\"E"`) and geohashes (`"u4pruydqqvj"`,
+//! or `"geohash:ezs42"`).
+//! Pairs without hemisphere letters are read in `Latitude, Longitude` order, as they are
+//! written by most people and shown by most map applications.
+//! As everywhere in `Geocoding`, the returned `Point` is in `[Longitude, Latitude]` order.
+//!
+//! [`CoordinateForward`](struct.CoordinateForward.html) wraps a `Forward` provider, and returns
+//! the parsed point instead of querying the provider when the query is a coordinate.
+//!
+//! Swapped coordinates are a common bug when points come from sources in `Latitude, Longitude`
+//! order. [`PointExt`](trait.PointExt.html) checks the order of a point, and
+//! [`StrictReverse`](struct.StrictReverse.html) wraps a reverse provider, rejecting the points
+//! which can't be `[Longitude, Latitude]` pairs instead of querying the provider.
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::coords::parse;
+//! use geocoding::Point;
+//!
+//! let p: Point<f64> = parse("41°24'5.06\"N 2°7'43.4\"E").unwrap();
+//! assert!((p.x() - 2.12872).abs() < 1e-5);
+//! assert!((p.y() - 41.40141).abs() < 1e-5);
+//! ```
+use crate::Forward;
+use crate::ForwardLabels;
+use crate::GeocodingError;
+use crate::LanguageTag;
+use crate::Point;
+use crate::{AdminArea, AdminReverse, GeocodeResult, Reverse, ReverseFull};
+use crate::{PrivacyControl, PrivacyMode};
+use num_traits::Float;
+use std::fmt::Debug;
+
+/// Parse a coordinate pair or a geohash into a `Point`
+///
+/// Returns `None` if `s` is not a coordinate, or if the coordinate is out of range.
+/// Geohashes prefixed with `geohash:` are always recognised. To avoid mistaking words and
+/// postcodes such as `E145HQ` or `C1425DKN` for geohashes, other geohashes are only
+/// recognised from [`GEOHASH_MIN_LENGTH`](constant.GEOHASH_MIN_LENGTH.html) characters on,
+/// longer than compact postcodes, and if they contain both digits and letters: use
+/// [`parse_geohash`](fn.parse_geohash.html) to accept any geohash.
+pub fn parse<T>(s: &str) -> Option<Point<T>>
+where
+    T: Float + Debug,
+{
+    let s = s.trim();
+    if let Some(geohash) = strip_prefix_ignore_case(s, GEOHASH_PREFIX) {
+        return parse_geohash(geohash.trim());
+    }
+    parse_decimal(s).or_else(|| parse_dms(s)).or_else(|| {
+        let looks_like_geohash = s.len() >= GEOHASH_MIN_LENGTH
+            && s.chars().any(|c| c.is_ascii_digit())
+            && s.chars().any(|c| c.is_ascii_alphabetic());
+        if looks_like_geohash {
+            parse_geohash(s)
+        } else {
+            None
+        }
+    })
+}
+
+/// The prefix of geohashes recognised whatever their length
+const GEOHASH_PREFIX: &str = "geohash:";
+
+/// The minimum length of geohashes recognised without a prefix
+pub const GEOHASH_MIN_LENGTH: usize = 9;
+
+/// `s` without `prefix`, compared case-insensitively
+fn strip_prefix_ignore_case<'s>(s: &'s str, prefix: &str) -> Option<&'s str> {
+    let head = s.get(..prefix.len())?;
+    if head.eq_ignore_ascii_case(prefix) {
+        Some(&s[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+/// Parse a geohash into the `Point` at the centre of its cell
+///
+/// This is the same as [`geohash::decode`](../geohash/fn.decode.html).
+pub fn parse_geohash<T>(s: &str) -> Option<Point<T>>
+where
+    T: Float + Debug,
+{
+    crate::geohash::decode(s)
+}
+
+/// Parse two decimal numbers separated by a comma and/or whitespace, in lat, lon order
+fn parse_decimal<T>(s: &str) -> Option<Point<T>>
+where
+    T: Float + Debug,
+{
+    let mut parts = s
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|part| !part.is_empty());
+    let lat: f64 = parts.next()?.parse().ok()?;
+    let lon: f64 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    to_point(lon, lat)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    // 0 for degrees, 1 for minutes, 2 for seconds
+    Unit(usize),
+    Hemisphere(char),
+    Separator,
+}
+
+/// A single coordinate in degrees, minutes and seconds
+#[derive(Debug, Default)]
+struct Angle {
+    values: Vec<(f64, usize)>,
+    hemisphere: Option<char>,
+}
+
+impl Angle {
+    fn degrees(&self) -> Option<f64> {
+        let mut degrees = 0.0;
+        for &(value, unit) in &self.values {
+            if unit > 0 && !(0.0..60.0).contains(&value) {
+                return None;
+            }
+            degrees += value.abs() / 60f64.powi(unit as i32);
+        }
+        let negative = self.values.first()?.0.is_sign_negative()
+            || matches!(self.hemisphere, Some('S') | Some('W'));
+        Some(if negative { -degrees } else { degrees })
+    }
+}
+
+/// Parse a pair of coordinates in degrees, minutes and seconds, with optional hemisphere
+/// letters before or after each coordinate
+fn parse_dms<T>(s: &str) -> Option<Point<T>>
+where
+    T: Float + Debug,
+{
+    let mut angles: Vec<Angle> = vec![Angle::default()];
+    let tokens = tokenize(s)?;
+    let mut iter = tokens.iter().peekable();
+    while let Some(token) = iter.next() {
+        let current = angles.last_mut().unwrap();
+        match *token {
+            Token::Number(value) => {
+                let last_unit = current.values.last().map(|&(_, unit)| unit);
+                let unit = match iter.peek() {
+                    Some(Token::Unit(unit)) => {
+                        iter.next();
+                        *unit
+                    }
+                    _ => last_unit.map_or(0, |unit| unit + 1),
+                };
+                if unit > 2 {
+                    return None;
+                }
+                if last_unit.is_some_and(|last| unit <= last) {
+                    angles.push(Angle::default());
+                }
+                angles.last_mut().unwrap().values.push((value, unit));
+            }
+            Token::Hemisphere(h) => {
+                if current.values.is_empty() && current.hemisphere.is_none() {
+                    // A prefix, such as N 41° 24'
+                    current.hemisphere = Some(h);
+                } else if current.hemisphere.is_none() {
+                    // A suffix, such as 41° 24' N
+                    current.hemisphere = Some(h);
+                    angles.push(Angle::default());
+                } else {
+                    angles.push(Angle {
+                        hemisphere: Some(h),
+                        ..Default::default()
+                    });
+                }
+            }
+            Token::Separator => {
+                if !current.values.is_empty() {
+                    angles.push(Angle::default());
+                }
+            }
+            Token::Unit(_) => return None,
+        }
+    }
+    angles.retain(|angle| !angle.values.is_empty() || angle.hemisphere.is_some());
+    if angles.len() != 2 {
+        return None;
+    }
+    let is_lon = |angle: &Angle| matches!(angle.hemisphere, Some('E') | Some('W'));
+    let is_lat = |angle: &Angle| matches!(angle.hemisphere, Some('N') | Some('S'));
+    let (lat, lon) = if is_lon(&angles[0]) || is_lat(&angles[1]) {
+        (&angles[1], &angles[0])
+    } else {
+        (&angles[0], &angles[1])
+    };
+    if is_lon(lat) || is_lat(lon) {
+        return None;
+    }
+    to_point(lon.degrees()?, lat.degrees()?)
+}
+
+fn tokenize(s: &str) -> Option<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            '0'..='9' | '.' | '-' | '+' => {
+                let mut number = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_ascii_digit() || c == '.' || (number.is_empty() && "-+".contains(c)) {
+                        number.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Number(number.parse().ok()?));
+                continue;
+            }
+            '°' | 'º' | 'd' | 'D' => tokens.push(Token::Unit(0)),
+            '\'' | '′' | '’' => tokens.push(Token::Unit(1)),
+            '"' | '″' | '”' => tokens.push(Token::Unit(2)),
+            'N' | 'S' | 'E' | 'W' | 'n' | 's' | 'e' | 'w' => {
+                tokens.push(Token::Hemisphere(c.to_ascii_uppercase()))
+            }
+            ',' | ';' => tokens.push(Token::Separator),
+            c if c.is_whitespace() => {}
+            _ => return None,
+        }
+        chars.next();
+    }
+    Some(tokens)
+}
+
+fn to_point<T>(lon: f64, lat: f64) -> Option<Point<T>>
+where
+    T: Float + Debug,
+{
+    if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+        return None;
+    }
+    Some(Point::new(T::from(lon)?, T::from(lat)?))
+}
+
+/// A `Forward` provider which short-circuits coordinate queries
+///
+/// Queries recognised by [`parse`](fn.parse.html) return the parsed point without a
+/// request to the wrapped provider. Other queries are passed on to the wrapped provider.
+///
+/// ```
+/// use geocoding::coords::CoordinateForward;
+/// use geocoding::{Forward, Openstreetmap, Point};
+///
+/// let osm = CoordinateForward::new(Openstreetmap::new());
+/// let res: Vec<Point<f64>> = osm.forward("41.40139, 2.12870").unwrap();
+/// assert_eq!(res, vec![Point::new(2.12870, 41.40139)]);
+/// ```
+pub struct CoordinateForward<P> {
+    provider: P,
+}
+
+impl<P> CoordinateForward<P> {
+    /// Wrap a `Forward` provider
+    pub fn new(provider: P) -> Self {
+        CoordinateForward { provider }
+    }
+
+    /// The wrapped provider
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+}
+
+impl<T, P> Forward<T> for CoordinateForward<P>
+where
+    T: Float + Debug,
+    P: Forward<T>,
+{
+    fn forward(&self, address: &str) -> Result<Vec<Point<T>>, GeocodingError> {
+        match parse(address) {
+            Some(point) => Ok(vec![point]),
+            None => self.provider.forward(address),
+        }
+    }
+}
+
+impl<T, P> ForwardLabels<T> for CoordinateForward<P>
+where
+    T: Float + Debug,
+    P: ForwardLabels<T>,
+{
+    /// Coordinate queries are labelled with the trimmed query
+    fn forward_with_labels(
+        &self,
+        address: &str,
+    ) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        match parse(address) {
+            Some(point) => Ok(vec![(point, address.trim().to_string())]),
+            None => self.provider.forward_with_labels(address),
+        }
+    }
+
+    fn forward_localized(
+        &self,
+        address: &str,
+        language: &LanguageTag,
+    ) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        match parse(address) {
+            Some(point) => Ok(vec![(point, address.trim().to_string())]),
+            None => self.provider.forward_localized(address, language),
+        }
+    }
+}
+
+impl<P> PrivacyControl for CoordinateForward<P>
+where
+    P: PrivacyControl,
+{
+    /// Set the privacy mode of the wrapped provider
+    fn with_privacy_mode(self, mode: PrivacyMode) -> Self {
+        CoordinateForward::new(self.provider.with_privacy_mode(mode))
+    }
+
+    fn privacy_mode(&self) -> PrivacyMode {
+        self.provider.privacy_mode()
+    }
+}
+
+/// Checks of the order of the coordinates of a point, which must be `[Longitude, Latitude]`
+///
+/// ```
+/// use geocoding::coords::PointExt;
+/// use geocoding::Point;
+///
+/// // Sydney, with its coordinates swapped
+/// let p = Point::new(-33.8688, 151.2093);
+/// assert!(p.looks_like_latlon());
+/// assert!(p.assert_lonlat().is_err());
+/// assert!(Point::new(151.2093, -33.8688).assert_lonlat().is_ok());
+/// ```
+pub trait PointExt {
+    /// Whether the point looks like a `[Latitude, Longitude]` pair: its y is out of the range
+    /// of latitudes, but within that of longitudes, and its x is within that of latitudes
+    ///
+    /// Points in the wrong order whose coordinates are both within ±90 can't be detected.
+    fn looks_like_latlon(&self) -> bool;
+
+    /// Check that the point can be a `[Longitude, Latitude]` pair, with its x within ±180 and
+    /// its y within ±90, failing with `GeocodingError::InvalidPoint` otherwise
+    fn assert_lonlat(&self) -> Result<(), GeocodingError>;
+}
+
+impl<T> PointExt for Point<T>
+where
+    T: Float + Debug,
+{
+    fn looks_like_latlon(&self) -> bool {
+        let (x, y) = (self.x().to_f64(), self.y().to_f64());
+        match (x, y) {
+            (Some(x), Some(y)) => {
+                (-90.0..=90.0).contains(&x)
+                    && !(-90.0..=90.0).contains(&y)
+                    && (-180.0..=180.0).contains(&y)
+            }
+            _ => false,
+        }
+    }
+
+    fn assert_lonlat(&self) -> Result<(), GeocodingError> {
+        let (x, y) = (self.x().to_f64(), self.y().to_f64());
+        let valid = match (x, y) {
+            (Some(x), Some(y)) => (-180.0..=180.0).contains(&x) && (-90.0..=90.0).contains(&y),
+            _ => false,
+        };
+        if valid {
+            return Ok(());
+        }
+        let mut message = format!(
+            "({:?}, {:?}) is not a [Longitude, Latitude] pair",
+            self.x(),
+            self.y()
+        );
+        if self.looks_like_latlon() {
+            message.push_str(", its coordinates look swapped");
+        }
+        Err(GeocodingError::InvalidPoint(message))
+    }
+}
+
+/// A reverse provider which rejects points that aren't `[Longitude, Latitude]` pairs
+///
+/// Points whose x is outside ±180 or whose y is outside ±90 fail with
+/// `GeocodingError::InvalidPoint`, explaining when the coordinates look swapped, without a
+/// request to the wrapped provider. Forward lookups are passed on to the wrapped provider.
+///
+/// ```
+/// use geocoding::coords::StrictReverse;
+/// use geocoding::{GeocodingError, Openstreetmap, Point, Reverse};
+///
+/// let osm = StrictReverse::new(Openstreetmap::new());
+/// let res = osm.reverse(&Point::new(41.40139, 2.12870 + 180.0));
+/// assert!(matches!(res, Err(GeocodingError::InvalidPoint(_))));
+/// ```
+pub struct StrictReverse<P> {
+    provider: P,
+}
+
+impl<P> StrictReverse<P> {
+    /// Wrap a reverse provider
+    pub fn new(provider: P) -> Self {
+        StrictReverse { provider }
+    }
+
+    /// The wrapped provider
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+}
+
+impl<T, P> Reverse<T> for StrictReverse<P>
+where
+    T: Float + Debug,
+    P: Reverse<T>,
+{
+    fn reverse(&self, point: &Point<T>) -> Result<Option<String>, GeocodingError> {
+        point.assert_lonlat()?;
+        self.provider.reverse(point)
+    }
+}
+
+impl<T, P> ReverseFull<T> for StrictReverse<P>
+where
+    T: Float + Debug,
+    P: ReverseFull<T>,
+{
+    fn reverse_result(&self, point: &Point<T>) -> Result<Option<GeocodeResult<T>>, GeocodingError> {
+        point.assert_lonlat()?;
+        self.provider.reverse_result(point)
+    }
+
+    fn reverse_localized(
+        &self,
+        point: &Point<T>,
+        language: &LanguageTag,
+    ) -> Result<Option<GeocodeResult<T>>, GeocodingError> {
+        point.assert_lonlat()?;
+        self.provider.reverse_localized(point, language)
+    }
+}
+
+impl<T, P> AdminReverse<T> for StrictReverse<P>
+where
+    T: Float + Debug,
+    P: AdminReverse<T>,
+{
+    fn admin_reverse(&self, point: &Point<T>) -> Result<Option<AdminArea>, GeocodingError> {
+        point.assert_lonlat()?;
+        self.provider.admin_reverse(point)
+    }
+}
+
+impl<T, P> Forward<T> for StrictReverse<P>
+where
+    T: Float + Debug,
+    P: Forward<T>,
+{
+    fn forward(&self, address: &str) -> Result<Vec<Point<T>>, GeocodingError> {
+        self.provider.forward(address)
+    }
+}
+
+impl<T, P> ForwardLabels<T> for StrictReverse<P>
+where
+    T: Float + Debug,
+    P: ForwardLabels<T>,
+{
+    fn forward_with_labels(
+        &self,
+        address: &str,
+    ) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        self.provider.forward_with_labels(address)
+    }
+
+    fn forward_localized(
+        &self,
+        address: &str,
+        language: &LanguageTag,
+    ) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        self.provider.forward_localized(address, language)
+    }
+}
+
+impl<P> PrivacyControl for StrictReverse<P>
+where
+    P: PrivacyControl,
+{
+    /// Set the privacy mode of the wrapped provider
+    fn with_privacy_mode(self, mode: PrivacyMode) -> Self {
+        StrictReverse::new(self.provider.with_privacy_mode(mode))
+    }
+
+    fn privacy_mode(&self) -> PrivacyMode {
+        self.provider.privacy_mode()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_close(p: Option<Point<f64>>, lon: f64, lat: f64) {
+        let p = p.unwrap();
+        assert!((p.x() - lon).abs() < 1e-5, "{:?}", p);
+        assert!((p.y() - lat).abs() < 1e-5, "{:?}", p);
+    }
+
+    #[test]
+    fn parse_decimal_test() {
+        assert_close(parse("41.40139, 2.12870"), 2.12870, 41.40139);
+        assert_close(parse("41.40139 2.12870"), 2.12870, 41.40139);
+        assert_close(parse(" -33.8688,151.2093 "), 151.2093, -33.8688);
+        assert_eq!(parse::<f64>("141.40139, 2.12870"), None);
+        assert_eq!(parse::<f64>("1, 2, 3"), None);
+    }
+
+    #[test]
+    fn parse_dms_test() {
+        assert_close(parse("41°24'5.06\"N 2°7'43.4\"E"), 2.12872, 41.40141);
+        assert_close(parse("41°24′5.06″N, 2°7′43.4″E"), 2.12872, 41.40141);
+        assert_close(parse("N 41° 24.0843' E 2° 7.7233'"), 2.12872, 41.40141);
+        // Longitude first
+        assert_close(parse("2°7'43.4\"E 41°24'5.06\"N"), 2.12872, 41.40141);
+        assert_close(parse("33°52'S 151°12'E"), 151.2, -33.86667);
+        assert_close(parse("41 24 5.06 N 2 7 43.4 W"), -2.12872, 41.40141);
+        assert_close(parse("41.40139N 2.12870E"), 2.12870, 41.40139);
+        assert_eq!(parse::<f64>("41°75'N 2°7'E"), None);
+        assert_eq!(parse::<f64>("41°N 2°N"), None);
+        assert_eq!(parse::<f64>("41°24'5.06\"N"), None);
+    }
+
+    #[test]
+    fn parse_geohash_test() {
+        assert_close(parse("u4pruydqqvj"), 10.40744, 57.64911);
+        assert_close(parse_geohash("ezs42"), -5.60302, 42.60498);
+        assert_close(parse_geohash("EZS42"), -5.60302, 42.60498);
+        assert_eq!(parse_geohash::<f64>("ezs4a"), None);
+        // Words and postcodes are not mistaken for geohashes
+        assert_eq!(parse::<f64>("house"), None);
+        assert_eq!(parse::<f64>("Carrer de Calatrava, 68"), None);
+        assert_eq!(parse::<f64>("E145HQ"), None);
+        assert_eq!(parse::<f64>("K1A0B1"), None);
+        assert_eq!(parse::<f64>("C1425DKN"), None);
+        // Unless prefixed
+        assert_close(parse("geohash:ezs42"), -5.60302, 42.60498);
+        assert_close(parse("GEOHASH: ezs42"), -5.60302, 42.60498);
+        assert_eq!(parse::<f64>("geohash:house"), None);
+    }
+
+    #[test]
+    fn point_ext_test() {
+        assert!(Point::new(2.12870, 41.40139).assert_lonlat().is_ok());
+        assert!(Point::new(-180.0, 90.0).assert_lonlat().is_ok());
+        assert!(!Point::new(2.12870, 41.40139).looks_like_latlon());
+        // Coordinates within ±90 can't be told apart
+        assert!(!Point::new(41.40139, 2.12870).looks_like_latlon());
+        let swapped = Point::new(-33.8688, 151.2093);
+        assert!(swapped.looks_like_latlon());
+        match swapped.assert_lonlat() {
+            Err(GeocodingError::InvalidPoint(message)) => {
+                assert!(message.ends_with("look swapped"), "{}", message)
+            }
+            res => panic!("unexpected result: {:?}", res),
+        }
+        let far = Point::new(200.0, 10.0);
+        assert!(!far.looks_like_latlon());
+        assert!(far.assert_lonlat().is_err());
+        assert!(Point::new(f64::NAN, 0.0).assert_lonlat().is_err());
+    }
+
+    struct Unreachable;
+
+    impl Forward<f64> for Unreachable {
+        fn forward(&self, _: &str) -> Result<Vec<Point<f64>>, GeocodingError> {
+            Err(GeocodingError::Forward)
+        }
+    }
+
+    impl Reverse<f64> for Unreachable {
+        fn reverse(&self, _: &Point<f64>) -> Result<Option<String>, GeocodingError> {
+            Err(GeocodingError::Reverse)
+        }
+    }
+
+    impl ForwardLabels<f64> for Unreachable {
+        fn forward_with_labels(
+            &self,
+            _: &str,
+        ) -> Result<Vec<(Point<f64>, String)>, GeocodingError> {
+            Err(GeocodingError::Forward)
+        }
+    }
+
+    #[test]
+    fn coordinate_forward_test() {
+        let cf = CoordinateForward::new(Unreachable);
+        assert_eq!(
+            cf.forward("41.40139, 2.12870").unwrap(),
+            vec![Point::new(2.12870, 41.40139)]
+        );
+        assert!(cf.forward("Carrer de Calatrava, 68").is_err());
+        assert_eq!(
+            cf.forward_with_labels(" 41.40139, 2.12870 ").unwrap(),
+            vec![(
+                Point::new(2.12870, 41.40139),
+                "41.40139, 2.12870".to_string()
+            )]
+        );
+        assert!(cf.forward_with_labels("Carrer de Calatrava, 68").is_err());
+    }
+
+    #[test]
+    fn strict_reverse_test() {
+        let strict = StrictReverse::new(Unreachable);
+        // Valid points are passed on to the provider
+        assert!(matches!(
+            strict.reverse(&Point::new(2.12870, 41.40139)),
+            Err(GeocodingError::Reverse)
+        ));
+        assert!(matches!(
+            strict.reverse(&Point::new(41.40139, 182.12870)),
+            Err(GeocodingError::InvalidPoint(_))
+        ));
+    }
+
+    #[cfg(feature = "opencage")]
+    #[test]
+    fn privacy_mode_test() {
+        let cf = CoordinateForward::new(crate::Opencage::new("0123".to_string()));
+        assert_eq!(cf.privacy_mode(), PrivacyMode::NoRecord);
+        let cf = cf.with_privacy_mode(PrivacyMode::Standard);
+        assert_eq!(cf.provider().privacy_mode(), PrivacyMode::Standard);
+    }
+}
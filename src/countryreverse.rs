@@ -0,0 +1,314 @@
+//! An offline country lookup provider.
+//!
+//! Lookups are implemented on the [`CountryReverse`](struct.CountryReverse.html) struct, which
+//! tests points against a set of country boundaries held in memory, without any network access.
+//! This makes it suitable for high-volume workloads that only need country-level attribution.
+//!
+//! The boundaries are read from a GeoJSON `FeatureCollection` of `Polygon` or `MultiPolygon`
+//! features, whose properties contain the ISO 3166-1 alpha-2 code of each country.
+//! The [Natural Earth](https://www.naturalearthdata.com/downloads/110m-cultural-vectors/)
+//! Admin 0 – Countries dataset can be used as is, and the 1:110m scale is accurate enough
+//! away from borders. Use the 1:10m scale if points close to borders matter.
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::{CountryReverse, Point, Reverse};
+//!
+//! let boundaries = r#"{
+//!   "type": "FeatureCollection",
+//!   "features": [{
+//!     "type": "Feature",
+//!     "properties": { "ISO_A2": "CH", "NAME": "Switzerland" },
+//!     "geometry": {
+//!       "type": "Polygon",
+//!       "coordinates": [[[6.0, 45.8], [10.5, 45.8], [10.5, 47.8], [6.0, 47.8], [6.0, 45.8]]]
+//!     }
+//!   }]
+//! }"#;
+//! let cr = CountryReverse::from_geojson(boundaries).unwrap();
+//! let p = Point::new(7.451352119445801, 46.92793655395508);
+//! let res = cr.reverse(&p);
+//! assert_eq!(res.unwrap(), Some("CH".to_string()));
+//! ```
+use crate::Deserialize;
+use crate::GeocodingError;
+use crate::Point;
+use crate::{AdminArea, AdminReverse, PrivacyControl, PrivacyMode, Reverse};
+use num_traits::Float;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+/// Property keys holding the ISO 3166-1 alpha-2 code, in order of preference
+const CODE_KEYS: [&str; 4] = ["ISO_A2_EH", "ISO_A2", "iso_a2", "ISO3166-1-Alpha-2"];
+/// Property keys holding the country name, in order of preference
+const NAME_KEYS: [&str; 4] = ["NAME", "ADMIN", "name", "admin"];
+
+/// An offline country lookup, backed by a set of country boundaries
+pub struct CountryReverse {
+    countries: Vec<Country>,
+}
+
+/// A country boundary, with its bounding box to speed up lookups
+struct Country {
+    code: String,
+    name: Option<String>,
+    bbox: (f64, f64, f64, f64),
+    polygons: Vec<Vec<Vec<(f64, f64)>>>,
+}
+
+impl CountryReverse {
+    /// Create a new instance from a GeoJSON `FeatureCollection` of country boundaries
+    ///
+    /// Features without an ISO 3166-1 alpha-2 code (Natural Earth uses `"-99"` for these)
+    /// or without a `Polygon` or `MultiPolygon` geometry are skipped.
+    pub fn from_geojson(geojson: &str) -> Result<Self, GeocodingError> {
+        let collection: FeatureCollection =
+            serde_json::from_str(geojson).map_err(GeocodingError::Boundaries)?;
+        let countries = collection
+            .features
+            .into_iter()
+            .filter_map(|feature| {
+                let property = |keys: &[&str], valid: fn(&str) -> bool| {
+                    keys.iter()
+                        .filter_map(|key| feature.properties.get(*key).and_then(|v| v.as_str()))
+                        .find(|value| valid(value))
+                        .map(String::from)
+                };
+                let code = property(&CODE_KEYS, |code| code.len() == 2)?;
+                let name = property(&NAME_KEYS, |_| true);
+                let polygons = match feature.geometry? {
+                    Geometry::Polygon { coordinates } => vec![coordinates],
+                    Geometry::MultiPolygon { coordinates } => coordinates,
+                    Geometry::Other => return None,
+                };
+                Some(Country::new(code.to_uppercase(), name, polygons))
+            })
+            .collect();
+        Ok(CountryReverse { countries })
+    }
+
+    /// The ISO 3166-1 alpha-2 code of the country containing a point,
+    /// or `None` if the point is not within any country
+    pub fn country_code<T>(&self, point: &Point<T>) -> Option<&str>
+    where
+        T: Float + Debug,
+    {
+        self.find(point).map(|country| country.code.as_str())
+    }
+
+    fn find<T>(&self, point: &Point<T>) -> Option<&Country>
+    where
+        T: Float + Debug,
+    {
+        let x = point.x().to_f64().unwrap();
+        let y = point.y().to_f64().unwrap();
+        self.countries.iter().find(|country| country.contains(x, y))
+    }
+}
+
+impl Country {
+    fn new(code: String, name: Option<String>, polygons: Vec<Vec<Vec<(f64, f64)>>>) -> Self {
+        let bbox = polygons
+            .iter()
+            .flat_map(|polygon| polygon.first())
+            .flatten()
+            .fold(
+                (
+                    f64::INFINITY,
+                    f64::INFINITY,
+                    f64::NEG_INFINITY,
+                    f64::NEG_INFINITY,
+                ),
+                |(min_x, min_y, max_x, max_y), &(x, y)| {
+                    (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+                },
+            );
+        Country {
+            code,
+            name,
+            bbox,
+            polygons,
+        }
+    }
+
+    fn contains(&self, x: f64, y: f64) -> bool {
+        let (min_x, min_y, max_x, max_y) = self.bbox;
+        if x < min_x || x > max_x || y < min_y || y > max_y {
+            return false;
+        }
+        // The first ring of a polygon is its exterior, the others are holes
+        self.polygons.iter().any(|polygon| {
+            let mut rings = polygon.iter();
+            rings.next().is_some_and(|exterior| {
+                ring_contains(exterior, x, y) && !rings.any(|hole| ring_contains(hole, x, y))
+            })
+        })
+    }
+}
+
+/// Even-odd ray casting test of a point against a closed ring
+fn ring_contains(ring: &[(f64, f64)], x: f64, y: f64) -> bool {
+    let mut inside = false;
+    for edge in ring.windows(2) {
+        let ((x1, y1), (x2, y2)) = (edge[0], edge[1]);
+        if (y1 > y) != (y2 > y) && x < (x2 - x1) * (y - y1) / (y2 - y1) + x1 {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+impl<T> Reverse<T> for CountryReverse
+where
+    T: Float + Debug,
+{
+    /// A lookup of the ISO 3166-1 alpha-2 code of the country containing a point.
+    ///
+    /// This method never accesses the network.
+    fn reverse(&self, point: &Point<T>) -> Result<Option<String>, GeocodingError> {
+        Ok(self.country_code(point).map(String::from))
+    }
+}
+
+impl<T> AdminReverse<T> for CountryReverse
+where
+    T: Float + Debug,
+{
+    /// A lookup of the country containing a point. Only the `country` and
+    /// `country_code` fields of the returned `AdminArea` are set.
+    ///
+    /// This method never accesses the network.
+    fn admin_reverse(&self, point: &Point<T>) -> Result<Option<AdminArea>, GeocodingError> {
+        Ok(self.find(point).map(|country| AdminArea {
+            country: country.name.clone(),
+            country_code: Some(country.code.clone()),
+            ..Default::default()
+        }))
+    }
+}
+
+impl PrivacyControl for CountryReverse {
+    /// Lookups never access the network, so the privacy mode has no effect
+    fn with_privacy_mode(self, _mode: PrivacyMode) -> Self {
+        self
+    }
+
+    /// Always `NoRecord`, as queries aren't sent to any service
+    fn privacy_mode(&self) -> PrivacyMode {
+        PrivacyMode::NoRecord
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct FeatureCollection {
+    features: Vec<Feature>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Feature {
+    #[serde(default)]
+    properties: HashMap<String, serde_json::Value>,
+    geometry: Option<Geometry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum Geometry {
+    Polygon {
+        coordinates: Vec<Vec<(f64, f64)>>,
+    },
+    MultiPolygon {
+        coordinates: Vec<Vec<Vec<(f64, f64)>>>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const BOUNDARIES: &str = r#"{
+      "type": "FeatureCollection",
+      "features": [
+        {
+          "type": "Feature",
+          "properties": { "ISO_A2": "AA", "NAME": "Holey" },
+          "geometry": {
+            "type": "Polygon",
+            "coordinates": [
+              [[0.0, 0.0], [10.0, 0.0], [10.0, 10.0], [0.0, 10.0], [0.0, 0.0]],
+              [[4.0, 4.0], [6.0, 4.0], [6.0, 6.0], [4.0, 6.0], [4.0, 4.0]]
+            ]
+          }
+        },
+        {
+          "type": "Feature",
+          "properties": { "ISO_A2_EH": "-99", "ISO_A2": "bb", "NAME": "Islands" },
+          "geometry": {
+            "type": "MultiPolygon",
+            "coordinates": [
+              [[[20.0, 0.0], [22.0, 0.0], [21.0, 2.0], [20.0, 0.0]]],
+              [[[30.0, 0.0], [32.0, 0.0], [31.0, 2.0], [30.0, 0.0]]]
+            ]
+          }
+        },
+        {
+          "type": "Feature",
+          "properties": { "ISO_A2": "-99", "NAME": "Unknown" },
+          "geometry": {
+            "type": "Polygon",
+            "coordinates": [[[40.0, 0.0], [42.0, 0.0], [42.0, 2.0], [40.0, 0.0]]]
+          }
+        },
+        {
+          "type": "Feature",
+          "properties": { "ISO_A2": "CC" },
+          "geometry": { "type": "Point", "coordinates": [50.0, 0.0] }
+        }
+      ]
+    }"#;
+
+    #[test]
+    fn from_geojson_test() {
+        let cr = CountryReverse::from_geojson(BOUNDARIES).unwrap();
+        assert_eq!(cr.countries.len(), 2);
+        assert_eq!(cr.countries[1].code, "BB");
+        assert_eq!(cr.countries[1].bbox, (20.0, 0.0, 32.0, 2.0));
+        assert!(CountryReverse::from_geojson("{}").is_err());
+    }
+
+    #[test]
+    fn country_code_test() {
+        let cr = CountryReverse::from_geojson(BOUNDARIES).unwrap();
+        assert_eq!(cr.country_code(&Point::new(1.0, 1.0)), Some("AA"));
+        // Within the hole
+        assert_eq!(cr.country_code(&Point::new(5.0, 5.0)), None);
+        assert_eq!(cr.country_code(&Point::new(31.0, 1.0)), Some("BB"));
+        // Within the bounding box, but between the islands
+        assert_eq!(cr.country_code(&Point::new(25.0, 1.0)), None);
+        assert_eq!(cr.country_code(&Point::new(41.5, 1.0)), None);
+    }
+
+    #[test]
+    fn reverse_test() {
+        let cr = CountryReverse::from_geojson(BOUNDARIES).unwrap();
+        let res = cr.reverse(&Point::new(21.0_f32, 0.5));
+        assert_eq!(res.unwrap(), Some("BB".to_string()));
+    }
+
+    #[test]
+    fn admin_reverse_test() {
+        let cr = CountryReverse::from_geojson(BOUNDARIES).unwrap();
+        let res = cr.admin_reverse(&Point::new(9.0, 1.0)).unwrap().unwrap();
+        assert_eq!(
+            res,
+            AdminArea {
+                country: Some("Holey".to_string()),
+                country_code: Some("AA".to_string()),
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! Failover between several endpoints of a provider.
+//!
+//! Self-hosted services are often run as several mirrors, e.g. Nominatim instances in
+//! different data centres. A [`Failover`] lists the endpoints of a provider: each request is
+//! sent to the first endpoint, and sent again to the next one when it fails to connect or
+//! the server answers with a `5xx` error. The response of the last endpoint is returned
+//! whatever its status.
+//!
+//! Requests are prepared against the provider's own endpoint, which is replaced by each
+//! endpoint of the failover in turn, so the endpoints must expose the same API under their
+//! URLs. The provider's endpoint usually comes first in the list.
+//!
+//! With [`with_health_selection`](struct.Failover.html#method.with_health_selection),
+//! endpoints which failed are tried last until a cooldown has elapsed, so that requests
+//! don't keep waiting for an endpoint which is down.
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::failover::Failover;
+//! use geocoding::Openstreetmap;
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! let failover = Failover::new(vec![
+//!     "https://nominatim-1.example.com/",
+//!     "https://nominatim-2.example.com/",
+//! ])
+//! .with_health_selection(Duration::from_secs(30));
+//! let osm = Openstreetmap::new_with_endpoint("https://nominatim-1.example.com/".to_string())
+//!     .with_failover(Arc::new(failover));
+//! ```
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// An ordered list of interchangeable endpoints of a provider
+///
+/// A failover can be shared between provider instances, which then share the health of
+/// the endpoints.
+#[derive(Debug)]
+pub struct Failover {
+    endpoints: Vec<String>,
+    cooldown: Option<Duration>,
+    // The time until which each endpoint that failed is tried last
+    down: Mutex<HashMap<String, Instant>>,
+}
+
+impl Failover {
+    /// Create a failover trying `endpoints` in order
+    pub fn new<I, S>(endpoints: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Failover {
+            endpoints: endpoints.into_iter().map(Into::into).collect(),
+            cooldown: None,
+            down: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Try the endpoints which failed last, until `cooldown` has elapsed or they succeed again
+    pub fn with_health_selection(mut self, cooldown: Duration) -> Self {
+        self.cooldown = Some(cooldown);
+        self
+    }
+
+    /// The endpoints, in the configured order
+    pub fn endpoints(&self) -> &[String] {
+        &self.endpoints
+    }
+
+    /// Whether `endpoint` is tried in its configured order
+    ///
+    /// This is `false` during the cooldown following a failure of the endpoint, and always
+    /// `true` without health selection.
+    pub fn is_healthy(&self, endpoint: &str) -> bool {
+        self.lock()
+            .get(endpoint)
+            .map_or(true, |until| *until <= Instant::now())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Instant>> {
+        self.down.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The endpoints in the order in which they are tried
+    ///
+    /// The healthy endpoints come first in their configured order, followed by the others
+    /// in the order in which they recover.
+    pub(crate) fn candidates(&self) -> Vec<&str> {
+        let now = Instant::now();
+        let down = self.lock();
+        let mut candidates: Vec<(Option<Instant>, &str)> = self
+            .endpoints
+            .iter()
+            .map(|endpoint| {
+                let until = down.get(endpoint).copied().filter(|until| *until > now);
+                (until, endpoint.as_str())
+            })
+            .collect();
+        // The sort is stable, keeping the configured order of the healthy endpoints
+        candidates.sort_by_key(|(until, _)| until.map_or((false, now), |until| (true, until)));
+        candidates
+            .into_iter()
+            .map(|(_, endpoint)| endpoint)
+            .collect()
+    }
+
+    /// Record whether a request to `endpoint` succeeded
+    pub(crate) fn record(&self, endpoint: &str, success: bool) {
+        let mut down = self.lock();
+        match self.cooldown {
+            Some(cooldown) if !success => {
+                down.insert(endpoint.to_string(), Instant::now() + cooldown);
+            }
+            _ => {
+                down.remove(endpoint);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[cfg(feature = "osm")]
+    use crate::openstreetmap::{OpenstreetmapParams, OpenstreetmapResponse};
+    #[cfg(feature = "osm")]
+    use crate::test_server::{Response, Server};
+    #[cfg(feature = "osm")]
+    use crate::Openstreetmap;
+    #[cfg(feature = "osm")]
+    use std::sync::Arc;
+
+    #[test]
+    fn candidates_test() {
+        let failover = Failover::new(vec!["a", "b", "c"]);
+        failover.record("a", false);
+        // Without health selection, failures don't change the order
+        assert_eq!(failover.candidates(), vec!["a", "b", "c"]);
+        assert!(failover.is_healthy("a"));
+
+        let failover =
+            Failover::new(vec!["a", "b", "c"]).with_health_selection(Duration::from_secs(60));
+        failover.record("b", false);
+        failover.record("a", false);
+        assert_eq!(failover.candidates(), vec!["c", "b", "a"]);
+        assert!(!failover.is_healthy("a"));
+        failover.record("a", true);
+        assert_eq!(failover.candidates(), vec!["a", "c", "b"]);
+
+        let failover = Failover::new(vec!["a", "b"]).with_health_selection(Duration::ZERO);
+        failover.record("a", false);
+        assert_eq!(failover.candidates(), vec!["a", "b"]);
+    }
+
+    #[cfg(feature = "osm")]
+    #[test]
+    fn with_failover_test() {
+        let feature = r#"{"type": "Feature", "properties": {"place_id": 1, "osm_type": "node", "osm_id": 1, "display_name": "Carrer de Calatrava", "place_rank": 26, "category": "highway", "type": "residential", "importance": 0.1}, "bbox": [2.1, 41.4, 2.1, 41.4], "geometry": {"type": "Point", "coordinates": [2.1, 41.4]}}"#;
+        let server = Server::single(Response::json(format!(
+            r#"{{"type": "FeatureCollection", "licence": "ODbL", "features": [{}]}}"#,
+            feature
+        )));
+        let mirror = server.url("nominatim/");
+        // Nothing listens on the first endpoint
+        let endpoint = "http://127.0.0.1:9/".to_string();
+        let failover = Arc::new(
+            Failover::new(vec![endpoint.clone(), mirror.clone()])
+                .with_health_selection(Duration::from_secs(60)),
+        );
+        let osm =
+            Openstreetmap::new_with_endpoint(endpoint.clone()).with_failover(failover.clone());
+        let params = OpenstreetmapParams::new("Calatrava").build();
+        let res: OpenstreetmapResponse<f64> = osm.forward_full(&params).unwrap();
+        let request = server.request();
+        assert!(request.starts_with("GET /nominatim/search?"));
+        assert!(request.contains("q=Calatrava"));
+        assert_eq!(res.features[0].geometry.coordinates, (2.1, 41.4));
+        assert!(!failover.is_healthy(&endpoint));
+        assert_eq!(res.meta.attempts, 2);
+        assert_eq!(res.meta.endpoint, mirror);
+        assert!(res.meta.elapsed >= res.meta.request_duration);
+    }
+}
@@ -0,0 +1,242 @@
+//! Fallback chains of geocoders.
+//!
+//! A [`FallbackGeocoder`] tries its providers in order, moving on to the next one when a
+//! provider fails or finds nothing. With a minimum [match score](../score/index.html), set
+//! with [`with_min_score`](struct.FallbackGeocoder.html#method.with_min_score), forward
+//! lookups also move on when the best result of a provider matches the query poorly. The
+//! best results found so far are kept, and returned if no provider does better than the
+//! threshold.
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::fallback::FallbackGeocoder;
+//! use geocoding::{ForwardLabels, GeoAdmin, Openstreetmap, Point};
+//!
+//! let geocoder = FallbackGeocoder::new(Box::new(GeoAdmin::new()))
+//!     .with_fallback(Box::new(Openstreetmap::new()))
+//!     .with_min_score(0.8);
+//! // GeoAdmin only covers Switzerland, so this is answered by Nominatim
+//! let res: Vec<(Point<f64>, String)> =
+//!     geocoder.forward_with_labels("Carrer de Calatrava 68, Barcelona").unwrap();
+//! ```
+use crate::score::match_score;
+use crate::{Forward, ForwardLabels, Geocoder, GeocodingError, LanguageTag, Point, Reverse};
+
+/// Points with their labels, as returned by `ForwardLabels`
+type Labelled = Vec<(Point<f64>, String)>;
+
+/// A geocoder trying its providers in order until one answers well enough
+pub struct FallbackGeocoder {
+    providers: Vec<Box<dyn Geocoder>>,
+    min_score: Option<f64>,
+}
+
+impl FallbackGeocoder {
+    /// Create a chain starting with `provider`
+    pub fn new(provider: Box<dyn Geocoder>) -> Self {
+        FallbackGeocoder {
+            providers: vec![provider],
+            min_score: None,
+        }
+    }
+
+    /// Try `provider` after the providers of the chain
+    pub fn with_fallback(mut self, provider: Box<dyn Geocoder>) -> Self {
+        self.providers.push(provider);
+        self
+    }
+
+    /// Move on to the next provider when the best result of a forward lookup has a
+    /// [`match_score`](../score/fn.match_score.html) below `min_score`
+    ///
+    /// Without a minimum score, the first provider finding any result answers.
+    pub fn with_min_score(mut self, min_score: f64) -> Self {
+        self.min_score = Some(min_score);
+        self
+    }
+
+    /// The labelled results of the first provider answering well enough, or the best ones
+    /// found if none does
+    fn labelled(
+        &self,
+        address: &str,
+        lookup: impl Fn(&dyn Geocoder) -> Result<Labelled, GeocodingError>,
+    ) -> Result<Labelled, GeocodingError> {
+        let mut best: Option<(f64, Labelled)> = None;
+        let mut error = None;
+        for provider in &self.providers {
+            let res = match lookup(provider.as_ref()) {
+                Ok(res) if !res.is_empty() => res,
+                Ok(_) => continue,
+                Err(e) => {
+                    error = Some(e);
+                    continue;
+                }
+            };
+            let min_score = match self.min_score {
+                Some(min_score) => min_score,
+                None => return Ok(res),
+            };
+            let score = res
+                .iter()
+                .map(|(_, label)| match_score(address, label, None))
+                .fold(0.0, f64::max);
+            if score >= min_score {
+                return Ok(res);
+            }
+            if best.as_ref().map_or(true, |(best, _)| score > *best) {
+                best = Some((score, res));
+            }
+        }
+        finish(best.map(|(_, res)| res), error)
+    }
+}
+
+/// The results found, or the last error if every provider failed
+fn finish<R>(res: Option<R>, error: Option<GeocodingError>) -> Result<R, GeocodingError>
+where
+    R: Default,
+{
+    match (res, error) {
+        (Some(res), _) => Ok(res),
+        (None, Some(e)) => Err(e),
+        (None, None) => Ok(R::default()),
+    }
+}
+
+impl Forward<f64> for FallbackGeocoder {
+    /// With a minimum score, results are looked up with their labels, to be scored
+    fn forward(&self, address: &str) -> Result<Vec<Point<f64>>, GeocodingError> {
+        if self.min_score.is_some() {
+            let res = self.forward_with_labels(address)?;
+            return Ok(res.into_iter().map(|(point, _)| point).collect());
+        }
+        let mut error = None;
+        for provider in &self.providers {
+            match provider.forward(address) {
+                Ok(res) if !res.is_empty() => return Ok(res),
+                Ok(_) => {}
+                Err(e) => error = Some(e),
+            }
+        }
+        finish(None, error)
+    }
+}
+
+impl ForwardLabels<f64> for FallbackGeocoder {
+    fn forward_with_labels(
+        &self,
+        address: &str,
+    ) -> Result<Vec<(Point<f64>, String)>, GeocodingError> {
+        self.labelled(address, |provider| provider.forward_with_labels(address))
+    }
+
+    fn forward_localized(
+        &self,
+        address: &str,
+        language: &LanguageTag,
+    ) -> Result<Vec<(Point<f64>, String)>, GeocodingError> {
+        self.labelled(address, |provider| {
+            provider.forward_localized(address, language)
+        })
+    }
+}
+
+impl Reverse<f64> for FallbackGeocoder {
+    /// Reverse lookups have no query to score results against: the first provider finding
+    /// a result answers
+    fn reverse(&self, point: &Point<f64>) -> Result<Option<String>, GeocodingError> {
+        let mut error = None;
+        for provider in &self.providers {
+            match provider.reverse(point) {
+                Ok(Some(res)) => return Ok(Some(res)),
+                Ok(None) => {}
+                Err(e) => error = Some(e),
+            }
+        }
+        finish(None, error)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Answers every lookup with the same label, or fails if it has none
+    struct Fixed(Option<&'static str>);
+
+    impl Forward<f64> for Fixed {
+        fn forward(&self, address: &str) -> Result<Vec<Point<f64>>, GeocodingError> {
+            let res = self.forward_with_labels(address)?;
+            Ok(res.into_iter().map(|(point, _)| point).collect())
+        }
+    }
+
+    impl ForwardLabels<f64> for Fixed {
+        fn forward_with_labels(
+            &self,
+            _: &str,
+        ) -> Result<Vec<(Point<f64>, String)>, GeocodingError> {
+            match self.0 {
+                Some(label) => Ok(vec![(Point::new(0.0, 0.0), label.to_string())]),
+                None => Err(GeocodingError::Config("unavailable".to_string())),
+            }
+        }
+    }
+
+    impl Reverse<f64> for Fixed {
+        fn reverse(&self, _: &Point<f64>) -> Result<Option<String>, GeocodingError> {
+            Ok(self.0.map(String::from))
+        }
+    }
+
+    fn label(geocoder: &FallbackGeocoder, address: &str) -> String {
+        geocoder.forward_with_labels(address).unwrap()[0].1.clone()
+    }
+
+    #[test]
+    fn fallback_test() {
+        let geocoder = FallbackGeocoder::new(Box::new(Fixed(None)))
+            .with_fallback(Box::new(Fixed(Some("Bundesplatz, Bern"))));
+        assert_eq!(
+            label(&geocoder, "Calatrava, Barcelona"),
+            "Bundesplatz, Bern"
+        );
+        assert!(FallbackGeocoder::new(Box::new(Fixed(None)))
+            .forward("Calatrava")
+            .is_err());
+        assert_eq!(
+            geocoder.reverse(&Point::new(0.0, 0.0)).unwrap().as_deref(),
+            Some("Bundesplatz, Bern")
+        );
+    }
+
+    #[test]
+    fn min_score_test() {
+        let geocoder = FallbackGeocoder::new(Box::new(Fixed(Some("Bundesplatz, Bern"))))
+            .with_fallback(Box::new(Fixed(Some("Carrer de Calatrava, Madrid"))))
+            .with_fallback(Box::new(Fixed(Some("Carrer de Calatrava, Barcelona"))));
+        // Without a minimum score, the first result answers
+        assert_eq!(
+            label(&geocoder, "Calatrava, Barcelona"),
+            "Bundesplatz, Bern"
+        );
+        let geocoder = geocoder.with_min_score(0.5);
+        assert_eq!(
+            label(&geocoder, "Calatrava, Barcelona"),
+            "Carrer de Calatrava, Madrid"
+        );
+        let geocoder = geocoder.with_min_score(1.0);
+        assert_eq!(
+            label(&geocoder, "Calatrava, Barcelona"),
+            "Carrer de Calatrava, Barcelona"
+        );
+        // The best result is kept when none is good enough
+        let geocoder = geocoder.with_min_score(1.0);
+        assert_eq!(
+            label(&geocoder, "Calatrava, Sevilla"),
+            "Carrer de Calatrava, Madrid"
+        );
+    }
+}
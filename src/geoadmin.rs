@@ -1,7 +1,9 @@
 //! The [GeoAdmin](https://api3.geo.admin.ch) provider for geocoding in Switzerland exclusively.
 //!
 //! Based on the [Search API](https://api3.geo.admin.ch/services/sdiservices.html#search)
-//! and [Identify Features API](https://api3.geo.admin.ch/services/sdiservices.html#identify-features)
+//! and [Identify Features API](https://api3.geo.admin.ch/services/sdiservices.html#identify-features).
+//! Buildings and parcels can also be found by their federal identifiers, EGID and EGRID,
+//! with the [Find API](https://api3.geo.admin.ch/services/sdiservices.html#find).
 //!
 //! While GeoAdmin API is free, please respect their fair usage policy.
 //!
@@ -15,35 +17,261 @@
 //! let res = geoadmin.forward(&address);
 //! assert_eq!(res.unwrap(), vec![Point::new(7.451352119445801, 46.92793655395508)]);
 //! ```
+use crate::audit::AuditSink;
+use crate::deserialize_string_or_int;
+use crate::failover::Failover;
+use crate::from_json_str;
+use crate::request::{RequestHooks, SendHooked};
+use crate::stats::UsageStats;
+use crate::throttle::Throttle;
+use crate::Attribution;
+use crate::ClientSettings;
 use crate::Deserialize;
+use crate::GeocodeResult;
 use crate::GeocodingError;
 use crate::InputBounds;
-use crate::Point;
-use crate::UA_STRING;
-use crate::{Client, HeaderMap, HeaderValue, USER_AGENT};
-use crate::{Forward, Reverse};
-use num_traits::{Float, Pow};
-use std::fmt::Debug;
+use crate::LanguageTag;
+use crate::ResponseMeta;
+use crate::ReverseSnapping;
+use crate::{extra_params, format_coordinate, haversine_distance};
+use crate::{Address, NearbySearch, Reverse, ReverseFull};
+use crate::{Client, HeaderMap};
+use crate::{Elevation, Forward, ForwardLabels, ForwardStructured};
+use crate::{HealthCheck, ProviderStatus};
+use crate::{Point, Rect};
+use geo_types::{
+    Coord, Geometry, GeometryCollection, LineString, MultiLineString, MultiPolygon, Polygon,
+};
+use num_traits::Float;
+use reqwest::blocking::RequestBuilder;
+use serde::Deserializer;
+use std::convert::TryFrom;
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+use std::fmt::{self, Debug};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// The spatial references supported by the API: LV03, LV95, WGS84 and Web Pseudo-Mercator
+const SUPPORTED_SR: [&str; 4] = ["21781", "2056", "4326", "3857"];
 
 /// An instance of the GeoAdmin geocoding service
 pub struct GeoAdmin {
     client: Client,
+    headers: HeaderMap,
+    endpoint: String,
+    sr: String,
+    language: String,
+    limit: u8,
+    precision: Option<u8>,
+    hooks: RequestHooks,
+}
+
+/// An instance of a builder for the GeoAdmin geocoding service
+pub struct GeoAdminBuilder {
+    client: Option<Client>,
+    headers: HeaderMap,
     endpoint: String,
     sr: String,
+    language: String,
+    limit: u8,
+    precision: Option<u8>,
+    hooks: RequestHooks,
+    client_settings: ClientSettings,
+}
+
+impl GeoAdminBuilder {
+    /// Create a new GeoAdmin builder using the default endpoint, sr and language
+    /// # Example:
+    ///
+    /// ```
+    /// use geocoding::GeoAdmin;
+    /// use geocoding::geoadmin::GeoAdminBuilder;
+    ///
+    /// let geoadmin: GeoAdmin = GeoAdminBuilder::new()
+    ///     .with_sr("2056")
+    ///     .with_language("de")
+    ///     .with_limit(5)
+    ///     .build();
+    /// ```
+    pub fn new() -> GeoAdminBuilder {
+        GeoAdminBuilder {
+            client: None,
+            headers: HeaderMap::new(),
+            endpoint: "https://api3.geo.admin.ch/rest/services/api/".to_string(),
+            sr: "4326".to_string(),
+            language: "en".to_string(),
+            limit: 1,
+            precision: None,
+            hooks: RequestHooks::default(),
+            client_settings: ClientSettings::new(),
+        }
+    }
+
+    /// Set a custom endpoint
+    ///
+    /// Endpoint should include a trailing slash (i.e. "https://api3.geo.admin.ch/rest/services/api/")
+    pub fn with_endpoint(&mut self, endpoint: &str) -> &mut Self {
+        self.endpoint = endpoint.to_owned();
+        self
+    }
+
+    /// Set a custom sr
+    ///
+    /// Supported values: 21781 (LV03), 2056 (LV95), 4326 (WGS84) and 3857 (Web Pseudo-Mercator).
+    /// Requests of an instance with another sr fail with an `InvalidConfiguration` error.
+    pub fn with_sr(&mut self, sr: &str) -> &mut Self {
+        self.sr = sr.to_owned();
+        self
+    }
+
+    /// Set the language of returned labels and attributes, "en" by default
+    ///
+    /// Supported values: de, fr, it, rm and en
+    pub fn with_language(&mut self, language: &str) -> &mut Self {
+        self.language = language.to_owned();
+        self
+    }
+
+    /// Set the maximum number of results returned by `forward`, 1 by default
+    pub fn with_limit(&mut self, limit: u8) -> &mut Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Round the coordinates sent in reverse queries to `precision` decimals
+    ///
+    /// The precision applies to coordinates in the instance's sr: e.g. 5 decimals are precise to
+    /// about a meter in WGS84, while 0 decimals are precise to a meter in LV95.
+    pub fn with_precision(&mut self, precision: u8) -> &mut Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    /// Space the requests to the endpoint's host using `throttle`, which also slows down
+    /// when the server asks to
+    ///
+    /// See the [`throttle`](../throttle/index.html) module for details.
+    pub fn with_throttle(&mut self, throttle: Arc<Throttle>) -> &mut Self {
+        self.hooks.throttle = Some(throttle);
+        self
+    }
+
+    /// Record every request sent to the endpoint in `sink`
+    ///
+    /// See the [`audit`](../audit/index.html) module for details.
+    pub fn with_audit(&mut self, sink: Arc<dyn AuditSink>) -> &mut Self {
+        self.hooks.audit = Some(sink);
+        self
+    }
+
+    /// Send the requests failing to reach the endpoint to the other endpoints of `failover`
+    ///
+    /// See the [`failover`](../failover/index.html) module for details.
+    pub fn with_failover(&mut self, failover: Arc<Failover>) -> &mut Self {
+        self.hooks.failover = Some(failover);
+        self
+    }
+
+    /// Send the requests whose encoded query is longer than `length` as POST requests, with
+    /// the query as a form, for deployments limiting the length of URLs
+    pub fn with_max_query_length(&mut self, length: usize) -> &mut Self {
+        self.hooks.max_query_length = Some(length);
+        self
+    }
+
+    /// Fail with `GeocodingError::ResponseTooLarge` when a response body is larger than
+    /// `bytes`, instead of reading it into memory
+    pub fn with_max_response_size(&mut self, bytes: usize) -> &mut Self {
+        self.hooks.max_response_size = Some(bytes);
+        self
+    }
+
+    /// Fail with `GeocodingError::TooManyResults` when a response holds more than `count`
+    /// results
+    pub fn with_max_results(&mut self, count: usize) -> &mut Self {
+        self.hooks.max_results = Some(count);
+        self
+    }
+
+    /// Accept compressed responses, `true` by default
+    ///
+    /// Only the compressions enabled by the crate's features are negotiated. This has no
+    /// effect on a custom client.
+    pub fn with_compression(&mut self, compression: bool) -> &mut Self {
+        self.client_settings.with_compression(compression);
+        self
+    }
+
+    /// Apply connection pool and compression settings to the default HTTP client
+    ///
+    /// This has no effect on a custom client.
+    pub fn with_client_settings(&mut self, settings: &ClientSettings) -> &mut Self {
+        self.client_settings = settings.clone();
+        self
+    }
+
+    /// Use a custom HTTP client instead of a default one
+    ///
+    /// The crate's `User-Agent` header is only set on the default client.
+    pub fn with_client(&mut self, client: Client) -> &mut Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Set additional headers, sent on every request
+    ///
+    /// These take precedence over the client's default headers, so they can be used
+    /// to override the `User-Agent`.
+    pub fn with_headers(&mut self, headers: HeaderMap) -> &mut Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Build and return an instance of GeoAdmin
+    pub fn build(&self) -> GeoAdmin {
+        let client = self
+            .client
+            .clone()
+            .unwrap_or_else(|| self.client_settings.client());
+        let mut hooks = self.hooks.clone();
+        hooks.set_client(&client);
+        GeoAdmin {
+            client,
+            headers: self.headers.clone(),
+            endpoint: self.endpoint.clone(),
+            sr: self.sr.clone(),
+            language: self.language.clone(),
+            limit: self.limit,
+            precision: self.precision,
+            hooks,
+        }
+    }
+}
+
+impl Default for GeoAdminBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /// An instance of a parameter builder for GeoAdmin geocoding
-pub struct GeoAdminParams<'a, T>
+///
+/// The parameters own their data, so they can be built from strings created at runtime and
+/// moved across threads or tasks.
+#[derive(Clone, Debug)]
+pub struct GeoAdminParams<T>
 where
     T: Float + Debug,
 {
-    searchtext: &'a str,
-    origins: &'a str,
-    bbox: Option<&'a InputBounds<T>>,
+    searchtext: String,
+    origins: Vec<Origin>,
+    bbox: Option<InputBounds<T>>,
     limit: Option<u8>,
+    language: Option<String>,
+    extra_params: Vec<(String, String)>,
 }
 
-impl<'a, T> GeoAdminParams<'a, T>
+impl<T> GeoAdminParams<T>
 where
     T: Float + Debug,
 {
@@ -52,53 +280,248 @@ where
     ///
     /// ```
     /// use geocoding::{GeoAdmin, InputBounds, Point};
-    /// use geocoding::geoadmin::{GeoAdminParams};
+    /// use geocoding::geoadmin::{GeoAdminParams, Origin};
     ///
     /// let bbox = InputBounds::new(
     ///     (7.4513398, 46.92792859),
     ///     (7.4513662, 46.9279467),
     /// );
-    /// let params = GeoAdminParams::new(&"Seftigenstrasse Bern")
-    ///     .with_origins("address")
-    ///     .with_bbox(&bbox)
+    /// let params = GeoAdminParams::new(format!("{} {}", "Seftigenstrasse", "Bern"))
+    ///     .with_origins(&[Origin::Address])
+    ///     .with_bbox(bbox)
     ///     .build();
     /// ```
-    pub fn new(searchtext: &'a str) -> GeoAdminParams<'a, T> {
+    pub fn new<S>(searchtext: S) -> GeoAdminParams<T>
+    where
+        S: Into<String>,
+    {
         GeoAdminParams {
-            searchtext,
-            origins: "zipcode,gg25,district,kantone,gazetteer,address,parcel",
+            searchtext: searchtext.into(),
+            origins: vec![
+                Origin::Zipcode,
+                Origin::Gg25,
+                Origin::District,
+                Origin::Kantone,
+                Origin::Gazetteer,
+                Origin::Address,
+                Origin::Parcel,
+            ],
             bbox: None,
             limit: Some(50),
+            language: None,
+            extra_params: vec![],
         }
     }
 
     /// Set the `origins` property
-    pub fn with_origins(&mut self, origins: &'a str) -> &mut Self {
-        self.origins = origins;
+    pub fn with_origins(mut self, origins: &[Origin]) -> Self {
+        self.origins = origins.to_vec();
         self
     }
 
     /// Set the `bbox` property
-    pub fn with_bbox(&mut self, bbox: &'a InputBounds<T>) -> &mut Self {
+    pub fn with_bbox(mut self, bbox: InputBounds<T>) -> Self {
         self.bbox = Some(bbox);
         self
     }
 
     /// Set the `limit` property
-    pub fn with_limit(&mut self, limit: u8) -> &mut Self {
+    pub fn with_limit(mut self, limit: u8) -> Self {
         self.limit = Some(limit);
         self
     }
 
+    /// Set the `lang` property, the language of the labels
+    pub fn with_language<S>(mut self, language: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// Pass parameters not modelled by the crate to the API, such as new options
+    ///
+    /// The parameters set by the crate take precedence over these on conflict.
+    pub fn with_extra_params(mut self, extra_params: &[(&str, &str)]) -> Self {
+        self.extra_params = extra_params
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        self
+    }
+
     /// Build and return an instance of GeoAdminParams
-    pub fn build(&self) -> GeoAdminParams<'a, T> {
-        GeoAdminParams {
-            searchtext: self.searchtext,
-            origins: self.origins,
-            bbox: self.bbox,
-            limit: self.limit,
+    pub fn build(self) -> GeoAdminParams<T> {
+        self
+    }
+}
+
+/// The origin of a GeoAdmin location search result
+///
+/// See [the documentation](https://api3.geo.admin.ch/services/sdiservices.html#search) for details.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Origin {
+    /// A building address
+    Address,
+    /// A cadastral parcel
+    Parcel,
+    /// A municipality (`gg25`)
+    Gg25,
+    /// A district
+    District,
+    /// A canton
+    Kantone,
+    /// A postal code
+    Zipcode,
+    /// A named place of the swissNAMES3D gazetteer
+    Gazetteer,
+}
+
+impl Origin {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Origin::Address => "address",
+            Origin::Parcel => "parcel",
+            Origin::Gg25 => "gg25",
+            Origin::District => "district",
+            Origin::Kantone => "kantone",
+            Origin::Zipcode => "zipcode",
+            Origin::Gazetteer => "gazetteer",
+        }
+    }
+}
+
+/// An instance of a parameter builder for GeoAdmin reverse geocoding
+///
+/// The defaults query the Register of Buildings and Dwellings
+/// (`ch.bfs.gebaeude_wohnungs_register`) layer. Like
+/// [`GeoAdminParams`](struct.GeoAdminParams.html), the parameters own their data.
+#[derive(Clone, Debug)]
+pub struct GeoAdminReverseParams<T>
+where
+    T: Float + Debug,
+{
+    layers: Vec<String>,
+    tolerance: u32,
+    map_extent: Option<InputBounds<T>>,
+    image_display: (u32, u32, u32),
+    return_geometry: bool,
+    extra_params: Vec<(String, String)>,
+}
+
+impl<T> GeoAdminReverseParams<T>
+where
+    T: Float + Debug,
+{
+    /// Create a new GeoAdmin reverse parameter builder
+    /// # Example:
+    ///
+    /// ```
+    /// use geocoding::{GeoAdmin, InputBounds, Point};
+    /// use geocoding::geoadmin::{GeoAdminReverseParams};
+    ///
+    /// let map_extent = InputBounds::new(
+    ///     (2_600_868.75, 1_197_327.0),
+    ///     (2_601_068.75, 1_197_527.0),
+    /// );
+    /// let params = GeoAdminReverseParams::new()
+    ///     .with_layers(&["ch.swisstopo.amtliches-strassenverzeichnis"])
+    ///     .with_map_extent(map_extent)
+    ///     .with_image_display(200, 200, 96)
+    ///     .with_tolerance(10)
+    ///     .build();
+    /// ```
+    pub fn new() -> GeoAdminReverseParams<T> {
+        GeoAdminReverseParams {
+            layers: vec!["ch.bfs.gebaeude_wohnungs_register".to_string()],
+            tolerance: 50,
+            map_extent: None,
+            image_display: (100, 100, 100),
+            return_geometry: false,
+            extra_params: vec![],
         }
     }
+
+    /// Set the `layers` property, the ids of the layers to identify features in
+    pub fn with_layers<S>(mut self, layers: &[S]) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.layers = layers
+            .iter()
+            .map(|layer| layer.as_ref().to_string())
+            .collect();
+        self
+    }
+
+    /// Snap the point to the nearest address, the nearest road or the containing locality
+    ///
+    /// This sets the `layers` to the Register of Buildings and Dwellings
+    /// (`ch.bfs.gebaeude_wohnungs_register`), the official street directory
+    /// (`ch.swisstopo.amtliches-strassenverzeichnis`) or the official directory of localities
+    /// (`ch.swisstopo-vd.ortschaftenverzeichnis_plz`). The features of the latter two have
+    /// other attributes than [`ReverseLocationAttributes`](struct.ReverseLocationAttributes.html).
+    pub fn with_snapping(self, snapping: ReverseSnapping) -> Self {
+        self.with_layers(&[match snapping {
+            ReverseSnapping::Address => "ch.bfs.gebaeude_wohnungs_register",
+            ReverseSnapping::Road => "ch.swisstopo.amtliches-strassenverzeichnis",
+            ReverseSnapping::Locality => "ch.swisstopo-vd.ortschaftenverzeichnis_plz",
+        }])
+    }
+
+    /// Set the `tolerance` property, the search distance in pixels around the point
+    pub fn with_tolerance(mut self, tolerance: u32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Set the `mapExtent` property, the extent of the map in the instance's sr
+    pub fn with_map_extent(mut self, map_extent: InputBounds<T>) -> Self {
+        self.map_extent = Some(map_extent);
+        self
+    }
+
+    /// Set the `imageDisplay` property: width, height and dpi of the map
+    pub fn with_image_display(mut self, width: u32, height: u32, dpi: u32) -> Self {
+        self.image_display = (width, height, dpi);
+        self
+    }
+
+    /// Set the `returnGeometry` property
+    ///
+    /// The geometries are parsed into the `geometry` of the
+    /// [`GeoAdminReverseLocation`](struct.GeoAdminReverseLocation.html)s, e.g. the footprints
+    /// of buildings or the outlines of parcels.
+    pub fn with_return_geometry(mut self, return_geometry: bool) -> Self {
+        self.return_geometry = return_geometry;
+        self
+    }
+
+    /// Pass parameters not modelled by the crate to the API, such as new options
+    ///
+    /// The parameters set by the crate take precedence over these on conflict.
+    pub fn with_extra_params(mut self, extra_params: &[(&str, &str)]) -> Self {
+        self.extra_params = extra_params
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        self
+    }
+
+    /// Build and return an instance of GeoAdminReverseParams
+    pub fn build(self) -> GeoAdminReverseParams<T> {
+        self
+    }
+}
+
+impl<T> Default for GeoAdminReverseParams<T>
+where
+    T: Float + Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GeoAdmin {
@@ -107,20 +530,70 @@ impl GeoAdmin {
         GeoAdmin::default()
     }
 
-    /// Set a custom endpoint of a GeoAdmin geocoding instance
-    ///
-    /// Endpoint should include a trailing slash (i.e. "https://api3.geo.admin.ch/rest/services/api/")
-    pub fn with_endpoint(mut self, endpoint: &str) -> Self {
-        self.endpoint = endpoint.to_owned();
-        self
+    /// Create a new [`GeoAdminBuilder`](struct.GeoAdminBuilder.html) to configure an instance
+    pub fn builder() -> GeoAdminBuilder {
+        GeoAdminBuilder::new()
+    }
+
+    /// The credit swisstopo requires for the data of geo.admin.ch
+    pub fn attribution(&self) -> Attribution {
+        Attribution::new(
+            "Source: Federal Office of Topography swisstopo",
+            None,
+            "https://www.geo.admin.ch/en/general-terms-of-use-fsdi",
+        )
     }
 
-    /// Set a custom sr of a GeoAdmin geocoding instance
+    /// The requests sent by this instance so far
     ///
-    /// Supported values: 21781 (LV03), 2056 (LV95), 4326 (WGS84) and 3857 (Web Pseudo-Mercator)
-    pub fn with_sr(mut self, sr: &str) -> Self {
-        self.sr = sr.to_owned();
-        self
+    /// See the [`stats`](../stats/index.html) module for details.
+    pub fn stats(&self) -> UsageStats {
+        self.hooks.usage.snapshot()
+    }
+
+    /// Transform a point from WGS84 to the instance's sr
+    #[cfg(all(feature = "opencage", feature = "osm"))]
+    pub(crate) fn wgs84_to_sr<T>(&self, point: &Point<T>) -> Point<T>
+    where
+        T: Float + Debug,
+    {
+        match self.sr.as_str() {
+            "2056" => wgs84_to_lv95(point),
+            "21781" => wgs84_to_lv03(point),
+            "3857" => wgs84_to_web_mercator(point),
+            _ => *point,
+        }
+    }
+
+    /// Transform a point from the instance's sr to WGS84
+    fn to_wgs84<T>(&self, point: &Point<T>) -> Point<T>
+    where
+        T: Float + Debug,
+    {
+        match self.sr.as_str() {
+            "2056" => lv95_to_wgs84(point),
+            "21781" => lv03_to_wgs84(point),
+            "3857" => web_mercator_to_wgs84(point),
+            _ => *point,
+        }
+    }
+
+    /// Check that the instance can send requests, failing with an `InvalidConfiguration` error
+    /// if the sr isn't supported by the API
+    pub(crate) fn validate(&self) -> Result<(), GeocodingError> {
+        if !SUPPORTED_SR.contains(&self.sr.as_str()) {
+            return Err(GeocodingError::InvalidConfiguration(format!(
+                "unsupported GeoAdmin sr {:?}, expected one of {}",
+                self.sr,
+                SUPPORTED_SR.join(", ")
+            )));
+        }
+        Ok(())
+    }
+
+    /// Prepare a GET request to `url`, with the configured additional headers
+    fn get(&self, url: String) -> RequestBuilder {
+        self.client.get(url).headers(self.headers.clone())
     }
 
     /// A forward-geocoding search of a location, returning a full detailed response
@@ -137,16 +610,16 @@ impl GeoAdmin {
     ///
     /// ```
     /// use geocoding::{GeoAdmin, InputBounds, Point};
-    /// use geocoding::geoadmin::{GeoAdminParams, GeoAdminForwardResponse};
+    /// use geocoding::geoadmin::{GeoAdminParams, GeoAdminForwardResponse, Origin};
     ///
     /// let geoadmin = GeoAdmin::new();
     /// let bbox = InputBounds::new(
     ///     (7.4513398, 46.92792859),
     ///     (7.4513662, 46.9279467),
     /// );
-    /// let params = GeoAdminParams::new(&"Seftigenstrasse Bern")
-    ///     .with_origins("address")
-    ///     .with_bbox(&bbox)
+    /// let params = GeoAdminParams::new("Seftigenstrasse Bern")
+    ///     .with_origins(&[Origin::Address])
+    ///     .with_bbox(bbox)
     ///     .build();
     /// let res: GeoAdminForwardResponse<f64> = geoadmin.forward_full(&params).unwrap();
     /// let result = &res.features[0];
@@ -163,26 +636,56 @@ impl GeoAdmin {
         T: Float + Debug,
         for<'de> T: Deserialize<'de>,
     {
+        self.validate()?;
+        let (resp, meta) = self
+            .search_request(params)
+            .send_with_meta(&self.hooks, &self.endpoint)?;
+        let mut res: GeoAdminForwardResponse<T> = resp.error_for_status()?.json()?;
+        res.meta = meta;
+        Ok(res)
+    }
+
+    /// The request of a location search
+    pub(crate) fn search_request<T>(&self, params: &GeoAdminParams<T>) -> RequestBuilder
+    where
+        T: Float + Debug,
+    {
+        let origins = params
+            .origins
+            .iter()
+            .map(|origin| origin.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
         // For lifetime issues
         let bbox;
         let limit;
 
         let mut query = vec![
-            ("searchText", params.searchtext),
+            ("searchText", params.searchtext.as_str()),
             ("type", "locations"),
-            ("origins", params.origins),
+            ("origins", &origins),
             ("sr", &self.sr),
             ("geometryFormat", "geojson"),
         ];
 
-        if let Some(bb) = params.bbox.cloned().as_mut() {
-            if vec!["4326", "3857"].contains(&self.sr.as_str()) {
-                *bb = InputBounds::new(
-                    wgs84_to_lv03(&bb.minimum_lonlat),
-                    wgs84_to_lv03(&bb.maximum_lonlat),
-                );
+        if let Some(mut bb) = params.bbox {
+            // The SearchServer expects the bbox in Swiss coordinates
+            match self.sr.as_str() {
+                "4326" => {
+                    bb = InputBounds::new(
+                        wgs84_to_lv03(&bb.minimum_lonlat),
+                        wgs84_to_lv03(&bb.maximum_lonlat),
+                    )
+                }
+                "3857" => {
+                    bb = InputBounds::new(
+                        wgs84_to_lv03(&web_mercator_to_wgs84(&bb.minimum_lonlat)),
+                        wgs84_to_lv03(&web_mercator_to_wgs84(&bb.maximum_lonlat)),
+                    )
+                }
+                _ => {}
             }
-            bbox = String::from(*bb);
+            bbox = String::from(bb);
             query.push(("bbox", &bbox));
         }
 
@@ -191,72 +694,632 @@ impl GeoAdmin {
             query.push(("limit", &limit));
         }
 
-        let resp = self
-            .client
-            .get(&format!("{}SearchServer", self.endpoint))
+        if let Some(language) = &params.language {
+            query.push(("lang", language));
+        }
+
+        self.get(format!("{}SearchServer", self.endpoint))
             .query(&query)
-            .send()?
-            .error_for_status()?;
-        let res: GeoAdminForwardResponse<T> = resp.json()?;
+            .query(&extra_params(&query, &params.extra_params))
+    }
+
+    /// A reverse lookup of a point, returning the full identify response
+    ///
+    /// Accepts a [`GeoAdminReverseParams`](struct.GeoAdminReverseParams.html) struct for
+    /// specifying the layers to identify features in, and the search tolerance.
+    /// The attributes of the returned features depend on the queried layers: the default
+    /// [`ReverseLocationAttributes`](struct.ReverseLocationAttributes.html) match the default
+    /// building register layer, while `serde_json::Value` can hold the attributes of any layer.
+    ///
+    /// Please see [the documentation](https://api3.geo.admin.ch/services/sdiservices.html#identify-features) for details.
+    ///
+    /// This method passes the `geometryType`, `geometryFormat`, `sr` and `lang` parameters to the API.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::{GeoAdmin, Point};
+    /// use geocoding::geoadmin::{GeoAdminReverseParams, GeoAdminReverseResponse};
+    ///
+    /// let geoadmin = GeoAdmin::new();
+    /// let p = Point::new(7.451352119445801, 46.92793655395508);
+    /// let params = GeoAdminReverseParams::new()
+    ///     .with_tolerance(10)
+    ///     .build();
+    /// let res: GeoAdminReverseResponse = geoadmin.reverse_full(&p, &params).unwrap();
+    /// let result = &res.results[0];
+    /// assert_eq!(result.properties.strname_deinr, "Seftigenstrasse 264");
+    /// ```
+    pub fn reverse_full<T, P>(
+        &self,
+        point: &Point<T>,
+        params: &GeoAdminReverseParams<T>,
+    ) -> Result<GeoAdminReverseResponse<P>, GeocodingError>
+    where
+        T: Float + Debug,
+        for<'de> P: Deserialize<'de>,
+    {
+        self.validate()?;
+        let (resp, meta) = self
+            .identify_request(point, params)
+            .send_with_meta(&self.hooks, &self.endpoint)?;
+        let mut res: GeoAdminReverseResponse<P> = resp.error_for_status()?.json()?;
+        res.meta = meta;
         Ok(res)
     }
-}
 
-impl Default for GeoAdmin {
-    fn default() -> Self {
-        let mut headers = HeaderMap::new();
-        headers.insert(USER_AGENT, HeaderValue::from_static(UA_STRING));
-        let client = Client::builder()
-            .default_headers(headers)
-            .build()
-            .expect("Couldn't build a client!");
-        GeoAdmin {
-            client,
-            endpoint: "https://api3.geo.admin.ch/rest/services/api/".to_string(),
-            sr: "4326".to_string(),
-        }
+    /// The request identifying the features at a point
+    pub(crate) fn identify_request<T>(
+        &self,
+        point: &Point<T>,
+        params: &GeoAdminReverseParams<T>,
+    ) -> RequestBuilder
+    where
+        T: Float + Debug,
+    {
+        let geometry = format!(
+            "{},{}",
+            format_coordinate(point.x(), self.precision),
+            format_coordinate(point.y(), self.precision)
+        );
+        let layers = format!("all:{}", params.layers.join(","));
+        let map_extent = match params.map_extent {
+            Some(extent) => String::from(extent),
+            None => "0,0,100,100".to_string(),
+        };
+        let (width, height, dpi) = params.image_display;
+        let image_display = format!("{},{},{}", width, height, dpi);
+        let tolerance = params.tolerance.to_string();
+        let query = [
+            ("geometry", geometry.as_str()),
+            ("geometryType", "esriGeometryPoint"),
+            ("layers", &layers),
+            ("mapExtent", &map_extent),
+            ("imageDisplay", &image_display),
+            ("tolerance", &tolerance),
+            (
+                "returnGeometry",
+                if params.return_geometry {
+                    "true"
+                } else {
+                    "false"
+                },
+            ),
+            ("geometryFormat", "geojson"),
+            ("sr", &self.sr),
+            ("lang", &self.language),
+        ];
+
+        self.get(format!("{}MapServer/identify", self.endpoint))
+            .query(&query)
+            .query(&extra_params(&query, &params.extra_params))
     }
-}
 
-impl<T> Forward<T> for GeoAdmin
-where
-    T: Float + Debug,
-    for<'de> T: Deserialize<'de>,
-{
-    /// A forward-geocoding lookup of an address. Please see [the documentation](https://api3.geo.admin.ch/services/sdiservices.html#search) for details.
+    /// Query the elevation of a point, in meters above sea level
     ///
-    /// This method passes the `type`,  `origins`, `limit` and `sr` parameter to the API.
-    fn forward(&self, place: &str) -> Result<Vec<Point<T>>, GeocodingError> {
-        let resp = self
-            .client
-            .get(&format!("{}SearchServer", self.endpoint))
-            .query(&[
-                ("searchText", place),
-                ("type", "locations"),
-                ("origins", "address"),
-                ("limit", "1"),
+    /// The height service belongs to the same API family as the search and identify
+    /// services, and is queried at `height` next to the configured `api/` endpoint.
+    ///
+    /// Please see [the documentation](https://api3.geo.admin.ch/services/sdiservices.html#height) for details.
+    ///
+    /// This method passes the `easting`, `northing`, `sr` and `elevation_model` parameters to the API.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::{GeoAdmin, Point};
+    /// use geocoding::geoadmin::ElevationModel;
+    ///
+    /// let geoadmin = GeoAdmin::new();
+    /// let p = Point::new(7.451352119445801, 46.92793655395508);
+    /// let height = geoadmin.height(&p, ElevationModel::Dtm25).unwrap();
+    /// assert!(height > 500.0 && height < 600.0);
+    /// ```
+    pub fn height<T>(&self, point: &Point<T>, model: ElevationModel) -> Result<f64, GeocodingError>
+    where
+        T: Float + Debug,
+    {
+        self.validate()?;
+        // The height service only accepts Swiss coordinates
+        let (point, sr) = match self.sr.as_str() {
+            "4326" => (wgs84_to_lv95(point), "2056"),
+            "3857" => (wgs84_to_lv95(&web_mercator_to_wgs84(point)), "2056"),
+            sr => (*point, sr),
+        };
+        let easting = format_coordinate(point.x(), None);
+        let northing = format_coordinate(point.y(), None);
+
+        let resp = self
+            .get(format!("{}height", self.endpoint.trim_end_matches("api/")))
+            .query(&[
+                ("easting", easting.as_str()),
+                ("northing", &northing),
+                ("sr", sr),
+                ("elevation_model", model.as_str()),
+            ])
+            .send_hooked(&self.hooks, &self.endpoint)?
+            .error_for_status()?;
+        let res: GeoAdminHeightResponse = resp.json()?;
+        Ok(res.height)
+    }
+
+    /// A search of map layers matching a text, such as "Lärm" or "Bauzonen"
+    ///
+    /// Please see [the documentation](https://api3.geo.admin.ch/services/sdiservices.html#search) for details.
+    ///
+    /// This method passes the `type` and `lang` parameters to the API.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::GeoAdmin;
+    ///
+    /// let geoadmin = GeoAdmin::new();
+    /// let res = geoadmin.search_layers("Gebäude- und Wohnungsregister").unwrap();
+    /// assert!(res
+    ///     .results
+    ///     .iter()
+    ///     .any(|r| r.attrs.layer == "ch.bfs.gebaeude_wohnungs_register"));
+    /// ```
+    pub fn search_layers(
+        &self,
+        searchtext: &str,
+    ) -> Result<GeoAdminLayerSearchResponse, GeocodingError> {
+        self.validate()?;
+        let resp = self
+            .get(format!("{}SearchServer", self.endpoint))
+            .query(&[
+                ("searchText", searchtext),
+                ("type", "layers"),
+                ("lang", &self.language),
+            ])
+            .send_hooked(&self.hooks, &self.endpoint)?
+            .error_for_status()?;
+        let res: GeoAdminLayerSearchResponse = resp.json()?;
+        Ok(res)
+    }
+
+    /// A search of features matching a text within the given layers, such as cadastral
+    /// parcels by their number
+    ///
+    /// Only layers flagged as searchable by GeoAdmin can be used.
+    ///
+    /// Please see [the documentation](https://api3.geo.admin.ch/services/sdiservices.html#search) for details.
+    ///
+    /// This method passes the `type`, `sr` and `lang` parameters to the API.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::GeoAdmin;
+    /// use geocoding::geoadmin::GeoAdminFeatureSearchResponse;
+    ///
+    /// let geoadmin = GeoAdmin::new();
+    /// let res: GeoAdminFeatureSearchResponse<f64> = geoadmin
+    ///     .search_features("Seftigenstrasse 264", &["ch.bfs.gebaeude_wohnungs_register"])
+    ///     .unwrap();
+    /// assert_eq!(res.results[0].attrs.layer, "ch.bfs.gebaeude_wohnungs_register");
+    /// ```
+    pub fn search_features<T>(
+        &self,
+        searchtext: &str,
+        layers: &[&str],
+    ) -> Result<GeoAdminFeatureSearchResponse<T>, GeocodingError>
+    where
+        T: Float + Debug,
+        for<'de> T: Deserialize<'de>,
+    {
+        self.validate()?;
+        let features = layers.join(",");
+        let resp = self
+            .get(format!("{}SearchServer", self.endpoint))
+            .query(&[
+                ("searchText", searchtext),
+                ("type", "featuresearch"),
+                ("features", &features),
+                ("sr", &self.sr),
+                ("lang", &self.language),
+            ])
+            .send_hooked(&self.hooks, &self.endpoint)?
+            .error_for_status()?;
+        let res: GeoAdminFeatureSearchResponse<T> = resp.json()?;
+        Ok(res)
+    }
+
+    /// Retrieve a single feature of a layer by its id
+    ///
+    /// As with [`reverse_full`](struct.GeoAdmin.html#method.reverse_full), the type of the
+    /// returned attributes depends on the layer; `serde_json::Value` can hold any of them.
+    ///
+    /// Please see [the documentation](https://api3.geo.admin.ch/services/sdiservices.html#feature-resource) for details.
+    ///
+    /// This method passes the `geometryFormat`, `sr` and `lang` parameters to the API.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::GeoAdmin;
+    /// use geocoding::geoadmin::GeoAdminFeatureResponse;
+    ///
+    /// let geoadmin = GeoAdmin::new();
+    /// let res: GeoAdminFeatureResponse =
+    ///     geoadmin.feature("ch.bfs.gebaeude_wohnungs_register", "1272199_0").unwrap();
+    /// assert_eq!(res.feature.properties.strname_deinr, "Seftigenstrasse 264");
+    /// ```
+    pub fn feature<P>(
+        &self,
+        layer: &str,
+        id: &str,
+    ) -> Result<GeoAdminFeatureResponse<P>, GeocodingError>
+    where
+        for<'de> P: Deserialize<'de>,
+    {
+        self.validate()?;
+        let resp = self
+            .get(format!("{}MapServer/{}/{}", self.endpoint, layer, id))
+            .query(&[
+                ("geometryFormat", "geojson"),
                 ("sr", &self.sr),
+                ("lang", &self.language),
+            ])
+            .send_hooked(&self.hooks, &self.endpoint)?
+            .error_for_status()?;
+        let res: GeoAdminFeatureResponse<P> = resp.json()?;
+        Ok(res)
+    }
+
+    /// Find the features of a layer whose `field` attribute is exactly `value`
+    ///
+    /// As with [`reverse_full`](struct.GeoAdmin.html#method.reverse_full), the type of the
+    /// returned attributes depends on the layer; `serde_json::Value` can hold any of them.
+    /// The geometries of the features are returned.
+    ///
+    /// Please see [the documentation](https://api3.geo.admin.ch/services/sdiservices.html#find) for details.
+    ///
+    /// This method passes the `contains`, `returnGeometry`, `geometryFormat`, `sr` and `lang`
+    /// parameters to the API.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::GeoAdmin;
+    /// use geocoding::geoadmin::GeoAdminReverseResponse;
+    ///
+    /// let geoadmin = GeoAdmin::new();
+    /// let res: GeoAdminReverseResponse =
+    ///     geoadmin.find("ch.bfs.gebaeude_wohnungs_register", "egid", "1272199").unwrap();
+    /// assert_eq!(res.results[0].properties.strname_deinr, "Seftigenstrasse 264");
+    /// ```
+    pub fn find<P>(
+        &self,
+        layer: &str,
+        field: &str,
+        value: &str,
+    ) -> Result<GeoAdminReverseResponse<P>, GeocodingError>
+    where
+        for<'de> P: Deserialize<'de>,
+    {
+        self.validate()?;
+        let (resp, meta) = self
+            .get(format!("{}MapServer/find", self.endpoint))
+            .query(&[
+                ("layer", layer),
+                ("searchField", field),
+                ("searchText", value),
+                ("contains", "false"),
+                ("returnGeometry", "true"),
                 ("geometryFormat", "geojson"),
+                ("sr", &self.sr),
+                ("lang", &self.language),
             ])
-            .send()?
+            .send_with_meta(&self.hooks, &self.endpoint)?;
+        let mut res: GeoAdminReverseResponse<P> = resp.error_for_status()?.json()?;
+        res.meta = meta;
+        Ok(res)
+    }
+
+    /// Find a building by its federal building identifier
+    ///
+    /// Buildings are found in the Register of Buildings and Dwellings
+    /// (`ch.bfs.gebaeude_wohnungs_register`), which has a feature per entrance: the results
+    /// of a building with several entrances differ by their `edid` and address.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::GeoAdmin;
+    ///
+    /// let geoadmin = GeoAdmin::new();
+    /// let entrances = geoadmin.find_building("1272199".parse().unwrap()).unwrap();
+    /// assert_eq!(entrances[0].properties.strname_deinr, "Seftigenstrasse 264");
+    /// ```
+    pub fn find_building(
+        &self,
+        egid: Egid,
+    ) -> Result<Vec<GeoAdminReverseLocation>, GeocodingError> {
+        let res: GeoAdminReverseResponse = self.find(BUILDINGS_LAYER, "egid", &egid.to_string())?;
+        Ok(res.results)
+    }
+
+    /// Find a parcel by its federal real estate identifier
+    ///
+    /// Parcels are found in the cadastral web map (`ch.kantone.cadastralwebmap-farbe`), and
+    /// returned with their outline as `geometry`.
+    pub fn find_parcel(
+        &self,
+        egrid: &Egrid,
+    ) -> Result<Option<GeoAdminReverseLocation<ParcelAttributes>>, GeocodingError> {
+        let res: GeoAdminReverseResponse<ParcelAttributes> =
+            self.find(PARCELS_LAYER, "egris_egrid", egrid.as_str())?;
+        Ok(res.results.into_iter().next())
+    }
+}
+
+/// The layer of the Register of Buildings and Dwellings
+const BUILDINGS_LAYER: &str = "ch.bfs.gebaeude_wohnungs_register";
+
+/// The layer of the cadastral web map, holding the parcels
+const PARCELS_LAYER: &str = "ch.kantone.cadastralwebmap-farbe";
+
+/// A federal building identifier (EGID), a positive number of up to 9 digits
+///
+/// # Examples
+///
+/// ```
+/// use geocoding::geoadmin::Egid;
+///
+/// let egid: Egid = "1272199".parse().unwrap();
+/// assert_eq!(egid.id(), 1_272_199);
+/// assert!("0".parse::<Egid>().is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Egid(u32);
+
+impl Egid {
+    /// The maximum EGID
+    const MAX: u32 = 999_999_999;
+
+    /// Create an EGID from its number, if it is valid
+    pub fn new(id: u32) -> Option<Self> {
+        (1..=Egid::MAX).contains(&id).then_some(Egid(id))
+    }
+
+    /// The number of the EGID
+    pub fn id(&self) -> u32 {
+        self.0
+    }
+}
+
+impl fmt::Display for Egid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for Egid {
+    type Err = GeocodingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim()
+            .parse()
+            .ok()
+            .and_then(Egid::new)
+            .ok_or_else(|| GeocodingError::InvalidEgid(s.to_string()))
+    }
+}
+
+/// A federal real estate identifier (EGRID), `CH` followed by 12 digits or letters
+///
+/// EGRIDs are parsed case-insensitively, and kept in upper case.
+///
+/// # Examples
+///
+/// ```
+/// use geocoding::geoadmin::Egrid;
+///
+/// let egrid: Egrid = "ch607465170666".parse().unwrap();
+/// assert_eq!(egrid.to_string(), "CH607465170666");
+/// assert!("CH6074651706".parse::<Egrid>().is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Egrid(String);
+
+impl Egrid {
+    /// The EGRID, in upper case
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Egrid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Egrid {
+    type Err = GeocodingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let egrid = s.trim().to_ascii_uppercase();
+        let valid = egrid.len() == 14
+            && egrid.starts_with("CH")
+            && egrid.bytes().all(|byte| byte.is_ascii_alphanumeric());
+        match valid {
+            true => Ok(Egrid(egrid)),
+            false => Err(GeocodingError::InvalidEgrid(s.to_string())),
+        }
+    }
+}
+
+/// A digital elevation model of the GeoAdmin height service
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ElevationModel {
+    /// The swissALTI3D terrain model resampled to a 25 m grid (DHM25 outside Switzerland)
+    #[default]
+    Dtm25,
+    /// The swissALTI3D terrain model at a 2 m resolution
+    Dtm2,
+    /// A combination of `Dtm2` within Switzerland and `Dtm25` elsewhere
+    Comb,
+}
+
+impl ElevationModel {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ElevationModel::Dtm25 => "DTM25",
+            ElevationModel::Dtm2 => "DTM2",
+            ElevationModel::Comb => "COMB",
+        }
+    }
+}
+
+impl Default for GeoAdmin {
+    fn default() -> Self {
+        GeoAdminBuilder::new().build()
+    }
+}
+
+impl GeoAdmin {
+    /// The request shared by `forward`, `forward_with_labels` and `forward_localized`, in
+    /// `language` if any
+    fn forward_lookup<T>(
+        &self,
+        place: &str,
+        language: Option<&str>,
+    ) -> Result<GeoAdminForwardResponse<T>, GeocodingError>
+    where
+        T: Float + Debug,
+        for<'de> T: Deserialize<'de>,
+    {
+        self.validate()?;
+        let limit = self.limit.to_string();
+        let mut query = vec![
+            ("searchText", place),
+            ("type", "locations"),
+            ("origins", "address"),
+            ("limit", &limit),
+            ("sr", &self.sr),
+            ("geometryFormat", "geojson"),
+        ];
+        if let Some(language) = language {
+            query.push(("lang", language));
+        }
+        let resp = self
+            .get(format!("{}SearchServer", self.endpoint))
+            .query(&query)
+            .send_hooked(&self.hooks, &self.endpoint)?
             .error_for_status()?;
         let res: GeoAdminForwardResponse<T> = resp.json()?;
-        // return easting & northing consistent
-        let results = if vec!["2056", "21781"].contains(&self.sr.as_str()) {
-            res.features
-                .iter()
-                .map(|feature| Point::new(feature.properties.y, feature.properties.x)) // y = west-east, x = north-south
-                .collect()
+        Ok(res)
+    }
+
+    /// The point of a forward result, with easting & northing consistent
+    fn forward_point<T>(&self, feature: &GeoAdminForwardLocation<T>) -> Point<T>
+    where
+        T: Float + Debug,
+    {
+        let properties = &feature.properties;
+        if ["2056", "21781"].contains(&self.sr.as_str()) {
+            Point::new(properties.y, properties.x) // y = west-east, x = north-south
         } else {
-            res.features
-                .iter()
-                .map(|feature| Point::new(feature.properties.x, feature.properties.y)) // x = west-east, y = north-south
-                .collect()
-        };
-        Ok(results)
+            Point::new(properties.x, properties.y) // x = west-east, y = north-south
+        }
+    }
+
+    /// A forward result in WGS84, whatever the instance's sr
+    ///
+    /// Unlike the `From` conversion, the point is the location's `lon` and `lat`, and the
+    /// bounding box is transformed from the instance's sr, in which the API returns it.
+    pub fn geocode_result<T>(&self, location: &GeoAdminForwardLocation<T>) -> GeocodeResult<T>
+    where
+        T: Float + Debug,
+    {
+        let properties = &location.properties;
+        let mut result = GeocodeResult::from(location);
+        result.point = Point::new(properties.lon, properties.lat);
+        result.bbox = location.bbox.map(|(min_x, min_y, max_x, max_y)| {
+            Rect::new(
+                self.to_wgs84(&Point::new(min_x, min_y)).0,
+                self.to_wgs84(&Point::new(max_x, max_y)).0,
+            )
+        });
+        result
+    }
+}
+
+impl<T> Forward<T> for GeoAdmin
+where
+    T: Float + Debug,
+    for<'de> T: Deserialize<'de>,
+{
+    /// A forward-geocoding lookup of an address. Please see [the documentation](https://api3.geo.admin.ch/services/sdiservices.html#search) for details.
+    ///
+    /// This method passes the `type`,  `origins`, `limit` and `sr` parameter to the API.
+    /// The `limit` can be set using [`GeoAdminBuilder::with_limit`](struct.GeoAdminBuilder.html#method.with_limit).
+    fn forward(&self, place: &str) -> Result<Vec<Point<T>>, GeocodingError> {
+        let res: GeoAdminForwardResponse<T> = self.forward_lookup(place, None)?;
+        Ok(res
+            .features
+            .iter()
+            .map(|feature| self.forward_point(feature))
+            .collect())
     }
 }
 
+impl<T> ForwardLabels<T> for GeoAdmin
+where
+    T: Float + Debug,
+    for<'de> T: Deserialize<'de>,
+{
+    /// A forward-geocoding lookup of an address, returning the `label` of each result
+    /// without its HTML tags.
+    ///
+    /// This method passes the `type`,  `origins`, `limit` and `sr` parameter to the API.
+    fn forward_with_labels(&self, place: &str) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        let res: GeoAdminForwardResponse<T> = self.forward_lookup(place, None)?;
+        Ok(res
+            .features
+            .iter()
+            .map(|feature| {
+                (
+                    self.forward_point(feature),
+                    strip_tags(&feature.properties.label),
+                )
+            })
+            .collect())
+    }
+
+    /// This method also passes the `lang` parameter to the API, the primary subtag of
+    /// `language` such as `fr` for `fr-CH`.
+    fn forward_localized(
+        &self,
+        place: &str,
+        language: &LanguageTag,
+    ) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        let primary = language.as_str().split('-').next().unwrap_or_default();
+        let res: GeoAdminForwardResponse<T> =
+            self.forward_lookup(place, Some(&primary.to_lowercase()))?;
+        Ok(res
+            .features
+            .iter()
+            .map(|feature| {
+                (
+                    self.forward_point(feature),
+                    strip_tags(&feature.properties.label),
+                )
+            })
+            .collect())
+    }
+}
+
+impl<T> ForwardStructured<T> for GeoAdmin
+where
+    T: Float + Debug,
+    for<'de> T: Deserialize<'de>,
+{
+}
+
 impl<T> Reverse<T> for GeoAdmin
 where
     T: Float + Debug,
@@ -267,31 +1330,8 @@ where
     ///
     /// This method passes the `format` parameter to the API.
     fn reverse(&self, point: &Point<T>) -> Result<Option<String>, GeocodingError> {
-        let resp = self
-            .client
-            .get(&format!("{}MapServer/identify", self.endpoint))
-            .query(&[
-                (
-                    "geometry",
-                    format!(
-                        "{},{}",
-                        point.x().to_f64().unwrap(),
-                        point.y().to_f64().unwrap()
-                    )
-                    .as_str(),
-                ),
-                ("geometryType", "esriGeometryPoint"),
-                ("layers", "all:ch.bfs.gebaeude_wohnungs_register"),
-                ("mapExtent", "0,0,100,100"),
-                ("imageDisplay", "100,100,100"),
-                ("tolerance", "50"),
-                ("geometryFormat", "geojson"),
-                ("sr", &self.sr),
-                ("lang", "en"),
-            ])
-            .send()?
-            .error_for_status()?;
-        let res: GeoAdminReverseResponse = resp.json()?;
+        let res: GeoAdminReverseResponse =
+            self.reverse_full(point, &GeoAdminReverseParams::new())?;
         if !res.results.is_empty() {
             let properties = &res.results[0].properties;
             let address = format!(
@@ -305,88 +1345,620 @@ where
     }
 }
 
-// Approximately transform Point from WGS84 to LV03
-//
-// See [the documentation](https://www.swisstopo.admin.ch/content/swisstopo-internet/en/online/calculation-services/_jcr_content/contentPar/tabs/items/documents_publicatio/tabPar/downloadlist/downloadItems/19_1467104393233.download/ch1903wgs84_e.pdf) for more details
-fn wgs84_to_lv03<T>(p: &Point<T>) -> Point<T>
-where
-    T: Float + Debug,
-{
-    let lambda = (p.x().to_f64().unwrap() * 3600.0 - 26782.5) / 10000.0;
-    let phi = (p.y().to_f64().unwrap() * 3600.0 - 169028.66) / 10000.0;
-    let x = 2600072.37 + 211455.93 * lambda
-        - 10938.51 * lambda * phi
-        - 0.36 * lambda * phi.pow(2)
-        - 44.54 * lambda.pow(3);
-    let y = 1200147.07 + 308807.95 * phi + 3745.25 * lambda.pow(2) + 76.63 * phi.pow(2)
-        - 194.56 * lambda.pow(2) * phi
-        + 119.79 * phi.pow(3);
-    Point::new(
-        T::from(x - 2000000.0).unwrap(),
-        T::from(y - 1000000.0).unwrap(),
-    )
+impl<T> ReverseFull<T> for GeoAdmin
+where
+    T: Float + Debug,
+    for<'de> T: Deserialize<'de>,
+{
+    /// A reverse lookup of the building address at a point.
+    ///
+    /// The result is located at `point`, as addresses are returned without their location.
+    ///
+    /// This method passes the `format` parameter to the API.
+    fn reverse_result(&self, point: &Point<T>) -> Result<Option<GeocodeResult<T>>, GeocodingError> {
+        let res: GeoAdminReverseResponse =
+            self.reverse_full(point, &GeoAdminReverseParams::new())?;
+        Ok(res.results.first().map(|result| {
+            let properties = &result.properties;
+            let label = format!(
+                "{}, {} {}",
+                properties.strname_deinr, properties.dplz4, properties.dplzname
+            );
+            let mut res = GeocodeResult::new(*point, label);
+            res.address = Some(Address::from(properties));
+            res
+        }))
+    }
+}
+
+impl HealthCheck for GeoAdmin {
+    /// A `HEAD` request to the endpoint
+    fn ping(&self) -> Result<ProviderStatus, GeocodingError> {
+        let (resp, meta) = self
+            .client
+            .head(&self.endpoint)
+            .headers(self.headers.clone())
+            .send_with_meta(&self.hooks, &self.endpoint)?;
+        resp.error_for_status()?;
+        Ok(ProviderStatus {
+            meta,
+            message: None,
+        })
+    }
+}
+
+impl<T> Elevation<T> for GeoAdmin
+where
+    T: Float + Debug,
+{
+    /// An elevation lookup of a point, using the default `DTM25` model.
+    /// Please see [the documentation](https://api3.geo.admin.ch/services/sdiservices.html#height) for details.
+    ///
+    /// The height service only covers Switzerland and its surroundings.
+    fn elevation(&self, point: &Point<T>) -> Result<Option<f64>, GeocodingError> {
+        Ok(Some(self.height(point, ElevationModel::default())?))
+    }
+}
+
+impl<T> NearbySearch<T> for GeoAdmin
+where
+    T: Float + Debug,
+{
+    /// A search for features of a layer around a point, where `category` is the layer id,
+    /// such as `"ch.bav.haltestellen-oev"` for public transport stops.
+    /// Please see [the documentation](https://api3.geo.admin.ch/services/sdiservices.html#identify-features) for details.
+    ///
+    /// Features are located at the centre of their bounding box, and labelled with their
+    /// `label` attribute if the layer has one, or with their layer name and id otherwise.
+    ///
+    /// This method passes the `mapExtent`, `imageDisplay`, `tolerance` and `returnGeometry`
+    /// parameters to the API.
+    fn nearby(
+        &self,
+        point: &Point<T>,
+        radius: f64,
+        category: &str,
+    ) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        let center = self.to_wgs84(point);
+        // The identify tolerance is given in pixels: map 100 pixels to the radius
+        let units = match self.sr.as_str() {
+            "2056" | "21781" => radius,
+            "3857" => radius / center.y().to_f64().unwrap().to_radians().cos(),
+            _ => radius / 111_320.0 / center.y().to_f64().unwrap().to_radians().cos(),
+        };
+        let units = T::from(units).unwrap();
+        let map_extent = InputBounds::new(
+            (point.x() - units, point.y() - units),
+            (point.x() + units, point.y() + units),
+        );
+        let layers = [category];
+        let params = GeoAdminReverseParams::new()
+            .with_layers(&layers)
+            .with_map_extent(map_extent)
+            .with_image_display(200, 200, 96)
+            .with_tolerance(100)
+            .with_return_geometry(true)
+            .build();
+        let res: GeoAdminReverseResponse<serde_json::Value> = self.reverse_full(point, &params)?;
+
+        let mut places: Vec<(f64, Point<T>, String)> = res
+            .results
+            .into_iter()
+            .filter_map(|feature| {
+                let (min_x, min_y, max_x, max_y) = feature.bbox?;
+                let p = Point::new(
+                    T::from((min_x + max_x) / 2.0).unwrap(),
+                    T::from((min_y + max_y) / 2.0).unwrap(),
+                );
+                let label = match feature.properties.get("label") {
+                    Some(serde_json::Value::String(label)) => label.clone(),
+                    _ => format!("{} {}", feature.layer_name, feature.feature_id),
+                };
+                Some((haversine_distance(&center, &self.to_wgs84(&p)), p, label))
+            })
+            .filter(|(distance, _, _)| *distance <= radius)
+            .collect();
+        places.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(places.into_iter().map(|(_, p, label)| (p, label)).collect())
+    }
+}
+
+// Constants of the Swiss projection, based on the Bessel 1841 ellipsoid
+//
+// See [the documentation](https://www.swisstopo.admin.ch/en/knowledge-facts/surveying-geodesy/reference-frames.html)
+// for the formulas of the Swiss oblique Mercator projection and the CH1903 datum.
+const BESSEL_A: f64 = 6_377_397.155;
+const BESSEL_E2: f64 = 0.006_674_372_230_614;
+const WGS84_A: f64 = 6_378_137.0;
+const WGS84_E2: f64 = 0.006_694_379_990_14;
+// Translation from the CH1903(+) to the WGS84 geocentric datum, in meters
+const CH1903_TO_WGS84: (f64, f64, f64) = (674.374, 15.056, 405.346);
+// Projection centre (old observatory of Bern) on the Bessel ellipsoid, in degrees
+const PHI_0: f64 = 46.0 + 57.0 / 60.0 + 8.66 / 3600.0;
+const LAMBDA_0: f64 = 7.0 + 26.0 / 60.0 + 22.5 / 3600.0;
+// False easting and northing of LV95; LV03 is offset by 2'000'000 m and 1'000'000 m
+const LV95_E0: f64 = 2_600_000.0;
+const LV95_N0: f64 = 1_200_000.0;
+const LV03_OFFSET: (f64, f64) = (2_000_000.0, 1_000_000.0);
+
+/// Derived constants of the projection: sphere radius, `alpha`, `b0` and `K`
+fn projection_constants() -> (f64, f64, f64, f64) {
+    let e = BESSEL_E2.sqrt();
+    let phi0 = PHI_0.to_radians();
+    let r = BESSEL_A * (1.0 - BESSEL_E2).sqrt() / (1.0 - BESSEL_E2 * phi0.sin().powi(2));
+    let alpha = (1.0 + BESSEL_E2 / (1.0 - BESSEL_E2) * phi0.cos().powi(4)).sqrt();
+    let b0 = (phi0.sin() / alpha).asin();
+    let k = (FRAC_PI_4 + b0 / 2.0).tan().ln() - alpha * (FRAC_PI_4 + phi0 / 2.0).tan().ln()
+        + alpha * e * (e * phi0.sin()).atanh();
+    (r, alpha, b0, k)
+}
+
+/// Convert geodetic coordinates (radians) on an ellipsoid to geocentric cartesian coordinates
+fn geodetic_to_geocentric(phi: f64, lambda: f64, a: f64, e2: f64) -> (f64, f64, f64) {
+    let n = a / (1.0 - e2 * phi.sin().powi(2)).sqrt();
+    (
+        n * phi.cos() * lambda.cos(),
+        n * phi.cos() * lambda.sin(),
+        n * (1.0 - e2) * phi.sin(),
+    )
+}
+
+/// Convert geocentric cartesian coordinates to geodetic coordinates (radians) on an ellipsoid
+fn geocentric_to_geodetic(x: f64, y: f64, z: f64, a: f64, e2: f64) -> (f64, f64) {
+    let p = (x * x + y * y).sqrt();
+    let lambda = y.atan2(x);
+    let mut phi = z.atan2(p * (1.0 - e2));
+    for _ in 0..10 {
+        let n = a / (1.0 - e2 * phi.sin().powi(2)).sqrt();
+        let h = p / phi.cos() - n;
+        phi = z.atan2(p * (1.0 - e2 * n / (n + h)));
+    }
+    (phi, lambda)
+}
+
+/// Project WGS84 longitude and latitude (degrees) to unshifted Swiss coordinates (meters)
+fn wgs84_to_swiss(lon: f64, lat: f64) -> (f64, f64) {
+    // Datum shift from WGS84 to the Bessel ellipsoid
+    let (x, y, z) = geodetic_to_geocentric(lat.to_radians(), lon.to_radians(), WGS84_A, WGS84_E2);
+    let (dx, dy, dz) = CH1903_TO_WGS84;
+    let (phi, lambda) = geocentric_to_geodetic(x - dx, y - dy, z - dz, BESSEL_A, BESSEL_E2);
+
+    // Ellipsoid to sphere
+    let (r, alpha, b0, k) = projection_constants();
+    let e = BESSEL_E2.sqrt();
+    let s = alpha * (FRAC_PI_4 + phi / 2.0).tan().ln() - alpha * e * (e * phi.sin()).atanh() + k;
+    let b = 2.0 * (s.exp().atan() - FRAC_PI_4);
+    let l = alpha * (lambda - LAMBDA_0.to_radians());
+
+    // Sphere to pseudo-equatorial system, then to the plane
+    let l_bar = (l.sin() / (b0.sin() * b.tan() + b0.cos() * l.cos())).atan();
+    let b_bar = (b0.cos() * b.sin() - b0.sin() * b.cos() * l.cos()).asin();
+    let east = r * l_bar;
+    let north = r / 2.0 * ((1.0 + b_bar.sin()) / (1.0 - b_bar.sin())).ln();
+    (east, north)
+}
+
+/// Unproject unshifted Swiss coordinates (meters) to WGS84 longitude and latitude (degrees)
+fn swiss_to_wgs84(east: f64, north: f64) -> (f64, f64) {
+    let (r, alpha, b0, k) = projection_constants();
+    let e = BESSEL_E2.sqrt();
+
+    // Plane to pseudo-equatorial system, then to the sphere
+    let l_bar = east / r;
+    let b_bar = 2.0 * ((north / r).exp().atan() - FRAC_PI_4);
+    let b = (b0.cos() * b_bar.sin() + b0.sin() * b_bar.cos() * l_bar.cos()).asin();
+    let l = (l_bar.sin() / (b0.cos() * l_bar.cos() - b0.sin() * b_bar.tan())).atan();
+
+    // Sphere to ellipsoid
+    let lambda = LAMBDA_0.to_radians() + l / alpha;
+    let mut phi = b;
+    for _ in 0..10 {
+        let s = ((FRAC_PI_4 + b / 2.0).tan().ln() - k) / alpha + e * (e * phi.sin()).atanh();
+        phi = 2.0 * s.exp().atan() - FRAC_PI_2;
+    }
+
+    // Datum shift from the Bessel ellipsoid to WGS84
+    let (x, y, z) = geodetic_to_geocentric(phi, lambda, BESSEL_A, BESSEL_E2);
+    let (dx, dy, dz) = CH1903_TO_WGS84;
+    let (phi, lambda) = geocentric_to_geodetic(x + dx, y + dy, z + dz, WGS84_A, WGS84_E2);
+    (lambda.to_degrees(), phi.to_degrees())
+}
+
+/// Transform a Point from WGS84 (`lon, lat`) to LV95 (`E, N`)
+///
+/// Uses the rigorous formulas of the Swiss projection and the CH1903+ datum,
+/// which are accurate to a few centimeters within Switzerland.
+///
+/// ```
+/// use geocoding::Point;
+/// use geocoding::geoadmin::wgs84_to_lv95;
+///
+/// let p = wgs84_to_lv95(&Point::new(7.438632495_f64, 46.951082877));
+/// assert!((p.x() - 2_600_000.0).abs() < 0.1);
+/// assert!((p.y() - 1_200_000.0).abs() < 0.1);
+/// ```
+pub fn wgs84_to_lv95<T>(p: &Point<T>) -> Point<T>
+where
+    T: Float + Debug,
+{
+    let (east, north) = wgs84_to_swiss(p.x().to_f64().unwrap(), p.y().to_f64().unwrap());
+    Point::new(
+        T::from(east + LV95_E0).unwrap(),
+        T::from(north + LV95_N0).unwrap(),
+    )
+}
+
+/// Transform a Point from WGS84 (`lon, lat`) to LV03 (`y, x`, i.e. easting, northing)
+///
+/// LV03 is derived from LV95 by removing the false easting and northing offsets;
+/// local distortions of the LV03 network (up to about 1.5 m) are not modelled.
+pub fn wgs84_to_lv03<T>(p: &Point<T>) -> Point<T>
+where
+    T: Float + Debug,
+{
+    let lv95 = wgs84_to_lv95(p);
+    Point::new(
+        lv95.x() - T::from(LV03_OFFSET.0).unwrap(),
+        lv95.y() - T::from(LV03_OFFSET.1).unwrap(),
+    )
+}
+
+/// Transform a Point from LV95 (`E, N`) to WGS84 (`lon, lat`)
+///
+/// ```
+/// use geocoding::Point;
+/// use geocoding::geoadmin::lv95_to_wgs84;
+///
+/// let p = lv95_to_wgs84(&Point::new(2_600_000.0_f64, 1_200_000.0));
+/// assert!((p.x() - 7.438632495).abs() < 1e-6);
+/// assert!((p.y() - 46.951082877).abs() < 1e-6);
+/// ```
+pub fn lv95_to_wgs84<T>(p: &Point<T>) -> Point<T>
+where
+    T: Float + Debug,
+{
+    let (lon, lat) = swiss_to_wgs84(
+        p.x().to_f64().unwrap() - LV95_E0,
+        p.y().to_f64().unwrap() - LV95_N0,
+    );
+    Point::new(T::from(lon).unwrap(), T::from(lat).unwrap())
+}
+
+/// Transform a Point from LV03 (`y, x`, i.e. easting, northing) to WGS84 (`lon, lat`)
+pub fn lv03_to_wgs84<T>(p: &Point<T>) -> Point<T>
+where
+    T: Float + Debug,
+{
+    lv95_to_wgs84(&Point::new(
+        p.x() + T::from(LV03_OFFSET.0).unwrap(),
+        p.y() + T::from(LV03_OFFSET.1).unwrap(),
+    ))
+}
+
+/// Transform a Point from Web Pseudo-Mercator (EPSG:3857) to WGS84 (`lon, lat`)
+fn web_mercator_to_wgs84<T>(p: &Point<T>) -> Point<T>
+where
+    T: Float + Debug,
+{
+    let x = p.x().to_f64().unwrap();
+    let y = p.y().to_f64().unwrap();
+    let lon = (x / WGS84_A).to_degrees();
+    let lat = (2.0 * (y / WGS84_A).exp().atan() - FRAC_PI_2).to_degrees();
+    Point::new(T::from(lon).unwrap(), T::from(lat).unwrap())
+}
+
+/// Transform a Point from WGS84 (`lon, lat`) to Web Pseudo-Mercator (EPSG:3857)
+#[cfg(any(test, all(feature = "opencage", feature = "osm")))]
+fn wgs84_to_web_mercator<T>(p: &Point<T>) -> Point<T>
+where
+    T: Float + Debug,
+{
+    let lon = p.x().to_f64().unwrap();
+    let lat = p.y().to_f64().unwrap();
+    let x = WGS84_A * lon.to_radians();
+    let y = WGS84_A * (FRAC_PI_4 + lat.to_radians() / 2.0).tan().ln();
+    Point::new(T::from(x).unwrap(), T::from(y).unwrap())
+}
+
+/// The top-level full JSON (GeoJSON Feature Collection) response returned by a forward-geocoding request
+///
+/// See [the documentation](https://api3.geo.admin.ch/services/sdiservices.html#search) for more details
+///
+///```json
+///{
+///     "type": "FeatureCollection",
+///     "features": [
+///         {
+///             "properties": {
+///                 "origin": "address",
+///                 "geom_quadindex": "021300220302203002031",
+///                 "weight": 1512,
+///                 "zoomlevel": 10,
+///                 "lon": 7.451352119445801,
+///                 "detail": "seftigenstrasse 264 3084 wabern 355 koeniz ch be",
+///                 "rank": 7,
+///                 "lat": 46.92793655395508,
+///                 "num": 264,
+///                 "y": 2600968.75,
+///                 "x": 1197427.0,
+///                 "label": "Seftigenstrasse 264 <b>3084 Wabern</b>"
+///                 "id": 1420809,
+///             }
+///         }
+///     ]
+/// }
+///```
+#[derive(Debug, Deserialize)]
+pub struct GeoAdminForwardResponse<T>
+where
+    T: Float + Debug,
+{
+    pub features: Vec<GeoAdminForwardLocation<T>>,
+    /// Details of the HTTP response, not part of the JSON
+    #[serde(skip)]
+    pub meta: ResponseMeta,
+}
+
+impl<T> GeoAdminForwardResponse<T>
+where
+    T: Float + Debug,
+    for<'de> T: Deserialize<'de>,
+{
+    /// Parse a response from its JSON text, e.g. a recorded response
+    pub fn from_json_str(json: &str) -> Result<Self, GeocodingError> {
+        from_json_str(json)
+    }
+}
+
+/// A forward geocoding location
+#[derive(Debug, Deserialize)]
+pub struct GeoAdminForwardLocation<T>
+where
+    T: Float + Debug,
+{
+    // Only read by `Debug`
+    #[allow(dead_code)]
+    id: Option<usize>,
+    pub bbox: Option<(T, T, T, T)>,
+    pub properties: ForwardLocationProperties<T>,
+}
+
+/// Forward Geocoding location attributes
+#[derive(Clone, Debug, Deserialize)]
+pub struct ForwardLocationProperties<T> {
+    pub origin: String,
+    pub geom_quadindex: String,
+    pub weight: u32,
+    pub rank: u32,
+    pub detail: String,
+    pub lat: T,
+    pub lon: T,
+    pub num: Option<usize>,
+    pub x: T,
+    pub y: T,
+    pub label: String,
+    pub zoomlevel: u32,
+}
+
+impl<T> From<&GeoAdminForwardLocation<T>> for GeocodeResult<T>
+where
+    T: Float + Debug,
+{
+    /// The point and bounding box are in the instance's sr, like the result of `forward`,
+    /// while the geohash is always computed from the WGS84 coordinates.
+    /// HTML tags are removed from the label.
+    ///
+    /// Use [`GeoAdmin::geocode_result`](struct.GeoAdmin.html#method.geocode_result) for a
+    /// result in WGS84.
+    fn from(location: &GeoAdminForwardLocation<T>) -> Self {
+        let properties = &location.properties;
+        let geohash = crate::geohash::encode(
+            &Point::new(properties.lon, properties.lat),
+            crate::geohash::MAX_PRECISION,
+        );
+        // y = west-east, x = north-south
+        let mut result = GeocodeResult::new(
+            Point::new(properties.y, properties.x),
+            strip_tags(&properties.label),
+        );
+        result.geohash = geohash.unwrap_or_default();
+        result.bbox = location
+            .bbox
+            .map(|(min_x, min_y, max_x, max_y)| Rect::new((min_x, min_y), (max_x, max_y)));
+        result
+    }
+}
+
+/// Remove HTML tags such as `<b>` from a label
+fn strip_tags(label: &str) -> String {
+    let mut stripped = String::with_capacity(label.len());
+    let mut in_tag = false;
+    for c in label.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => stripped.push(c),
+            _ => {}
+        }
+    }
+    stripped
+}
+
+/// The top-level JSON response returned by a layer search
+///
+/// See [the documentation](https://api3.geo.admin.ch/services/sdiservices.html#search) for more details
+///
+///```json
+/// {
+///     "results": [
+///         {
+///             "id": 1036,
+///             "weight": 101,
+///             "attrs": {
+///                 "label": "Register of Buildings and Dwellings",
+///                 "layer": "ch.bfs.gebaeude_wohnungs_register",
+///                 "lang": "en",
+///                 "staging": "prod",
+///                 "topics": "api,ech,inspire"
+///             }
+///         }
+///     ]
+/// }
+///```
+#[derive(Debug, Deserialize)]
+pub struct GeoAdminLayerSearchResponse {
+    pub results: Vec<GeoAdminLayerSearchResult>,
+}
+
+impl GeoAdminLayerSearchResponse {
+    /// Parse a response from its JSON text, e.g. a recorded response
+    pub fn from_json_str(json: &str) -> Result<Self, GeocodingError> {
+        from_json_str(json)
+    }
+}
+
+/// A layer search result
+#[derive(Debug, Deserialize)]
+pub struct GeoAdminLayerSearchResult {
+    pub weight: Option<u32>,
+    pub attrs: LayerSearchAttributes,
 }
-/// The top-level full JSON (GeoJSON Feature Collection) response returned by a forward-geocoding request
+
+/// Layer search result attributes
+#[derive(Clone, Debug, Deserialize)]
+pub struct LayerSearchAttributes {
+    pub label: String,
+    pub layer: String,
+    pub lang: Option<String>,
+    pub staging: Option<String>,
+    pub topics: Option<String>,
+    pub detail: Option<String>,
+}
+
+/// The top-level JSON response returned by a feature search
 ///
 /// See [the documentation](https://api3.geo.admin.ch/services/sdiservices.html#search) for more details
 ///
 ///```json
-///{
-///     "type": "FeatureCollection",
-///     "features": [
+/// {
+///     "results": [
 ///         {
-///             "properties": {
-///                 "origin": "address",
-///                 "geom_quadindex": "021300220302203002031",
-///                 "weight": 1512,
-///                 "zoomlevel": 10,
-///                 "lon": 7.451352119445801,
-///                 "detail": "seftigenstrasse 264 3084 wabern 355 koeniz ch be",
-///                 "rank": 7,
+///             "id": 190256,
+///             "weight": 1,
+///             "attrs": {
+///                 "origin": "feature",
+///                 "layer": "ch.bfs.gebaeude_wohnungs_register",
+///                 "feature_id": "1272199_0",
+///                 "label": "Seftigenstrasse 264 3084 Wabern",
+///                 "detail": "seftigenstrasse 264 3084 wabern 355 koeniz",
 ///                 "lat": 46.92793655395508,
-///                 "num": 264,
-///                 "y": 2600968.75,
-///                 "x": 1197427.0,
-///                 "label": "Seftigenstrasse 264 <b>3084 Wabern</b>"
-///                 "id": 1420809,
+///                 "lon": 7.451352119445801,
+///                 "geom_st_box2d": "BOX(2600968.75 1197427,2600968.75 1197427)",
+///                 "zoomlevel": 10
 ///             }
 ///         }
 ///     ]
 /// }
 ///```
 #[derive(Debug, Deserialize)]
-pub struct GeoAdminForwardResponse<T>
+pub struct GeoAdminFeatureSearchResponse<T>
 where
     T: Float + Debug,
 {
-    pub features: Vec<GeoAdminForwardLocation<T>>,
+    pub results: Vec<GeoAdminFeatureSearchResult<T>>,
 }
 
-/// A forward geocoding location
+impl<T> GeoAdminFeatureSearchResponse<T>
+where
+    T: Float + Debug,
+    for<'de> T: Deserialize<'de>,
+{
+    /// Parse a response from its JSON text, e.g. a recorded response
+    pub fn from_json_str(json: &str) -> Result<Self, GeocodingError> {
+        from_json_str(json)
+    }
+}
+
+/// A feature search result
 #[derive(Debug, Deserialize)]
-pub struct GeoAdminForwardLocation<T>
+pub struct GeoAdminFeatureSearchResult<T>
 where
     T: Float + Debug,
 {
-    id: Option<usize>,
-    pub properties: ForwardLocationProperties<T>,
+    pub weight: Option<u32>,
+    pub attrs: FeatureSearchAttributes<T>,
 }
 
-/// Forward Geocoding location attributes
+/// Feature search result attributes
 #[derive(Clone, Debug, Deserialize)]
-pub struct ForwardLocationProperties<T> {
+pub struct FeatureSearchAttributes<T> {
     pub origin: String,
-    pub geom_quadindex: String,
-    pub weight: u32,
-    pub rank: u32,
-    pub detail: String,
-    pub lat: T,
-    pub lon: T,
-    pub num: Option<usize>,
-    pub x: T,
-    pub y: T,
+    pub layer: String,
+    #[serde(deserialize_with = "deserialize_string_or_int")]
+    pub feature_id: String,
     pub label: String,
-    pub zoomlevel: u32,
+    pub detail: Option<String>,
+    pub lat: Option<T>,
+    pub lon: Option<T>,
+    pub geom_st_box2d: Option<String>,
+    pub zoomlevel: Option<u32>,
+}
+
+/// The top-level JSON response returned by a feature request
+///
+/// See [the documentation](https://api3.geo.admin.ch/services/sdiservices.html#feature-resource) for more details
+#[derive(Debug, Deserialize)]
+pub struct GeoAdminFeatureResponse<P = ReverseLocationAttributes> {
+    pub feature: GeoAdminReverseLocation<P>,
+}
+
+impl<P> GeoAdminFeatureResponse<P>
+where
+    for<'de> P: Deserialize<'de>,
+{
+    /// Parse a response from its JSON text, e.g. a recorded response
+    pub fn from_json_str(json: &str) -> Result<Self, GeocodingError> {
+        from_json_str(json)
+    }
+}
+
+/// The JSON response returned by a height request
+///
+/// See [the documentation](https://api3.geo.admin.ch/services/sdiservices.html#height) for more details
+///
+///```json
+/// {
+///     "height": "560.2"
+/// }
+///```
+#[derive(Debug, Deserialize)]
+pub struct GeoAdminHeightResponse {
+    #[serde(deserialize_with = "deserialize_float_or_string")]
+    pub height: f64,
+}
+
+impl GeoAdminHeightResponse {
+    /// Parse a response from its JSON text, e.g. a recorded response
+    pub fn from_json_str(json: &str) -> Result<Self, GeocodingError> {
+        from_json_str(json)
+    }
+}
+
+fn deserialize_float_or_string<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum FloatOrString {
+        Float(f64),
+        String(String),
+    }
+
+    match FloatOrString::deserialize(deserializer)? {
+        FloatOrString::Float(f) => Ok(f),
+        FloatOrString::String(s) => s.parse().map_err(serde::de::Error::custom),
+    }
 }
 
 /// The top-level full JSON (GeoJSON FeatureCollection) response returned by a reverse-geocoding request
@@ -410,21 +1982,46 @@ pub struct ForwardLocationProperties<T> {
 /// }
 ///```
 #[derive(Debug, Deserialize)]
-pub struct GeoAdminReverseResponse {
-    pub results: Vec<GeoAdminReverseLocation>,
+pub struct GeoAdminReverseResponse<P = ReverseLocationAttributes> {
+    pub results: Vec<GeoAdminReverseLocation<P>>,
+    /// Details of the HTTP response, not part of the JSON
+    #[serde(skip)]
+    pub meta: ResponseMeta,
+}
+
+impl<P> GeoAdminReverseResponse<P>
+where
+    for<'de> P: Deserialize<'de>,
+{
+    /// Parse a response from its JSON text, e.g. a recorded response
+    pub fn from_json_str(json: &str) -> Result<Self, GeocodingError> {
+        from_json_str(json)
+    }
 }
 
 /// A reverse geocoding result
 #[derive(Debug, Deserialize)]
-pub struct GeoAdminReverseLocation {
+pub struct GeoAdminReverseLocation<P = ReverseLocationAttributes> {
+    // Only read by `Debug`
+    #[allow(dead_code)]
+    #[serde(deserialize_with = "deserialize_string_or_int")]
     id: String,
-    #[serde(rename = "featureId")]
+    #[serde(rename = "featureId", deserialize_with = "deserialize_string_or_int")]
     pub feature_id: String,
     #[serde(rename = "layerBodId")]
     pub layer_bod_id: String,
     #[serde(rename = "layerName")]
     pub layer_name: String,
-    pub properties: ReverseLocationAttributes,
+    pub bbox: Option<(f64, f64, f64, f64)>,
+    /// The geometry of the feature, such as a building footprint or a parcel outline, in
+    /// the spatial reference of the request
+    ///
+    /// Identify responses only include it when requested with
+    /// [`with_return_geometry`](struct.GeoAdminReverseParams.html#method.with_return_geometry).
+    /// Both GeoJSON and esri JSON geometries are parsed; other geometries are `None`.
+    #[serde(default, deserialize_with = "deserialize_geometry")]
+    pub geometry: Option<Geometry<f64>>,
+    pub properties: P,
 }
 
 /// Reverse geocoding result attributes
@@ -447,24 +2044,263 @@ pub struct ReverseLocationAttributes {
     pub label: String,
 }
 
+/// A position, whose coordinates after the first two are ignored
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(try_from = "Vec<f64>")]
+struct Position(f64, f64);
+
+impl TryFrom<Vec<f64>> for Position {
+    type Error = String;
+
+    fn try_from(coordinates: Vec<f64>) -> Result<Self, Self::Error> {
+        match coordinates[..] {
+            [x, y, ..] => Ok(Position(x, y)),
+            _ => Err(format!("invalid position: {:?}", coordinates)),
+        }
+    }
+}
+
+impl From<Position> for Coord<f64> {
+    fn from(Position(x, y): Position) -> Self {
+        Coord { x, y }
+    }
+}
+
+fn line_string(positions: Vec<Position>) -> LineString<f64> {
+    positions.into_iter().map(Coord::from).collect()
+}
+
+fn polygon(rings: Vec<Vec<Position>>) -> Polygon<f64> {
+    let mut rings = rings.into_iter().map(line_string);
+    let exterior = rings.next().unwrap_or_else(|| LineString::new(vec![]));
+    Polygon::new(exterior, rings.collect())
+}
+
+/// A GeoJSON geometry
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum GeoJsonGeometry {
+    Point {
+        coordinates: Position,
+    },
+    MultiPoint {
+        coordinates: Vec<Position>,
+    },
+    LineString {
+        coordinates: Vec<Position>,
+    },
+    MultiLineString {
+        coordinates: Vec<Vec<Position>>,
+    },
+    Polygon {
+        coordinates: Vec<Vec<Position>>,
+    },
+    MultiPolygon {
+        coordinates: Vec<Vec<Vec<Position>>>,
+    },
+    GeometryCollection {
+        geometries: Vec<GeoJsonGeometry>,
+    },
+}
+
+impl From<GeoJsonGeometry> for Geometry<f64> {
+    fn from(geometry: GeoJsonGeometry) -> Self {
+        match geometry {
+            GeoJsonGeometry::Point { coordinates } => {
+                Geometry::Point(Coord::from(coordinates).into())
+            }
+            GeoJsonGeometry::MultiPoint { coordinates } => Geometry::MultiPoint(
+                coordinates
+                    .into_iter()
+                    .map(|position| Point::from(Coord::from(position)))
+                    .collect(),
+            ),
+            GeoJsonGeometry::LineString { coordinates } => {
+                Geometry::LineString(line_string(coordinates))
+            }
+            GeoJsonGeometry::MultiLineString { coordinates } => Geometry::MultiLineString(
+                MultiLineString::new(coordinates.into_iter().map(line_string).collect()),
+            ),
+            GeoJsonGeometry::Polygon { coordinates } => Geometry::Polygon(polygon(coordinates)),
+            GeoJsonGeometry::MultiPolygon { coordinates } => Geometry::MultiPolygon(
+                MultiPolygon::new(coordinates.into_iter().map(polygon).collect()),
+            ),
+            GeoJsonGeometry::GeometryCollection { geometries } => Geometry::GeometryCollection(
+                GeometryCollection::new_from(geometries.into_iter().map(Geometry::from).collect()),
+            ),
+        }
+    }
+}
+
+/// A GeoJSON or esri JSON geometry
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum AnyGeometry {
+    GeoJson(GeoJsonGeometry),
+    EsriPoint { x: f64, y: f64 },
+    EsriMultipoint { points: Vec<Position> },
+    EsriPolyline { paths: Vec<Vec<Position>> },
+    EsriPolygon { rings: Vec<Vec<Position>> },
+}
+
+/// The polygons of the rings of an esri polygon
+///
+/// Exterior rings are clockwise, and followed by the counterclockwise rings of their holes.
+fn esri_polygons(rings: Vec<Vec<Position>>) -> Vec<Polygon<f64>> {
+    let mut polygons: Vec<(LineString<f64>, Vec<LineString<f64>>)> = vec![];
+    for ring in rings.into_iter().map(line_string) {
+        // Twice the signed area, negative for clockwise rings
+        let area: f64 = ring.lines().map(|line| line.determinant()).sum();
+        match polygons.last_mut() {
+            Some((_, holes)) if area > 0.0 => holes.push(ring),
+            _ => polygons.push((ring, vec![])),
+        }
+    }
+    polygons
+        .into_iter()
+        .map(|(exterior, holes)| Polygon::new(exterior, holes))
+        .collect()
+}
+
+impl From<AnyGeometry> for Geometry<f64> {
+    fn from(geometry: AnyGeometry) -> Self {
+        match geometry {
+            AnyGeometry::GeoJson(geometry) => geometry.into(),
+            AnyGeometry::EsriPoint { x, y } => Geometry::Point(Point::new(x, y)),
+            AnyGeometry::EsriMultipoint { points } => GeoJsonGeometry::MultiPoint {
+                coordinates: points,
+            }
+            .into(),
+            AnyGeometry::EsriPolyline { mut paths } if paths.len() == 1 => {
+                Geometry::LineString(line_string(paths.remove(0)))
+            }
+            AnyGeometry::EsriPolyline { paths } => {
+                GeoJsonGeometry::MultiLineString { coordinates: paths }.into()
+            }
+            AnyGeometry::EsriPolygon { rings } => {
+                let mut polygons = esri_polygons(rings);
+                match polygons.len() {
+                    1 => Geometry::Polygon(polygons.remove(0)),
+                    _ => Geometry::MultiPolygon(MultiPolygon::new(polygons)),
+                }
+            }
+        }
+    }
+}
+
+/// Deserialize a GeoJSON or esri JSON geometry, or `None` for any other value
+fn deserialize_geometry<'de, D>(deserializer: D) -> Result<Option<Geometry<f64>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(value
+        .and_then(|value| serde_json::from_value::<AnyGeometry>(value).ok())
+        .map(Geometry::from))
+}
+
+/// The attributes of a parcel of the cadastral web map
+#[derive(Clone, Debug, Deserialize)]
+pub struct ParcelAttributes {
+    /// The federal real estate identifier of the parcel
+    pub egris_egrid: String,
+    /// The number of the parcel in its cadastral survey area
+    pub number: Option<String>,
+    /// The identifier of the cadastral survey area
+    pub identnd: Option<String>,
+    /// The abbreviation of the canton
+    pub ak: Option<String>,
+    /// The BFS number of the municipality
+    pub bfsnr: Option<u32>,
+    /// The page of the parcel on the geoportal of the canton
+    pub geoportal_url: Option<String>,
+}
+
+impl From<&ReverseLocationAttributes> for Address {
+    fn from(properties: &ReverseLocationAttributes) -> Self {
+        Address {
+            house_number: properties.deinr.clone(),
+            road: properties.strname.first().cloned(),
+            suburb: None,
+            city: Some(properties.dplzname.clone()),
+            postcode: Some(properties.dplz4.to_string()),
+            state: None,
+            state_code: Some(properties.gdekt.clone()),
+            country: Some("Switzerland".to_string()),
+            country_code: Some("CH".to_string()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::test_server::{Response, Server};
+    use crate::{HeaderValue, USER_AGENT};
+
+    #[test]
+    fn wgs84_to_lv95_test() {
+        // Reference point of the swisstopo documentation
+        let p = Point::new(
+            8.0 + 43.0 / 60.0 + 49.79 / 3600.0,
+            46.0 + 2.0 / 60.0 + 38.87 / 3600.0,
+        );
+        let lv95 = wgs84_to_lv95(&p);
+        assert!((lv95.x() - 2_700_000.0).abs() < 1.0);
+        assert!((lv95.y() - 1_100_000.0).abs() < 1.0);
+        let lv03 = wgs84_to_lv03(&p);
+        assert!((lv03.x() - 700_000.0).abs() < 1.0);
+        assert!((lv03.y() - 100_000.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn lv95_round_trip_test() {
+        for p in [
+            Point::new(2_600_000.0, 1_200_000.0),
+            Point::new(2_485_000.0, 1_075_000.0),
+            Point::new(2_833_000.0, 1_295_000.0),
+        ] {
+            let back = wgs84_to_lv95(&lv95_to_wgs84(&p));
+            assert!((back.x() - p.x()).abs() < 1e-2);
+            assert!((back.y() - p.y()).abs() < 1e-2);
+        }
+        let p = Point::new(600_968.75_f64, 197_427.0);
+        let back = wgs84_to_lv03(&lv03_to_wgs84(&p));
+        assert!((back.x() - p.x()).abs() < 1e-2);
+        assert!((back.y() - p.y()).abs() < 1e-2);
+    }
+
+    #[test]
+    fn web_mercator_to_wgs84_test() {
+        let p = web_mercator_to_wgs84(&Point::new(829_474.9, 5_930_313.4));
+        assert!((p.x() - 7.4513).abs() < 1e-4);
+        assert!((p.y() - 46.9279).abs() < 1e-4);
+    }
+
+    #[test]
+    fn wgs84_to_web_mercator_test() {
+        let p = Point::new(7.4513, 46.9279);
+        let back = web_mercator_to_wgs84(&wgs84_to_web_mercator(&p));
+        assert!((back.x() - p.x()).abs() < 1e-9);
+        assert!((back.y() - p.y()).abs() < 1e-9);
+        assert!((wgs84_to_web_mercator(&p).x() - 829_474.9).abs() < 10.0);
+    }
 
     #[test]
     fn new_with_sr_forward_test() {
-        let geoadmin = GeoAdmin::new().with_sr("2056");
+        let geoadmin = GeoAdmin::builder().with_sr("2056").build();
         let address = "Seftigenstrasse 264, 3084 Wabern";
-        let res = geoadmin.forward(&address);
+        let res = geoadmin.forward(address);
         assert_eq!(res.unwrap(), vec![Point::new(2_600_968.75, 1_197_427.0)]);
     }
 
     #[test]
     fn new_with_endpoint_forward_test() {
-        let geoadmin =
-            GeoAdmin::new().with_endpoint("https://api3.geo.admin.ch/rest/services/api/");
+        let geoadmin = GeoAdmin::builder()
+            .with_endpoint("https://api3.geo.admin.ch/rest/services/api/")
+            .build();
         let address = "Seftigenstrasse 264, 3084 Wabern";
-        let res = geoadmin.forward(&address);
+        let res = geoadmin.forward(address);
         assert_eq!(
             res.unwrap(),
             vec![Point::new(7.451352119445801, 46.92793655395508)]
@@ -473,11 +2309,11 @@ mod test {
 
     #[test]
     fn with_sr_forward_full_test() {
-        let geoadmin = GeoAdmin::new().with_sr("2056");
+        let geoadmin = GeoAdmin::builder().with_sr("2056").build();
         let bbox = InputBounds::new((2_600_967.75, 1_197_426.0), (2_600_969.75, 1_197_428.0));
-        let params = GeoAdminParams::new(&"Seftigenstrasse Bern")
-            .with_origins("address")
-            .with_bbox(&bbox)
+        let params = GeoAdminParams::new("Seftigenstrasse Bern")
+            .with_origins(&[Origin::Address])
+            .with_bbox(bbox)
             .build();
         let res: GeoAdminForwardResponse<f64> = geoadmin.forward_full(&params).unwrap();
         let result = &res.features[0];
@@ -491,9 +2327,9 @@ mod test {
     fn forward_full_test() {
         let geoadmin = GeoAdmin::new();
         let bbox = InputBounds::new((7.4513398, 46.92792859), (7.4513662, 46.9279467));
-        let params = GeoAdminParams::new(&"Seftigenstrasse Bern")
-            .with_origins("address")
-            .with_bbox(&bbox)
+        let params = GeoAdminParams::new("Seftigenstrasse Bern")
+            .with_origins(&[Origin::Address])
+            .with_bbox(bbox)
             .build();
         let res: GeoAdminForwardResponse<f64> = geoadmin.forward_full(&params).unwrap();
         let result = &res.features[0];
@@ -507,16 +2343,73 @@ mod test {
     fn forward_test() {
         let geoadmin = GeoAdmin::new();
         let address = "Seftigenstrasse 264, 3084 Wabern";
-        let res = geoadmin.forward(&address);
+        let res = geoadmin.forward(address);
         assert_eq!(
             res.unwrap(),
             vec![Point::new(7.451352119445801, 46.92793655395508)]
         );
     }
 
+    #[test]
+    fn forward_with_labels_test() {
+        let geoadmin = GeoAdmin::new();
+        let address = "Seftigenstrasse 264, 3084 Wabern";
+        let res = geoadmin.forward_with_labels(address).unwrap();
+        assert_eq!(
+            res,
+            vec![(
+                Point::new(7.451352119445801, 46.92793655395508),
+                "Seftigenstrasse 264 3084 Wabern".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn unsupported_sr_test() {
+        assert!(GeoAdmin::new().validate().is_ok());
+        // Nothing listens on this endpoint, so the request would fail otherwise
+        let geoadmin = GeoAdmin::builder()
+            .with_endpoint("http://127.0.0.1:9/")
+            .with_sr("2065")
+            .build();
+        let err = geoadmin
+            .forward_full(&GeoAdminParams::<f64>::new("Bern").build())
+            .unwrap_err();
+        assert!(
+            matches!(err, GeocodingError::InvalidConfiguration(ref message) if message.contains("\"2065\""))
+        );
+    }
+
+    #[test]
+    fn builder_test() {
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, HeaderValue::from_static("my-app/1.0"));
+        let geoadmin = GeoAdmin::builder()
+            .with_endpoint("https://example.com/api/")
+            .with_language("de")
+            .with_headers(headers)
+            .build();
+        let req = geoadmin
+            .get(format!("{}SearchServer", geoadmin.endpoint))
+            .build()
+            .unwrap();
+        assert_eq!(req.url().as_str(), "https://example.com/api/SearchServer");
+        assert_eq!(req.headers()[USER_AGENT], "my-app/1.0");
+        assert_eq!(geoadmin.language, "de");
+        assert_eq!(geoadmin.sr, "4326");
+    }
+
+    #[test]
+    fn with_limit_forward_test() {
+        let geoadmin = GeoAdmin::builder().with_limit(5).build();
+        let address = "Seftigenstrasse, 3084 Wabern";
+        let res: Vec<Point<f64>> = geoadmin.forward(address).unwrap();
+        assert_eq!(res.len(), 5);
+    }
+
     #[test]
     fn with_sr_reverse_test() {
-        let geoadmin = GeoAdmin::new().with_sr("2056");
+        let geoadmin = GeoAdmin::builder().with_sr("2056").build();
         let p = Point::new(2_600_968.75, 1_197_427.0);
         let res = geoadmin.reverse(&p);
         assert_eq!(
@@ -525,6 +2418,214 @@ mod test {
         );
     }
 
+    #[test]
+    fn reverse_full_test() {
+        let geoadmin = GeoAdmin::builder().with_sr("2056").build();
+        let p = Point::new(2_600_968.75, 1_197_427.0);
+        let map_extent = InputBounds::new((2_600_868.75, 1_197_327.0), (2_601_068.75, 1_197_527.0));
+        let params = GeoAdminReverseParams::new()
+            .with_layers(&["ch.swisstopo.amtliches-strassenverzeichnis"])
+            .with_map_extent(map_extent)
+            .with_image_display(200, 200, 96)
+            .with_tolerance(20)
+            .build();
+        let res: GeoAdminReverseResponse<serde_json::Value> =
+            geoadmin.reverse_full(&p, &params).unwrap();
+        let result = &res.results[0];
+        assert_eq!(
+            result.layer_bod_id,
+            "ch.swisstopo.amtliches-strassenverzeichnis"
+        );
+        assert_eq!(result.properties["label"], "Seftigenstrasse");
+    }
+
+    #[test]
+    fn height_test() {
+        let geoadmin = GeoAdmin::builder().with_sr("2056").build();
+        let p = Point::new(2_600_968.75, 1_197_427.0);
+        let height = geoadmin.height(&p, ElevationModel::Dtm25).unwrap();
+        assert!(height > 500.0 && height < 600.0);
+    }
+
+    #[test]
+    fn search_layers_test() {
+        let geoadmin = GeoAdmin::new();
+        let res = geoadmin.search_layers("Gebäude").unwrap();
+        assert!(!res.results.is_empty());
+    }
+
+    #[test]
+    fn search_features_test() {
+        let geoadmin = GeoAdmin::new();
+        let res: GeoAdminFeatureSearchResponse<f64> = geoadmin
+            .search_features(
+                "Seftigenstrasse 264",
+                &["ch.bfs.gebaeude_wohnungs_register"],
+            )
+            .unwrap();
+        let attrs = &res.results[0].attrs;
+        let feature: GeoAdminFeatureResponse =
+            geoadmin.feature(&attrs.layer, &attrs.feature_id).unwrap();
+        assert_eq!(feature.feature.properties.dplz4, 3084);
+    }
+
+    #[test]
+    fn feature_search_response_test() {
+        let res: GeoAdminFeatureSearchResponse<f64> = serde_json::from_str(
+            r#"{"results": [{"id": 190256, "weight": 1, "attrs": {
+                "origin": "feature",
+                "layer": "ch.swisstopo-vd.stand-oerebkataster",
+                "feature_id": 351,
+                "label": "Köniz"
+            }}]}"#,
+        )
+        .unwrap();
+        let attrs = &res.results[0].attrs;
+        assert_eq!(attrs.feature_id, "351");
+        assert_eq!(attrs.lat, None);
+    }
+
+    #[test]
+    fn elevation_test() {
+        let geoadmin = GeoAdmin::new();
+        let p = Point::new(7.451352119445801, 46.92793655395508);
+        let res = geoadmin.elevation(&p).unwrap().unwrap();
+        assert!(res > 500.0 && res < 600.0);
+    }
+
+    #[test]
+    fn nearby_test() {
+        let geoadmin = GeoAdmin::builder().with_sr("2056").build();
+        let p = Point::new(2_600_968.75, 1_197_427.0);
+        let res = geoadmin
+            .nearby(&p, 300.0, "ch.bav.haltestellen-oev")
+            .unwrap();
+        assert!(!res.is_empty());
+    }
+
+    #[test]
+    fn geocode_result_test() {
+        let location: GeoAdminForwardLocation<f64> = serde_json::from_str(
+            r#"{
+                "id": 1420809,
+                "bbox": [2600763.75, 1196681.375, 2600763.75, 1196681.375],
+                "properties": {
+                    "origin": "address",
+                    "geom_quadindex": "021300220302203002031",
+                    "weight": 1512,
+                    "rank": 7,
+                    "detail": "seftigenstrasse 264 3084 wabern 355 koeniz ch be",
+                    "lat": 46.92793655395508,
+                    "lon": 7.451352119445801,
+                    "num": 264,
+                    "x": 1196681.375,
+                    "y": 2600763.75,
+                    "label": "Seftigenstrasse 264 <b>3084 Wabern</b>",
+                    "zoomlevel": 10
+                }
+            }"#,
+        )
+        .unwrap();
+        let result = GeocodeResult::from(&location);
+        assert_eq!(result.point, Point::new(2_600_763.75, 1_196_681.375));
+        assert_eq!(result.label, "Seftigenstrasse 264 3084 Wabern");
+        assert_eq!(&result.geohash[..5], "u0m6c");
+        assert_eq!(
+            result.bbox.unwrap().center(),
+            Coord {
+                x: 2_600_763.75,
+                y: 1_196_681.375
+            }
+        );
+    }
+
+    #[test]
+    fn geocode_result_wgs84_test() {
+        let location: GeoAdminForwardLocation<f64> = serde_json::from_str(
+            r#"{
+                "id": 1420809,
+                "bbox": [2600968.75, 1197427.0, 2600968.75, 1197427.0],
+                "properties": {
+                    "origin": "address",
+                    "geom_quadindex": "021300220302203002031",
+                    "weight": 1512,
+                    "rank": 7,
+                    "detail": "seftigenstrasse 264 3084 wabern 355 koeniz ch be",
+                    "lat": 46.92793655395508,
+                    "lon": 7.451352119445801,
+                    "num": 264,
+                    "x": 1197427.0,
+                    "y": 2600968.75,
+                    "label": "Seftigenstrasse 264 <b>3084 Wabern</b>",
+                    "zoomlevel": 10
+                }
+            }"#,
+        )
+        .unwrap();
+        let geoadmin = GeoAdmin::builder().with_sr("2056").build();
+        let result = geoadmin.geocode_result(&location);
+        assert_eq!(
+            result.point,
+            Point::new(7.451352119445801, 46.92793655395508)
+        );
+        assert_eq!(result.label, "Seftigenstrasse 264 3084 Wabern");
+        let center = result.bbox.unwrap().center();
+        assert!((center.x - 7.451352119445801).abs() < 1e-4);
+        assert!((center.y - 46.92793655395508).abs() < 1e-4);
+
+        // The bounding box is left as is in WGS84
+        let mut location = location;
+        location.bbox = Some((7.45, 46.92, 7.46, 46.93));
+        let result = GeoAdmin::new().geocode_result(&location);
+        assert_eq!(result.bbox, Some(Rect::new((7.45, 46.92), (7.46, 46.93))));
+    }
+
+    #[test]
+    fn height_response_test() {
+        let res: GeoAdminHeightResponse = serde_json::from_str(r#"{"height": "560.2"}"#).unwrap();
+        assert_eq!(res.height, 560.2);
+    }
+
+    #[test]
+    fn geometry_test() {
+        let json = r#"{"results": [
+            {"id": 1, "featureId": 1, "layerBodId": "ch.kantone.cadastralwebmap-farbe", "layerName": "Parcels", "properties": {},
+             "geometry": {"type": "Polygon", "coordinates": [[[7.0, 46.0, 560.0], [7.1, 46.0, 560.0], [7.1, 46.1, 560.0], [7.0, 46.0, 560.0]]]}},
+            {"id": 2, "featureId": 2, "layerBodId": "ch.bfs.gebaeude_wohnungs_register", "layerName": "Buildings", "properties": {},
+             "geometry": {"rings": [[[0, 0], [0, 10], [10, 10], [10, 0], [0, 0]], [[2, 2], [4, 2], [4, 4], [2, 2]], [[20, 20], [20, 30], [30, 30], [20, 20]]], "spatialReference": {"wkid": 2056}}},
+            {"id": 3, "featureId": 3, "layerBodId": "ch.swisstopo.amtliches-strassenverzeichnis", "layerName": "Streets", "properties": {},
+             "geometry": {"paths": [[[0, 0], [1, 1]]]}},
+            {"id": 4, "featureId": 4, "layerBodId": "ch.swisstopo.amtliches-strassenverzeichnis", "layerName": "Streets", "properties": {},
+             "geometry": {"type": "Unknown"}},
+            {"id": 5, "featureId": 5, "layerBodId": "ch.swisstopo.amtliches-strassenverzeichnis", "layerName": "Streets", "properties": {}}
+        ]}"#;
+        let res: GeoAdminReverseResponse<serde_json::Value> =
+            GeoAdminReverseResponse::from_json_str(json).unwrap();
+        let geometries: Vec<Option<Geometry<f64>>> = res
+            .results
+            .into_iter()
+            .map(|location| location.geometry)
+            .collect();
+        match &geometries[0] {
+            Some(Geometry::Polygon(polygon)) => {
+                assert_eq!(polygon.exterior().0[1], Coord { x: 7.1, y: 46.0 });
+            }
+            other => panic!("unexpected geometry {:?}", other),
+        }
+        // The counterclockwise ring is a hole of the clockwise ring before it
+        match &geometries[1] {
+            Some(Geometry::MultiPolygon(polygons)) => {
+                assert_eq!(polygons.0.len(), 2);
+                assert_eq!(polygons.0[0].interiors().len(), 1);
+                assert!(polygons.0[1].interiors().is_empty());
+            }
+            other => panic!("unexpected geometry {:?}", other),
+        }
+        assert!(matches!(geometries[2], Some(Geometry::LineString(_))));
+        assert_eq!(geometries[3], None);
+        assert_eq!(geometries[4], None);
+    }
+
     #[test]
     fn reverse_test() {
         let geoadmin = GeoAdmin::new();
@@ -535,4 +2636,63 @@ mod test {
             Some("Seftigenstrasse 264, 3084 Wabern".to_string()),
         );
     }
+
+    #[test]
+    fn ping_test() {
+        let server = Server::single(Response::new("200 OK", ""));
+        let geoadmin = GeoAdminBuilder::new()
+            .with_endpoint(&server.url("rest/services/api/"))
+            .build();
+        let status = geoadmin.ping().unwrap();
+        let request = server.request();
+        assert!(request.starts_with("HEAD /rest/services/api/ "));
+        assert_eq!(status.meta.status, 200);
+        assert_eq!(status.message, None);
+    }
+
+    #[test]
+    fn swiss_ids_test() {
+        assert_eq!(" 1272199".parse::<Egid>().unwrap(), Egid(1_272_199));
+        assert!(matches!(
+            "-1".parse::<Egid>(),
+            Err(GeocodingError::InvalidEgid(_))
+        ));
+        assert!("1000000000".parse::<Egid>().is_err());
+        assert_eq!(
+            "CH60746517066a".parse::<Egrid>().unwrap().as_str(),
+            "CH60746517066A"
+        );
+        assert!(matches!(
+            "DE607465170666".parse::<Egrid>(),
+            Err(GeocodingError::InvalidEgrid(_))
+        ));
+        assert!("CH60746517066-".parse::<Egrid>().is_err());
+    }
+
+    #[test]
+    fn find_test() {
+        let parcel = r#"{"results": [{"id": "CH607465170666", "featureId": "CH607465170666", "layerBodId": "ch.kantone.cadastralwebmap-farbe", "layerName": "Parcels",
+                "properties": {"egris_egrid": "CH607465170666", "number": "4171", "identnd": "BE0200000351", "ak": "BE", "bfsnr": 351, "geoportal_url": null},
+                "geometry": {"type": "Polygon", "coordinates": [[[7.45, 46.92], [7.46, 46.92], [7.46, 46.93], [7.45, 46.92]]]}}]}"#;
+        let server = Server::new(vec![
+            Response::json(include_str!("../tests/fixtures/geoadmin_identify.json")),
+            Response::json(parcel),
+        ]);
+        let geoadmin = GeoAdminBuilder::new()
+            .with_endpoint(&server.url("rest/services/api/"))
+            .build();
+        let entrances = geoadmin.find_building("1272199".parse().unwrap()).unwrap();
+        assert_eq!(entrances[0].properties.strname_deinr, "Seftigenstrasse 264");
+        let parcel = geoadmin
+            .find_parcel(&"CH607465170666".parse().unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(parcel.properties.number.as_deref(), Some("4171"));
+        assert!(matches!(parcel.geometry, Some(Geometry::Polygon(_))));
+        let requests = server.requests();
+        assert!(requests[0].starts_with(
+            "GET /rest/services/api/MapServer/find?layer=ch.bfs.gebaeude_wohnungs_register&searchField=egid&searchText=1272199&contains=false&returnGeometry=true&"
+        ));
+        assert!(requests[1].contains("&searchField=egris_egrid&searchText=CH607465170666&"));
+    }
 }
@@ -0,0 +1,160 @@
+//! [Geohash](https://en.wikipedia.org/wiki/Geohash) encoding and decoding.
+//!
+//! A geohash identifies a cell of a grid covering the world: each additional character
+//! divides the cell into 32 smaller cells, and points sharing a prefix are close to each other.
+//! This makes geohashes convenient keys to bucket or join results spatially.
+//! Geohashes are included in [`GeocodeResult`](../struct.GeocodeResult.html).
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::{geohash, Point};
+//!
+//! let p = Point::new(10.40744, 57.64911);
+//! assert_eq!(geohash::encode(&p, 11).unwrap(), "u4pruydqqvj");
+//! let centre: Point<f64> = geohash::decode("u4pruydqqvj").unwrap();
+//! assert!((centre.x() - 10.40744).abs() < 1e-5);
+//! ```
+use crate::Point;
+use geo_types::Rect;
+use num_traits::Float;
+use std::fmt::Debug;
+
+/// The geohash base 32 alphabet
+const ALPHABET: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// The longest supported geohash, with cells smaller than 4 cm
+pub const MAX_PRECISION: usize = 12;
+
+/// Encode a point as a geohash of `precision` characters
+///
+/// Returns `None` if the point is out of range, or if `precision` is not between 1 and
+/// [`MAX_PRECISION`](constant.MAX_PRECISION.html).
+pub fn encode<T>(point: &Point<T>, precision: usize) -> Option<String>
+where
+    T: Float + Debug,
+{
+    let lon = point.x().to_f64()?;
+    let lat = point.y().to_f64()?;
+    if !(1..=MAX_PRECISION).contains(&precision)
+        || !(-180.0..=180.0).contains(&lon)
+        || !(-90.0..=90.0).contains(&lat)
+    {
+        return None;
+    }
+    let (mut lon_range, mut lat_range) = ((-180.0, 180.0), (-90.0, 90.0));
+    let mut even = true;
+    let mut hash = String::with_capacity(precision);
+    for _ in 0..precision {
+        let mut value = 0;
+        for _ in 0..5 {
+            // Bits alternate between longitude and latitude, starting with longitude
+            let (range, coordinate): (&mut (f64, f64), f64) = if even {
+                (&mut lon_range, lon)
+            } else {
+                (&mut lat_range, lat)
+            };
+            let mid = (range.0 + range.1) / 2.0;
+            value <<= 1;
+            if coordinate >= mid {
+                value |= 1;
+                range.0 = mid;
+            } else {
+                range.1 = mid;
+            }
+            even = !even;
+        }
+        hash.push(ALPHABET[value] as char);
+    }
+    Some(hash)
+}
+
+/// Decode a geohash into the point at the centre of its cell
+///
+/// Returns `None` if `hash` is not a valid geohash.
+pub fn decode<T>(hash: &str) -> Option<Point<T>>
+where
+    T: Float + Debug,
+{
+    let bbox = decode_bbox::<f64>(hash)?;
+    let centre = bbox.center();
+    Some(Point::new(T::from(centre.x)?, T::from(centre.y)?))
+}
+
+/// Decode a geohash into the bounding box of its cell
+///
+/// Returns `None` if `hash` is not a valid geohash.
+pub fn decode_bbox<T>(hash: &str) -> Option<Rect<T>>
+where
+    T: Float + Debug,
+{
+    if hash.is_empty() || hash.len() > MAX_PRECISION {
+        return None;
+    }
+    let (mut lon, mut lat) = ((-180.0, 180.0), (-90.0, 90.0));
+    let mut even = true;
+    for c in hash.to_ascii_lowercase().bytes() {
+        let value = ALPHABET.iter().position(|&a| a == c)?;
+        for bit in (0..5).rev() {
+            let range: &mut (f64, f64) = if even { &mut lon } else { &mut lat };
+            let mid = (range.0 + range.1) / 2.0;
+            if (value >> bit) & 1 == 1 {
+                range.0 = mid;
+            } else {
+                range.1 = mid;
+            }
+            even = !even;
+        }
+    }
+    Some(Rect::new(
+        (T::from(lon.0)?, T::from(lat.0)?),
+        (T::from(lon.1)?, T::from(lat.1)?),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode_test() {
+        let p = Point::new(-5.6, 42.6);
+        assert_eq!(encode(&p, 5).unwrap(), "ezs42");
+        assert_eq!(
+            encode(&Point::new(10.40744_f32, 57.64911), 6).unwrap(),
+            "u4pruy"
+        );
+        assert_eq!(encode(&Point::new(180.0, 90.0), 3).unwrap(), "zzz");
+        assert_eq!(encode(&p, 0), None);
+        assert_eq!(encode(&p, 13), None);
+        assert_eq!(encode(&Point::new(0.0, 91.0), 5), None);
+    }
+
+    #[test]
+    fn decode_test() {
+        let p: Point<f64> = decode("ezs42").unwrap();
+        assert!((p.x() - -5.60302734375).abs() < 1e-9);
+        assert!((p.y() - 42.60498046875).abs() < 1e-9);
+        assert_eq!(decode::<f64>("EZS42"), Some(p));
+        assert_eq!(decode::<f64>("ezs4a"), None);
+        assert_eq!(decode::<f64>(""), None);
+    }
+
+    #[test]
+    fn decode_bbox_test() {
+        let bbox: Rect<f64> = decode_bbox("ezs42").unwrap();
+        assert_eq!(bbox.min().x, -5.625);
+        assert_eq!(bbox.min().y, 42.5830078125);
+        assert_eq!(bbox.max().x, -5.5810546875);
+        assert_eq!(bbox.max().y, 42.626953125);
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let p = Point::new(2.12870, 41.40139);
+        let hash = encode(&p, MAX_PRECISION).unwrap();
+        let bbox: Rect<f64> = decode_bbox(&hash).unwrap();
+        assert!(bbox.min().x <= p.x() && p.x() <= bbox.max().x);
+        assert!(bbox.min().y <= p.y() && p.y() <= bbox.max().y);
+    }
+}
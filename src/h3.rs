@@ -0,0 +1,60 @@
+//! [H3](https://h3geo.org/) cell indexes, enabled by the `h3` feature.
+//!
+//! H3 divides the world into hexagonal cells at 16 resolutions, from 0 (about 1,100 km
+//! across) to 15 (about 1 m across). Cell indexes are returned as their `u64` value, which
+//! can be joined against datasets indexed with any H3 implementation.
+//! They can be added to a [`GeocodeResult`](../struct.GeocodeResult.html) using
+//! [`with_h3_cell`](../struct.GeocodeResult.html#method.with_h3_cell).
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::{h3, Point};
+//!
+//! let p = Point::new(2.12870, 41.40139);
+//! let cell = h3::cell(&p, 9).unwrap();
+//! assert_eq!(format!("{:x}", cell), "893944600cfffff");
+//! ```
+use crate::Point;
+use h3o::{LatLng, Resolution};
+use num_traits::Float;
+use std::convert::TryFrom;
+use std::fmt::Debug;
+
+/// The finest H3 resolution
+pub const MAX_RESOLUTION: u8 = 15;
+
+/// The index of the H3 cell containing a point, at `resolution`
+///
+/// Returns `None` if the point is out of range, or if `resolution` is greater than
+/// [`MAX_RESOLUTION`](constant.MAX_RESOLUTION.html).
+pub fn cell<T>(point: &Point<T>, resolution: u8) -> Option<u64>
+where
+    T: Float + Debug,
+{
+    let resolution = Resolution::try_from(resolution).ok()?;
+    let lat = point.y().to_f64()?;
+    let lon = point.x().to_f64()?;
+    if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+        return None;
+    }
+    let latlng = LatLng::new(lat, lon).ok()?;
+    Some(u64::from(latlng.to_cell(resolution)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cell_test() {
+        let p = Point::new(2.12870, 41.40139);
+        let coarse = cell(&p, 0).unwrap();
+        let fine = cell(&p, MAX_RESOLUTION).unwrap();
+        // The resolution is stored in bits 52 to 55
+        assert_eq!((coarse >> 52) & 0xf, 0);
+        assert_eq!((fine >> 52) & 0xf, 15);
+        assert_eq!(cell(&p, 16), None);
+        assert_eq!(cell(&Point::new(0.0, 91.0), 5), None);
+    }
+}
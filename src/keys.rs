@@ -0,0 +1,196 @@
+//! Rotation of API keys.
+//!
+//! Teams holding several free-tier keys can give all of them to a provider in a [`KeyRing`].
+//! Its [`Rotation`] picks the key of each request: either the keys in turn, or the same key
+//! until its quota is exhausted. In both cases, a request whose key is rate-limited or out of
+//! quota is sent again with the next key, until every key has been tried.
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::keys::{KeyRing, Rotation};
+//! use geocoding::{Opencage, Point, Reverse};
+//!
+//! let keys = KeyRing::new(
+//!     vec![
+//!         "dcdbf0d783374909b3debee728c7cc10".to_string(),
+//!         "0123456789abcdef0123456789abcdef".to_string(),
+//!     ],
+//!     Rotation::OnQuotaExhausted,
+//! );
+//! let oc = Opencage::new(String::new()).with_api_keys(keys);
+//! let res = oc.reverse(&Point::new(2.12870, 41.40139)).unwrap();
+//! ```
+#[cfg(any(test, feature = "opencage"))]
+use crate::ExposeSecret;
+use crate::SecretString;
+#[cfg(any(test, feature = "opencage"))]
+use reqwest::Url;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// How a [`KeyRing`] picks the key of each request
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rotation {
+    /// Use the keys in turn, one request each, spreading the requests over their quotas
+    RoundRobin,
+    /// Use the same key until it's rate-limited or its quota is exhausted, then the next one
+    #[default]
+    OnQuotaExhausted,
+}
+
+/// Several API keys of a provider, used according to a [`Rotation`]
+///
+/// The keys are kept in [`SecretString`](../struct.SecretString.html)s, and aren't revealed
+/// in `Debug` output.
+pub struct KeyRing {
+    keys: Vec<SecretString>,
+    rotation: Rotation,
+    /// The index of the key of the next request, modulo the number of keys
+    next: AtomicUsize,
+}
+
+impl KeyRing {
+    /// Create a ring of `keys`, rotated according to `rotation`
+    pub fn new<I, K>(keys: I, rotation: Rotation) -> Self
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<SecretString>,
+    {
+        KeyRing {
+            keys: keys.into_iter().map(Into::into).collect(),
+            rotation,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// The number of keys
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Whether the ring has no key
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The rotation of the keys
+    pub fn rotation(&self) -> Rotation {
+        self.rotation
+    }
+
+    /// The keys of the ring
+    pub fn keys(&self) -> &[SecretString] {
+        &self.keys
+    }
+
+    /// The key the next request would use, without rotating the keys
+    ///
+    /// Returns `None` if the ring has no key.
+    pub fn current(&self) -> Option<&SecretString> {
+        self.keys
+            .get(self.next.load(Ordering::Relaxed) % self.len().max(1))
+    }
+
+    /// The index and the key of the next request, rotating the keys if they are used in turn
+    #[cfg(any(test, feature = "opencage"))]
+    pub(crate) fn select(&self) -> Option<(usize, &SecretString)> {
+        if self.keys.is_empty() {
+            return None;
+        }
+        let next = match self.rotation {
+            Rotation::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed),
+            Rotation::OnQuotaExhausted => self.next.load(Ordering::Relaxed),
+        };
+        let index = next % self.keys.len();
+        Some((index, &self.keys[index]))
+    }
+
+    /// Move on from the key at `index`, which was rate-limited or whose quota is exhausted
+    ///
+    /// Keys used in turn already moved on when they were selected.
+    #[cfg(any(test, feature = "opencage"))]
+    pub(crate) fn exhausted(&self, index: usize) {
+        if self.rotation == Rotation::OnQuotaExhausted {
+            let _ = self
+                .next
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |next| {
+                    (next % self.keys.len() == index).then_some(index + 1)
+                });
+        }
+    }
+}
+
+impl fmt::Debug for KeyRing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("KeyRing")
+            .field("keys", &self.keys)
+            .field("rotation", &self.rotation)
+            .finish()
+    }
+}
+
+/// `url`, with the value of its `param` query parameter replaced by `key`
+#[cfg(any(test, feature = "opencage"))]
+pub(crate) fn with_key(url: &Url, param: &str, key: &SecretString) -> Url {
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(name, value)| {
+            let value = if name == param {
+                key.expose_secret().to_string()
+            } else {
+                value.into_owned()
+            };
+            (name.into_owned(), value)
+        })
+        .collect();
+    let mut url = url.clone();
+    url.query_pairs_mut().clear().extend_pairs(pairs);
+    url
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn keys(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|key| key.to_string()).collect()
+    }
+
+    #[test]
+    fn round_robin_test() {
+        let ring = KeyRing::new(keys(&["a", "b", "c"]), Rotation::RoundRobin);
+        let selected: Vec<usize> = (0..4).map(|_| ring.select().unwrap().0).collect();
+        assert_eq!(selected, vec![0, 1, 2, 0]);
+        ring.exhausted(1);
+        assert_eq!(ring.select().unwrap().0, 1);
+    }
+
+    #[test]
+    fn on_quota_exhausted_test() {
+        let ring = KeyRing::new(keys(&["a", "b"]), Rotation::OnQuotaExhausted);
+        assert_eq!(ring.select().unwrap().0, 0);
+        assert_eq!(ring.select().unwrap().0, 0);
+        ring.exhausted(0);
+        assert_eq!(ring.select().unwrap().1.expose_secret(), "b");
+        // A concurrent request failing with the same key doesn't skip the next one
+        ring.exhausted(0);
+        assert_eq!(ring.select().unwrap().0, 1);
+        ring.exhausted(1);
+        assert_eq!(ring.current().unwrap().expose_secret(), "a");
+        assert!(KeyRing::new(Vec::<String>::new(), Rotation::RoundRobin)
+            .select()
+            .is_none());
+    }
+
+    #[test]
+    fn with_key_test() {
+        let url =
+            Url::parse("https://api.opencagedata.com/geocode/v1/json?q=Berlin&key=a").unwrap();
+        let url = with_key(&url, "key", &SecretString::from("b".to_string()));
+        assert_eq!(
+            url.as_str(),
+            "https://api.opencagedata.com/geocode/v1/json?q=Berlin&key=b"
+        );
+    }
+}
@@ -6,6 +6,13 @@
 //! as this is the lowest common denominator reverse-geocoding result.
 //! Individual providers may implement additional methods, which return more
 //! finely-structured and/or extensive data, and enable more specific query tuning.
+//! Most providers also implement the `ForwardLabels` trait, which returns the label of each
+//! forward-geocoding result along with its coordinates.
+//! The `ReverseFull` trait returns the reverse-geocoding result with its structured `Address`.
+//! Some providers also implement the `AdminReverse` trait, which returns the city, region and
+//! country containing a point, the `NearbySearch` trait, which finds places of a category
+//! around a point, and the `Elevation` trait, which looks up the altitude of a point.
+//! The `HealthCheck` trait verifies that a provider is reachable.
 //! Coordinate data are specified using the [`Point`](struct.Point.html) struct, which has several
 //! convenient `From` implementations to allow for easy construction using primitive types.
 //!
@@ -22,35 +29,257 @@
 //!
 //!```toml
 //![dependencies]
-//!geocoding = { version = "*", default-features = false, features = ["rustls-tls"] }
+//!geocoding = { version = "*", default-features = false, features = ["rustls-tls", "opencage", "osm", "geoadmin"] }
+//!```
+//!
+//! ### Compression and connection reuse
+//!
+//! The default `compression` feature enables gzip, deflate and brotli compressed responses,
+//! which are much smaller for full OpenCage annotations or Nominatim polygons. The
+//! `gzip`, `deflate` and `brotli` features enable each of them separately, e.g. when
+//! using `rustls-tls` without the default features. Compression can be disabled at
+//! runtime with the `with_compression` method of each provider, and connection reuse can be
+//! tuned with [`ClientSettings`](struct.ClientSettings.html).
+//!
+//! ### Cell indexes
+//!
+//! The `h3` and `s2` features enable the [`h3`](h3/index.html) and [`s2`](s2/index.html)
+//! modules, which index points on the H3 and S2 grids, and the corresponding methods of
+//! [`GeocodeResult`](struct.GeocodeResult.html).
+//!
+//! ### Geofencing
+//!
+//! The `geo` feature enables [`GeocodeResult::within`](struct.GeocodeResult.html#method.within),
+//! which checks whether a result is inside any area of the [geo](https://docs.rs/geo) crate,
+//! such as a polygon of a delivery zone.
+//!
+//! ### Batch output
+//!
+//! The `arrow` feature enables the [`arrow`](arrow/index.html) module, which converts
+//! [`batch`](batch/index.html) results to Arrow record batches and writes them to Parquet files.
+//! The `rayon` feature enables parallel batches on a thread pool, and the `async` feature
+//! enables the [`stream`](stream/index.html) module, which geocodes asynchronous streams of
+//! addresses in Tokio applications.
+//!
+//! The `simd-json` feature parses Nominatim and OpenCage responses, including recorded ones
+//! parsed with their `from_json_str` and `from_json_slice` methods, with
+//! [simd-json](https://github.com/simd-lite/simd-json), reducing the CPU time of batches
+//! parsing millions of responses from caches or replay files.
+//!
+//! ### Schema changes
+//!
+//! Nominatim and OpenCage responses keep the top-level fields this crate doesn't know in their
+//! `unknown_fields`, and `Openstreetmap::api_version` returns the Nominatim version of an
+//! instance. With the `tracing` feature, a warning is emitted when a response has unknown
+//! fields, giving early notice that a provider changed its schema.
+//!
+//! ### Address parsing
+//!
+//! The `libpostal` feature enables the [`libpostal`](libpostal/index.html) module, which parses
+//! free-form addresses using [libpostal](https://github.com/openvenues/libpostal). The libpostal
+//! library and its data files must be installed; the library is found with pkg-config.
+//!
+//! ### Choosing providers
+//!
+//! Each provider is enabled by a feature: `opencage`, `osm` (Nominatim) and `geoadmin`, all
+//! enabled by default. The `blocking` feature enables the blocking HTTP client shared by the
+//! providers, and the Open-Elevation and Open Topo Data elevation providers, and is enabled by
+//! the `opencage` and `geoadmin` features. The `async` feature enables the
+//! [`AsyncOpenstreetmap`](openstreetmap/struct.AsyncOpenstreetmap.html) client, which sends
+//! requests without blocking and doesn't need the `blocking` feature, and the
+//! [`stream`](stream/index.html) module, which runs the requests of blocking providers on
+//! Tokio's blocking threads; `stream` is an alias of it. With the `osm` feature, the blocking
+//! `Openstreetmap` client requires the `blocking` feature. The [`service`](service/index.html) and [`config`](config/index.html) modules, which
+//! choose between providers at runtime, require the three providers. The `replay` feature
+//! answers requests from recorded responses, see the [`replay`](replay/index.html) module.
+//!
+//! Without any of these features, the crate still provides the traits, the types of
+//! results, and the offline modules, such as [`coords`](coords/index.html) and
+//! [`CountryReverse`](struct.CountryReverse.html). Nominatim can be used from async code
+//! without the blocking HTTP client:
+//!
+//!```toml
+//![dependencies]
+//!geocoding = { version = "*", default-features = false, features = ["async", "osm"] }
 //!```
 
+#[cfg(any(feature = "blocking", all(feature = "osm", feature = "async")))]
 static UA_STRING: &str = "Rust-Geocoding";
 
-use chrono;
-pub use geo_types::{Coordinate, Point};
+#[allow(deprecated)]
+pub use geo_types::{Coord, Coordinate, Geometry, LineString, Point, Polygon, Rect};
 use num_traits::Float;
+#[cfg(feature = "blocking")]
 use reqwest::blocking::Client;
+#[cfg(any(feature = "blocking", all(feature = "osm", feature = "async")))]
+use reqwest::header::HeaderMap;
 use reqwest::header::ToStrError;
-use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
+#[cfg(feature = "blocking")]
+use reqwest::header::{HeaderValue, USER_AGENT};
+pub use secrecy::{ExposeSecret, SecretString};
+#[cfg(any(feature = "blocking", feature = "osm"))]
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+#[cfg(any(
+    feature = "opencage",
+    all(feature = "osm", any(feature = "blocking", feature = "async"))
+))]
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::num::ParseIntError;
+use std::time::Duration;
 use thiserror::Error;
 
 // The OpenCage geocoding provider
+#[cfg(feature = "opencage")]
 pub mod opencage;
+#[cfg(feature = "opencage")]
 pub use crate::opencage::Opencage;
 
 // The OpenStreetMap Nominatim geocoding provider
+#[cfg(feature = "osm")]
 pub mod openstreetmap;
+#[cfg(all(feature = "osm", feature = "async"))]
+pub use crate::openstreetmap::AsyncOpenstreetmap;
+#[cfg(all(feature = "osm", feature = "blocking"))]
 pub use crate::openstreetmap::Openstreetmap;
 
 // The GeoAdmin geocoding provider
+#[cfg(feature = "geoadmin")]
 pub mod geoadmin;
+#[cfg(feature = "geoadmin")]
 pub use crate::geoadmin::GeoAdmin;
 
+// The Open-Elevation elevation provider
+#[cfg(feature = "blocking")]
+pub mod openelevation;
+#[cfg(feature = "blocking")]
+pub use crate::openelevation::Openelevation;
+
+// The Open Topo Data elevation provider
+#[cfg(feature = "blocking")]
+pub mod opentopodata;
+#[cfg(feature = "blocking")]
+pub use crate::opentopodata::Opentopodata;
+
+// The offline country lookup provider
+pub mod countryreverse;
+pub use crate::countryreverse::CountryReverse;
+
+// Parsing of coordinates typed into search boxes
+pub mod coords;
+
+// A single query type for text, structured addresses and coordinates
+pub mod query;
+pub use crate::query::{geocode, Query, StructuredQuery};
+
+// Geohash encoding and decoding
+pub mod geohash;
+
+// Country-specific formatting of addresses
+pub mod address_format;
+
+// Scoring of results against queries
+pub mod score;
+
+// Human-readable tables of results
+pub mod report;
+
+// Rate limiting of requests shared across provider instances
+pub mod throttle;
+
+// Audit log of the requests sent to providers
+pub mod audit;
+
+// Rotation of the API keys of a provider
+pub mod keys;
+
+// Failover between several endpoints of a provider
+#[cfg(feature = "blocking")]
+pub mod failover;
+
+// Usage statistics of provider instances
+#[cfg(feature = "blocking")]
+pub mod stats;
+
+// Provenance records of lookups
+#[cfg(feature = "blocking")]
+pub mod lineage;
+
+// Sending of requests through the throttle, audit and failover hooks
+#[cfg(feature = "blocking")]
+mod request;
+#[cfg(feature = "blocking")]
+pub use crate::request::{current_request_id, with_request_id, REQUEST_ID_HEADER};
+
+// Stub server of the tests, not all of its helpers being used by every set of features
+#[cfg(all(test, feature = "blocking"))]
+#[allow(dead_code)]
+mod test_server;
+
+// Replay of recorded provider responses
+#[cfg(all(feature = "replay", feature = "blocking"))]
+pub mod replay;
+
+// URLs of provider requests, built without sending them
+#[cfg(all(
+    feature = "blocking",
+    any(feature = "opencage", feature = "osm", feature = "geoadmin")
+))]
+pub mod raw;
+
+// In-memory caching of provider results
+pub mod cache;
+
+// Selection of a provider at runtime
+#[cfg(all(feature = "opencage", feature = "osm", feature = "geoadmin"))]
+pub mod service;
+#[cfg(all(feature = "opencage", feature = "osm", feature = "geoadmin"))]
+pub use crate::service::GeocodingService;
+
+// Setup of providers from configuration files
+#[cfg(all(feature = "opencage", feature = "osm", feature = "geoadmin"))]
+pub mod config;
+
+// Completion of partial addresses
+pub mod completion;
+
+// Fallback chains of geocoders
+pub mod fallback;
+
+// Routing of queries to national geocoders
+pub mod router;
+
+// Batch forward geocoding
+pub mod batch;
+
+// Validation of batch results
+pub mod validate;
+
+// Comparison of the results of two providers
+pub mod compare;
+
+// Arrow and Parquet output of batch results
+#[cfg(feature = "arrow")]
+pub mod arrow;
+
+// Geocoding of asynchronous streams of addresses
+#[cfg(feature = "async")]
+pub mod stream;
+
+// Address parsing with libpostal
+#[cfg(feature = "libpostal")]
+pub mod libpostal;
+
+// H3 cell indexes
+#[cfg(feature = "h3")]
+pub mod h3;
+
+// S2 cell ids
+#[cfg(feature = "s2")]
+pub mod s2;
+
 /// Errors that can occur during geocoding operations
 #[derive(Error, Debug)]
 pub enum GeocodingError {
@@ -58,12 +287,127 @@ pub enum GeocodingError {
     Forward,
     #[error("Reverse geocoding failed")]
     Reverse,
+    /// The credentials of the URL of the request are redacted
     #[error("HTTP request error")]
-    Request(#[from] reqwest::Error),
+    Request(#[source] reqwest::Error),
+    /// The host name of the provider couldn't be resolved
+    #[error("DNS resolution failed")]
+    DnsFailure(#[source] reqwest::Error),
+    /// The connection to the provider timed out
+    #[error("Connection timed out")]
+    ConnectTimeout(#[source] reqwest::Error),
+    /// The TLS handshake with the provider failed, e.g. on an invalid certificate
+    #[error("TLS error")]
+    TlsError(#[source] reqwest::Error),
+    /// The response timed out once connected
+    #[error("Response timed out")]
+    ReadTimeout(#[source] reqwest::Error),
     #[error("Error converting headers to String")]
     HeaderConversion(#[from] ToStrError),
     #[error("Error converting int to String")]
     ParseInt(#[from] ParseIntError),
+    #[error("Invalid OSM id: {0}")]
+    InvalidOsmId(String),
+    #[error("Invalid EGID: {0}")]
+    InvalidEgid(String),
+    #[error("Invalid EGRID: {0}")]
+    InvalidEgrid(String),
+    #[error("Invalid country code: {0}")]
+    InvalidCountryCode(String),
+    #[error("Invalid language tag: {0}")]
+    InvalidLanguageTag(String),
+    /// A point isn't a `[Longitude, Latitude]` pair, e.g. because its coordinates are swapped
+    #[error("Invalid point: {0}")]
+    InvalidPoint(String),
+    #[error("Error parsing boundary data")]
+    Boundaries(#[source] serde_json::Error),
+    #[error("Error writing output")]
+    Io(#[from] std::io::Error),
+    #[error("Error parsing JSON response")]
+    Json(#[source] serde_json::Error),
+    /// The response body isn't JSON, e.g. an HTML error page sent with a 200 status by an
+    /// overloaded server; `snippet` is its beginning
+    #[error("Unexpected response body: {snippet}")]
+    UnexpectedBody { snippet: String },
+    /// A result of the response lacks the `coordinate` of its point
+    #[error("Result without a {coordinate} coordinate")]
+    MissingCoordinate { coordinate: &'static str },
+    /// The response body is larger than the maximum size set on the provider; reading it
+    /// stopped once `limit` bytes were exceeded
+    #[error("Response body larger than {limit} bytes")]
+    ResponseTooLarge { limit: usize },
+    /// The response holds `count` results, more than the maximum count set on the provider
+    #[error("Response with {count} results, more than the maximum of {limit}")]
+    TooManyResults { count: usize, limit: usize },
+    /// The request quota is exhausted until `reset`, if the provider reports it
+    #[error("Request quota exhausted")]
+    QuotaExceeded {
+        reset: Option<chrono::DateTime<chrono::Utc>>,
+    },
+    #[error("Invalid provider configuration: {0}")]
+    Config(String),
+    /// A provider is configured in a way its API rejects, e.g. with an empty API key,
+    /// detected before sending the request
+    #[error("Invalid configuration: {0}")]
+    InvalidConfiguration(String),
+    #[cfg(feature = "libpostal")]
+    #[error("libpostal error: {0}")]
+    Libpostal(String),
+    #[cfg(feature = "arrow")]
+    #[error("Arrow error")]
+    Arrow(#[from] arrow_schema::ArrowError),
+    #[cfg(feature = "arrow")]
+    #[error("Parquet error")]
+    Parquet(#[from] parquet::errors::ParquetError),
+    #[cfg(feature = "rayon")]
+    #[error("Error building the thread pool")]
+    ThreadPool(#[from] rayon::ThreadPoolBuildError),
+}
+
+impl From<reqwest::Error> for GeocodingError {
+    /// Classify the transport failures, and redact the credentials from the URL of the error,
+    /// which its message includes, so that logging errors doesn't reveal API keys
+    fn from(mut error: reqwest::Error) -> Self {
+        if let Some(url) = error.url_mut() {
+            if let Ok(redacted) = reqwest::Url::parse(&audit::redact(url, &[])) {
+                *url = redacted;
+            }
+        }
+        // The connector errors are only distinguished by their messages
+        let mut causes = String::new();
+        let mut source = std::error::Error::source(&error);
+        while let Some(cause) = source {
+            causes.push_str(&cause.to_string().to_lowercase());
+            causes.push('\n');
+            source = cause.source();
+        }
+        let tls = ["tls", "ssl", "certificate", "handshake"];
+        if error.is_connect() && causes.contains("dns error") {
+            GeocodingError::DnsFailure(error)
+        } else if error.is_connect() && error.is_timeout() {
+            GeocodingError::ConnectTimeout(error)
+        } else if error.is_connect() && tls.iter().any(|word| causes.contains(word)) {
+            GeocodingError::TlsError(error)
+        } else if error.is_timeout() {
+            GeocodingError::ReadTimeout(error)
+        } else {
+            GeocodingError::Request(error)
+        }
+    }
+}
+
+impl GeocodingError {
+    /// The error of the HTTP request, if the request failed
+    pub fn request_error(&self) -> Option<&reqwest::Error> {
+        match self {
+            GeocodingError::Request(error)
+            | GeocodingError::DnsFailure(error)
+            | GeocodingError::ConnectTimeout(error)
+            | GeocodingError::TlsError(error)
+            | GeocodingError::ReadTimeout(error) => Some(error),
+            _ => None,
+        }
+    }
 }
 
 /// Reverse-geocode a coordinate.
@@ -94,6 +438,50 @@ where
     fn reverse(&self, point: &Point<T>) -> Result<Option<String>, GeocodingError>;
 }
 
+/// Reverse-geocode a coordinate to a provider-independent result.
+///
+/// Unlike [`Reverse`], the result keeps the components of the address, such as the postcode
+/// and the city, in its normalized [`Address`], so that they don't have to be parsed back
+/// from a formatted string.
+///
+/// Examples
+///
+/// ```
+/// use geocoding::{Openstreetmap, Point, ReverseFull};
+///
+/// let osm = Openstreetmap::new();
+/// let p = Point::new(2.12870, 41.40139);
+/// let address = osm.reverse_structured(&p).unwrap().unwrap();
+/// assert_eq!(address.postcode.unwrap(), "08017");
+/// ```
+pub trait ReverseFull<T>
+where
+    T: Float + Debug,
+{
+    // NOTE TO IMPLEMENTERS: Point coordinates are lon, lat (x, y), and the result should
+    // include its address whenever the provider returns one
+    fn reverse_result(&self, point: &Point<T>) -> Result<Option<GeocodeResult<T>>, GeocodingError>;
+
+    /// The address of the result closest to `point`, if any
+    fn reverse_structured(&self, point: &Point<T>) -> Result<Option<Address>, GeocodingError> {
+        Ok(self.reverse_result(point)?.and_then(|res| res.address))
+    }
+
+    /// A lookup like [`reverse_result`](#tymethod.reverse_result), whose label and address
+    /// are in `language` if the provider has them
+    ///
+    /// The language applies to this call only. Providers which can't localize their results
+    /// return those of `reverse_result`.
+    fn reverse_localized(
+        &self,
+        point: &Point<T>,
+        language: &LanguageTag,
+    ) -> Result<Option<GeocodeResult<T>>, GeocodingError> {
+        let _ = language;
+        self.reverse_result(point)
+    }
+}
+
 /// Forward-geocode a coordinate.
 ///
 /// This trait represents the most simple and minimal implementation available
@@ -122,11 +510,1052 @@ where
     fn forward(&self, address: &str) -> Result<Vec<Point<T>>, GeocodingError>;
 }
 
+/// Forward-geocode a coordinate, keeping the label of each result.
+///
+/// Like [`Forward`], but each `Point` is returned with the provider's formatted label,
+/// e.g. its display name, so that results can be shown to users without resorting to the
+/// provider-specific response types of `forward_full`.
+///
+/// Examples
+///
+/// ```
+/// use geocoding::{ForwardLabels, Openstreetmap, Point};
+///
+/// let osm = Openstreetmap::new();
+/// let res: Vec<(Point<f64>, String)> = osm.forward_with_labels("Schwabing, München").unwrap();
+/// assert!(res[0].1.contains("München"));
+/// ```
+pub trait ForwardLabels<T>
+where
+    T: Float + Debug,
+{
+    // As for Forward, the returned Points must be in lon, lat (x, y) order
+    fn forward_with_labels(&self, address: &str)
+        -> Result<Vec<(Point<T>, String)>, GeocodingError>;
+
+    /// A lookup like [`forward_with_labels`](#tymethod.forward_with_labels), whose labels
+    /// are in `language` if the provider has them
+    ///
+    /// The language applies to this call only, so that a provider shared by users of
+    /// different languages doesn't have to be reconfigured. Providers which can't localize
+    /// their results return those of `forward_with_labels`.
+    fn forward_localized(
+        &self,
+        address: &str,
+        language: &LanguageTag,
+    ) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        let _ = language;
+        self.forward_with_labels(address)
+    }
+}
+
+/// Forward-geocode a structured address, keeping the label of each result.
+///
+/// Providers with a structured search, such as Nominatim, send the components of the
+/// address separately. The others search for the address formatted on one line by
+/// [`address_format::format_one_line`](address_format/fn.format_one_line.html).
+///
+/// Examples
+///
+/// ```
+/// use geocoding::{Address, ForwardStructured, Openstreetmap, Point};
+///
+/// let osm = Openstreetmap::new();
+/// let address = Address {
+///     road: Some("Bundesplatz".to_string()),
+///     house_number: Some("3".to_string()),
+///     city: Some("Bern".to_string()),
+///     ..Default::default()
+/// };
+/// let res: Vec<(Point<f64>, String)> = osm.forward_structured(&address).unwrap();
+/// assert!(res[0].1.contains("Bern"));
+/// ```
+pub trait ForwardStructured<T>: ForwardLabels<T>
+where
+    T: Float + Debug,
+{
+    // As for Forward, the returned Points must be in lon, lat (x, y) order
+    fn forward_structured(
+        &self,
+        address: &Address,
+    ) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        self.forward_with_labels(&address_format::format_one_line(address))
+    }
+
+    /// A lookup like [`forward_structured`](#method.forward_structured), returning each
+    /// result with its address when the provider returns one
+    ///
+    /// Providers without address details return the points and labels of
+    /// `forward_structured`, without an address.
+    fn forward_structured_results(
+        &self,
+        address: &Address,
+    ) -> Result<Vec<GeocodeResult<T>>, GeocodingError> {
+        Ok(self
+            .forward_structured(address)?
+            .into_iter()
+            .map(|(point, label)| GeocodeResult::new(point, label))
+            .collect())
+    }
+}
+
+/// Look up the elevation of a coordinate.
+///
+/// This trait represents the most simple and minimal implementation available
+/// from a given elevation provider: the elevation in meters above sea level, or `None`
+/// if the provider has no data for the coordinate.
+///
+/// Examples
+///
+/// ```
+/// use geocoding::{Elevation, Opentopodata, Point};
+///
+/// let otd = Opentopodata::new();
+/// let p = Point::new(7.451352119445801, 46.92793655395508);
+/// let res = otd.elevation(&p).unwrap();
+/// assert!(res.unwrap() > 500.0);
+/// ```
+pub trait Elevation<T>
+where
+    T: Float + Debug,
+{
+    // NOTE TO IMPLEMENTERS: Point coordinates are lon, lat (x, y)
+    fn elevation(&self, point: &Point<T>) -> Result<Option<f64>, GeocodingError>;
+}
+
+/// Search for places of a category around a coordinate.
+///
+/// Returns the location and label of each place found within `radius` meters of `point`,
+/// ordered by increasing distance. The meaning of `category` depends on the provider,
+/// e.g. an OpenStreetMap tag value such as `"cafe"` for Nominatim, or a layer id for GeoAdmin.
+///
+/// Examples
+///
+/// ```
+/// use geocoding::{NearbySearch, Openstreetmap, Point};
+///
+/// let osm = Openstreetmap::new();
+/// let p = Point::new(2.12870, 41.40139);
+/// let res = osm.nearby(&p, 500.0, "cafe").unwrap();
+/// assert!(!res.is_empty());
+/// ```
+pub trait NearbySearch<T>
+where
+    T: Float + Debug,
+{
+    // NOTE TO IMPLEMENTERS: Point coordinates are lon, lat (x, y), and `radius` is in meters
+    fn nearby(
+        &self,
+        point: &Point<T>,
+        radius: f64,
+        category: &str,
+    ) -> Result<Vec<(Point<T>, String)>, GeocodingError>;
+}
+
+/// Reverse-geocode a coordinate to the administrative areas containing it.
+///
+/// Returns the city, region and country containing `point`, including their ISO codes
+/// where the provider supplies them, or `None` if the point is not within any country.
+///
+/// Examples
+///
+/// ```
+/// use geocoding::{AdminReverse, Openstreetmap, Point};
+///
+/// let osm = Openstreetmap::new();
+/// let p = Point::new(2.12870, 41.40139);
+/// let res = osm.admin_reverse(&p).unwrap().unwrap();
+/// assert_eq!(res.country_code.unwrap(), "ES");
+/// ```
+pub trait AdminReverse<T>
+where
+    T: Float + Debug,
+{
+    // NOTE TO IMPLEMENTERS: Point coordinates are lon, lat (x, y)
+    fn admin_reverse(&self, point: &Point<T>) -> Result<Option<AdminArea>, GeocodingError>;
+}
+
+/// Check that a provider is reachable and accepts the configured credentials.
+///
+/// Orchestration layers can call `ping` at startup, instead of failing on the first real
+/// request. It fails with the error of the request, e.g. an HTTP error for an invalid API key.
+/// Each provider sends its cheapest request: Nominatim queries its `/status` endpoint,
+/// OpenCage sends a trivial query, which counts against the quota, and GeoAdmin sends a `HEAD`
+/// request to its endpoint.
+///
+/// Examples
+///
+/// ```
+/// use geocoding::{HealthCheck, Openstreetmap};
+///
+/// let osm = Openstreetmap::new();
+/// let status = osm.ping().unwrap();
+/// assert_eq!(status.meta.status, 200);
+/// ```
+pub trait HealthCheck {
+    fn ping(&self) -> Result<ProviderStatus, GeocodingError>;
+}
+
+/// The result of a successful [`HealthCheck`](trait.HealthCheck.html)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProviderStatus {
+    /// The details of the response, including the time it took
+    pub meta: ResponseMeta,
+    /// The status message reported by the provider, if any
+    pub message: Option<String>,
+}
+
+/// A geocoder of `f64` coordinates, whatever its provider and wrappers
+///
+/// This is implemented by all types implementing [`Forward`], [`ForwardLabels`] and
+/// [`Reverse`] for `f64`, so that a configured stack can be used as a `Box<dyn Geocoder>`,
+/// e.g. one built by [`GeocoderConfig::build`](config/struct.GeocoderConfig.html#method.build).
+pub trait Geocoder: Forward<f64> + ForwardLabels<f64> + Reverse<f64> + Send + Sync {}
+
+impl<G> Geocoder for G where G: Forward<f64> + ForwardLabels<f64> + Reverse<f64> + Send + Sync {}
+
+/// The credit a provider requires applications displaying its results to give
+///
+/// Returned by the `attribution` method of each geocoding provider, so that applications
+/// can comply with licences such as the ODbL programmatically. Services behind custom
+/// endpoints, e.g. Nominatim-compatible commercial services, may require other credits.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Attribution {
+    /// The credit to display
+    pub text: String,
+    /// The licence of the data, as an SPDX identifier, if the provider has a single one
+    pub licence: Option<String>,
+    /// The page detailing the requirements
+    pub url: String,
+}
+
+impl Attribution {
+    #[cfg(any(
+        feature = "opencage",
+        feature = "geoadmin",
+        all(feature = "osm", any(feature = "blocking", feature = "async"))
+    ))]
+    pub(crate) fn new(text: &str, licence: Option<&str>, url: &str) -> Self {
+        Attribution {
+            text: text.to_string(),
+            licence: licence.map(str::to_string),
+            url: url.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for Attribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// How much of the queries sent to a provider it may retain
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrivacyMode {
+    /// The provider may log and store queries following its usual policy
+    #[default]
+    Standard,
+    /// The provider is asked not to log or store the queries, e.g. OpenCage's `no_record`
+    NoRecord,
+}
+
+/// Control whether a provider may retain the queries sent to it.
+///
+/// Only providers which can honor every [`PrivacyMode`] implement this trait, so that
+/// privacy-sensitive code can require it: providers without a privacy parameter, such as
+/// `Openstreetmap` and `GeoAdmin`, are rejected at compile time.
+///
+/// Examples
+///
+/// ```
+/// use geocoding::{Forward, Opencage, Point, PrivacyControl, PrivacyMode};
+///
+/// fn private_forward<P>(provider: P, address: &str) -> Vec<Point<f64>>
+/// where
+///     P: Forward<f64> + PrivacyControl,
+/// {
+///     let provider = provider.with_privacy_mode(PrivacyMode::NoRecord);
+///     provider.forward(address).unwrap()
+/// }
+///
+/// let oc = Opencage::new("dcdbf0d783374909b3debee728c7cc10".to_string());
+/// let res = private_forward(oc, "Moabit, Berlin");
+/// ```
+///
+/// ```compile_fail
+/// # use geocoding::{Openstreetmap, PrivacyControl, PrivacyMode};
+/// // Nominatim has no way to opt out of query logging
+/// let osm = Openstreetmap::new().with_privacy_mode(PrivacyMode::NoRecord);
+/// ```
+pub trait PrivacyControl {
+    /// Set the privacy mode of the queries sent to the provider
+    fn with_privacy_mode(self, mode: PrivacyMode) -> Self
+    where
+        Self: Sized;
+
+    /// The privacy mode of the queries sent to the provider
+    fn privacy_mode(&self) -> PrivacyMode;
+}
+
+/// An ISO 3166-1 alpha-2 country code, such as `"CH"`
+///
+/// Codes are parsed ignoring case, and displayed in upper case.
+///
+/// ```
+/// use geocoding::CountryCode;
+///
+/// let code: CountryCode = "ch".parse().unwrap();
+/// assert_eq!(code.to_string(), "CH");
+/// assert!("CHE".parse::<CountryCode>().is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CountryCode([u8; 2]);
+
+impl CountryCode {
+    /// The upper-case code
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.0).expect("country codes are ASCII letters")
+    }
+}
+
+impl std::str::FromStr for CountryCode {
+    type Err = GeocodingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.as_bytes() {
+            [a, b] if a.is_ascii_alphabetic() && b.is_ascii_alphabetic() => Ok(CountryCode([
+                a.to_ascii_uppercase(),
+                b.to_ascii_uppercase(),
+            ])),
+            _ => Err(GeocodingError::InvalidCountryCode(s.to_string())),
+        }
+    }
+}
+
+impl std::convert::TryFrom<String> for CountryCode {
+    type Error = GeocodingError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<CountryCode> for String {
+    fn from(code: CountryCode) -> Self {
+        code.as_str().to_string()
+    }
+}
+
+impl std::fmt::Display for CountryCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A BCP 47 language tag, such as `"de"` or `"pt-BR"`
+///
+/// Tags are checked to consist of alphanumeric subtags of up to 8 characters, starting
+/// with a language subtag of 2 to 8 letters, and are kept as given.
+///
+/// ```
+/// use geocoding::LanguageTag;
+///
+/// let tag: LanguageTag = "pt-BR".parse().unwrap();
+/// assert_eq!(tag.as_str(), "pt-BR");
+/// assert!("pt_BR".parse::<LanguageTag>().is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct LanguageTag(String);
+
+impl LanguageTag {
+    /// The tag
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::str::FromStr for LanguageTag {
+    type Err = GeocodingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut subtags = s.split('-');
+        let language = subtags.next().unwrap_or_default();
+        let valid = (2..=8).contains(&language.len())
+            && language.bytes().all(|b| b.is_ascii_alphabetic())
+            && subtags.all(|subtag| {
+                (1..=8).contains(&subtag.len()) && subtag.bytes().all(|b| b.is_ascii_alphanumeric())
+            });
+        if valid {
+            Ok(LanguageTag(s.to_string()))
+        } else {
+            Err(GeocodingError::InvalidLanguageTag(s.to_string()))
+        }
+    }
+}
+
+impl std::convert::TryFrom<String> for LanguageTag {
+    type Error = GeocodingError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<LanguageTag> for String {
+    fn from(tag: LanguageTag) -> Self {
+        tag.0
+    }
+}
+
+impl std::fmt::Display for LanguageTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// The kind of feature a reverse lookup snaps to
+///
+/// Providers snap points to different features by default: Nominatim returns the nearest
+/// object of any kind, OpenCage the nearest address, and GeoAdmin the nearest building.
+/// The preference is mapped to the parameters of each provider:
+///
+/// - Nominatim: the `zoom` level (18, 17 or 10) and the `address` layer
+/// - OpenCage: `address_only` for addresses and `roadinfo` for roads; it has no parameter
+///   for localities
+/// - GeoAdmin: the layer identified, see
+///   [`GeoAdminReverseParams::with_snapping`](geoadmin/struct.GeoAdminReverseParams.html#method.with_snapping)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReverseSnapping {
+    /// The nearest address, e.g. a building with its house number
+    Address,
+    /// The nearest road
+    Road,
+    /// The locality containing the point, e.g. a city or village
+    Locality,
+}
+
+/// The administrative areas containing a coordinate, as returned by [`AdminReverse`]
+///
+/// - `region_code` is an ISO 3166-2 subdivision code, e.g. `"ES-CT"`
+/// - `country_code` is an upper-case ISO 3166-1 alpha-2 code, e.g. `"ES"`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdminArea {
+    pub city: Option<String>,
+    pub region: Option<String>,
+    pub region_code: Option<String>,
+    pub country: Option<String>,
+    pub country_code: Option<String>,
+}
+
+/// A postal address in a provider-independent form
+///
+/// Provider-specific addresses can be converted into this type using `From`, and rendered
+/// in the format used in their country with the [`address_format`](address_format/index.html) module.
+///
+/// - `state_code` is the abbreviation of the state used in postal addresses, e.g. `"CA"`
+/// - `country_code` is an upper-case ISO 3166-1 alpha-2 code, e.g. `"US"`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Address {
+    pub house_number: Option<String>,
+    pub road: Option<String>,
+    pub suburb: Option<String>,
+    pub city: Option<String>,
+    pub postcode: Option<String>,
+    pub state: Option<String>,
+    pub state_code: Option<String>,
+    pub country: Option<String>,
+    pub country_code: Option<String>,
+}
+
+/// A geocoding result in a provider-independent form
+///
+/// Provider-specific results can be converted into this type using `From`, e.g.
+/// `GeocodeResult::from(&response.features[0])` for an `OpenstreetmapResponse`, or
+/// `TryFrom` for OpenCage results, whose `geometry` may lack a coordinate.
+///
+/// `geohash` is the geohash of `point` at full (12 character) precision, which can be
+/// truncated to bucket results into larger cells. `address` and `bbox` are set when the
+/// provider returned a structured address or a bounding box, and `match_score` once set by
+/// [`with_match_score`](#method.with_match_score). `licence` is the licence of the data
+/// reported in the provider's response, set by the
+/// [`GeocodingService`](service/enum.GeocodingService.html) methods.
+///
+/// With the `h3` and `s2` features, results can also be annotated with the H3 cell
+/// or S2 cell containing them, at a chosen resolution. With the `geo` feature,
+/// [`within`](#method.within) checks whether a result is inside an area.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeocodeResult<T>
+where
+    T: Float + Debug,
+{
+    pub point: Point<T>,
+    pub label: String,
+    pub bbox: Option<Rect<T>>,
+    pub address: Option<Address>,
+    pub geohash: String,
+    pub match_score: Option<f64>,
+    pub licence: Option<String>,
+    #[cfg(feature = "h3")]
+    pub h3_cell: Option<u64>,
+    #[cfg(feature = "s2")]
+    pub s2_cell: Option<u64>,
+}
+
+impl<T> GeocodeResult<T>
+where
+    T: Float + Debug,
+{
+    /// Create a new result, computing the geohash of `point`
+    pub fn new(point: Point<T>, label: String) -> Self {
+        let geohash = geohash::encode(&point, geohash::MAX_PRECISION).unwrap_or_default();
+        GeocodeResult {
+            point,
+            label,
+            bbox: None,
+            address: None,
+            geohash,
+            match_score: None,
+            licence: None,
+            #[cfg(feature = "h3")]
+            h3_cell: None,
+            #[cfg(feature = "s2")]
+            s2_cell: None,
+        }
+    }
+
+    /// Set `match_score` to the score of the result against `query`
+    ///
+    /// See the [`score`](score/index.html) module for details.
+    pub fn with_match_score(mut self, query: &str) -> Self {
+        self.match_score = Some(score::match_score(
+            query,
+            &self.label,
+            self.address.as_ref(),
+        ));
+        self
+    }
+
+    /// Set `h3_cell` to the index of the H3 cell containing `point` at `resolution`
+    ///
+    /// `point` must be in WGS84 coordinates.
+    #[cfg(feature = "h3")]
+    pub fn with_h3_cell(mut self, resolution: u8) -> Self {
+        self.h3_cell = h3::cell(&self.point, resolution);
+        self
+    }
+
+    /// Set `s2_cell` to the id of the S2 cell containing `point` at `level`
+    ///
+    /// `point` must be in WGS84 coordinates.
+    #[cfg(feature = "s2")]
+    pub fn with_s2_cell(mut self, level: u8) -> Self {
+        self.s2_cell = s2::cell_id(&self.point, level);
+        self
+    }
+}
+
+#[cfg(feature = "geo")]
+impl<T> GeocodeResult<T>
+where
+    T: Float + Debug + geo::CoordNum,
+{
+    /// Whether `point` is inside `area`, e.g. a service area, delivery zone or country polygon
+    ///
+    /// Points on the boundary of a polygon aren't inside it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geo::polygon;
+    /// use geocoding::{GeocodeResult, Point};
+    ///
+    /// let zone = polygon![(x: 2.0, y: 41.3), (x: 2.3, y: 41.3), (x: 2.3, y: 41.5), (x: 2.0, y: 41.5)];
+    /// let result = GeocodeResult::new(Point::new(2.1287, 41.4014), "Carrer de Calatrava".to_string());
+    /// assert!(result.within(&zone));
+    /// ```
+    pub fn within<G>(&self, area: &G) -> bool
+    where
+        G: geo::Contains<Point<T>> + ?Sized,
+    {
+        area.contains(&self.point)
+    }
+
+    /// The position of the first of `areas` containing `point`, if any
+    ///
+    /// This assigns results to zones, e.g. to the delivery zone serving them.
+    pub fn within_any<G>(&self, areas: &[G]) -> Option<usize>
+    where
+        G: geo::Contains<Point<T>>,
+    {
+        areas.iter().position(|area| self.within(area))
+    }
+}
+
+/// Deserialize a string or an integer into a string, as providers return ids as either
+#[cfg(any(feature = "opencage", feature = "geoadmin"))]
+pub(crate) fn deserialize_string_or_int<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrInt {
+        String(String),
+        Int(i64),
+    }
+
+    match StringOrInt::deserialize(deserializer)? {
+        StringOrInt::String(s) => Ok(s),
+        StringOrInt::Int(i) => Ok(i.to_string()),
+    }
+}
+
+/// Parse a provider response from its JSON text
+#[cfg(any(feature = "blocking", feature = "osm"))]
+pub(crate) fn from_json_str<R>(json: &str) -> Result<R, GeocodingError>
+where
+    R: DeserializeOwned,
+{
+    #[cfg(feature = "simd-json")]
+    return from_json_slice(&mut json.as_bytes().to_vec());
+    #[cfg(not(feature = "simd-json"))]
+    serde_json::from_str(json).map_err(GeocodingError::Json)
+}
+
+/// The maximum length in characters of the snippets of unexpected bodies
+#[cfg(any(
+    feature = "opencage",
+    all(feature = "osm", any(feature = "blocking", feature = "async"))
+))]
+const SNIPPET_LENGTH: usize = 200;
+
+/// Parse the JSON body of a response, with simd-json when the `simd-json` feature is enabled
+///
+/// Bodies which aren't a JSON object or array, such as the HTML error pages of overloaded
+/// servers, fail with `GeocodingError::UnexpectedBody`.
+#[cfg(any(
+    feature = "opencage",
+    all(feature = "osm", any(feature = "blocking", feature = "async"))
+))]
+pub(crate) fn from_json_body<R>(body: hyper::body::Bytes) -> Result<R, GeocodingError>
+where
+    R: DeserializeOwned,
+{
+    match body.iter().find(|byte| !byte.is_ascii_whitespace()) {
+        Some(b'{') | Some(b'[') => from_json_bytes(body),
+        _ => Err(GeocodingError::UnexpectedBody {
+            snippet: snippet(&body),
+        }),
+    }
+}
+
+/// The beginning of a body, with its whitespace collapsed
+#[cfg(any(
+    feature = "opencage",
+    all(feature = "osm", any(feature = "blocking", feature = "async"))
+))]
+fn snippet(body: &[u8]) -> String {
+    String::from_utf8_lossy(body)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(SNIPPET_LENGTH)
+        .collect()
+}
+
+/// Parse a provider response from a body read by `reqwest`
+///
+/// The body is only copied with the `simd-json` feature, which parses a mutable buffer.
+#[cfg(any(
+    feature = "opencage",
+    all(feature = "osm", any(feature = "blocking", feature = "async"))
+))]
+fn from_json_bytes<R>(json: hyper::body::Bytes) -> Result<R, GeocodingError>
+where
+    R: DeserializeOwned,
+{
+    #[cfg(feature = "simd-json")]
+    return from_json_slice(&mut Vec::from(json));
+    #[cfg(not(feature = "simd-json"))]
+    serde_json::from_slice(&json).map_err(GeocodingError::Json)
+}
+
+/// Parse a provider response from its JSON bytes
+///
+/// With the `simd-json` feature, the bytes are parsed in place with simd-json, and are left
+/// in an unspecified state.
+#[cfg(any(
+    feature = "opencage",
+    feature = "osm",
+    all(feature = "blocking", feature = "simd-json")
+))]
+pub(crate) fn from_json_slice<R>(json: &mut [u8]) -> Result<R, GeocodingError>
+where
+    R: DeserializeOwned,
+{
+    #[cfg(feature = "simd-json")]
+    return simd_json::serde::from_slice(json)
+        .map_err(|e| GeocodingError::Json(serde::de::Error::custom(e)));
+    #[cfg(not(feature = "simd-json"))]
+    serde_json::from_slice(json).map_err(GeocodingError::Json)
+}
+
+/// Warn that the response of `provider` has top-level fields unknown to this crate
+///
+/// This is a no-op without the `tracing` feature.
+#[cfg(any(
+    feature = "opencage",
+    all(feature = "osm", any(feature = "blocking", feature = "async"))
+))]
+pub(crate) fn warn_unknown_fields(provider: &str, fields: &HashMap<String, serde_json::Value>) {
+    #[cfg(feature = "tracing")]
+    if !fields.is_empty() {
+        let mut names: Vec<&str> = fields.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        tracing::warn!(
+            provider,
+            fields = %names.join(", "),
+            "response with unknown fields, the provider may have changed its schema"
+        );
+    }
+    #[cfg(not(feature = "tracing"))]
+    let _ = (provider, fields);
+}
+
+/// The `extra` query parameters which aren't already in `query`, as the parameters modelled
+/// by the crate take precedence
+#[cfg(any(
+    feature = "opencage",
+    feature = "geoadmin",
+    all(feature = "osm", any(feature = "blocking", feature = "async"))
+))]
+pub(crate) fn extra_params<'e, K, V, N, X>(
+    query: &[(K, V)],
+    extra: &'e [(N, X)],
+) -> Vec<(&'e str, &'e str)>
+where
+    K: AsRef<str>,
+    N: AsRef<str>,
+    X: AsRef<str>,
+{
+    extra
+        .iter()
+        .filter(|(name, _)| !query.iter().any(|(key, _)| key.as_ref() == name.as_ref()))
+        .map(|(name, value)| (name.as_ref(), value.as_ref()))
+        .collect()
+}
+
+/// Format a coordinate for a query string
+///
+/// With `precision`, the coordinate is rounded to that many decimals, and trailing zeros are
+/// removed. Otherwise, `f32` coordinates are formatted with the shortest representation of
+/// their `f32` value, rather than of their (longer) `f64` conversion.
+pub(crate) fn format_coordinate<T>(value: T, precision: Option<u8>) -> String
+where
+    T: Float + Debug,
+{
+    let v = value.to_f64().unwrap();
+    // The shortest representation of an `f32` value, e.g. 51.6 rather than 51.5999985
+    let is_f32 = T::epsilon().to_f64().unwrap() > f64::EPSILON;
+    let v = if is_f32 {
+        (v as f32).to_string().parse().unwrap_or(v)
+    } else {
+        v
+    };
+    match precision {
+        Some(precision) => {
+            let rounded = format!("{:.*}", precision as usize, v);
+            let trimmed = if rounded.contains('.') {
+                rounded.trim_end_matches('0').trim_end_matches('.')
+            } else {
+                &rounded
+            };
+            match trimmed {
+                "-0" => "0".to_string(),
+                t => t.to_string(),
+            }
+        }
+        None if is_f32 => (v as f32).to_string(),
+        None => v.to_string(),
+    }
+}
+
+/// The headers kept in [`ResponseMeta`](struct.ResponseMeta.html), in addition to the
+/// `x-ratelimit-*` and `ratelimit-*` headers
+pub const HEADERS_OF_INTEREST: &[&str] = &[
+    "age",
+    "cache-control",
+    "date",
+    "retry-after",
+    "server",
+    "x-cache",
+    "x-request-id",
+];
+
+/// Details of the HTTP response from which a full response was parsed
+///
+/// `forward_full` and `reverse_full` responses include them, to help debugging the
+/// behavior of a provider: `status` is the HTTP status code, `headers_of_interest` the
+/// rate limit, caching and tracing headers with their values, and `request_duration` the
+/// time until the response headers were received, excluding any throttling delay.
+///
+/// They also describe the call, to track its latency: `elapsed` is the time it took in
+/// total, including throttling delays and the requests to endpoints which failed, `attempts`
+/// the number of requests sent, and `endpoint` the endpoint which sent the response, which
+/// differs from the provider's own when a [failover](failover/index.html) endpoint served it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResponseMeta {
+    pub status: u16,
+    pub headers_of_interest: BTreeMap<String, String>,
+    pub request_duration: Duration,
+    pub elapsed: Duration,
+    pub attempts: u32,
+    pub endpoint: String,
+}
+
+/// How a request was sent, whatever the transport
+///
+/// Only read by the providers, into the `ResponseMeta` of their responses.
+#[cfg(any(feature = "blocking", all(feature = "osm", feature = "async")))]
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    not(any(
+        feature = "opencage",
+        feature = "geoadmin",
+        all(feature = "osm", any(feature = "blocking", feature = "async"))
+    )),
+    allow(dead_code)
+)]
+pub(crate) struct Sent {
+    /// The time until the response headers were received, excluding throttling delays
+    pub(crate) duration: Duration,
+    /// The time the call took in total, including throttling delays and failed attempts
+    pub(crate) elapsed: Duration,
+    /// The number of requests sent, including those to failed endpoints
+    pub(crate) attempts: u32,
+    /// The endpoint which sent the response
+    pub(crate) endpoint: String,
+}
+
+impl ResponseMeta {
+    #[cfg(any(
+        feature = "opencage",
+        feature = "geoadmin",
+        all(feature = "osm", any(feature = "blocking", feature = "async"))
+    ))]
+    pub(crate) fn new(status: reqwest::StatusCode, headers: &HeaderMap, sent: Sent) -> Self {
+        let headers_of_interest = headers
+            .iter()
+            .filter(|(name, _)| {
+                let name = name.as_str();
+                HEADERS_OF_INTEREST.contains(&name)
+                    || name.starts_with("x-ratelimit-")
+                    || name.starts_with("ratelimit-")
+            })
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        ResponseMeta {
+            status: status.as_u16(),
+            headers_of_interest,
+            request_duration: sent.duration,
+            elapsed: sent.elapsed,
+            attempts: sent.attempts,
+            endpoint: sent.endpoint,
+        }
+    }
+}
+
+/// A response body as received, for deserializing into types which borrow from it
+///
+/// Returned by the `forward_full_raw` methods of providers. Deserializing into types with
+/// `&str` or `Cow<str>` fields, such as the Nominatim
+/// [`BorrowedResponse`](openstreetmap/struct.BorrowedResponse.html), avoids allocating a
+/// `String` per field, which shows up in profiles of high-throughput pipelines.
+#[derive(Clone, Debug)]
+pub struct RawResponse {
+    body: Vec<u8>,
+    /// Details of the HTTP response
+    pub meta: ResponseMeta,
+}
+
+impl RawResponse {
+    #[cfg(all(feature = "blocking", any(feature = "opencage", feature = "osm")))]
+    pub(crate) fn new(body: Vec<u8>, meta: ResponseMeta) -> Self {
+        RawResponse { body, meta }
+    }
+
+    /// The body of the response
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Return the body of the response
+    pub fn into_body(self) -> Vec<u8> {
+        self.body
+    }
+
+    /// Deserialize the body into `R`, which may borrow from the body
+    pub fn parse<'a, R>(&'a self) -> Result<R, GeocodingError>
+    where
+        R: Deserialize<'a>,
+    {
+        serde_json::from_slice(&self.body).map_err(GeocodingError::Json)
+    }
+}
+
+/// Settings of the HTTP client of a provider, for connection reuse and compression
+///
+/// The defaults suit occasional requests. High-throughput batch jobs can keep more idle
+/// connections open for longer, so that TLS handshakes aren't repeated for each request.
+/// Settings are applied with the `with_client_settings` method of each provider.
+///
+/// # Examples
+///
+/// ```
+/// use geocoding::{ClientSettings, Openstreetmap};
+/// use std::time::Duration;
+///
+/// let settings = ClientSettings::new()
+///     .with_pool_max_idle_per_host(16)
+///     .with_pool_idle_timeout(Some(Duration::from_secs(300)))
+///     .with_tcp_keepalive(Some(Duration::from_secs(60)))
+///     .build();
+/// let osm = Openstreetmap::new().with_client_settings(&settings);
+/// ```
+#[cfg(feature = "blocking")]
+#[derive(Clone, Debug)]
+pub struct ClientSettings {
+    // Default headers, in addition to the crate's `User-Agent`, which they may override
+    pub(crate) headers: HeaderMap,
+    pub(crate) compression: bool,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Option<Duration>>,
+    tcp_keepalive: Option<Duration>,
+    http2_prior_knowledge: bool,
+}
+
+#[cfg(feature = "blocking")]
+impl ClientSettings {
+    /// Create new client settings, with the defaults of the HTTP client
+    pub fn new() -> ClientSettings {
+        ClientSettings {
+            headers: HeaderMap::new(),
+            compression: true,
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: None,
+            tcp_keepalive: None,
+            http2_prior_knowledge: false,
+        }
+    }
+
+    /// Accept compressed responses, `true` by default
+    ///
+    /// Only the compressions enabled by the crate's features are negotiated.
+    pub fn with_compression(&mut self, compression: bool) -> &mut Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Set the maximum number of idle connections kept open to each host, unlimited by default
+    pub fn with_pool_max_idle_per_host(&mut self, max: usize) -> &mut Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Set how long idle connections are kept open, 90 seconds by default
+    ///
+    /// `None` keeps idle connections open until the server closes them.
+    pub fn with_pool_idle_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Send TCP keep-alive probes on idle connections at this interval, disabled by default
+    pub fn with_tcp_keepalive(&mut self, interval: Option<Duration>) -> &mut Self {
+        self.tcp_keepalive = interval;
+        self
+    }
+
+    /// Use HTTP/2 without negotiating it, `false` by default
+    ///
+    /// HTTP/2 multiplexes concurrent requests over a single connection, but the server
+    /// must support it: this is the case of the Nominatim and OpenCage servers.
+    pub fn with_http2_prior_knowledge(&mut self, http2: bool) -> &mut Self {
+        self.http2_prior_knowledge = http2;
+        self
+    }
+
+    /// Build and return an instance of ClientSettings
+    pub fn build(&self) -> ClientSettings {
+        self.clone()
+    }
+
+    /// Build a client with these settings
+    pub(crate) fn client(&self) -> Client {
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, HeaderValue::from_static(UA_STRING));
+        headers.extend(self.headers.clone());
+        let mut builder = Client::builder()
+            .default_headers(headers)
+            .tcp_keepalive(self.tcp_keepalive);
+        #[cfg(feature = "gzip")]
+        {
+            builder = builder.gzip(self.compression);
+        }
+        #[cfg(feature = "deflate")]
+        {
+            builder = builder.deflate(self.compression);
+        }
+        #[cfg(feature = "brotli")]
+        {
+            builder = builder.brotli(self.compression);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if self.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+        builder.build().expect("Couldn't build a client!")
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl Default for ClientSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The great-circle distance between two WGS84 points, in meters
+pub(crate) fn haversine_distance<T>(a: &Point<T>, b: &Point<T>) -> f64
+where
+    T: Float + Debug,
+{
+    const EARTH_RADIUS: f64 = 6_371_008.8;
+    let (lon_a, lat_a) = (a.x().to_f64().unwrap(), a.y().to_f64().unwrap());
+    let (lon_b, lat_b) = (b.x().to_f64().unwrap(), b.y().to_f64().unwrap());
+    let d_lat = (lat_b - lat_a).to_radians();
+    let d_lon = (lon_b - lon_a).to_radians();
+    let h = (d_lat / 2.0).sin().powi(2)
+        + lat_a.to_radians().cos() * lat_b.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * h.sqrt().asin()
+}
+
 /// Used to specify a bounding box to search within when forward-geocoding
 ///
 /// - `minimum` refers to the **bottom-left** or **south-west** corner of the bounding box
 /// - `maximum` refers to the **top-right** or **north-east** corner of the bounding box.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct InputBounds<T>
 where
     T: Float + Debug,
@@ -151,21 +1580,520 @@ where
             maximum_lonlat: maximum_lonlat.into(),
         }
     }
+
+    /// The query string representation of the bounds, with coordinates rounded to
+    /// `precision` decimals
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::InputBounds;
+    ///
+    /// let bounds = InputBounds::new((-0.13806939125061035, 51.51989264641164), (-0.13427138328552246, 51.52319711775629));
+    /// assert_eq!(bounds.to_string_with_precision(4), "-0.1381,51.5199,-0.1343,51.5232");
+    /// ```
+    pub fn to_string_with_precision(&self, precision: u8) -> String {
+        // Return in lon, lat order
+        format!(
+            "{},{},{},{}",
+            format_coordinate(self.minimum_lonlat.x(), Some(precision)),
+            format_coordinate(self.minimum_lonlat.y(), Some(precision)),
+            format_coordinate(self.maximum_lonlat.x(), Some(precision)),
+            format_coordinate(self.maximum_lonlat.y(), Some(precision))
+        )
+    }
 }
 
+/// The number of decimals of the coordinates of bounds sent to providers
+///
+/// 7 decimals are precise to about a centimeter in degrees, and to a tenth of a micrometer
+/// in the meters of projected coordinates.
+pub const BOUNDS_PRECISION: u8 = 7;
+
 /// Convert borrowed input bounds into the correct String representation
+///
+/// The coordinates are rounded to [`BOUNDS_PRECISION`] decimals, since the full
+/// representation of floats makes long URLs which some providers reject.
 impl<T> From<InputBounds<T>> for String
 where
     T: Float + Debug,
 {
     fn from(ip: InputBounds<T>) -> String {
-        // Return in lon, lat order
-        format!(
-            "{},{},{},{}",
-            ip.minimum_lonlat.x().to_f64().unwrap(),
-            ip.minimum_lonlat.y().to_f64().unwrap(),
-            ip.maximum_lonlat.x().to_f64().unwrap(),
-            ip.maximum_lonlat.y().to_f64().unwrap()
-        )
+        ip.to_string_with_precision(BOUNDS_PRECISION)
+    }
+}
+
+/// A page of results, from which the following pages can be requested
+///
+/// Providers which paginate their results return the first page, and
+/// [`next_page`](#method.next_page) requests the following ones, until a page is empty.
+///
+/// # Examples
+///
+/// ```
+/// use geocoding::Paged;
+///
+/// let mut pages = vec![vec![3, 4], vec![5]].into_iter();
+/// let mut paged = Paged::new(vec![1, 2], move || Ok(pages.next()));
+/// assert_eq!(paged.items(), &[1, 2]);
+/// assert_eq!(paged.next_page().unwrap(), Some(&[3, 4][..]));
+/// assert_eq!(paged.next_page().unwrap(), Some(&[5][..]));
+/// assert_eq!(paged.next_page().unwrap(), None);
+/// assert!(!paged.has_next_page());
+/// ```
+pub struct Paged<'a, T> {
+    items: Vec<T>,
+    next: Option<NextPage<'a, T>>,
+}
+
+/// A function returning the following page
+type NextPage<'a, T> = Box<dyn FnMut() -> Result<Option<Vec<T>>, GeocodingError> + 'a>;
+
+impl<'a, T> Paged<'a, T> {
+    /// Create the first page of `items`, where `next` returns the following page, or `None`
+    /// after the last page
+    pub fn new<F>(items: Vec<T>, next: F) -> Self
+    where
+        F: FnMut() -> Result<Option<Vec<T>>, GeocodingError> + 'a,
+    {
+        Paged {
+            items,
+            next: Some(Box::new(next)),
+        }
+    }
+
+    /// Create the only page of `items`
+    pub fn single(items: Vec<T>) -> Self {
+        Paged { items, next: None }
+    }
+
+    /// The results of the current page
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Return the results of the current page
+    pub fn into_items(self) -> Vec<T> {
+        self.items
+    }
+
+    /// Whether a following page may exist
+    pub fn has_next_page(&self) -> bool {
+        self.next.is_some()
+    }
+
+    /// Request the following page, which becomes the current page, and return its results
+    ///
+    /// Returns `None` once there are no more pages, or the following page is empty. An error
+    /// also ends the pages.
+    pub fn next_page(&mut self) -> Result<Option<&[T]>, GeocodingError> {
+        let next = match self.next.as_mut() {
+            Some(next) => next,
+            None => return Ok(None),
+        };
+        match next() {
+            Ok(Some(items)) if !items.is_empty() => {
+                self.items = items;
+                Ok(Some(&self.items))
+            }
+            Ok(_) => {
+                self.next = None;
+                Ok(None)
+            }
+            Err(e) => {
+                self.next = None;
+                Err(e)
+            }
+        }
+    }
+}
+
+impl<'a, T> Debug for Paged<'a, T>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Paged")
+            .field("items", &self.items)
+            .field("has_next_page", &self.has_next_page())
+            .finish()
+    }
+}
+
+/// An area to search within when forward-geocoding
+///
+/// Providers restrict their searches to the [`bounds`](#method.bounds) of the area, as they
+/// don't support circles or polygons. Results can be checked against the exact area with
+/// [`contains`](#method.contains). Coordinates are in lon, lat order, and `radius` is in meters.
+///
+/// # Examples
+///
+/// ```
+/// use geocoding::{Point, SearchArea};
+///
+/// let area = SearchArea::Circle {
+///     center: Point::new(2.12870, 41.40139),
+///     radius: 500.0,
+/// };
+/// assert!(area.contains(&Point::new(2.1300, 41.4020)));
+/// assert!(!area.contains(&Point::new(2.1500, 41.4020)));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchArea<T>
+where
+    T: Float + Debug,
+{
+    /// A bounding box
+    Bbox(InputBounds<T>),
+    /// A circle of `radius` meters around `center`
+    Circle { center: Point<T>, radius: f64 },
+    /// A polygon, whose interior rings are holes
+    Polygon(Polygon<T>),
+}
+
+impl<T> SearchArea<T>
+where
+    T: Float + Debug,
+{
+    /// The bounding box of the area
+    ///
+    /// The bounding box of an empty polygon is the point `0, 0`.
+    pub fn bounds(&self) -> InputBounds<T> {
+        match self {
+            SearchArea::Bbox(bounds) => *bounds,
+            SearchArea::Circle { center, radius } => {
+                let lat = center.y().to_f64().unwrap();
+                let d_lat = radius / 111_320.0;
+                let d_lon = (d_lat / lat.to_radians().cos()).min(180.0);
+                let d_lat = T::from(d_lat).unwrap();
+                let d_lon = T::from(d_lon).unwrap();
+                InputBounds::new(
+                    (center.x() - d_lon, center.y() - d_lat),
+                    (center.x() + d_lon, center.y() + d_lat),
+                )
+            }
+            SearchArea::Polygon(polygon) => {
+                let mut coords = polygon.exterior().coords();
+                let first = coords.next().copied().unwrap_or(Coord {
+                    x: T::zero(),
+                    y: T::zero(),
+                });
+                let (min, max) = coords.fold((first, first), |(min, max), c| {
+                    (
+                        Coord {
+                            x: min.x.min(c.x),
+                            y: min.y.min(c.y),
+                        },
+                        Coord {
+                            x: max.x.max(c.x),
+                            y: max.y.max(c.y),
+                        },
+                    )
+                });
+                InputBounds::new(min, max)
+            }
+        }
+    }
+
+    /// Whether `point` is within the area
+    pub fn contains(&self, point: &Point<T>) -> bool {
+        match self {
+            SearchArea::Bbox(bounds) => {
+                bounds.minimum_lonlat.x() <= point.x()
+                    && point.x() <= bounds.maximum_lonlat.x()
+                    && bounds.minimum_lonlat.y() <= point.y()
+                    && point.y() <= bounds.maximum_lonlat.y()
+            }
+            SearchArea::Circle { center, radius } => haversine_distance(center, point) <= *radius,
+            SearchArea::Polygon(polygon) => {
+                ring_contains(polygon.exterior(), point)
+                    && !polygon
+                        .interiors()
+                        .iter()
+                        .any(|ring| ring_contains(ring, point))
+            }
+        }
+    }
+}
+
+/// Whether `point` is within a closed ring, by counting the crossings of a ray from the point
+fn ring_contains<T>(ring: &LineString<T>, point: &Point<T>) -> bool
+where
+    T: Float + Debug,
+{
+    let (x, y) = (point.x(), point.y());
+    ring.lines().fold(false, |inside, line| {
+        let (a, b) = (line.start, line.end);
+        if (a.y > y) != (b.y > y) && x < a.x + (y - a.y) * (b.x - a.x) / (b.y - a.y) {
+            !inside
+        } else {
+            inside
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[cfg(any(
+        feature = "opencage",
+        all(feature = "osm", any(feature = "blocking", feature = "async"))
+    ))]
+    fn snippet_test() {
+        let body = b"<html>\n  <head><title>503 Service Unavailable</title></head>\n</html>";
+        assert_eq!(
+            snippet(body),
+            "<html> <head><title>503 Service Unavailable</title></head> </html>"
+        );
+        assert_eq!(snippet("é".repeat(300).as_bytes()).chars().count(), 200);
+    }
+
+    #[test]
+    fn country_code_test() {
+        let code: CountryCode = " es".trim().parse().unwrap();
+        assert_eq!(code.as_str(), "ES");
+        assert_eq!(code, "ES".parse().unwrap());
+        for invalid in ["", "E", "ESP", "E1", "é"] {
+            assert!(matches!(
+                invalid.parse::<CountryCode>(),
+                Err(GeocodingError::InvalidCountryCode(_))
+            ));
+        }
+        assert_eq!(serde_json::to_string(&code).unwrap(), r#""ES""#);
+        let parsed: CountryCode = serde_json::from_str(r#""es""#).unwrap();
+        assert_eq!(parsed, code);
+        assert!(serde_json::from_str::<CountryCode>(r#""Spain""#).is_err());
+    }
+
+    #[test]
+    fn search_area_test() {
+        let bbox = SearchArea::Bbox(InputBounds::new((7.0, 46.0), (8.0, 47.0)));
+        assert!(bbox.contains(&Point::new(7.5, 46.5)));
+        assert!(!bbox.contains(&Point::new(8.5, 46.5)));
+
+        let circle = SearchArea::Circle {
+            center: Point::new(7.439122, 46.948825),
+            radius: 1_000.0,
+        };
+        let bounds = circle.bounds();
+        assert!((bounds.maximum_lonlat.y() - 46.957808).abs() < 1e-5);
+        assert!((bounds.maximum_lonlat.x() - 7.452277).abs() < 1e-5);
+        assert!(circle.contains(&Point::new(7.445, 46.95)));
+        // Within the bounds, but not the circle
+        assert!(!circle.contains(&Point::new(7.452, 46.957)));
+
+        let exterior = LineString::from(vec![(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)]);
+        let hole = LineString::from(vec![(1.0, 1.0), (2.0, 1.0), (2.0, 2.0), (1.0, 2.0)]);
+        let polygon = SearchArea::Polygon(Polygon::new(exterior, vec![hole]));
+        assert!(polygon.contains(&Point::new(3.0, 3.0)));
+        assert!(!polygon.contains(&Point::new(1.5, 1.5)));
+        assert!(!polygon.contains(&Point::new(5.0, 3.0)));
+        let bounds = polygon.bounds();
+        assert_eq!(bounds.minimum_lonlat, Point::new(0.0, 0.0));
+        assert_eq!(bounds.maximum_lonlat, Point::new(4.0, 4.0));
+    }
+
+    #[test]
+    fn language_tag_test() {
+        for valid in ["de", "pt-BR", "zh-Hant-TW", "es-419"] {
+            assert_eq!(valid.parse::<LanguageTag>().unwrap().as_str(), valid);
+        }
+        for invalid in ["", "d", "pt_BR", "de-", "1a", "en-toolongsubtag"] {
+            assert!(matches!(
+                invalid.parse::<LanguageTag>(),
+                Err(GeocodingError::InvalidLanguageTag(_))
+            ));
+        }
+        let tag: LanguageTag = serde_json::from_str(r#""fr-CH""#).unwrap();
+        assert_eq!(serde_json::to_string(&tag).unwrap(), r#""fr-CH""#);
+    }
+
+    #[test]
+    fn haversine_distance_test() {
+        // Zurich main station to Bern main station
+        let zurich = Point::new(8.540192, 47.378177);
+        let bern = Point::new(7.439122, 46.948825);
+        let distance = haversine_distance(&zurich, &bern);
+        assert!((distance - 95_500.0).abs() < 500.0);
+        assert_eq!(haversine_distance(&bern, &bern), 0.0);
+    }
+
+    /// Send a request with `client` to a local server, and return the request it received
+    #[cfg(feature = "blocking")]
+    fn received_request(client: &Client) -> String {
+        let server = test_server::Server::single(test_server::Response::new("200 OK", ""));
+        client.get(server.endpoint()).send().unwrap();
+        server.request().to_lowercase()
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn client_settings_test() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-test", HeaderValue::from_static("1"));
+        let mut settings = ClientSettings::new();
+        settings.headers = headers;
+        let request = received_request(&settings.client());
+        assert!(request.contains("user-agent: rust-geocoding"));
+        assert!(request.contains("x-test: 1"));
+        if cfg!(feature = "compression") {
+            assert!(request.contains("accept-encoding: gzip, br, deflate"));
+        }
+
+        let settings = ClientSettings::new()
+            .with_compression(false)
+            .with_pool_max_idle_per_host(2)
+            .with_pool_idle_timeout(None)
+            .with_tcp_keepalive(Some(Duration::from_secs(60)))
+            .build();
+        let request = received_request(&settings.client());
+        assert!(!request.contains("accept-encoding"));
+    }
+
+    #[test]
+    fn format_coordinate_test() {
+        assert_eq!(format_coordinate(41.40139_f64, None), "41.40139");
+        assert_eq!(format_coordinate(41.40139_f32, None), "41.40139");
+        assert_eq!(format_coordinate(0.1_f32, None), "0.1");
+        assert_eq!(format_coordinate(2.128701234567_f64, Some(5)), "2.1287");
+        assert_eq!(format_coordinate(2.5_f64, Some(0)), "2");
+        assert_eq!(format_coordinate(-0.000001_f64, Some(5)), "0");
+        assert_eq!(format_coordinate(-33.8688_f32, Some(3)), "-33.869");
+    }
+
+    #[test]
+    fn localized_default_test() {
+        struct Fixed;
+        impl ForwardLabels<f64> for Fixed {
+            fn forward_with_labels(
+                &self,
+                address: &str,
+            ) -> Result<Vec<(Point<f64>, String)>, GeocodingError> {
+                Ok(vec![(Point::new(0.0, 0.0), address.to_string())])
+            }
+        }
+        let language: LanguageTag = "de".parse().unwrap();
+        // Providers which can't localize their results ignore the language
+        assert_eq!(
+            Fixed.forward_localized("Genf", &language).unwrap(),
+            Fixed.forward_with_labels("Genf").unwrap()
+        );
+    }
+
+    #[test]
+    fn input_bounds_f32_test() {
+        let bounds = InputBounds::new((0.1_f32, 51.5), (0.2, 51.6));
+        assert_eq!(String::from(bounds), "0.1,51.5,0.2,51.6");
+    }
+
+    #[test]
+    fn input_bounds_precision_test() {
+        let bounds = InputBounds::new(
+            (7.451339848721545, 46.92792859),
+            (2_600_969.758489046_f64, -0.00000001),
+        );
+        assert_eq!(
+            String::from(bounds),
+            "7.4513398,46.9279286,2600969.758489,0"
+        );
+        assert_eq!(bounds.to_string_with_precision(0), "7,47,2600970,0");
+    }
+
+    #[test]
+    fn geocode_result_test() {
+        let res = GeocodeResult::new(Point::new(-5.6, 42.6), "Somewhere".to_string());
+        assert_eq!(&res.geohash[..5], "ezs42");
+        assert_eq!(res.geohash.len(), 12);
+        let res = GeocodeResult::new(Point::new(-5.6, 95.0), "Nowhere".to_string());
+        assert_eq!(res.geohash, "");
+    }
+
+    #[test]
+    fn with_match_score_test() {
+        let res = GeocodeResult::new(Point::new(2.12870, 41.40139), "Calatrava 68".to_string());
+        assert_eq!(res.match_score, None);
+        assert_eq!(res.with_match_score("calatrava 68").match_score, Some(1.0));
+    }
+
+    #[cfg(feature = "h3")]
+    #[test]
+    fn with_h3_cell_test() {
+        let res = GeocodeResult::new(Point::new(2.12870, 41.40139), String::new());
+        assert_eq!(res.with_h3_cell(9).h3_cell, Some(0x893944600cfffff));
+    }
+
+    #[cfg(feature = "s2")]
+    #[test]
+    fn with_s2_cell_test() {
+        let res = GeocodeResult::new(Point::new(-74.0060, 40.7128), String::new());
+        let id = res.with_s2_cell(12).s2_cell.unwrap();
+        assert!(s2::token(id).starts_with("89c25"));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn transport_error_test() {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_millis(200))
+            .build()
+            .unwrap();
+        let err: GeocodingError = client
+            .get("http://geocoding.invalid/?key=0123")
+            .send()
+            .unwrap_err()
+            .into();
+        assert!(matches!(err, GeocodingError::DnsFailure(_)));
+        assert!(!err.request_error().unwrap().to_string().contains("0123"));
+
+        let server = test_server::Server::new(vec![
+            // A plain HTTP answer to the TLS handshake
+            test_server::Response::Raw("HTTP/1.1 400 Bad Request\r\n\r\n".to_string()),
+            // No answer to the request
+            test_server::Response::Silent(Duration::from_millis(500)),
+        ]);
+        let addr = server.addr();
+        let err: GeocodingError = client
+            .get(format!("https://{}/", addr))
+            .send()
+            .unwrap_err()
+            .into();
+        assert!(matches!(err, GeocodingError::TlsError(_)), "{:?}", err);
+        let err: GeocodingError = client
+            .get(format!("http://{}/", addr))
+            .send()
+            .unwrap_err()
+            .into();
+        assert!(matches!(err, GeocodingError::ReadTimeout(_)), "{:?}", err);
+        server.requests();
+    }
+
+    #[cfg(all(feature = "opencage", feature = "osm", feature = "geoadmin"))]
+    #[test]
+    fn send_sync_test() {
+        fn assert_send_sync<P: Send + Sync>() {}
+        assert_send_sync::<Opencage<'static>>();
+        assert_send_sync::<Openstreetmap>();
+        assert_send_sync::<GeoAdmin>();
+        assert_send_sync::<Openelevation>();
+        assert_send_sync::<Opentopodata>();
+        assert_send_sync::<countryreverse::CountryReverse>();
+        assert_send_sync::<service::GeocodingService>();
+        assert_send_sync::<cache::Cached<service::GeocodingService>>();
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    fn within_test() {
+        use geo::{polygon, Rect as GeoRect};
+        let res = GeocodeResult::new(Point::new(2.12870, 41.40139), String::new());
+        let madrid = GeoRect::new((-3.9, 40.3), (-3.5, 40.6));
+        let barcelona =
+            polygon![(x: 2.0, y: 41.3), (x: 2.3, y: 41.3), (x: 2.3, y: 41.5), (x: 2.0, y: 41.5)];
+        assert!(!res.within(&madrid));
+        assert!(res.within(&barcelona));
+        assert_eq!(res.within_any(&[madrid.to_polygon(), barcelona]), Some(1));
+        assert_eq!(res.within_any::<geo::Polygon<f64>>(&[]), None);
     }
 }
@@ -0,0 +1,287 @@
+//! Address parsing and normalization with [libpostal](https://github.com/openvenues/libpostal),
+//! enabled by the `libpostal` feature.
+//!
+//! libpostal must be installed on the system, including its data files, which are loaded
+//! the first time one of the functions of this module is called. Loading takes a few seconds
+//! and about 2 GB of memory. The library is found with pkg-config when building the crate; if
+//! it isn't found, the crate still builds with a warning, and the functions of this module
+//! return an error.
+//!
+//! Parsing free-form input into an [`Address`](../struct.Address.html) allows to send
+//! structured queries to providers supporting them, which improves matches for messy input:
+//!
+//! ```no_run
+//! use geocoding::libpostal::parse_address;
+//! use geocoding::openstreetmap::{OpenstreetmapParams, OpenstreetmapResponse};
+//! use geocoding::{Address, Openstreetmap};
+//!
+//! let osm = Openstreetmap::new();
+//! let parsed = parse_address("carrer de calatrava 68 barcelona 08017 spain").unwrap();
+//! let address = Address::from(&parsed);
+//! let params = OpenstreetmapParams::new("")
+//!     .with_structured_address(address)
+//!     .build();
+//! let res: OpenstreetmapResponse<f64> = osm.forward_full(&params).unwrap();
+//! ```
+use crate::Address;
+use crate::GeocodingError;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+#[cfg(not(libpostal_missing))]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(not(libpostal_missing))]
+use std::sync::Once;
+
+#[repr(C)]
+struct ParserOptions {
+    language: *mut c_char,
+    country: *mut c_char,
+}
+
+#[repr(C)]
+struct ParserResponse {
+    num_components: usize,
+    components: *mut *mut c_char,
+    labels: *mut *mut c_char,
+}
+
+#[repr(C)]
+struct NormalizeOptions {
+    languages: *mut *mut c_char,
+    num_languages: usize,
+    address_components: u16,
+    latin_ascii: bool,
+    transliterate: bool,
+    strip_accents: bool,
+    decompose: bool,
+    lowercase: bool,
+    trim_string: bool,
+    drop_parentheticals: bool,
+    replace_numeric_hyphens: bool,
+    delete_numeric_hyphens: bool,
+    split_alpha_from_numeric: bool,
+    replace_word_hyphens: bool,
+    delete_word_hyphens: bool,
+    delete_final_periods: bool,
+    delete_acronym_periods: bool,
+    drop_english_possessives: bool,
+    delete_apostrophes: bool,
+    expand_numex: bool,
+    roman_numerals: bool,
+}
+
+// Linked by the build script
+#[cfg(not(libpostal_missing))]
+extern "C" {
+    fn libpostal_setup() -> bool;
+    fn libpostal_setup_parser() -> bool;
+    fn libpostal_setup_language_classifier() -> bool;
+    fn libpostal_get_address_parser_default_options() -> ParserOptions;
+    fn libpostal_parse_address(address: *mut c_char, options: ParserOptions)
+        -> *mut ParserResponse;
+    fn libpostal_address_parser_response_destroy(response: *mut ParserResponse);
+    fn libpostal_get_default_options() -> NormalizeOptions;
+    fn libpostal_expand_address(
+        input: *mut c_char,
+        options: NormalizeOptions,
+        n: *mut usize,
+    ) -> *mut *mut c_char;
+    fn libpostal_expansion_array_destroy(expansions: *mut *mut c_char, n: usize);
+}
+
+/// Stand-ins for the functions of libpostal when it isn't installed, never called as `setup`
+/// fails first
+#[cfg(libpostal_missing)]
+mod unlinked {
+    use super::{NormalizeOptions, ParserOptions, ParserResponse};
+    use std::os::raw::c_char;
+
+    pub unsafe fn libpostal_get_address_parser_default_options() -> ParserOptions {
+        unreachable!()
+    }
+    pub unsafe fn libpostal_parse_address(_: *mut c_char, _: ParserOptions) -> *mut ParserResponse {
+        unreachable!()
+    }
+    pub unsafe fn libpostal_address_parser_response_destroy(_: *mut ParserResponse) {}
+    pub unsafe fn libpostal_get_default_options() -> NormalizeOptions {
+        unreachable!()
+    }
+    pub unsafe fn libpostal_expand_address(
+        _: *mut c_char,
+        _: NormalizeOptions,
+        _: *mut usize,
+    ) -> *mut *mut c_char {
+        unreachable!()
+    }
+    pub unsafe fn libpostal_expansion_array_destroy(_: *mut *mut c_char, _: usize) {}
+}
+
+#[cfg(libpostal_missing)]
+use unlinked::*;
+
+#[cfg(not(libpostal_missing))]
+static SETUP: Once = Once::new();
+#[cfg(not(libpostal_missing))]
+static SETUP_OK: AtomicBool = AtomicBool::new(false);
+
+/// Load the libpostal data files, once per process
+#[cfg(not(libpostal_missing))]
+fn setup() -> Result<(), GeocodingError> {
+    SETUP.call_once(|| {
+        // SAFETY: the setup functions take no arguments, and are only called once
+        let ok = unsafe {
+            libpostal_setup() && libpostal_setup_parser() && libpostal_setup_language_classifier()
+        };
+        SETUP_OK.store(ok, Ordering::Release);
+    });
+    if SETUP_OK.load(Ordering::Acquire) {
+        Ok(())
+    } else {
+        Err(GeocodingError::Libpostal(
+            "could not load the libpostal data files".to_string(),
+        ))
+    }
+}
+
+#[cfg(libpostal_missing)]
+fn setup() -> Result<(), GeocodingError> {
+    Err(GeocodingError::Libpostal(
+        "libpostal wasn't found when building the crate".to_string(),
+    ))
+}
+
+fn to_c_string(input: &str) -> Result<CString, GeocodingError> {
+    CString::new(input)
+        .map_err(|_| GeocodingError::Libpostal("input contains a NUL byte".to_string()))
+}
+
+/// An address parsed by libpostal: its components, labelled in input order
+///
+/// See [the libpostal documentation](https://github.com/openvenues/libpostal#parser-labels)
+/// for the list of labels, such as `house_number`, `road`, `postcode` or `city`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParsedAddress {
+    pub components: Vec<(String, String)>,
+}
+
+impl ParsedAddress {
+    /// The value of the first component with `label`
+    pub fn get(&self, label: &str) -> Option<&str> {
+        self.components
+            .iter()
+            .find(|(l, _)| l == label)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl From<&ParsedAddress> for Address {
+    fn from(parsed: &ParsedAddress) -> Self {
+        let component = |label: &str| parsed.get(label).map(String::from);
+        Address {
+            house_number: component("house_number"),
+            road: component("road"),
+            suburb: component("suburb"),
+            city: component("city"),
+            postcode: component("postcode"),
+            state: component("state"),
+            state_code: None,
+            country: component("country"),
+            country_code: None,
+        }
+    }
+}
+
+/// Parse a free-form address into labelled components
+///
+/// libpostal normalizes the components, e.g. lower-cases them.
+pub fn parse_address(address: &str) -> Result<ParsedAddress, GeocodingError> {
+    setup()?;
+    let input = to_c_string(address)?;
+    // SAFETY: libpostal is set up, the input is a valid C string which libpostal doesn't
+    // modify, and the response is read before it is destroyed
+    unsafe {
+        let options = libpostal_get_address_parser_default_options();
+        let response = libpostal_parse_address(input.as_ptr() as *mut c_char, options);
+        if response.is_null() {
+            return Err(GeocodingError::Libpostal(
+                "could not parse the address".to_string(),
+            ));
+        }
+        let r = &*response;
+        let components = (0..r.num_components)
+            .map(|i| {
+                (
+                    CStr::from_ptr(*r.labels.add(i))
+                        .to_string_lossy()
+                        .into_owned(),
+                    CStr::from_ptr(*r.components.add(i))
+                        .to_string_lossy()
+                        .into_owned(),
+                )
+            })
+            .collect();
+        libpostal_address_parser_response_destroy(response);
+        Ok(ParsedAddress { components })
+    }
+}
+
+/// Expand an address into its normalized variants, e.g. with abbreviations expanded
+///
+/// Comparing expansions is a robust way to match addresses written differently, such as
+/// "Quatre-vingt-douze Ave des Champs-Élysées" and "92 avenue des champs elysees".
+pub fn expand_address(address: &str) -> Result<Vec<String>, GeocodingError> {
+    setup()?;
+    let input = to_c_string(address)?;
+    // SAFETY: as in parse_address
+    unsafe {
+        let options = libpostal_get_default_options();
+        let mut n = 0;
+        let expansions = libpostal_expand_address(input.as_ptr() as *mut c_char, options, &mut n);
+        if expansions.is_null() {
+            return Ok(vec![]);
+        }
+        let res = (0..n)
+            .map(|i| {
+                CStr::from_ptr(*expansions.add(i))
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        libpostal_expansion_array_destroy(expansions, n);
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[cfg_attr(libpostal_missing, ignore)]
+    fn parse_address_test() {
+        let parsed = parse_address("Carrer de Calatrava 68, 08017 Barcelona, Spain").unwrap();
+        assert_eq!(parsed.get("house_number"), Some("68"));
+        assert_eq!(parsed.get("postcode"), Some("08017"));
+        let address = Address::from(&parsed);
+        assert_eq!(address.road.as_deref(), Some("carrer de calatrava"));
+        assert_eq!(address.city.as_deref(), Some("barcelona"));
+        assert!(parse_address("a\0b").is_err());
+    }
+
+    #[test]
+    #[cfg_attr(libpostal_missing, ignore)]
+    fn expand_address_test() {
+        let expansions = expand_address("Quatre-vingt-douze Ave des Champs-Élysées").unwrap();
+        assert!(expansions.contains(&"92 avenue des champs elysees".to_string()));
+    }
+
+    #[test]
+    #[cfg(libpostal_missing)]
+    fn missing_test() {
+        assert!(matches!(
+            parse_address("Carrer de Calatrava 68, Barcelona"),
+            Err(GeocodingError::Libpostal(_))
+        ));
+        assert!(expand_address("Carrer de Calatrava 68").is_err());
+    }
+}
@@ -0,0 +1,322 @@
+//! Provenance records of geocoding lookups.
+//!
+//! Pipelines which must account for the origin of every geocoded value, e.g. in regulated
+//! industries, can wrap a provider in a [`Lineage`]. After each lookup, its
+//! [`LineageSink`] receives a [`LineageRecord`] with the input, the name of the provider,
+//! the raw responses the provider received, the parsed results and the time of the lookup,
+//! to be persisted in the pipeline's own store.
+//!
+//! Unlike the [audit log](../audit/index.html), which records each request of a provider,
+//! lineage records follow lookups, and keep the bodies of the responses with the results
+//! parsed from them. As in the audit log, credentials are redacted from the URLs.
+//!
+//! Responses are captured on the thread of the lookup: providers sending requests on other
+//! threads record their results without their responses.
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::lineage::{Lineage, LineageRecord};
+//! use geocoding::{Forward, Openstreetmap, Point};
+//! use std::sync::Arc;
+//!
+//! let sink = |record: &LineageRecord<f64>| {
+//!     println!("{} answered {:?} with {} responses", record.provider, record.input, record.responses.len());
+//! };
+//! let osm = Lineage::new(Openstreetmap::new(), "openstreetmap", Arc::new(sink));
+//! let res: Vec<Point<f64>> = osm.forward("Schwabing, München").unwrap();
+//! ```
+use crate::query::{Query, StructuredQuery};
+use crate::request;
+use crate::{
+    Forward, ForwardLabels, ForwardStructured, GeocodeResult, GeocodingError, LanguageTag, Point,
+    Reverse, ReverseFull,
+};
+use chrono::{DateTime, Utc};
+use num_traits::Float;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// A response received by a provider during a lookup
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LineageResponse {
+    /// The URL of the request, with credentials redacted
+    pub url: String,
+    /// The HTTP status of the response
+    pub status: u16,
+    /// The body of the response, decoded as UTF-8
+    pub body: String,
+}
+
+/// The provenance of a lookup
+///
+/// The results of [`Forward`] lookups have empty labels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineageRecord<T>
+where
+    T: Float + Debug,
+{
+    /// When the lookup started
+    pub timestamp: DateTime<Utc>,
+    /// The name of the provider given to [`Lineage::new`](struct.Lineage.html#method.new)
+    pub provider: String,
+    /// The text, structured address or point looked up
+    pub input: Query<T>,
+    /// The responses received during the lookup, in the order they were received
+    pub responses: Vec<LineageResponse>,
+    /// The results of the lookup, or its error
+    pub result: Result<Vec<GeocodeResult<T>>, String>,
+}
+
+/// A destination of lineage records
+///
+/// Sinks are called on the thread of the lookup, once it completed.
+pub trait LineageSink<T>: Send + Sync
+where
+    T: Float + Debug,
+{
+    /// Record a lookup
+    fn record(&self, record: &LineageRecord<T>);
+}
+
+impl<T, F> LineageSink<T> for F
+where
+    T: Float + Debug,
+    F: Fn(&LineageRecord<T>) + Send + Sync,
+{
+    fn record(&self, record: &LineageRecord<T>) {
+        self(record)
+    }
+}
+
+/// A provider recording the provenance of its lookups in a [`LineageSink`]
+pub struct Lineage<P, T>
+where
+    T: Float + Debug,
+{
+    provider: P,
+    name: String,
+    sink: Arc<dyn LineageSink<T>>,
+}
+
+impl<P, T> Lineage<P, T>
+where
+    T: Float + Debug,
+{
+    /// Wrap a provider, recording its lookups under `name` in `sink`
+    pub fn new(provider: P, name: &str, sink: Arc<dyn LineageSink<T>>) -> Self {
+        Lineage {
+            provider,
+            name: name.to_string(),
+            sink,
+        }
+    }
+
+    /// The wrapped provider
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    /// Run `lookup`, recording its input, responses and results
+    fn record<R>(
+        &self,
+        input: Query<T>,
+        lookup: impl FnOnce() -> Result<R, GeocodingError>,
+        results: impl FnOnce(&R) -> Vec<GeocodeResult<T>>,
+    ) -> Result<R, GeocodingError> {
+        let timestamp = Utc::now();
+        let (res, responses) = request::capture(lookup);
+        self.sink.record(&LineageRecord {
+            timestamp,
+            provider: self.name.clone(),
+            input,
+            responses,
+            result: res.as_ref().map(results).map_err(|e| e.to_string()),
+        });
+        res
+    }
+}
+
+/// The results of labelled lookups
+fn labelled<T>(res: &[(Point<T>, String)]) -> Vec<GeocodeResult<T>>
+where
+    T: Float + Debug,
+{
+    res.iter()
+        .map(|(point, label)| GeocodeResult::new(*point, label.clone()))
+        .collect()
+}
+
+impl<T, P> Forward<T> for Lineage<P, T>
+where
+    T: Float + Debug,
+    P: Forward<T>,
+{
+    fn forward(&self, address: &str) -> Result<Vec<Point<T>>, GeocodingError> {
+        self.record(
+            Query::from(address),
+            || self.provider.forward(address),
+            |res| {
+                res.iter()
+                    .map(|point| GeocodeResult::new(*point, String::new()))
+                    .collect()
+            },
+        )
+    }
+}
+
+impl<T, P> ForwardLabels<T> for Lineage<P, T>
+where
+    T: Float + Debug,
+    P: ForwardLabels<T>,
+{
+    fn forward_with_labels(
+        &self,
+        address: &str,
+    ) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        self.record(
+            Query::from(address),
+            || self.provider.forward_with_labels(address),
+            |res| labelled(res),
+        )
+    }
+
+    fn forward_localized(
+        &self,
+        address: &str,
+        language: &LanguageTag,
+    ) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        self.record(
+            Query::from(address),
+            || self.provider.forward_localized(address, language),
+            |res| labelled(res),
+        )
+    }
+}
+
+impl<T, P> ForwardStructured<T> for Lineage<P, T>
+where
+    T: Float + Debug,
+    P: ForwardStructured<T>,
+{
+    fn forward_structured(
+        &self,
+        address: &StructuredQuery,
+    ) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        self.record(
+            Query::from(address.clone()),
+            || self.provider.forward_structured(address),
+            |res| labelled(res),
+        )
+    }
+
+    fn forward_structured_results(
+        &self,
+        address: &StructuredQuery,
+    ) -> Result<Vec<GeocodeResult<T>>, GeocodingError> {
+        self.record(
+            Query::from(address.clone()),
+            || self.provider.forward_structured_results(address),
+            |res| res.clone(),
+        )
+    }
+}
+
+impl<T, P> Reverse<T> for Lineage<P, T>
+where
+    T: Float + Debug,
+    P: Reverse<T>,
+{
+    fn reverse(&self, point: &Point<T>) -> Result<Option<String>, GeocodingError> {
+        self.record(
+            Query::from(*point),
+            || self.provider.reverse(point),
+            |res| {
+                res.iter()
+                    .map(|label| GeocodeResult::new(*point, label.clone()))
+                    .collect()
+            },
+        )
+    }
+}
+
+impl<T, P> ReverseFull<T> for Lineage<P, T>
+where
+    T: Float + Debug,
+    P: ReverseFull<T>,
+{
+    fn reverse_result(&self, point: &Point<T>) -> Result<Option<GeocodeResult<T>>, GeocodingError> {
+        self.record(
+            Query::from(*point),
+            || self.provider.reverse_result(point),
+            |res| res.iter().cloned().collect(),
+        )
+    }
+
+    fn reverse_localized(
+        &self,
+        point: &Point<T>,
+        language: &LanguageTag,
+    ) -> Result<Option<GeocodeResult<T>>, GeocodingError> {
+        self.record(
+            Query::from(*point),
+            || self.provider.reverse_localized(point, language),
+            |res| res.iter().cloned().collect(),
+        )
+    }
+}
+
+#[cfg(all(test, feature = "osm"))]
+mod test {
+    use super::*;
+    use crate::test_server::{Response, Server};
+    use crate::Openstreetmap;
+    use std::sync::Mutex;
+
+    #[test]
+    fn lineage_test() {
+        let body = r#"{"type": "FeatureCollection", "licence": "ODbL", "features": [{"type": "Feature", "properties": {"place_id": 1, "osm_type": "way", "osm_id": 2, "display_name": "Carrer de Calatrava, Barcelona", "place_rank": 26, "category": "highway", "type": "residential", "importance": 0.1}, "bbox": [2.1, 41.4, 2.1, 41.4], "geometry": {"type": "Point", "coordinates": [2.1, 41.4]}}]}"#;
+        let server = Server::single(Response::json(body));
+        let endpoint = server.endpoint();
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let sink = {
+            let records = records.clone();
+            move |record: &LineageRecord<f64>| records.lock().unwrap().push(record.clone())
+        };
+        let osm = Openstreetmap::new_with_endpoint(endpoint.clone()).with_api_key("key", "secret");
+        let osm = Lineage::new(osm, "openstreetmap", Arc::new(sink));
+        let res = osm.forward_with_labels("Calatrava").unwrap();
+        server.requests();
+        let records = records.lock().unwrap();
+        let record = &records[0];
+        assert_eq!(record.provider, "openstreetmap");
+        assert_eq!(record.input, Query::Text("Calatrava".to_string()));
+        assert_eq!(record.responses.len(), 1);
+        assert!(record.responses[0]
+            .url
+            .starts_with(&format!("{}search?key=REDACTED&q=Calatrava", endpoint)));
+        assert_eq!(record.responses[0].status, 200);
+        assert_eq!(record.responses[0].body, body);
+        let results = record.result.as_ref().unwrap();
+        assert_eq!(results[0].label, res[0].1);
+        assert_eq!(results[0].point, Point::new(2.1, 41.4));
+    }
+
+    #[test]
+    fn lineage_error_test() {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let sink = {
+            let records = records.clone();
+            move |record: &LineageRecord<f64>| records.lock().unwrap().push(record.clone())
+        };
+        // Nothing listens on the discard port
+        let osm = Openstreetmap::new_with_endpoint("http://127.0.0.1:9/".to_string());
+        let osm = Lineage::new(osm, "openstreetmap", Arc::new(sink));
+        assert!(osm.reverse(&Point::new(2.1, 41.4)).is_err());
+        let records = records.lock().unwrap();
+        assert_eq!(records[0].input, Query::Coords(Point::new(2.1, 41.4)));
+        assert!(records[0].responses.is_empty());
+        assert!(records[0].result.is_err());
+    }
+}
@@ -16,27 +16,53 @@
 //!
 //! ```
 //! use geocoding::{Opencage, Point, Reverse};
+//! use geocoding::opencage::Parameters;
 //!
-//! let mut oc = Opencage::new("dcdbf0d783374909b3debee728c7cc10".to_string());
-//! oc.parameters.language = Some("fr");
+//! let oc = Opencage::new("dcdbf0d783374909b3debee728c7cc10".to_string())
+//!     .with_parameters(Parameters {
+//!         language: Some("fr"),
+//!         ..Parameters::default()
+//!     });
 //! let p = Point::new(2.12870, 41.40139);
 //! let res = oc.reverse(&p);
 //! // "Carrer de Calatrava, 68, 08017 Barcelone, Espagne"
 //! println!("{:?}", res.unwrap());
 //! ```
-use crate::chrono::naive::serde::ts_seconds::deserialize as from_ts;
-use crate::chrono::NaiveDateTime;
+use crate::audit::AuditSink;
+use crate::failover::Failover;
+use crate::keys::{self, KeyRing, Rotation};
+use crate::request::{self, RequestHooks, SendHooked};
+use crate::stats::UsageStats;
+use crate::throttle::Throttle;
+use crate::Client;
+use crate::ClientSettings;
 use crate::DeserializeOwned;
 use crate::GeocodingError;
 use crate::InputBounds;
-use crate::Point;
-use crate::UA_STRING;
-use crate::{Client, HeaderMap, HeaderValue, USER_AGENT};
+use crate::LanguageTag;
+use crate::RawResponse;
+use crate::ResponseMeta;
+use crate::ReverseSnapping;
+use crate::{extra_params, from_json_slice, from_json_str, warn_unknown_fields};
+use crate::{
+    format_coordinate, AdminArea, AdminReverse, Forward, ForwardLabels, ForwardStructured,
+};
+use crate::{Address, Attribution, GeocodeResult};
 use crate::{Deserialize, Serialize};
-use crate::{Forward, Reverse};
+use crate::{ExposeSecret, SecretString};
+use crate::{HealthCheck, ProviderStatus};
+use crate::{Point, Rect};
+use crate::{PrivacyControl, PrivacyMode};
+use crate::{Reverse, ReverseFull};
+use chrono::naive::serde::ts_seconds::deserialize as from_ts;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use num_traits::Float;
+use reqwest::blocking::{Request, RequestBuilder, Response};
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
 use serde::Deserializer;
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fmt::Debug;
 use std::sync::{Arc, Mutex};
 
@@ -49,19 +75,92 @@ macro_rules! add_optional_param {
 }
 
 // Please see the [API documentation](https://opencagedata.com/api#forward-opt) for details.
-#[derive(Default)]
+#[derive(Clone, Copy, Debug, Default)]
 pub struct Parameters<'a> {
     pub language: Option<&'a str>,
     pub countrycode: Option<&'a str>,
-    pub limit: Option<&'a str>,
+    /// The maximum number of results, at most [`MAX_LIMIT`]
+    pub limit: Option<u8>,
+    /// The kind of feature reverse lookups snap to
+    pub snapping: Option<ReverseSnapping>,
+    /// Keep duplicate results, passing `no_dedupe`
+    pub no_dedupe: bool,
+    /// Echo the parameters of the request in the `request` of the response, passing `add_request`
+    pub add_request: bool,
+    /// Pretty-print the JSON of the response, passing `pretty`; responses are compact otherwise
+    pub pretty: bool,
+    /// Parameters not modelled by the crate, which are ignored when they conflict with others
+    pub extra_params: &'a [(&'a str, &'a str)],
 }
 
 impl<'a> Parameters<'a> {
-    fn as_query(&self) -> Vec<(&'a str, &'a str)> {
+    /// Pass parameters not modelled by the crate to the API, such as new options
+    ///
+    /// The parameters set by the crate take precedence over these on conflict.
+    pub fn with_extra_params(&mut self, extra_params: &'a [(&'a str, &'a str)]) -> &mut Self {
+        self.extra_params = extra_params;
+        self
+    }
+
+    /// Return at most `limit` results, capped to [`MAX_LIMIT`] by the API
+    pub fn with_limit(&mut self, limit: u8) -> &mut Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Keep duplicate results, which OpenCage removes by default
+    pub fn with_no_dedupe(&mut self, no_dedupe: bool) -> &mut Self {
+        self.no_dedupe = no_dedupe;
+        self
+    }
+
+    /// Echo the parameters of the request in the [`request`](struct.OpencageResponse.html#structfield.request)
+    /// of the response, to debug how OpenCage interpreted them
+    pub fn with_add_request(&mut self, add_request: bool) -> &mut Self {
+        self.add_request = add_request;
+        self
+    }
+
+    /// Pretty-print the JSON of the response, e.g. to read raw responses
+    pub fn with_pretty(&mut self, pretty: bool) -> &mut Self {
+        self.pretty = pretty;
+        self
+    }
+
+    /// Snap reverse lookups to the nearest address or road
+    ///
+    /// This sets the `address_only` or `roadinfo` parameter of reverse lookups. OpenCage has
+    /// no parameter for localities, whose results are unchanged.
+    pub fn with_snapping(&mut self, snapping: ReverseSnapping) -> &mut Self {
+        self.snapping = Some(snapping);
+        self
+    }
+
+    /// The parameters of reverse lookups expressing the snapping preference
+    fn snapping_query(&self) -> Vec<(&'a str, &'a str)> {
+        match self.snapping {
+            Some(ReverseSnapping::Address) => vec![("address_only", "1")],
+            Some(ReverseSnapping::Road) => vec![("roadinfo", "1")],
+            Some(ReverseSnapping::Locality) | None => vec![],
+        }
+    }
+
+    /// The query parameters, with the `limit` formatted into `limit`
+    fn as_query<'b>(&'b self, limit: &'b Option<String>) -> Vec<(&'b str, &'b str)> {
         let mut query = vec![];
         add_optional_param!(query, self.language, "language");
         add_optional_param!(query, self.countrycode, "countrycode");
-        add_optional_param!(query, self.limit, "limit");
+        add_optional_param!(query, limit.as_deref(), "limit");
+        let toggles = [
+            ("no_dedupe", self.no_dedupe),
+            ("add_request", self.add_request),
+            ("pretty", self.pretty),
+        ];
+        for (name, enabled) in toggles.iter() {
+            if *enabled {
+                query.push((*name, "1"));
+            }
+        }
         query
     }
 }
@@ -70,64 +169,206 @@ pub fn deserialize_string_or_int<'de, D>(deserializer: D) -> Result<String, D::E
 where
     D: Deserializer<'de>,
 {
-    #[derive(Deserialize)]
-    #[serde(untagged)]
-    enum StringOrInt {
-        String(String),
-        Int(i32),
-    }
-
-    match StringOrInt::deserialize(deserializer)? {
-        StringOrInt::String(s) => Ok(s),
-        StringOrInt::Int(i) => Ok(i.to_string()),
-    }
+    crate::deserialize_string_or_int(deserializer)
 }
 
 // OpenCage has a custom rate-limit header, indicating remaining calls
 // header! { (XRatelimitRemaining, "X-RateLimit-Remaining") => [i32] }
 static XRL: &str = "x-ratelimit-remaining";
+// The time at which the quota is reset, in seconds since the epoch
+static XRL_RESET: &str = "x-ratelimit-reset";
+
+/// The time at which the quota is reset, from the rate-limit headers of a response
+fn quota_reset(headers: &HeaderMap) -> Option<DateTime<Utc>> {
+    let seconds = headers.get(XRL_RESET)?.to_str().ok()?.trim().parse().ok()?;
+    DateTime::from_timestamp(seconds, 0)
+}
+/// The documentation of the version of the API this crate supports, as linked by its responses
+pub const DOCUMENTATION_URL: &str = "https://opencagedata.com/api";
+
+/// The maximum number of results of a request
+pub const MAX_LIMIT: u8 = 100;
+
 /// Use this constant if you don't need to restrict a `forward_full` call with a bounding box
 pub static NOBOX: Option<InputBounds<f64>> = None::<InputBounds<f64>>;
 
 /// An instance of the Opencage Geocoding service
+///
+/// Instances are `Send` and `Sync`, and their configuration is fixed once built, so a single
+/// instance behind an `Arc` can serve several threads. Parameters can be overridden for a
+/// single call with [`forward_full_with`](#method.forward_full_with) and
+/// [`reverse_full_with`](#method.reverse_full_with).
 pub struct Opencage<'a> {
-    api_key: String,
+    keys: KeyRing,
     client: Client,
+    client_settings: ClientSettings,
     endpoint: String,
-    pub parameters: Parameters<'a>,
+    parameters: Parameters<'a>,
+    /// The number of decimals of the coordinates sent in reverse queries, if rounded
+    precision: Option<u8>,
     remaining: Arc<Mutex<Option<i32>>>,
+    hooks: RequestHooks,
+    privacy_mode: PrivacyMode,
 }
 
 impl<'a> Opencage<'a> {
     /// Create a new OpenCage geocoding instance
-    pub fn new(api_key: String) -> Self {
-        let mut headers = HeaderMap::new();
-        headers.insert(USER_AGENT, HeaderValue::from_static(UA_STRING));
-        let client = Client::builder()
-            .default_headers(headers)
-            .build()
-            .expect("Couldn't build a client!");
+    ///
+    /// The key is kept in a [`SecretString`](../struct.SecretString.html), which doesn't
+    /// reveal it in `Debug` output, and is zeroed when the instance is dropped.
+    pub fn new<K>(api_key: K) -> Self
+    where
+        K: Into<SecretString>,
+    {
+        Opencage::new_with_endpoint(
+            api_key,
+            "https://api.opencagedata.com/geocode/v1/json".to_string(),
+        )
+    }
 
+    /// Create a new OpenCage geocoding instance with a custom endpoint, e.g. a proxy
+    ///
+    /// Endpoint should be the full URL of the JSON API (i.e. "https://api.opencagedata.com/geocode/v1/json")
+    pub fn new_with_endpoint<K>(api_key: K, endpoint: String) -> Self
+    where
+        K: Into<SecretString>,
+    {
+        let client_settings = ClientSettings::default();
+        let client = client_settings.client();
         let parameters = Parameters::default();
         Opencage {
-            api_key,
+            keys: KeyRing::new([api_key.into()], Rotation::default()),
+            hooks: RequestHooks::with_client(&client),
             client,
+            client_settings,
             parameters,
-            endpoint: "https://api.opencagedata.com/geocode/v1/json".to_string(),
+            precision: None,
+            endpoint,
             remaining: Arc::new(Mutex::new(None)),
+            privacy_mode: PrivacyMode::NoRecord,
         }
     }
+
+    /// The credit OpenCage requires, to OpenCage and to OpenStreetMap contributors
+    pub fn attribution(&self) -> Attribution {
+        Attribution::new(
+            "Geocoding by OpenCage, data © OpenStreetMap contributors and others",
+            None,
+            "https://opencagedata.com/credits",
+        )
+    }
+
+    /// Send `parameters` with every request, unless they are overridden for a call
+    pub fn with_parameters(mut self, parameters: Parameters<'a>) -> Self {
+        self.parameters = parameters;
+        self
+    }
+
+    /// The parameters sent with every request
+    pub fn parameters(&self) -> &Parameters<'a> {
+        &self.parameters
+    }
+
+    /// Round the coordinates sent in reverse queries to `precision` decimals
+    pub fn with_precision(mut self, precision: u8) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    /// Space the requests to the API using `throttle`, which also slows down when the
+    /// rate limit is exhausted
+    ///
+    /// See the [`throttle`](../throttle/index.html) module for details.
+    pub fn with_throttle(mut self, throttle: Arc<Throttle>) -> Self {
+        self.hooks.throttle = Some(throttle);
+        self
+    }
+
+    /// Record every request sent to the API, with the API key redacted in `sink`
+    ///
+    /// See the [`audit`](../audit/index.html) module for details.
+    pub fn with_audit(mut self, sink: Arc<dyn AuditSink>) -> Self {
+        self.hooks.audit = Some(sink);
+        self
+    }
+
+    /// Send the requests failing to reach the API to the other endpoints of `failover`,
+    /// e.g. proxies
+    ///
+    /// See the [`failover`](../failover/index.html) module for details.
+    pub fn with_failover(mut self, failover: Arc<Failover>) -> Self {
+        self.hooks.failover = Some(failover);
+        self
+    }
+
+    /// Use the keys of `keys` instead of the key the instance was created with
+    ///
+    /// When a key is rate-limited or its quota is exhausted, the request is sent again with
+    /// the next key. See the [`keys`](../keys/index.html) module for details.
+    pub fn with_api_keys(mut self, keys: KeyRing) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    /// Send the requests whose encoded query is longer than `length` as POST requests, with
+    /// the query as a form, for proxies limiting the length of URLs
+    pub fn with_max_query_length(mut self, length: usize) -> Self {
+        self.hooks.max_query_length = Some(length);
+        self
+    }
+
+    /// Fail with `GeocodingError::ResponseTooLarge` when a response body is larger than
+    /// `bytes`, instead of reading it into memory
+    pub fn with_max_response_size(mut self, bytes: usize) -> Self {
+        self.hooks.max_response_size = Some(bytes);
+        self
+    }
+
+    /// Fail with `GeocodingError::TooManyResults` when a response holds more than `count`
+    /// results
+    pub fn with_max_results(mut self, count: usize) -> Self {
+        self.hooks.max_results = Some(count);
+        self
+    }
+
+    /// The requests sent by this instance so far
+    ///
+    /// See the [`stats`](../stats/index.html) module for details.
+    pub fn stats(&self) -> UsageStats {
+        self.hooks.usage.snapshot()
+    }
+
+    /// Accept compressed responses, `true` by default
+    ///
+    /// Only the compressions enabled by the crate's features are negotiated.
+    pub fn with_compression(mut self, compression: bool) -> Self {
+        self.client_settings.compression = compression;
+        self.client = self.client_settings.client();
+        self.hooks.set_client(&self.client);
+        self
+    }
+
+    /// Apply connection pool and compression settings to the HTTP client
+    pub fn with_client_settings(mut self, settings: &ClientSettings) -> Self {
+        self.client_settings = settings.clone();
+        self.client = self.client_settings.client();
+        self.hooks.set_client(&self.client);
+        self
+    }
+
     /// Retrieve the remaining API calls in your daily quota
     ///
     /// Initially, this value is `None`. Any OpenCage API call using a "Free Tier" key
-    /// will update this value to reflect the remaining quota for the API key.
+    /// will update this value to reflect the remaining quota for the API key. With
+    /// [`with_api_keys`](#method.with_api_keys), it's the quota of the key of the last call.
     /// See the [API docs](https://opencagedata.com/api#rate-limiting) for details.
     pub fn remaining_calls(&self) -> Option<i32> {
         *self.remaining.lock().unwrap()
     }
     /// A reverse lookup of a point, returning an annotated response.
     ///
-    /// This method passes the `no_record` parameter to the API.
+    /// This method passes the `no_record` parameter to the API, unless the privacy mode
+    /// is `Standard`.
     ///
     /// # Examples
     ///
@@ -148,39 +389,68 @@ impl<'a> Opencage<'a> {
     pub fn reverse_full<T>(&self, point: &Point<T>) -> Result<OpencageResponse<T>, GeocodingError>
     where
         T: Float + DeserializeOwned + Debug,
+    {
+        self.reverse_full_with(point, &self.parameters)
+    }
+
+    /// A reverse lookup like [`reverse_full`](#method.reverse_full), sending `parameters`
+    /// instead of the instance's parameters
+    ///
+    /// # Examples
+    ///
+    ///```
+    /// use geocoding::{Opencage, Point};
+    /// use geocoding::opencage::Parameters;
+    ///
+    /// let oc = Opencage::new("dcdbf0d783374909b3debee728c7cc10".to_string());
+    /// let p = Point::new(2.12870, 41.40139);
+    /// let parameters = Parameters {
+    ///     language: Some("fr"),
+    ///     ..*oc.parameters()
+    /// };
+    /// let res = oc.reverse_full_with(&p, &parameters).unwrap();
+    /// assert!(res.results[0].formatted.contains("Espagne"));
+    ///```
+    pub fn reverse_full_with<T>(
+        &self,
+        point: &Point<T>,
+        parameters: &Parameters,
+    ) -> Result<OpencageResponse<T>, GeocodingError>
+    where
+        T: Float + DeserializeOwned + Debug,
+    {
+        let (resp, meta) = self.send(self.reverse_request(point, parameters))?;
+        let mut res: OpencageResponse<T> = resp.json()?;
+        res.meta = meta;
+        Ok(res)
+    }
+
+    /// The request of an annotated reverse lookup
+    pub(crate) fn reverse_request<T>(
+        &self,
+        point: &Point<T>,
+        parameters: &Parameters,
+    ) -> RequestBuilder
+    where
+        T: Float + Debug,
     {
         let q = format!(
             "{}, {}",
             // OpenCage expects lat, lon order
-            (&point.y().to_f64().unwrap().to_string()),
-            &point.x().to_f64().unwrap().to_string()
+            format_coordinate(point.y(), self.precision),
+            format_coordinate(point.x(), self.precision)
         );
+        let limit = parameters.limit.map(|limit| limit.to_string());
         let mut query = vec![
             ("q", q.as_str()),
-            ("key", &self.api_key),
+            ("key", self.api_key()),
             ("no_annotations", "0"),
-            ("no_record", "1"),
         ];
-        query.extend(self.parameters.as_query());
-
-        let resp = self
-            .client
-            .get(&self.endpoint)
-            .query(&query)
-            .send()?
-            .error_for_status()?;
-        // it's OK to index into this vec, because reverse-geocoding only returns a single result
-        if let Some(headers) = resp.headers().get::<_>(XRL) {
-            let mut lock = self.remaining.try_lock();
-            if let Ok(ref mut mutex) = lock {
-                // not ideal, but typed headers are currently impossible in 0.9.x
-                let h = headers.to_str()?;
-                let h: i32 = h.parse()?;
-                **mutex = Some(h)
-            }
-        }
-        let res: OpencageResponse<T> = resp.json()?;
-        Ok(res)
+        query.extend(parameters.as_query(&limit));
+        query.extend(parameters.snapping_query());
+        query.extend(self.privacy_query());
+        query.extend(extra_params(&query, parameters.extra_params));
+        self.request(&query)
     }
     /// A forward-geocoding lookup of an address, returning an annotated response.
     ///
@@ -192,7 +462,8 @@ impl<'a> Opencage<'a> {
     /// Please see [the documentation](https://opencagedata.com/api#ambiguous-results) for details
     /// of best practices in order to obtain good-quality results.
     ///
-    /// This method passes the `no_record` parameter to the API.
+    /// This method passes the `no_record` parameter to the API, unless the privacy mode
+    /// is `Standard`.
     ///
     /// # Examples
     ///
@@ -251,16 +522,161 @@ impl<'a> Opencage<'a> {
     where
         T: Float + DeserializeOwned + Debug,
         U: Into<Option<InputBounds<T>>>,
+    {
+        self.forward_full_with(place, bounds, &self.parameters)
+    }
+
+    /// A forward-geocoding lookup of an address, returning the response body as received
+    ///
+    /// The body can be deserialized with [`RawResponse::parse`](../struct.RawResponse.html#method.parse)
+    /// into types whose strings borrow from it, avoiding an allocation per field.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::Opencage;
+    /// use geocoding::opencage::NOBOX;
+    /// use serde::Deserialize;
+    /// use std::borrow::Cow;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Formatted<'a> {
+    ///     #[serde(borrow)]
+    ///     formatted: Cow<'a, str>,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Response<'a> {
+    ///     #[serde(borrow)]
+    ///     results: Vec<Formatted<'a>>,
+    /// }
+    ///
+    /// let oc = Opencage::new("dcdbf0d783374909b3debee728c7cc10".to_string());
+    /// let raw = oc.forward_full_raw::<f64, _>("Moabit, Berlin", NOBOX).unwrap();
+    /// let res: Response = raw.parse().unwrap();
+    /// assert_eq!(res.results[0].formatted, "Moabit, Berlin, Germany");
+    /// ```
+    pub fn forward_full_raw<T, U>(
+        &self,
+        place: &str,
+        bounds: U,
+    ) -> Result<RawResponse, GeocodingError>
+    where
+        T: Float + Debug,
+        U: Into<Option<InputBounds<T>>>,
+    {
+        let (resp, meta) = self.send_search(place, bounds, &self.parameters)?;
+        Ok(RawResponse::new(resp.bytes()?.to_vec(), meta))
+    }
+
+    /// A forward-geocoding lookup like [`forward_full`](#method.forward_full), sending
+    /// `parameters` instead of the instance's parameters
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::Opencage;
+    /// use geocoding::opencage::{Parameters, NOBOX};
+    ///
+    /// let oc = Opencage::new("dcdbf0d783374909b3debee728c7cc10".to_string());
+    /// let parameters = Parameters {
+    ///     countrycode: Some("de"),
+    ///     ..*oc.parameters()
+    /// };
+    /// let res = oc.forward_full_with("Moabit", NOBOX, &parameters).unwrap();
+    /// assert!(res.results[0].formatted.contains("Berlin"));
+    /// ```
+    pub fn forward_full_with<T, U>(
+        &self,
+        place: &str,
+        bounds: U,
+        parameters: &Parameters,
+    ) -> Result<OpencageResponse<T>, GeocodingError>
+    where
+        T: Float + DeserializeOwned + Debug,
+        U: Into<Option<InputBounds<T>>>,
+    {
+        let (resp, meta) = self.send_search(place, bounds, parameters)?;
+        let mut res: OpencageResponse<T> = request::json(resp)?;
+        res.meta = meta;
+        warn_unknown_fields("opencage", &res.unknown_fields);
+        Ok(res)
+    }
+
+    /// A forward-geocoding lookup of an address, returning an iterator over pages of at most
+    /// `page_size` results
+    ///
+    /// OpenCage has no offset parameter: each further page is requested with a `limit`
+    /// raised by `page_size`, and only the results which previous pages didn't return are
+    /// kept. Iteration stops once a response returns fewer results than its limit, the limit
+    /// reaches [`MAX_LIMIT`], or a request fails, so that only the first pages are cheap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::Opencage;
+    /// use geocoding::opencage::{OpencagePage, NOBOX};
+    ///
+    /// let oc = Opencage::new("dcdbf0d783374909b3debee728c7cc10".to_string());
+    /// let pages: Vec<OpencagePage<f64>> = oc
+    ///     .forward_full_paged("Springfield", NOBOX, 2)
+    ///     .take(2)
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    /// assert_eq!(pages[1].offset, 2);
+    /// ```
+    pub fn forward_full_paged<'b, T, U>(
+        &'b self,
+        place: &'b str,
+        bounds: U,
+        page_size: u8,
+    ) -> OpencagePages<'a, 'b, T>
+    where
+        T: Float + DeserializeOwned + Debug,
+        U: Into<Option<InputBounds<T>>>,
+    {
+        OpencagePages {
+            oc: self,
+            place,
+            bounds: bounds.into(),
+            page_size: page_size.max(1),
+            seen: 0,
+            done: false,
+        }
+    }
+
+    fn send_search<T, U>(
+        &self,
+        place: &str,
+        bounds: U,
+        parameters: &Parameters,
+    ) -> Result<(Response, ResponseMeta), GeocodingError>
+    where
+        T: Float + Debug,
+        U: Into<Option<InputBounds<T>>>,
+    {
+        self.send(self.search_request(place, bounds, parameters))
+    }
+
+    /// The request of an annotated forward-geocoding lookup
+    pub(crate) fn search_request<T, U>(
+        &self,
+        place: &str,
+        bounds: U,
+        parameters: &Parameters,
+    ) -> RequestBuilder
+    where
+        T: Float + Debug,
+        U: Into<Option<InputBounds<T>>>,
     {
         let ann = String::from("0");
-        let record = String::from("1");
+        let limit = parameters.limit.map(|limit| limit.to_string());
         // we need this to avoid lifetime inconvenience
         let bd;
         let mut query = vec![
             ("q", place),
-            ("key", &self.api_key),
+            ("key", self.api_key()),
             ("no_annotations", &ann),
-            ("no_record", &record),
         ];
 
         // If search bounds are passed, use them
@@ -268,14 +684,55 @@ impl<'a> Opencage<'a> {
             bd = String::from(bds);
             query.push(("bounds", &bd));
         }
-        query.extend(self.parameters.as_query());
-
-        let resp = self
-            .client
-            .get(&self.endpoint)
-            .query(&query)
-            .send()?
-            .error_for_status()?;
+        query.extend(parameters.as_query(&limit));
+        query.extend(self.privacy_query());
+        query.extend(extra_params(&query, parameters.extra_params));
+        self.request(&query)
+    }
+
+    /// Check that the instance can send requests, failing with an `InvalidConfiguration` error
+    /// if the API key is empty
+    pub(crate) fn validate(&self) -> Result<(), GeocodingError> {
+        let blank = |key: &SecretString| key.expose_secret().trim().is_empty();
+        if self.keys.is_empty() || self.keys.keys().iter().any(blank) {
+            return Err(GeocodingError::InvalidConfiguration(
+                "the OpenCage API key is empty".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The key sent in the URLs of requests, which is replaced by the key of the ring
+    /// selected for each request when there are several
+    fn api_key(&self) -> &str {
+        self.keys
+            .current()
+            .map(|key| key.expose_secret().as_str())
+            .unwrap_or_default()
+    }
+
+    /// The request of the API with the parameters of `query`
+    fn request(&self, query: &[(&str, &str)]) -> RequestBuilder {
+        self.client.get(&self.endpoint).query(query)
+    }
+
+    /// Send a request to the API, updating the remaining calls, and return the response with
+    /// its details
+    ///
+    /// A `402 Payment Required` response, returned once the quota is exhausted, is a
+    /// `QuotaExceeded` error with the time at which the quota is reset.
+    fn send(&self, req: RequestBuilder) -> Result<(Response, ResponseMeta), GeocodingError> {
+        self.validate()?;
+        let (resp, meta) = match req.try_clone().and_then(|req| req.build().ok()) {
+            Some(request) if self.keys.len() > 1 => self.send_rotating(&request)?,
+            _ => req.send_with_meta(&self.hooks, &self.endpoint)?,
+        };
+        if resp.status() == StatusCode::PAYMENT_REQUIRED {
+            return Err(GeocodingError::QuotaExceeded {
+                reset: quota_reset(resp.headers()),
+            });
+        }
+        let resp = resp.error_for_status()?;
         if let Some(headers) = resp.headers().get::<_>(XRL) {
             let mut lock = self.remaining.try_lock();
             if let Ok(ref mut mutex) = lock {
@@ -285,11 +742,177 @@ impl<'a> Opencage<'a> {
                 **mutex = Some(h)
             }
         }
-        let res: OpencageResponse<T> = resp.json()?;
+        Ok((resp, meta))
+    }
+
+    /// Send a request with the keys of the ring, moving on to the next key while they are
+    /// rate-limited or out of quota
+    ///
+    /// Returns the last response once every key has been tried.
+    fn send_rotating(&self, request: &Request) -> Result<(Response, ResponseMeta), GeocodingError> {
+        let mut tried = 0;
+        loop {
+            let (index, key) = self.keys.select().expect("the keys were validated");
+            let url = keys::with_key(request.url(), "key", key);
+            let (resp, meta) =
+                request::rebuild(&self.client, request, request.method().clone(), url)
+                    .send_with_meta(&self.hooks, &self.endpoint)?;
+            tried += 1;
+            let exhausted = matches!(
+                resp.status(),
+                StatusCode::PAYMENT_REQUIRED | StatusCode::TOO_MANY_REQUESTS
+            );
+            if !exhausted || tried == self.keys.len() {
+                return Ok((resp, meta));
+            }
+            self.keys.exhausted(index);
+        }
+    }
+
+    /// The query parameters of the privacy mode
+    fn privacy_query(&self) -> Vec<(&'static str, &'static str)> {
+        match self.privacy_mode {
+            PrivacyMode::Standard => vec![],
+            PrivacyMode::NoRecord => vec![("no_record", "1")],
+        }
+    }
+
+    /// The request shared by `forward`, `forward_with_labels` and `forward_localized`
+    fn forward_lookup<T>(
+        &self,
+        place: &str,
+        parameters: &Parameters,
+    ) -> Result<OpencageResponse<T>, GeocodingError>
+    where
+        T: Float + DeserializeOwned + Debug,
+    {
+        let limit = parameters.limit.map(|limit| limit.to_string());
+        let mut query = vec![
+            ("q", place),
+            ("key", self.api_key()),
+            ("no_annotations", "1"),
+        ];
+        query.extend(parameters.as_query(&limit));
+        query.extend(self.privacy_query());
+        query.extend(extra_params(&query, parameters.extra_params));
+
+        let (resp, meta) = self.send(self.request(&query))?;
+        let mut res: OpencageResponse<T> = resp.json()?;
+        res.meta = meta;
         Ok(res)
     }
 }
 
+/// A page of forward-geocoding results
+///
+/// Returned by [`OpencagePages`](struct.OpencagePages.html).
+#[derive(Debug)]
+pub struct OpencagePage<T>
+where
+    T: Float,
+{
+    /// The response of the page's request, keeping only the results which previous pages
+    /// didn't return, and whose `total_results` counts all the results of the request
+    pub response: OpencageResponse<T>,
+    /// The number of results returned by previous pages
+    pub offset: usize,
+    /// Whether the API returned as many results as it was asked for, so a further page may
+    /// return more
+    pub has_more: bool,
+}
+
+/// An iterator over successive pages of forward-geocoding results
+///
+/// Created by [`Opencage::forward_full_paged`](struct.Opencage.html#method.forward_full_paged).
+pub struct OpencagePages<'a, 'b, T>
+where
+    T: Float + Debug,
+{
+    oc: &'b Opencage<'a>,
+    place: &'b str,
+    bounds: Option<InputBounds<T>>,
+    page_size: u8,
+    seen: usize,
+    done: bool,
+}
+
+impl<'a, 'b, T> Iterator for OpencagePages<'a, 'b, T>
+where
+    T: Float + DeserializeOwned + Debug,
+{
+    type Item = Result<OpencagePage<T>, GeocodingError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let limit = (self.seen + self.page_size as usize).min(MAX_LIMIT as usize);
+        let parameters = Parameters {
+            limit: Some(limit as u8),
+            ..self.oc.parameters
+        };
+        match self
+            .oc
+            .forward_full_with(self.place, self.bounds, &parameters)
+        {
+            Ok(mut res) => {
+                let returned = res.results.len();
+                let has_more = returned >= limit && limit < MAX_LIMIT as usize;
+                self.done = !has_more;
+                if returned <= self.seen {
+                    self.done = true;
+                    return None;
+                }
+                res.results.drain(..self.seen);
+                let offset = std::mem::replace(&mut self.seen, returned);
+                Some(Ok(OpencagePage {
+                    response: res,
+                    offset,
+                    has_more,
+                }))
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl<'a> PrivacyControl for Opencage<'a> {
+    /// Set the privacy mode, `NoRecord` by default, which passes the `no_record` parameter
+    /// to the API
+    fn with_privacy_mode(mut self, mode: PrivacyMode) -> Self {
+        self.privacy_mode = mode;
+        self
+    }
+
+    fn privacy_mode(&self) -> PrivacyMode {
+        self.privacy_mode
+    }
+}
+
+impl<'a> HealthCheck for Opencage<'a> {
+    /// A trivial query of the coordinates `0,0`, which counts against the quota
+    ///
+    /// Fails with `GeocodingError::QuotaExceeded` if the quota is exhausted, and with an HTTP
+    /// error if the API key is invalid.
+    fn ping(&self) -> Result<ProviderStatus, GeocodingError> {
+        let mut query = vec![
+            ("q", "0,0"),
+            ("key", self.api_key()),
+            ("no_annotations", "1"),
+            ("limit", "1"),
+        ];
+        query.extend(self.privacy_query());
+        let (_, meta) = self.send(self.request(&query))?;
+        Ok(ProviderStatus {
+            meta,
+            message: None,
+        })
+    }
+}
+
 impl<'a, T> Reverse<T> for Opencage<'a>
 where
     T: Float + DeserializeOwned + Debug,
@@ -297,41 +920,78 @@ where
     /// A reverse lookup of a point. More detail on the format of the
     /// returned `String` can be found [here](https://blog.opencagedata.com/post/99059889253/good-looking-addresses-solving-the-berlin-berlin)
     ///
-    /// This method passes the `no_annotations` and `no_record` parameters to the API.
+    /// This method passes the `no_annotations` parameter to the API, and the `no_record`
+    /// parameter unless the privacy mode is `Standard`.
     fn reverse(&self, point: &Point<T>) -> Result<Option<String>, GeocodingError> {
         let q = format!(
             "{}, {}",
             // OpenCage expects lat, lon order
-            (&point.y().to_f64().unwrap().to_string()),
-            &point.x().to_f64().unwrap().to_string()
+            format_coordinate(point.y(), self.precision),
+            format_coordinate(point.x(), self.precision)
         );
+        let limit = self.parameters.limit.map(|limit| limit.to_string());
         let mut query = vec![
             ("q", q.as_str()),
-            ("key", &self.api_key),
+            ("key", self.api_key()),
             ("no_annotations", "1"),
-            ("no_record", "1"),
         ];
-        query.extend(self.parameters.as_query());
-
-        let resp = self
-            .client
-            .get(&self.endpoint)
-            .query(&query)
-            .send()?
-            .error_for_status()?;
-        if let Some(headers) = resp.headers().get::<_>(XRL) {
-            let mut lock = self.remaining.try_lock();
-            if let Ok(ref mut mutex) = lock {
-                // not ideal, but typed headers are currently impossible in 0.9.x
-                let h = headers.to_str()?;
-                let h: i32 = h.parse()?;
-                **mutex = Some(h)
-            }
-        }
+        query.extend(self.parameters.as_query(&limit));
+        query.extend(self.parameters.snapping_query());
+        query.extend(self.privacy_query());
+        query.extend(extra_params(&query, self.parameters.extra_params));
+
+        let (resp, _) = self.send(self.request(&query))?;
         let res: OpencageResponse<T> = resp.json()?;
-        // it's OK to index into this vec, because reverse-geocoding only returns a single result
-        let address = &res.results[0];
-        Ok(Some(address.formatted.to_string()))
+        Ok(res
+            .results
+            .first()
+            .map(|address| address.formatted.to_string()))
+    }
+}
+
+impl<'a, T> ReverseFull<T> for Opencage<'a>
+where
+    T: Float + DeserializeOwned + Debug,
+{
+    /// A reverse lookup of a point, returning the first result with its `components`.
+    ///
+    /// This method passes the `no_record` parameter to the API, unless the privacy mode
+    /// is `Standard`.
+    fn reverse_result(&self, point: &Point<T>) -> Result<Option<GeocodeResult<T>>, GeocodingError> {
+        let res: OpencageResponse<T> = self.reverse_full(point)?;
+        let licence = res.licence();
+        res.results
+            .first()
+            .map(|result| {
+                Ok(GeocodeResult {
+                    licence,
+                    ..GeocodeResult::try_from(result)?
+                })
+            })
+            .transpose()
+    }
+
+    /// This method passes the `language` parameter to the API, in place of the instance's.
+    fn reverse_localized(
+        &self,
+        point: &Point<T>,
+        language: &LanguageTag,
+    ) -> Result<Option<GeocodeResult<T>>, GeocodingError> {
+        let parameters = Parameters {
+            language: Some(language.as_str()),
+            ..self.parameters
+        };
+        let res: OpencageResponse<T> = self.reverse_full_with(point, &parameters)?;
+        let licence = res.licence();
+        res.results
+            .first()
+            .map(|result| {
+                Ok(GeocodeResult {
+                    licence,
+                    ..GeocodeResult::try_from(result)?
+                })
+            })
+            .transpose()
     }
 }
 
@@ -342,37 +1002,142 @@ where
     /// A forward-geocoding lookup of an address. Please see [the documentation](https://opencagedata.com/api#ambiguous-results) for details
     /// of best practices in order to obtain good-quality results.
     ///
-    /// This method passes the `no_annotations` and `no_record` parameters to the API.
+    /// This method passes the `no_annotations` parameter to the API, and the `no_record`
+    /// parameter unless the privacy mode is `Standard`.
     fn forward(&self, place: &str) -> Result<Vec<Point<T>>, GeocodingError> {
-        let mut query = vec![
-            ("q", place),
-            ("key", &self.api_key),
-            ("no_annotations", "1"),
-            ("no_record", "1"),
-        ];
-        query.extend(self.parameters.as_query());
-
-        let resp = self
-            .client
-            .get(&self.endpoint)
-            .query(&query)
-            .send()?
-            .error_for_status()?;
-        if let Some(headers) = resp.headers().get::<_>(XRL) {
-            let mut lock = self.remaining.try_lock();
-            if let Ok(ref mut mutex) = lock {
-                // not ideal, but typed headers are currently impossible in 0.9.x
-                let h = headers.to_str()?;
-                let h: i32 = h.parse()?;
-                **mutex = Some(h)
-            }
-        }
-        let res: OpencageResponse<T> = resp.json()?;
+        let res: OpencageResponse<T> = self.forward_lookup(place, &self.parameters)?;
+        res.results.iter().map(Results::point).collect()
+    }
+}
+
+impl<'a, T> ForwardLabels<T> for Opencage<'a>
+where
+    T: Float + DeserializeOwned + Debug,
+{
+    /// A forward-geocoding lookup of an address, returning the `formatted` address of each result.
+    ///
+    /// This method passes the `no_annotations` parameter to the API, and the `no_record`
+    /// parameter unless the privacy mode is `Standard`.
+    fn forward_with_labels(&self, place: &str) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        let res: OpencageResponse<T> = self.forward_lookup(place, &self.parameters)?;
+        labels(&res)
+    }
+
+    /// This method passes the `language` parameter to the API, in place of the instance's.
+    fn forward_localized(
+        &self,
+        place: &str,
+        language: &LanguageTag,
+    ) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        let parameters = Parameters {
+            language: Some(language.as_str()),
+            ..self.parameters
+        };
+        let res: OpencageResponse<T> = self.forward_lookup(place, &parameters)?;
+        labels(&res)
+    }
+}
+
+impl<'a, T> ForwardStructured<T> for Opencage<'a>
+where
+    T: Float + DeserializeOwned + Debug,
+{
+    /// A forward lookup of the address formatted on one line, returning each result with the
+    /// address of its `components`.
+    fn forward_structured_results(
+        &self,
+        address: &Address,
+    ) -> Result<Vec<GeocodeResult<T>>, GeocodingError> {
+        let place = crate::address_format::format_one_line(address);
+        let res: OpencageResponse<T> = self.forward_lookup(&place, &self.parameters)?;
+        let licence = res.licence();
+        res.results
+            .iter()
+            .map(|result| {
+                Ok(GeocodeResult {
+                    licence: licence.clone(),
+                    ..GeocodeResult::try_from(result)?
+                })
+            })
+            .collect()
+    }
+}
+
+/// The points of the results of a response, with their `formatted` address
+fn labels<T>(res: &OpencageResponse<T>) -> Result<Vec<(Point<T>, String)>, GeocodingError>
+where
+    T: Float + Debug,
+{
+    res.results
+        .iter()
+        .map(|res| Ok((res.point()?, res.formatted.clone())))
+        .collect()
+}
+
+impl<'a, T> AdminReverse<T> for Opencage<'a>
+where
+    T: Float + DeserializeOwned + Debug,
+{
+    /// A reverse lookup of the city, region and country containing a point,
+    /// taken from the `components` of the first result.
+    ///
+    /// This method passes the `no_record` parameter to the API, unless the privacy mode
+    /// is `Standard`.
+    fn admin_reverse(&self, point: &Point<T>) -> Result<Option<AdminArea>, GeocodingError> {
+        let res: OpencageResponse<T> = self.reverse_full(point)?;
         Ok(res
             .results
-            .iter()
-            .map(|res| Point::new(res.geometry["lng"], res.geometry["lat"]))
-            .collect())
+            .first()
+            .map(|result| admin_area(&result.components)))
+    }
+}
+
+impl<T> From<&Results<T>> for Address
+where
+    T: Float,
+{
+    fn from(result: &Results<T>) -> Self {
+        let component = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|key| match result.components.get(*key) {
+                    Some(serde_json::Value::String(value)) => Some(value.clone()),
+                    Some(serde_json::Value::Number(value)) => Some(value.to_string()),
+                    _ => None,
+                })
+        };
+        Address {
+            house_number: component(&["house_number"]),
+            road: component(&["road", "footway", "street", "pedestrian", "path"]),
+            suburb: component(&["suburb", "neighbourhood", "city_district"]),
+            city: component(&["city", "town", "village", "municipality", "hamlet"]),
+            postcode: component(&["postcode"]),
+            state: component(&["state", "region"]),
+            state_code: component(&["state_code"]),
+            country: component(&["country"]),
+            country_code: component(&["ISO_3166-1_alpha-2", "country_code"])
+                .map(|code| code.to_uppercase()),
+        }
+    }
+}
+
+/// Extract the administrative areas from the components of a result
+fn admin_area(components: &HashMap<String, serde_json::Value>) -> AdminArea {
+    let component = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| components.get(*key).and_then(|v| v.as_str()))
+            .map(String::from)
+    };
+    AdminArea {
+        city: component(&["city", "town", "village", "municipality"]),
+        region: component(&["state", "region"]),
+        region_code: components
+            .get("ISO_3166-2")
+            .and_then(|codes| codes.get(0))
+            .and_then(|code| code.as_str())
+            .map(String::from),
+        country: component(&["country"]),
+        country_code: component(&["ISO_3166-1_alpha-2", "country_code"])
+            .map(|code| code.to_uppercase()),
     }
 }
 
@@ -509,20 +1274,78 @@ where
 ///   "total_results": 1
 /// }
 ///```
+///
+/// Only `results` is required: the metadata is absent from some responses, e.g. `rate` for
+/// paid plans, and parts of it from error responses.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OpencageResponse<T>
 where
     T: Float,
 {
+    #[serde(default)]
     pub documentation: String,
+    #[serde(default)]
     pub licenses: Vec<HashMap<String, String>>,
-    pub rate: Option<HashMap<String, i32>>,
+    /// The daily quota, only reported for free trial keys
+    #[serde(default)]
+    pub rate: Option<Rate>,
     pub results: Vec<Results<T>>,
+    #[serde(default)]
     pub status: Status,
+    #[serde(default)]
     pub stay_informed: HashMap<String, String>,
+    #[serde(default)]
     pub thanks: String,
-    pub timestamp: Timestamp,
+    #[serde(default)]
+    pub timestamp: Option<Timestamp>,
+    #[serde(default)]
     pub total_results: i32,
+    /// The parameters of the request as OpenCage interpreted them, with the `add_request`
+    /// parameter
+    #[serde(default)]
+    pub request: Option<HashMap<String, serde_json::Value>>,
+    /// The top-level fields unknown to this crate, a sign that OpenCage changed its schema
+    #[serde(flatten)]
+    pub unknown_fields: HashMap<String, serde_json::Value>,
+    /// Details of the HTTP response, not part of the JSON
+    #[serde(skip)]
+    pub meta: ResponseMeta,
+}
+
+impl<T> OpencageResponse<T>
+where
+    T: Float + DeserializeOwned,
+{
+    /// Parse a response from its JSON text, e.g. a recorded response
+    pub fn from_json_str(json: &str) -> Result<Self, GeocodingError> {
+        from_json_str(json)
+    }
+
+    /// Parse a response from its JSON bytes, in place with the `simd-json` feature
+    pub fn from_json_slice(json: &mut [u8]) -> Result<Self, GeocodingError> {
+        from_json_slice(json)
+    }
+
+    /// Whether the schema of the response differs from the one this crate knows, i.e. it has
+    /// unknown fields, or its `documentation` isn't the [`DOCUMENTATION_URL`]
+    pub fn schema_changed(&self) -> bool {
+        !self.unknown_fields.is_empty()
+            || !(self.documentation.is_empty() || self.documentation == DOCUMENTATION_URL)
+    }
+
+    /// The names of the `licenses` of the data, e.g. `"CC-BY-SA, ODbL"`
+    pub fn licence(&self) -> Option<String> {
+        let names: Vec<&str> = self
+            .licenses
+            .iter()
+            .filter_map(|licence| licence.get("name").map(String::as_str))
+            .collect();
+        if names.is_empty() {
+            None
+        } else {
+            Some(names.join(", "))
+        }
+    }
 }
 
 /// A forward geocoding result
@@ -539,6 +1362,50 @@ where
     pub geometry: HashMap<String, T>,
 }
 
+impl<T> Results<T>
+where
+    T: Float + Debug,
+{
+    /// The point of the result, from the `lng` and `lat` of its `geometry`
+    pub fn point(&self) -> Result<Point<T>, GeocodingError> {
+        let coordinate = |coordinate| {
+            self.geometry
+                .get(coordinate)
+                .copied()
+                .ok_or(GeocodingError::MissingCoordinate { coordinate })
+        };
+        Ok(Point::new(coordinate("lng")?, coordinate("lat")?))
+    }
+}
+
+impl<T> TryFrom<&Results<T>> for GeocodeResult<T>
+where
+    T: Float + Debug,
+{
+    type Error = GeocodingError;
+
+    /// Uses the geohash returned by OpenCage when annotations were requested, and fails
+    /// if the result's `geometry` lacks a coordinate
+    fn try_from(result: &Results<T>) -> Result<Self, Self::Error> {
+        let mut res = GeocodeResult::new(result.point()?, result.formatted.clone());
+        res.address = Some(Address::from(result));
+        res.bbox = result.bounds.as_ref().and_then(|bounds| {
+            Some(Rect::new(
+                (*bounds.southwest.get("lng")?, *bounds.southwest.get("lat")?),
+                (*bounds.northeast.get("lng")?, *bounds.northeast.get("lat")?),
+            ))
+        });
+        if let Some(annotations) = &result.annotations {
+            res.geohash = annotations
+                .geohash
+                .chars()
+                .take(crate::geohash::MAX_PRECISION)
+                .collect();
+        }
+        Ok(res)
+    }
+}
+
 /// Annotations pertaining to the geocoding result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Annotations<T>
@@ -598,8 +1465,9 @@ pub struct Timezone {
 }
 
 /// HTTP status metadata
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Status {
+    #[serde(default)]
     pub message: String,
     pub code: i16,
 }
@@ -607,11 +1475,23 @@ pub struct Status {
 /// Timestamp metadata
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Timestamp {
+    #[serde(default)]
     pub created_http: String,
     #[serde(deserialize_with = "from_ts")]
     pub created_unix: NaiveDateTime,
 }
 
+/// Daily quota metadata
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rate {
+    /// The number of requests allowed per day
+    pub limit: i32,
+    pub remaining: i32,
+    /// When the quota is reset
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub reset: DateTime<Utc>,
+}
+
 /// Bounding-box metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Bounds<T>
@@ -625,7 +1505,69 @@ where
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::Coordinate;
+    use crate::test_server::{Response, Server};
+    use crate::Coord;
+
+    #[test]
+    fn redacted_api_key_test() {
+        // Nothing listens on this port
+        let oc =
+            Opencage::new_with_endpoint("0123abcd".to_string(), "http://127.0.0.1:9/".to_string());
+        let err = oc.forward_full::<f64, _>("Berlin", NOBOX).unwrap_err();
+        let details = crate::batch::error_details(&err);
+        assert!(details.contains("key=REDACTED"));
+        assert!(!details.contains("0123abcd"));
+        assert!(!format!("{:?}", err).contains("0123abcd"));
+    }
+
+    #[test]
+    fn empty_api_key_test() {
+        let oc = Opencage::new_with_endpoint(" ".to_string(), "http://127.0.0.1:9/".to_string());
+        let err = oc.forward_full::<f64, _>("Berlin", NOBOX).unwrap_err();
+        assert!(matches!(err, GeocodingError::InvalidConfiguration(_)));
+        let err = oc.reverse(&Point::new(2.12870, 41.40139)).unwrap_err();
+        assert!(matches!(err, GeocodingError::InvalidConfiguration(_)));
+    }
+
+    #[test]
+    fn forward_full_paged_test() {
+        let server = Server::new(
+            [2, 3]
+                .iter()
+                .map(|&count| {
+                    let results = (0..count)
+                        .map(|i| {
+                            format!(
+                                r#"{{"components": {{}}, "confidence": 9, "formatted": "Springfield {}", "geometry": {{"lat": 39.8, "lng": -89.6}}}}"#,
+                                i
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    Response::json(format!(
+                        r#"{{"results": [{}], "total_results": {}}}"#,
+                        results, count
+                    ))
+                })
+                .collect(),
+        );
+        let oc = Opencage::new_with_endpoint("0123".to_string(), server.endpoint());
+        let pages = oc
+            .forward_full_paged::<f64, _>("Springfield", NOBOX, 2)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let requests = server.requests();
+        assert!(requests[0].contains("&limit=2"));
+        assert!(requests[1].contains("&limit=4"));
+        assert_eq!(pages.len(), 2);
+        assert!(pages[0].has_more);
+        assert_eq!(pages[0].response.results.len(), 2);
+        // The API returned fewer results than the limit
+        assert!(!pages[1].has_more);
+        assert_eq!(pages[1].offset, 2);
+        assert_eq!(pages[1].response.total_results, 3);
+        assert_eq!(pages[1].response.results[0].formatted, "Springfield 2");
+    }
 
     #[test]
     fn reverse_test() {
@@ -640,8 +1582,12 @@ mod test {
 
     #[test]
     fn reverse_test_with_params() {
-        let mut oc = Opencage::new("dcdbf0d783374909b3debee728c7cc10".to_string());
-        oc.parameters.language = Some("fr");
+        let oc = Opencage::new("dcdbf0d783374909b3debee728c7cc10".to_string()).with_parameters(
+            Parameters {
+                language: Some("fr"),
+                ..Parameters::default()
+            },
+        );
         let p = Point::new(2.12870, 41.40139);
         let res = oc.reverse(&p);
         assert_eq!(
@@ -653,25 +1599,226 @@ mod test {
     fn forward_test() {
         let oc = Opencage::new("dcdbf0d783374909b3debee728c7cc10".to_string());
         let address = "Schwabing, München";
-        let res = oc.forward(&address);
+        let res = oc.forward(address);
         assert_eq!(
             res.unwrap(),
-            vec![Point(Coordinate {
+            vec![Point(Coord {
                 x: 11.5884858,
                 y: 48.1700887
             })]
         );
     }
+    #[test]
+    fn forward_with_labels_test() {
+        let oc = Opencage::new("dcdbf0d783374909b3debee728c7cc10".to_string());
+        let address = "Schwabing, München";
+        let res = oc.forward_with_labels(address).unwrap();
+        assert_eq!(res[0].0, Point::new(11.5884858, 48.1700887));
+        assert!(res[0].1.contains("München"));
+    }
+    #[test]
+    fn quota_reset_test() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(quota_reset(&headers), None);
+        headers.insert(XRL_RESET, "1402185600".parse().unwrap());
+        assert_eq!(
+            quota_reset(&headers).unwrap().to_rfc3339(),
+            "2014-06-08T00:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn snapping_query_test() {
+        let mut parameters = Parameters::default();
+        assert!(parameters.snapping_query().is_empty());
+        parameters.with_snapping(ReverseSnapping::Road);
+        assert_eq!(parameters.snapping_query(), vec![("roadinfo", "1")]);
+        parameters.with_snapping(ReverseSnapping::Address);
+        assert_eq!(parameters.snapping_query(), vec![("address_only", "1")]);
+        // Snapping only applies to reverse lookups
+        assert!(parameters.as_query(&None).is_empty());
+    }
+
+    #[test]
+    fn toggles_query_test() {
+        let mut parameters = Parameters::default();
+        parameters
+            .with_no_dedupe(true)
+            .with_add_request(true)
+            .with_pretty(false);
+        assert_eq!(
+            parameters.as_query(&None),
+            vec![("no_dedupe", "1"), ("add_request", "1")]
+        );
+        parameters.with_add_request(false).with_pretty(true);
+        assert_eq!(
+            parameters.as_query(&None),
+            vec![("no_dedupe", "1"), ("pretty", "1")]
+        );
+    }
+
+    #[test]
+    fn add_request_response_test() {
+        let json =
+            r#"{"request": {"add_request": 1, "query": "Berlin", "limit": 10}, "results": []}"#;
+        let res = OpencageResponse::<f64>::from_json_str(json).unwrap();
+        assert!(res.unknown_fields.is_empty());
+        assert_eq!(res.request.unwrap()["query"], "Berlin");
+    }
+
+    #[test]
+    fn reverse_empty_test() {
+        let server = Server::single(Response::json(r#"{"results": []}"#));
+        let oc = Opencage::new_with_endpoint("0123".to_string(), server.endpoint());
+        let res = oc.reverse(&Point::new(0.0, 0.0));
+        server.requests();
+        assert_eq!(res.unwrap(), None);
+    }
+
+    #[test]
+    fn key_rotation_test() {
+        use crate::keys::{KeyRing, Rotation};
+        let server = Server::new(vec![
+            Response::json(r#"{"status": {"code": 402}}"#).with_status("402 Payment Required"),
+            Response::json(r#"{"results": []}"#),
+            Response::json(r#"{"results": []}"#),
+        ]);
+        let keys = KeyRing::new(
+            vec!["first".to_string(), "second".to_string()],
+            Rotation::OnQuotaExhausted,
+        );
+        let oc = Opencage::new_with_endpoint(String::new(), server.endpoint()).with_api_keys(keys);
+        let res: Vec<Point<f64>> = oc.forward("Berlin").unwrap();
+        assert!(res.is_empty());
+        let _: Vec<Point<f64>> = oc.forward("Berlin").unwrap();
+        let lines = server.request_lines();
+        assert!(lines[0].contains("key=first"));
+        assert!(lines[1].contains("key=second"));
+        // The exhausted key isn't used again
+        assert!(lines[2].contains("key=second"));
+    }
+
+    #[test]
+    fn privacy_mode_test() {
+        let oc = Opencage::new("dcdbf0d783374909b3debee728c7cc10".to_string());
+        assert_eq!(oc.privacy_mode(), PrivacyMode::NoRecord);
+        assert_eq!(oc.privacy_query(), vec![("no_record", "1")]);
+        let oc = oc.with_privacy_mode(PrivacyMode::Standard);
+        assert_eq!(oc.privacy_mode(), PrivacyMode::Standard);
+        assert!(oc.privacy_query().is_empty());
+    }
+
     #[test]
     fn reverse_full_test() {
-        let mut oc = Opencage::new("dcdbf0d783374909b3debee728c7cc10".to_string());
-        oc.parameters.language = Some("fr");
+        let oc = Opencage::new("dcdbf0d783374909b3debee728c7cc10".to_string()).with_parameters(
+            Parameters {
+                language: Some("fr"),
+                ..Parameters::default()
+            },
+        );
         let p = Point::new(2.12870, 41.40139);
         let res = oc.reverse_full(&p).unwrap();
         let first_result = &res.results[0];
         assert_eq!(first_result.components["road"], "Carrer de Calatrava");
     }
     #[test]
+    fn admin_area_test() {
+        let components: HashMap<String, serde_json::Value> = serde_json::from_str(
+            r#"{
+                "ISO_3166-1_alpha-2": "ES",
+                "ISO_3166-2": ["ES-CT", "ES-B"],
+                "city": "Barcelona",
+                "country": "Spain",
+                "country_code": "es",
+                "road": "Carrer de Calatrava",
+                "state": "Catalonia",
+                "state_code": "CT"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            admin_area(&components),
+            AdminArea {
+                city: Some("Barcelona".to_string()),
+                region: Some("Catalonia".to_string()),
+                region_code: Some("ES-CT".to_string()),
+                country: Some("Spain".to_string()),
+                country_code: Some("ES".to_string()),
+            }
+        );
+    }
+    #[test]
+    fn geocode_result_test() {
+        let result: Results<f64> = serde_json::from_str(
+            r#"{
+                "components": { "road": "Carrer de Calatrava" },
+                "bounds": {
+                    "northeast": { "lat": 41.4015, "lng": 2.1289 },
+                    "southwest": { "lat": 41.4012, "lng": 2.1285 }
+                },
+                "confidence": 10,
+                "formatted": "Carrer de Calatrava, 68, 08017 Barcelona, Spain",
+                "geometry": { "lat": 41.40139, "lng": 2.12870 }
+            }"#,
+        )
+        .unwrap();
+        let res = GeocodeResult::try_from(&result).unwrap();
+        assert_eq!(
+            res.bbox,
+            Some(Rect::new((2.1285, 41.4012), (2.1289, 41.4015)))
+        );
+        assert_eq!(res.point, Point::new(2.12870, 41.40139));
+        assert_eq!(res.label, "Carrer de Calatrava, 68, 08017 Barcelona, Spain");
+        assert_eq!(res.geohash.len(), 12);
+        assert_eq!(&res.geohash[..8], "sp3e82yh");
+        assert_eq!(
+            res.address.unwrap().road.as_deref(),
+            Some("Carrer de Calatrava")
+        );
+
+        let mut result = result;
+        result.geometry.remove("lng");
+        assert!(matches!(
+            GeocodeResult::try_from(&result),
+            Err(GeocodingError::MissingCoordinate { coordinate: "lng" })
+        ));
+    }
+    #[test]
+    fn address_test() {
+        let result: Results<f64> = serde_json::from_str(
+            r#"{
+                "components": {
+                    "ISO_3166-1_alpha-2": "ES",
+                    "city": "Barcelona",
+                    "country": "Spain",
+                    "house_number": 68,
+                    "postcode": "08017",
+                    "road": "Carrer de Calatrava",
+                    "state": "Catalonia",
+                    "state_code": "CT"
+                },
+                "confidence": 10,
+                "formatted": "Carrer de Calatrava, 68, 08017 Barcelona, Spain",
+                "geometry": { "lat": 41.40139, "lng": 2.12870 }
+            }"#,
+        )
+        .unwrap();
+        let address = Address::from(&result);
+        assert_eq!(address.house_number.as_deref(), Some("68"));
+        assert_eq!(
+            crate::address_format::format(&address),
+            "Carrer de Calatrava 68\n08017 Barcelona\nSpain"
+        );
+    }
+    #[test]
+    fn admin_reverse_test() {
+        let oc = Opencage::new("dcdbf0d783374909b3debee728c7cc10".to_string());
+        let p = Point::new(2.12870, 41.40139);
+        let res = oc.admin_reverse(&p).unwrap().unwrap();
+        assert_eq!(res.city.unwrap(), "Barcelona");
+        assert_eq!(res.country_code.unwrap(), "ES");
+    }
+    #[test]
     fn forward_full_test() {
         let oc = Opencage::new("dcdbf0d783374909b3debee728c7cc10".to_string());
         let address = "UCL CASA";
@@ -679,7 +1826,7 @@ mod test {
             minimum_lonlat: Point::new(-0.13806939125061035, 51.51989264641164),
             maximum_lonlat: Point::new(-0.13427138328552246, 51.52319711775629),
         };
-        let res = oc.forward_full(&address, bbox).unwrap();
+        let res = oc.forward_full(address, bbox).unwrap();
         let first_result = &res.results[0];
         assert!(first_result.formatted.contains("UCL"));
     }
@@ -691,7 +1838,7 @@ mod test {
             Point::new(-0.13806939125061035, 51.51989264641164),
             Point::new(-0.13427138328552246, 51.52319711775629),
         );
-        let res = oc.forward_full(&address, bbox).unwrap();
+        let res = oc.forward_full(address, bbox).unwrap();
         let first_result = &res.results[0];
         assert!(first_result
             .formatted
@@ -705,7 +1852,7 @@ mod test {
             Point::from((-0.13806939125061035, 51.51989264641164)),
             Point::from((-0.13427138328552246, 51.52319711775629)),
         );
-        let res = oc.forward_full(&address, bbox).unwrap();
+        let res = oc.forward_full(address, bbox).unwrap();
         let first_result = &res.results[0];
         assert!(first_result
             .formatted
@@ -719,7 +1866,7 @@ mod test {
             (-0.13806939125061035, 51.51989264641164),
             (-0.13427138328552246, 51.52319711775629),
         );
-        let res = oc.forward_full(&address, bbox).unwrap();
+        let res = oc.forward_full(address, bbox).unwrap();
         let first_result = &res.results[0];
         assert!(first_result
             .formatted
@@ -729,8 +1876,41 @@ mod test {
     fn forward_full_test_nobox() {
         let oc = Opencage::new("dcdbf0d783374909b3debee728c7cc10".to_string());
         let address = "Moabit, Berlin, Germany";
-        let res = oc.forward_full(&address, NOBOX).unwrap();
+        let res = oc.forward_full(address, NOBOX).unwrap();
         let first_result = &res.results[0];
         assert_eq!(first_result.formatted, "Moabit, Berlin, Germany");
     }
+
+    #[test]
+    fn labels_test() {
+        let res = OpencageResponse::<f64>::from_json_str(
+            r#"{"results": [{
+                "components": {},
+                "confidence": 10,
+                "formatted": "Carrer de Calatrava, 68, 08017 Barcelona, Spain",
+                "geometry": { "lat": 41.40139, "lng": 2.12870 }
+            }]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            labels(&res).unwrap(),
+            vec![(
+                Point::new(2.12870, 41.40139),
+                "Carrer de Calatrava, 68, 08017 Barcelona, Spain".to_string()
+            )]
+        );
+        let res = OpencageResponse::<f64>::from_json_str(
+            r#"{"results": [{
+                "components": {},
+                "confidence": 1,
+                "formatted": "Spain",
+                "geometry": { "lng": -3.7 }
+            }]}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            labels(&res),
+            Err(GeocodingError::MissingCoordinate { coordinate: "lat" })
+        ));
+    }
 }
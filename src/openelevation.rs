@@ -0,0 +1,244 @@
+//! The [Open-Elevation](https://open-elevation.com/) provider.
+//!
+//! Elevation lookups are implemented on the [`Openelevation`](struct.Openelevation.html) struct.
+//! Please see the [API documentation](https://github.com/Jorl17/open-elevation/blob/master/docs/api.md) for details.
+//!
+//! The public Open-Elevation API is free, but has limited capacity: please consider
+//! [hosting your own instance](https://github.com/Jorl17/open-elevation/blob/master/docs/host-your-own.md)
+//! for large workloads.
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::{Elevation, Openelevation, Point};
+//!
+//! let oe = Openelevation::new();
+//! let p = Point::new(7.451352119445801, 46.92793655395508);
+//! let res = oe.elevation(&p);
+//! assert!(res.unwrap().unwrap() > 500.0);
+//! ```
+use crate::audit::AuditSink;
+use crate::format_coordinate;
+use crate::from_json_str;
+use crate::request::{RequestHooks, SendHooked};
+use crate::stats::UsageStats;
+use crate::throttle::Throttle;
+use crate::Client;
+use crate::ClientSettings;
+use crate::Deserialize;
+use crate::Elevation;
+use crate::GeocodingError;
+use crate::Point;
+use num_traits::Float;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// An instance of the Open-Elevation service
+pub struct Openelevation {
+    client: Client,
+    client_settings: ClientSettings,
+    endpoint: String,
+    hooks: RequestHooks,
+}
+
+impl Openelevation {
+    /// Create a new Open-Elevation instance using the default endpoint
+    pub fn new() -> Self {
+        Openelevation::new_with_endpoint("https://api.open-elevation.com/api/v1/".to_string())
+    }
+
+    /// Create a new Open-Elevation instance with a custom endpoint.
+    ///
+    /// Endpoint should include a trailing slash (i.e. "https://api.open-elevation.com/api/v1/")
+    pub fn new_with_endpoint(endpoint: String) -> Self {
+        let client_settings = ClientSettings::default();
+        Openelevation {
+            client: client_settings.client(),
+            client_settings,
+            endpoint,
+            hooks: RequestHooks::default(),
+        }
+    }
+
+    /// Space the requests to the endpoint's host using `throttle`, which also slows down
+    /// when the server asks to
+    ///
+    /// See the [`throttle`](../throttle/index.html) module for details.
+    pub fn with_throttle(mut self, throttle: Arc<Throttle>) -> Self {
+        self.hooks.throttle = Some(throttle);
+        self
+    }
+
+    /// Record every request sent to the endpoint in `sink`
+    ///
+    /// See the [`audit`](../audit/index.html) module for details.
+    pub fn with_audit(mut self, sink: Arc<dyn AuditSink>) -> Self {
+        self.hooks.audit = Some(sink);
+        self
+    }
+
+    /// The requests sent by this instance so far
+    ///
+    /// See the [`stats`](../stats/index.html) module for details.
+    pub fn stats(&self) -> UsageStats {
+        self.hooks.usage.snapshot()
+    }
+
+    /// Accept compressed responses, `true` by default
+    ///
+    /// Only the compressions enabled by the crate's features are negotiated.
+    pub fn with_compression(mut self, compression: bool) -> Self {
+        self.client_settings.compression = compression;
+        self.client = self.client_settings.client();
+        self
+    }
+
+    /// Apply connection pool and compression settings to the HTTP client
+    pub fn with_client_settings(mut self, settings: &ClientSettings) -> Self {
+        self.client_settings = settings.clone();
+        self.client = self.client_settings.client();
+        self
+    }
+
+    /// An elevation lookup of several points in a single request, returning the full response
+    ///
+    /// This method passes the `locations` parameter to the API.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::{Openelevation, Point};
+    /// use geocoding::openelevation::OpenelevationResponse;
+    ///
+    /// let oe = Openelevation::new();
+    /// let points = [
+    ///     Point::new(7.451352119445801, 46.92793655395508),
+    ///     Point::new(2.12870, 41.40139),
+    /// ];
+    /// let res: OpenelevationResponse<f64> = oe.lookup(&points).unwrap();
+    /// assert_eq!(res.results.len(), 2);
+    /// ```
+    pub fn lookup<T>(&self, points: &[Point<T>]) -> Result<OpenelevationResponse<T>, GeocodingError>
+    where
+        T: Float + Debug,
+        for<'de> T: Deserialize<'de>,
+    {
+        // Open-Elevation expects lat, lon order
+        let locations = points
+            .iter()
+            .map(|p| {
+                format!(
+                    "{},{}",
+                    format_coordinate(p.y(), None),
+                    format_coordinate(p.x(), None)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("|");
+
+        let resp = self
+            .client
+            .get(format!("{}lookup", self.endpoint))
+            .query(&[("locations", &locations)])
+            .send_hooked(&self.hooks, &self.endpoint)?
+            .error_for_status()?;
+        let res: OpenelevationResponse<T> = resp.json()?;
+        Ok(res)
+    }
+}
+
+impl Default for Openelevation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Elevation<T> for Openelevation
+where
+    T: Float + Debug,
+    for<'de> T: Deserialize<'de>,
+{
+    /// An elevation lookup of a point. Please see [the documentation](https://github.com/Jorl17/open-elevation/blob/master/docs/api.md) for details.
+    ///
+    /// This method passes the `locations` parameter to the API.
+    fn elevation(&self, point: &Point<T>) -> Result<Option<f64>, GeocodingError> {
+        let res = self.lookup(std::slice::from_ref(point))?;
+        Ok(res.results.first().and_then(|r| r.elevation))
+    }
+}
+
+/// The top-level full JSON response returned by an elevation lookup
+///
+/// See [the documentation](https://github.com/Jorl17/open-elevation/blob/master/docs/api.md) for more details
+///
+///```json
+/// {
+///     "results": [
+///         {
+///             "latitude": 46.92793655395508,
+///             "longitude": 7.451352119445801,
+///             "elevation": 557.0
+///         }
+///     ]
+/// }
+///```
+#[derive(Debug, Deserialize)]
+pub struct OpenelevationResponse<T>
+where
+    T: Float + Debug,
+{
+    pub results: Vec<OpenelevationResult<T>>,
+}
+
+impl<T> OpenelevationResponse<T>
+where
+    T: Float + Debug,
+    for<'de> T: Deserialize<'de>,
+{
+    /// Parse a response from its JSON text, e.g. a recorded response
+    pub fn from_json_str(json: &str) -> Result<Self, GeocodingError> {
+        from_json_str(json)
+    }
+}
+
+/// An elevation result
+#[derive(Clone, Debug, Deserialize)]
+pub struct OpenelevationResult<T>
+where
+    T: Float + Debug,
+{
+    pub latitude: T,
+    pub longitude: T,
+    pub elevation: Option<f64>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn elevation_test() {
+        let oe = Openelevation::new();
+        let p = Point::new(7.451352119445801, 46.92793655395508);
+        let res = oe.elevation(&p).unwrap().unwrap();
+        assert!(res > 500.0 && res < 600.0);
+    }
+
+    #[test]
+    fn lookup_test() {
+        let oe = Openelevation::new();
+        let points = [Point::new(7.451352119445801, 46.92793655395508)];
+        let res: OpenelevationResponse<f64> = oe.lookup(&points).unwrap();
+        assert_eq!(res.results.len(), 1);
+        assert!((res.results[0].longitude - 7.4513).abs() < 1e-3);
+    }
+
+    #[test]
+    fn response_test() {
+        let res: OpenelevationResponse<f64> = serde_json::from_str(
+            r#"{"results": [{"latitude": 41.161758, "longitude": -8.583933, "elevation": 117}]}"#,
+        )
+        .unwrap();
+        assert_eq!(res.results[0].elevation, Some(117.0));
+    }
+}
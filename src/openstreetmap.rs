@@ -16,33 +16,102 @@
 //! let res = osm.forward(&address);
 //! assert_eq!(res.unwrap(), vec![Point::new(11.5884858, 48.1700887)]);
 //! ```
+#[cfg(feature = "blocking")]
+use crate::audit::AuditSink;
+#[cfg(feature = "blocking")]
+use crate::failover::Failover;
+#[cfg(feature = "blocking")]
+use crate::request::{self, RequestHooks, SendHooked};
+#[cfg(feature = "blocking")]
+use crate::stats::UsageStats;
+#[cfg(feature = "blocking")]
+use crate::throttle::Throttle;
+#[cfg(feature = "blocking")]
+use crate::ClientSettings;
+use crate::CountryCode;
 use crate::GeocodingError;
 use crate::InputBounds;
-use crate::Point;
-use crate::UA_STRING;
-use crate::{Client, HeaderMap, HeaderValue, USER_AGENT};
+#[cfg(feature = "blocking")]
+use crate::LanguageTag;
+#[cfg(feature = "blocking")]
+use crate::RawResponse;
+use crate::ResponseMeta;
+use crate::ReverseSnapping;
+#[cfg(any(feature = "blocking", feature = "async"))]
+use crate::{extra_params, format_coordinate, Attribution, ExposeSecret, SecretString};
+#[cfg(any(feature = "blocking", feature = "async"))]
+use crate::{from_json_body, warn_unknown_fields};
+use crate::{from_json_slice, from_json_str};
+#[cfg(feature = "blocking")]
+use crate::{
+    haversine_distance, AdminReverse, Forward, ForwardLabels, ForwardStructured, NearbySearch,
+    Reverse, ReverseFull,
+};
+use crate::{Address, AdminArea, GeocodeResult};
+#[cfg(feature = "blocking")]
+use crate::{Client, HeaderMap};
 use crate::{Deserialize, Serialize};
-use crate::{Forward, Reverse};
+#[cfg(feature = "blocking")]
+use crate::{HealthCheck, ProviderStatus};
+use crate::{Point, Rect};
+use chrono::{DateTime, FixedOffset};
+#[cfg(any(feature = "blocking", feature = "async"))]
+use hyper::body::Bytes;
 use num_traits::Float;
+#[cfg(feature = "blocking")]
+use reqwest::blocking::{RequestBuilder, Response};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
 use std::fmt::Debug;
+use std::str::FromStr;
+#[cfg(feature = "blocking")]
+use std::sync::Arc;
+#[cfg(feature = "async")]
+use std::time::Instant;
+
+/// The maximum number of OSM ids Nominatim resolves in a single `/lookup` request
+#[cfg(any(feature = "blocking", feature = "async"))]
+const LOOKUP_MAX_IDS: usize = 50;
 
 /// An instance of the Openstreetmap geocoding service
+#[cfg(feature = "blocking")]
 pub struct Openstreetmap {
     client: Client,
+    client_settings: ClientSettings,
     endpoint: String,
+    api_key: Option<(String, SecretString)>,
+    email: Option<String>,
+    precision: Option<u8>,
+    hooks: RequestHooks,
 }
 
 /// An instance of a parameter builder for Openstreetmap geocoding
-pub struct OpenstreetmapParams<'a, T>
+///
+/// The parameters own their data, so they can be built from strings created at runtime and
+/// moved across threads or tasks.
+#[derive(Clone, Debug)]
+pub struct OpenstreetmapParams<T>
 where
     T: Float + Debug,
 {
-    query: &'a str,
+    // Only read by the clients
+    #[cfg_attr(not(any(feature = "blocking", feature = "async")), allow(dead_code))]
+    query: String,
     addressdetails: bool,
-    viewbox: Option<&'a InputBounds<T>>,
+    viewbox: Option<InputBounds<T>>,
+    exclude_place_ids: Vec<u64>,
+    layers: Vec<Layer>,
+    feature_type: Option<FeatureType>,
+    structured_address: Option<Address>,
+    countrycodes: Vec<CountryCode>,
+    accept_language: Option<String>,
+    bounded: bool,
+    format: Format,
+    extra_params: Vec<(String, String)>,
 }
 
-impl<'a, T> OpenstreetmapParams<'a, T>
+impl<T> OpenstreetmapParams<T>
 where
     T: Float + Debug,
 {
@@ -57,306 +126,2376 @@ where
     ///     (-0.13806939125061035, 51.51989264641164),
     ///     (-0.13427138328552246, 51.52319711775629),
     /// );
-    /// let params = OpenstreetmapParams::new(&"UCL CASA")
+    /// let params = OpenstreetmapParams::new(format!("UCL {}", "CASA"))
     ///     .with_addressdetails(true)
-    ///     .with_viewbox(&viewbox)
+    ///     .with_viewbox(viewbox)
     ///     .build();
     /// ```
-    pub fn new(query: &'a str) -> OpenstreetmapParams<'a, T> {
+    pub fn new<S>(query: S) -> OpenstreetmapParams<T>
+    where
+        S: Into<String>,
+    {
         OpenstreetmapParams {
-            query,
+            query: query.into(),
             addressdetails: false,
             viewbox: None,
+            exclude_place_ids: vec![],
+            layers: vec![],
+            feature_type: None,
+            structured_address: None,
+            countrycodes: vec![],
+            accept_language: None,
+            bounded: false,
+            format: Format::default(),
+            extra_params: vec![],
         }
     }
 
     /// Set the `addressdetails` property
-    pub fn with_addressdetails(&mut self, addressdetails: bool) -> &mut Self {
+    pub fn with_addressdetails(mut self, addressdetails: bool) -> Self {
         self.addressdetails = addressdetails;
         self
     }
 
     /// Set the `viewbox` property
-    pub fn with_viewbox(&mut self, viewbox: &'a InputBounds<T>) -> &mut Self {
+    pub fn with_viewbox(mut self, viewbox: InputBounds<T>) -> Self {
         self.viewbox = Some(viewbox);
         self
     }
 
-    /// Build and return an instance of OpenstreetmapParams
-    pub fn build(&self) -> OpenstreetmapParams<'a, T> {
-        OpenstreetmapParams {
-            query: self.query,
-            addressdetails: self.addressdetails,
-            viewbox: self.viewbox,
-        }
+    /// Set the `exclude_place_ids` property
+    pub fn with_exclude_place_ids(mut self, exclude_place_ids: &[u64]) -> Self {
+        self.exclude_place_ids = exclude_place_ids.to_vec();
+        self
     }
-}
 
-impl Openstreetmap {
-    /// Create a new Openstreetmap geocoding instance using the default endpoint
-    pub fn new() -> Self {
-        Openstreetmap::new_with_endpoint("https://nominatim.openstreetmap.org/".to_string())
+    /// Set the `layer` property
+    pub fn with_layers(mut self, layers: &[Layer]) -> Self {
+        self.layers = layers.to_vec();
+        self
     }
 
-    /// Create a new Openstreetmap geocoding instance with a custom endpoint.
-    ///
-    /// Endpoint should include a trailing slash (i.e. "https://nominatim.openstreetmap.org/")
-    pub fn new_with_endpoint(endpoint: String) -> Self {
-        let mut headers = HeaderMap::new();
-        headers.insert(USER_AGENT, HeaderValue::from_static(UA_STRING));
-        let client = Client::builder()
-            .default_headers(headers)
-            .build()
-            .expect("Couldn't build a client!");
-        Openstreetmap { client, endpoint }
+    /// Set the `featureType` property
+    pub fn with_feature_type(mut self, feature_type: FeatureType) -> Self {
+        self.feature_type = Some(feature_type);
+        self
     }
 
-    /// A forward-geocoding lookup of an address, returning a full detailed response
-    ///
-    /// Accepts an [`OpenstreetmapParams`](struct.OpenstreetmapParams.html) struct for specifying
-    /// options, including whether to include address details in the response and whether to filter
-    /// by a bounding box.
-    ///
-    /// Please see [the documentation](https://nominatim.org/release-docs/develop/api/Search/) for details.
+    /// Search for a structured address instead of the free-form query
     ///
-    /// This method passes the `format` parameter to the API.
+    /// The `street` (house number and road), `city`, `state`, `country` and `postalcode`
+    /// parameters are sent instead of `q`, which improves matches for addresses whose
+    /// components are known, e.g. after parsing them with the `libpostal` feature.
+    /// Please see [the documentation](https://nominatim.org/release-docs/develop/api/Search/#structured-query) for details.
+    pub fn with_structured_address(mut self, address: Address) -> Self {
+        self.structured_address = Some(address);
+        self
+    }
+
+    /// Restrict the results to the viewbox, instead of preferring results within it
+    pub fn with_bounded(mut self, bounded: bool) -> Self {
+        self.bounded = bounded;
+        self
+    }
+
+    /// Restrict the results to the countries of `countrycodes`
+    pub fn with_countrycodes(mut self, countrycodes: &[CountryCode]) -> Self {
+        self.countrycodes = countrycodes.to_vec();
+        self
+    }
+
+    /// Set the preferred languages of the results, as an `Accept-Language` value such as "de,en"
+    pub fn with_accept_language<S>(mut self, accept_language: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.accept_language = Some(accept_language.into());
+        self
+    }
+
+    /// Set the `format` of the response, for instances which don't serve GeoJSON correctly
+    pub fn with_format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Pass parameters not modelled by the crate to the API, such as new options
     ///
-    /// # Examples
+    /// The parameters set by the crate take precedence over these on conflict.
+    pub fn with_extra_params(mut self, extra_params: &[(&str, &str)]) -> Self {
+        self.extra_params = extra_params
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        self
+    }
+
+    /// Build and return an instance of OpenstreetmapParams
+    pub fn build(self) -> OpenstreetmapParams<T> {
+        self
+    }
+
+    /// The query of a search with these parameters, excluding the places in `seen`
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    fn query_pairs(&self, seen: &[u64]) -> Vec<(&str, String)> {
+        let addressdetails = if self.addressdetails { "1" } else { "0" };
+        let mut query = vec![
+            ("format", self.format.as_str().to_string()),
+            ("addressdetails", addressdetails.to_string()),
+        ];
+
+        if let Some(address) = &self.structured_address {
+            let street = [&address.house_number, &address.road]
+                .iter()
+                .filter_map(|component| component.as_deref())
+                .collect::<Vec<_>>()
+                .join(" ");
+            if !street.is_empty() {
+                query.push(("street", street));
+            }
+            let components = [
+                ("city", &address.city),
+                ("state", &address.state),
+                ("country", &address.country),
+                ("postalcode", &address.postcode),
+            ];
+            for (name, component) in components.iter() {
+                if let Some(value) = component {
+                    query.push((*name, value.clone()));
+                }
+            }
+        } else {
+            query.push(("q", self.query.clone()));
+        }
+
+        if let Some(vb) = self.viewbox {
+            query.push(("viewbox", String::from(vb)));
+            if self.bounded {
+                query.push(("bounded", "1".to_string()));
+            }
+        }
+
+        if !self.exclude_place_ids.is_empty() || !seen.is_empty() {
+            let exclude_place_ids = self
+                .exclude_place_ids
+                .iter()
+                .chain(seen)
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            query.push(("exclude_place_ids", exclude_place_ids));
+        }
+
+        if !self.layers.is_empty() {
+            let layers = self
+                .layers
+                .iter()
+                .map(|layer| layer.as_str())
+                .collect::<Vec<_>>()
+                .join(",");
+            query.push(("layer", layers));
+        }
+
+        if let Some(ft) = self.feature_type {
+            query.push(("featureType", ft.as_str().to_string()));
+        }
+
+        if !self.countrycodes.is_empty() {
+            let countrycodes = self
+                .countrycodes
+                .iter()
+                .map(|code| code.as_str().to_lowercase())
+                .collect::<Vec<_>>()
+                .join(",");
+            query.push(("countrycodes", countrycodes));
+        }
+
+        if let Some(accept_language) = &self.accept_language {
+            query.push(("accept-language", accept_language.clone()));
+        }
+
+        let extra: Vec<(&str, String)> = extra_params(&query, &self.extra_params)
+            .into_iter()
+            .map(|(name, value)| (name, value.to_string()))
+            .collect();
+        query.extend(extra);
+        query
+    }
+}
+
+/// An instance of a parameter builder for Openstreetmap reverse geocoding
+#[derive(Clone, Debug, Default)]
+pub struct OpenstreetmapReverseParams {
+    addressdetails: bool,
+    extratags: bool,
+    namedetails: bool,
+    zoom: Option<u8>,
+    layers: Vec<Layer>,
+    accept_language: Option<String>,
+    format: Format,
+    extra_params: Vec<(String, String)>,
+}
+
+impl OpenstreetmapReverseParams {
+    /// Create a new OpenStreetMap reverse parameter builder
+    /// # Example:
     ///
     /// ```
-    /// use geocoding::{Openstreetmap, InputBounds, Point};
-    /// use geocoding::openstreetmap::{OpenstreetmapParams, OpenstreetmapResponse};
+    /// use geocoding::openstreetmap::OpenstreetmapReverseParams;
     ///
-    /// let osm = Openstreetmap::new();
-    /// let viewbox = InputBounds::new(
-    ///     (-0.13806939125061035, 51.51989264641164),
-    ///     (-0.13427138328552246, 51.52319711775629),
-    /// );
-    /// let params = OpenstreetmapParams::new(&"UCL CASA")
+    /// let params = OpenstreetmapReverseParams::new()
     ///     .with_addressdetails(true)
-    ///     .with_viewbox(&viewbox)
+    ///     .with_extratags(true)
+    ///     .with_zoom(18)
     ///     .build();
-    /// let res: OpenstreetmapResponse<f64> = osm.forward_full(&params).unwrap();
-    /// let result = res.features[0].properties.clone();
-    /// assert!(result.display_name.contains("Gordon Square"));
     /// ```
-    pub fn forward_full<T>(
-        &self,
-        params: &OpenstreetmapParams<T>,
-    ) -> Result<OpenstreetmapResponse<T>, GeocodingError>
+    pub fn new() -> OpenstreetmapReverseParams {
+        OpenstreetmapReverseParams::default()
+    }
+
+    /// Set the `addressdetails` property
+    pub fn with_addressdetails(mut self, addressdetails: bool) -> Self {
+        self.addressdetails = addressdetails;
+        self
+    }
+
+    /// Set the `extratags` property
+    pub fn with_extratags(mut self, extratags: bool) -> Self {
+        self.extratags = extratags;
+        self
+    }
+
+    /// Set the `namedetails` property
+    pub fn with_namedetails(mut self, namedetails: bool) -> Self {
+        self.namedetails = namedetails;
+        self
+    }
+
+    /// Set the `zoom` property, the level of detail required for the address (0 - 18)
+    pub fn with_zoom(mut self, zoom: u8) -> Self {
+        self.zoom = Some(zoom);
+        self
+    }
+
+    /// Set the `layer` property
+    pub fn with_layers(mut self, layers: &[Layer]) -> Self {
+        self.layers = layers.to_vec();
+        self
+    }
+
+    /// Snap the point to the nearest address, the nearest road or the containing locality
+    ///
+    /// This sets the `zoom` to 18, 17 or 10, and the `layer` to `address`, so that points of
+    /// interest aren't returned.
+    pub fn with_snapping(self, snapping: ReverseSnapping) -> Self {
+        let zoom = match snapping {
+            ReverseSnapping::Address => 18,
+            ReverseSnapping::Road => 17,
+            ReverseSnapping::Locality => 10,
+        };
+        self.with_zoom(zoom).with_layers(&[Layer::Address])
+    }
+
+    /// Set the `accept-language` property, the preferred languages of the result
+    pub fn with_accept_language<S>(mut self, accept_language: S) -> Self
     where
-        T: Float + Debug,
-        for<'de> T: Deserialize<'de>,
+        S: Into<String>,
     {
-        let format = String::from("geojson");
-        let addressdetails = String::from(if params.addressdetails { "1" } else { "0" });
-        // For lifetime issues
-        let viewbox;
+        self.accept_language = Some(accept_language.into());
+        self
+    }
 
-        let mut query = vec![
-            (&"q", params.query),
-            (&"format", &format),
-            (&"addressdetails", &addressdetails),
-        ];
+    /// Set the `format` of the response, for instances which don't serve GeoJSON correctly
+    pub fn with_format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Pass parameters not modelled by the crate to the API, such as new options
+    ///
+    /// The parameters set by the crate take precedence over these on conflict.
+    pub fn with_extra_params(mut self, extra_params: &[(&str, &str)]) -> Self {
+        self.extra_params = extra_params
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        self
+    }
 
-        if let Some(vb) = params.viewbox {
-            viewbox = String::from(*vb);
-            query.push((&"viewbox", &viewbox));
+    /// Build and return an instance of OpenstreetmapReverseParams
+    pub fn build(self) -> OpenstreetmapReverseParams {
+        self
+    }
+
+    /// The query of a reverse lookup of `location`, a point or an OSM object, with these
+    /// parameters
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    fn query_pairs(&self, location: &[(&'static str, String)]) -> Vec<(&str, String)> {
+        let flag = |set: bool| String::from(if set { "1" } else { "0" });
+        let mut query: Vec<(&str, String)> = location.to_vec();
+        query.extend([
+            ("format", self.format.as_str().to_string()),
+            ("addressdetails", flag(self.addressdetails)),
+            ("extratags", flag(self.extratags)),
+            ("namedetails", flag(self.namedetails)),
+        ]);
+
+        if let Some(z) = self.zoom {
+            query.push(("zoom", z.to_string()));
         }
 
-        let resp = self
-            .client
-            .get(&format!("{}search", self.endpoint))
-            .query(&query)
-            .send()?
-            .error_for_status()?;
-        let res: OpenstreetmapResponse<T> = resp.json()?;
-        Ok(res)
+        if !self.layers.is_empty() {
+            let layers = self
+                .layers
+                .iter()
+                .map(|layer| layer.as_str())
+                .collect::<Vec<_>>()
+                .join(",");
+            query.push(("layer", layers));
+        }
+
+        if let Some(accept_language) = &self.accept_language {
+            query.push(("accept-language", accept_language.clone()));
+        }
+
+        let extra: Vec<(&str, String)> = extra_params(&query, &self.extra_params)
+            .into_iter()
+            .map(|(name, value)| (name, value.to_string()))
+            .collect();
+        query.extend(extra);
+        query
     }
 }
 
-impl Default for Openstreetmap {
-    fn default() -> Self {
-        Self::new()
-    }
+/// A Nominatim data layer, used to restrict search results to a kind of feature
+///
+/// See [the documentation](https://nominatim.org/release-docs/develop/api/Search/#result-restriction) for details.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Layer {
+    Address,
+    Poi,
+    Railway,
+    Natural,
+    Manmade,
 }
 
-impl<T> Forward<T> for Openstreetmap
-where
-    T: Float + Debug,
-    for<'de> T: Deserialize<'de>,
-{
-    /// A forward-geocoding lookup of an address. Please see [the documentation](https://nominatim.org/release-docs/develop/api/Search/) for details.
-    ///
-    /// This method passes the `format` parameter to the API.
-    fn forward(&self, place: &str) -> Result<Vec<Point<T>>, GeocodingError> {
-        let resp = self
-            .client
-            .get(&format!("{}search", self.endpoint))
-            .query(&[(&"q", place), (&"format", &String::from("geojson"))])
-            .send()?
-            .error_for_status()?;
-        let res: OpenstreetmapResponse<T> = resp.json()?;
-        Ok(res
-            .features
-            .iter()
-            .map(|res| Point::new(res.geometry.coordinates.0, res.geometry.coordinates.1))
-            .collect())
+impl Layer {
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    fn as_str(&self) -> &'static str {
+        match self {
+            Layer::Address => "address",
+            Layer::Poi => "poi",
+            Layer::Railway => "railway",
+            Layer::Natural => "natural",
+            Layer::Manmade => "manmade",
+        }
     }
 }
 
-impl<T> Reverse<T> for Openstreetmap
-where
-    T: Float + Debug,
-    for<'de> T: Deserialize<'de>,
-{
-    /// A reverse lookup of a point. More detail on the format of the
-    /// returned `String` can be found [here](https://nominatim.org/release-docs/develop/api/Reverse/)
-    ///
-    /// This method passes the `format` parameter to the API.
-    fn reverse(&self, point: &Point<T>) -> Result<Option<String>, GeocodingError> {
-        let resp = self
-            .client
-            .get(&format!("{}reverse", self.endpoint))
-            .query(&[
-                (&"lon", &point.x().to_f64().unwrap().to_string()),
-                (&"lat", &point.y().to_f64().unwrap().to_string()),
-                (&"format", &String::from("geojson")),
-            ])
-            .send()?
-            .error_for_status()?;
-        let res: OpenstreetmapResponse<T> = resp.json()?;
-        let address = &res.features[0];
-        Ok(Some(address.properties.display_name.to_string()))
+/// A Nominatim feature type, used to restrict address search results to a level of the
+/// address hierarchy
+///
+/// See [the documentation](https://nominatim.org/release-docs/develop/api/Search/#result-restriction) for details.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FeatureType {
+    Country,
+    State,
+    City,
+    Settlement,
+}
+
+impl FeatureType {
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    fn as_str(&self) -> &'static str {
+        match self {
+            FeatureType::Country => "country",
+            FeatureType::State => "state",
+            FeatureType::City => "city",
+            FeatureType::Settlement => "settlement",
+        }
     }
 }
 
-/// The top-level full GeoJSON response returned by a forward-geocoding request
+/// The format of Nominatim responses
 ///
-/// See [the documentation](https://nominatim.org/release-docs/develop/api/Search/#geojson) for more details
+/// Responses in all formats are returned as an
+/// [`OpenstreetmapResponse`](struct.OpenstreetmapResponse.html). The `jsonv2` and `json`
+/// formats are parsed as [`OpenstreetmapJsonResult`](struct.OpenstreetmapJsonResult.html)s
+/// first, which is useful with older self-hosted instances whose GeoJSON output is broken.
 ///
-///```json
-///{
-///  "type": "FeatureCollection",
-///  "licence": "Data © OpenStreetMap contributors, ODbL 1.0. https://osm.org/copyright",
-///  "features": [
-///    {
-///      "type": "Feature",
-///      "properties": {
-///        "place_id": 263681481,
-///        "osm_type": "way",
-///        "osm_id": 355421084,
-///        "display_name": "68, Carrer de Calatrava, les Tres Torres, Sarrià - Sant Gervasi, Barcelona, BCN, Catalonia, 08017, Spain",
-///        "place_rank": 30,
-///        "category": "building",
-///        "type": "apartments",
-///        "importance": 0.7409999999999999,
-///        "address": {
-///          "house_number": "68",
-///          "road": "Carrer de Calatrava",
-///          "suburb": "les Tres Torres",
-///          "city_district": "Sarrià - Sant Gervasi",
-///          "city": "Barcelona",
-///          "county": "BCN",
-///          "state": "Catalonia",
-///          "postcode": "08017",
-///          "country": "Spain",
-///          "country_code": "es"
-///        }
-///      },
-///      "bbox": [
-///        2.1284918,
-///        41.401227,
-///        2.128952,
-///        41.4015815
-///      ],
-///      "geometry": {
-///        "type": "Point",
-///        "coordinates": [
-///          2.12872241167437,
-///          41.40140675
-///        ]
-///      }
-///    }
-///  ]
-///}
-///```
-#[derive(Debug, Serialize, Deserialize)]
-pub struct OpenstreetmapResponse<T>
-where
-    T: Float + Debug,
-{
-    pub r#type: String,
-    pub licence: String,
-    pub features: Vec<OpenstreetmapResult<T>>,
+/// See [the documentation](https://nominatim.org/release-docs/develop/api/Output/) for details.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Format {
+    #[default]
+    Geojson,
+    Jsonv2,
+    Json,
 }
 
-/// A geocoding result
-#[derive(Debug, Serialize, Deserialize)]
-pub struct OpenstreetmapResult<T>
-where
-    T: Float + Debug,
-{
-    pub r#type: String,
-    pub properties: ResultProperties,
-    pub bbox: (T, T, T, T),
-    pub geometry: ResultGeometry<T>,
+impl Format {
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    fn as_str(&self) -> &'static str {
+        match self {
+            Format::Geojson => "geojson",
+            Format::Jsonv2 => "jsonv2",
+            Format::Json => "json",
+        }
+    }
 }
 
-/// Geocoding result properties
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct ResultProperties {
-    pub place_id: u64,
-    pub osm_type: String,
-    pub osm_id: u64,
-    pub display_name: String,
+// Define an enum of OpenStreetMap tag values, with an `Other` variant for the rest, which
+// (de)serializes from and to the tag value
+macro_rules! tag_enum {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident => $value:expr,)* }) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        #[serde(from = "String", into = "String")]
+        #[non_exhaustive]
+        pub enum $name {
+            $($variant,)*
+            /// Any other value
+            Other(String),
+        }
+
+        impl $name {
+            /// The tag value
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $($name::$variant => $value,)*
+                    $name::Other(value) => value,
+                }
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(value: &str) -> Self {
+                match value {
+                    $($value => $name::$variant,)*
+                    _ => $name::Other(value.to_string()),
+                }
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(value: String) -> Self {
+                match $name::from(value.as_str()) {
+                    $name::Other(_) => $name::Other(value),
+                    known => known,
+                }
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                match value {
+                    $name::Other(value) => value,
+                    known => known.as_str().to_string(),
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.as_str() == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.as_str() == *other
+            }
+        }
+    };
+}
+
+tag_enum! {
+    /// The main OpenStreetMap tag key of a result, such as `amenity` or `highway`
+    ///
+    /// See [the OpenStreetMap wiki](https://wiki.openstreetmap.org/wiki/Map_features) for details.
+    ///
+    /// ```
+    /// use geocoding::openstreetmap::Category;
+    ///
+    /// assert_eq!(Category::from("highway"), Category::Highway);
+    /// assert_eq!(Category::from("club"), Category::Other("club".to_string()));
+    /// ```
+    Category {
+        Aerialway => "aerialway",
+        Aeroway => "aeroway",
+        Amenity => "amenity",
+        Boundary => "boundary",
+        Bridge => "bridge",
+        Building => "building",
+        Craft => "craft",
+        Emergency => "emergency",
+        Highway => "highway",
+        Historic => "historic",
+        Landuse => "landuse",
+        Leisure => "leisure",
+        ManMade => "man_made",
+        Military => "military",
+        Natural => "natural",
+        Office => "office",
+        Place => "place",
+        PublicTransport => "public_transport",
+        Railway => "railway",
+        Shop => "shop",
+        Tourism => "tourism",
+        Waterway => "waterway",
+    }
+}
+
+tag_enum! {
+    /// The value of the main OpenStreetMap tag of a result, such as `cafe` or `residential`
+    ///
+    /// Values are shared across categories, e.g. `residential` is both a `highway` and a
+    /// `landuse` value.
+    ///
+    /// ```
+    /// use geocoding::openstreetmap::PlaceType;
+    ///
+    /// assert_eq!(PlaceType::from("postcode"), PlaceType::Postcode);
+    /// assert_eq!(PlaceType::Cafe.as_str(), "cafe");
+    /// ```
+    PlaceType {
+        Administrative => "administrative",
+        Attraction => "attraction",
+        Bar => "bar",
+        BusStop => "bus_stop",
+        Cafe => "cafe",
+        City => "city",
+        Country => "country",
+        County => "county",
+        Hamlet => "hamlet",
+        Hospital => "hospital",
+        Hotel => "hotel",
+        House => "house",
+        Island => "island",
+        Locality => "locality",
+        Museum => "museum",
+        Neighbourhood => "neighbourhood",
+        Park => "park",
+        Peak => "peak",
+        Pedestrian => "pedestrian",
+        Pharmacy => "pharmacy",
+        Postcode => "postcode",
+        Primary => "primary",
+        Residential => "residential",
+        Restaurant => "restaurant",
+        River => "river",
+        School => "school",
+        Secondary => "secondary",
+        Service => "service",
+        State => "state",
+        Station => "station",
+        Suburb => "suburb",
+        Supermarket => "supermarket",
+        Tertiary => "tertiary",
+        Town => "town",
+        Unclassified => "unclassified",
+        University => "university",
+        Village => "village",
+        Yes => "yes",
+    }
+}
+
+/// The type of an OpenStreetMap object
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OsmType {
+    Node,
+    Way,
+    Relation,
+}
+
+impl OsmType {
+    /// The `N`/`W`/`R` letter of the type, as in the `osm_type` parameter of Nominatim
+    fn letter(self) -> char {
+        match self {
+            OsmType::Node => 'N',
+            OsmType::Way => 'W',
+            OsmType::Relation => 'R',
+        }
+    }
+}
+
+/// A typed OpenStreetMap object identifier, such as `N240109189` or `R146656`
+///
+/// Identifiers can be parsed from, and formatted to, the `N`/`W`/`R`-prefixed notation
+/// used by the [`lookup`](struct.Openstreetmap.html#method.lookup) method and other OSM tools:
+///
+/// ```
+/// use geocoding::openstreetmap::{OsmId, OsmType};
+///
+/// let id: OsmId = "W355421084".parse().unwrap();
+/// assert_eq!(id, OsmId::new(OsmType::Way, 355421084));
+/// assert_eq!(id.to_string(), "W355421084");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OsmId {
+    pub osm_type: OsmType,
+    #[serde(rename = "osm_id")]
+    pub id: u64,
+}
+
+impl OsmId {
+    /// Create a new OSM identifier from an object type and a numeric id
+    pub fn new(osm_type: OsmType, id: u64) -> Self {
+        OsmId { osm_type, id }
+    }
+}
+
+/// Format the identifier using the `N`/`W`/`R`-prefixed notation expected by Nominatim
+impl fmt::Display for OsmId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.osm_type.letter(), self.id)
+    }
+}
+
+/// Parse an identifier in the `N`/`W`/`R`-prefixed notation (case-insensitive)
+impl FromStr for OsmId {
+    type Err = GeocodingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        let osm_type = match chars.next().map(|c| c.to_ascii_uppercase()) {
+            Some('N') => OsmType::Node,
+            Some('W') => OsmType::Way,
+            Some('R') => OsmType::Relation,
+            _ => return Err(GeocodingError::InvalidOsmId(s.to_string())),
+        };
+        let id = chars
+            .as_str()
+            .parse()
+            .map_err(|_| GeocodingError::InvalidOsmId(s.to_string()))?;
+        Ok(OsmId { osm_type, id })
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl Openstreetmap {
+    /// Create a new Openstreetmap geocoding instance using the default endpoint
+    pub fn new() -> Self {
+        Openstreetmap::new_with_endpoint("https://nominatim.openstreetmap.org/".to_string())
+    }
+
+    /// Create a new Openstreetmap geocoding instance with a custom endpoint.
+    ///
+    /// Endpoint should include a trailing slash (i.e. "https://nominatim.openstreetmap.org/")
+    pub fn new_with_endpoint(endpoint: String) -> Self {
+        let client = ClientSettings::default().client();
+        Openstreetmap {
+            hooks: RequestHooks::with_client(&client),
+            client,
+            client_settings: ClientSettings::default(),
+            endpoint,
+            api_key: None,
+            email: None,
+            precision: None,
+        }
+    }
+
+    /// The credit OpenStreetMap requires under the ODbL
+    pub fn attribution(&self) -> Attribution {
+        Attribution::new(
+            "© OpenStreetMap contributors",
+            Some("ODbL-1.0"),
+            "https://www.openstreetmap.org/copyright",
+        )
+    }
+
+    /// Set an API key, sent as the `param` query parameter on every request
+    ///
+    /// Commercial and authenticated Nominatim-compatible services expect the key in
+    /// different parameters (e.g. `key` for LocationIQ, `api_key` for maps.co).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::Openstreetmap;
+    ///
+    /// let osm = Openstreetmap::new_with_endpoint("https://us1.locationiq.com/v1/".to_string())
+    ///     .with_api_key("key", "pk.0123456789abcdef");
+    /// ```
+    pub fn with_api_key(mut self, param: &str, key: &str) -> Self {
+        self.api_key = Some((param.to_owned(), SecretString::from(key.to_owned())));
+        self.hooks.redacted_params = vec![param.to_owned()];
+        self
+    }
+
+    /// Set a contact email address, sent as the `email` query parameter on every request
+    ///
+    /// The [Nominatim Usage Policy](https://operations.osmfoundation.org/policies/nominatim/)
+    /// asks for this when making a large number of requests.
+    pub fn with_email(mut self, email: &str) -> Self {
+        self.email = Some(email.to_owned());
+        self
+    }
+
+    /// Round the coordinates sent in reverse queries to `precision` decimals
+    ///
+    /// 5 decimals are precise to about a meter, which is enough for reverse geocoding.
+    pub fn with_precision(mut self, precision: u8) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    /// Set additional default headers, sent on every request
+    ///
+    /// The `User-Agent` header defaults to the crate's own, unless it is overridden here.
+    pub fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.client_settings.headers = headers;
+        self.client = self.client_settings.client();
+        self.hooks.set_client(&self.client);
+        self
+    }
+
+    /// Accept compressed responses, `true` by default
+    ///
+    /// Only the compressions enabled by the crate's features are negotiated.
+    pub fn with_compression(mut self, compression: bool) -> Self {
+        self.client_settings.compression = compression;
+        self.client = self.client_settings.client();
+        self.hooks.set_client(&self.client);
+        self
+    }
+
+    /// Apply connection pool and compression settings to the HTTP client
+    ///
+    /// Headers set with `with_headers` are kept.
+    pub fn with_client_settings(mut self, settings: &ClientSettings) -> Self {
+        self.client_settings = ClientSettings {
+            headers: self.client_settings.headers.clone(),
+            ..settings.clone()
+        };
+        self.client = self.client_settings.client();
+        self.hooks.set_client(&self.client);
+        self
+    }
+
+    /// Space the requests to the endpoint's host using `throttle`, which also slows down
+    /// when the server asks to
+    ///
+    /// Pass [`Throttle::global`](../throttle/struct.Throttle.html#method.global) to share the
+    /// [1 request per second](https://operations.osmfoundation.org/policies/nominatim/) allowed
+    /// by the public Nominatim server between all the instances of an application.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::throttle::Throttle;
+    /// use geocoding::Openstreetmap;
+    ///
+    /// let osm = Openstreetmap::new().with_throttle(Throttle::global());
+    /// ```
+    pub fn with_throttle(mut self, throttle: Arc<Throttle>) -> Self {
+        self.hooks.throttle = Some(throttle);
+        self
+    }
+
+    /// Record every request sent to the endpoint, with API keys and e-mail addresses redacted in `sink`
+    ///
+    /// See the [`audit`](../audit/index.html) module for details.
+    pub fn with_audit(mut self, sink: Arc<dyn AuditSink>) -> Self {
+        self.hooks.audit = Some(sink);
+        self
+    }
+
+    /// Send the requests failing to reach the endpoint to the mirrors of `failover`
+    ///
+    /// See the [`failover`](../failover/index.html) module for details.
+    pub fn with_failover(mut self, failover: Arc<Failover>) -> Self {
+        self.hooks.failover = Some(failover);
+        self
+    }
+
+    /// Send the requests whose encoded query is longer than `length` as POST requests, with
+    /// the query as a form, for deployments limiting the length of URLs
+    pub fn with_max_query_length(mut self, length: usize) -> Self {
+        self.hooks.max_query_length = Some(length);
+        self
+    }
+
+    /// Fail with `GeocodingError::ResponseTooLarge` when a response body is larger than
+    /// `bytes`, instead of reading it into memory
+    ///
+    /// Responses with `polygon_geojson` outlines of large boundaries, such as countries, can
+    /// weigh several megabytes.
+    pub fn with_max_response_size(mut self, bytes: usize) -> Self {
+        self.hooks.max_response_size = Some(bytes);
+        self
+    }
+
+    /// Fail with `GeocodingError::TooManyResults` when a response holds more than `count`
+    /// results
+    pub fn with_max_results(mut self, count: usize) -> Self {
+        self.hooks.max_results = Some(count);
+        self
+    }
+
+    /// The requests sent by this instance so far
+    ///
+    /// See the [`stats`](../stats/index.html) module for details.
+    pub fn stats(&self) -> UsageStats {
+        self.hooks.usage.snapshot()
+    }
+
+    /// Prepare a GET request to `path`, relative to the endpoint
+    fn get(&self, path: &str) -> RequestBuilder {
+        let mut req = self.client.get(format!("{}{}", self.endpoint, path));
+        if let Some((param, key)) = &self.api_key {
+            req = req.query(&[(param, key.expose_secret())]);
+        }
+        if let Some(email) = &self.email {
+            req = req.query(&[("email", email)]);
+        }
+        req
+    }
+
+    /// A forward-geocoding lookup of an address, returning a full detailed response
+    ///
+    /// Accepts an [`OpenstreetmapParams`](struct.OpenstreetmapParams.html) struct for specifying
+    /// options, including whether to include address details in the response and whether to filter
+    /// by a bounding box.
+    ///
+    /// Please see [the documentation](https://nominatim.org/release-docs/develop/api/Search/) for details.
+    ///
+    /// This method passes the `format` parameter to the API.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::{Openstreetmap, InputBounds, Point};
+    /// use geocoding::openstreetmap::{OpenstreetmapParams, OpenstreetmapResponse};
+    ///
+    /// let osm = Openstreetmap::new();
+    /// let viewbox = InputBounds::new(
+    ///     (-0.13806939125061035, 51.51989264641164),
+    ///     (-0.13427138328552246, 51.52319711775629),
+    /// );
+    /// let params = OpenstreetmapParams::new("UCL CASA")
+    ///     .with_addressdetails(true)
+    ///     .with_viewbox(viewbox)
+    ///     .build();
+    /// let res: OpenstreetmapResponse<f64> = osm.forward_full(&params).unwrap();
+    /// let result = res.features[0].properties.clone();
+    /// assert!(result.display_name.contains("Gordon Square"));
+    /// ```
+    pub fn forward_full<T>(
+        &self,
+        params: &OpenstreetmapParams<T>,
+    ) -> Result<OpenstreetmapResponse<T>, GeocodingError>
+    where
+        T: Float + Debug,
+        for<'de> T: Deserialize<'de>,
+    {
+        self.search(params, &[])
+    }
+
+    /// A forward-geocoding lookup of an address, returning an iterator over pages of results
+    ///
+    /// Nominatim caps the number of results per response. Each further page is requested
+    /// with the `place_id`s of all previously returned results added to `exclude_place_ids`,
+    /// and iteration stops once a page comes back empty or a request fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::Openstreetmap;
+    /// use geocoding::openstreetmap::{OpenstreetmapParams, OpenstreetmapResponse};
+    ///
+    /// let osm = Openstreetmap::new();
+    /// let params = OpenstreetmapParams::<f64>::new("Bahnhofstrasse")
+    ///     .build();
+    /// let pages: Vec<OpenstreetmapResponse<f64>> = osm
+    ///     .forward_paged(&params)
+    ///     .take(2)
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    /// assert_eq!(pages.len(), 2);
+    /// ```
+    pub fn forward_paged<'a, T>(
+        &'a self,
+        params: &'a OpenstreetmapParams<T>,
+    ) -> OpenstreetmapPages<'a, T>
+    where
+        T: Float + Debug,
+        for<'de> T: Deserialize<'de>,
+    {
+        OpenstreetmapPages {
+            osm: self,
+            params,
+            seen: vec![],
+            done: false,
+        }
+    }
+
+    /// A forward-geocoding lookup of an address, returning the response body as received
+    ///
+    /// The body can be deserialized into a [`BorrowedResponse`](struct.BorrowedResponse.html),
+    /// whose strings borrow from the body instead of being allocated, or into any other type
+    /// with [`RawResponse::parse`](../struct.RawResponse.html#method.parse).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::Openstreetmap;
+    /// use geocoding::openstreetmap::{BorrowedResponse, OpenstreetmapParams};
+    ///
+    /// let osm = Openstreetmap::new();
+    /// let params = OpenstreetmapParams::<f64>::new("UCL CASA").build();
+    /// let raw = osm.forward_full_raw(&params).unwrap();
+    /// let res: BorrowedResponse<f64> = raw.parse().unwrap();
+    /// assert!(res.features[0].properties.display_name.contains("Gordon Square"));
+    /// ```
+    pub fn forward_full_raw<T>(
+        &self,
+        params: &OpenstreetmapParams<T>,
+    ) -> Result<RawResponse, GeocodingError>
+    where
+        T: Float + Debug,
+    {
+        let (resp, meta) = self.send_search(params, &[])?;
+        Ok(RawResponse::new(resp.bytes()?.to_vec(), meta))
+    }
+
+    fn search<T>(
+        &self,
+        params: &OpenstreetmapParams<T>,
+        seen: &[u64],
+    ) -> Result<OpenstreetmapResponse<T>, GeocodingError>
+    where
+        T: Float + Debug,
+        for<'de> T: Deserialize<'de>,
+    {
+        let (resp, meta) = self.send_search(params, seen)?;
+        let mut res = search_response(resp.bytes()?, params.format)?;
+        res.meta = meta;
+        Ok(res)
+    }
+
+    fn send_search<T>(
+        &self,
+        params: &OpenstreetmapParams<T>,
+        seen: &[u64],
+    ) -> Result<(Response, ResponseMeta), GeocodingError>
+    where
+        T: Float + Debug,
+    {
+        let (resp, meta) = self
+            .search_request(params, seen)
+            .send_with_meta(&self.hooks, &self.endpoint)?;
+        Ok((resp.error_for_status()?, meta))
+    }
+
+    /// The request of a search, excluding the places in `seen`
+    pub(crate) fn search_request<T>(
+        &self,
+        params: &OpenstreetmapParams<T>,
+        seen: &[u64],
+    ) -> RequestBuilder
+    where
+        T: Float + Debug,
+    {
+        self.get("search").query(&params.query_pairs(seen))
+    }
+
+    /// A reverse lookup of a point, returning a full detailed response
+    ///
+    /// Accepts an [`OpenstreetmapReverseParams`](struct.OpenstreetmapReverseParams.html) struct
+    /// for specifying options, including whether to include address details, extra tags and
+    /// name details in the response, and the level of detail of the returned address.
+    ///
+    /// Please see [the documentation](https://nominatim.org/release-docs/develop/api/Reverse/) for details.
+    ///
+    /// This method passes the `format` parameter to the API.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::{Openstreetmap, Point};
+    /// use geocoding::openstreetmap::{OpenstreetmapResponse, OpenstreetmapReverseParams};
+    ///
+    /// let osm = Openstreetmap::new();
+    /// let p = Point::new(2.12870, 41.40139);
+    /// let params = OpenstreetmapReverseParams::new()
+    ///     .with_addressdetails(true)
+    ///     .build();
+    /// let res: OpenstreetmapResponse<f64> = osm.reverse_full(&p, &params).unwrap();
+    /// let address = res.features[0].properties.address.clone().unwrap();
+    /// assert_eq!(address.postcode.unwrap(), "08017");
+    /// ```
+    pub fn reverse_full<T>(
+        &self,
+        point: &Point<T>,
+        params: &OpenstreetmapReverseParams,
+    ) -> Result<OpenstreetmapResponse<T>, GeocodingError>
+    where
+        T: Float + Debug,
+        for<'de> T: Deserialize<'de>,
+    {
+        let (resp, meta) = self
+            .reverse_request(point, params)
+            .send_with_meta(&self.hooks, &self.endpoint)?;
+        Self::reverse_response_with_meta(resp, meta, params)
+    }
+
+    /// A reverse lookup of an OpenStreetMap object, returning a full detailed response
+    ///
+    /// Resolves the address of the object instead of that of the place nearest to a point,
+    /// for datasets which already reference OSM objects. Accepts the same
+    /// [`OpenstreetmapReverseParams`](struct.OpenstreetmapReverseParams.html) as
+    /// [`reverse_full`](#method.reverse_full), and returns an empty response if the object
+    /// doesn't exist.
+    ///
+    /// Please see [the documentation](https://nominatim.org/release-docs/develop/api/Reverse/) for details.
+    ///
+    /// This method passes the `osm_type`, `osm_id` and `format` parameters to the API.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::Openstreetmap;
+    /// use geocoding::openstreetmap::{
+    ///     OpenstreetmapResponse, OpenstreetmapReverseParams, OsmId, OsmType,
+    /// };
+    ///
+    /// let osm = Openstreetmap::new();
+    /// let id = OsmId::new(OsmType::Way, 355421084);
+    /// let params = OpenstreetmapReverseParams::new().build();
+    /// let res: OpenstreetmapResponse<f64> = osm.reverse_object(&id, &params).unwrap();
+    /// assert!(res.features[0].properties.display_name.contains("Carrer de Calatrava"));
+    /// ```
+    pub fn reverse_object<T>(
+        &self,
+        id: &OsmId,
+        params: &OpenstreetmapReverseParams,
+    ) -> Result<OpenstreetmapResponse<T>, GeocodingError>
+    where
+        T: Float + Debug,
+        for<'de> T: Deserialize<'de>,
+    {
+        let location = [
+            ("osm_type", id.osm_type.letter().to_string()),
+            ("osm_id", id.id.to_string()),
+        ];
+        let (resp, meta) = self
+            .reverse_request_for(&location, params)
+            .send_with_meta(&self.hooks, &self.endpoint)?;
+        Self::reverse_response_with_meta(resp, meta, params)
+    }
+
+    /// The typed response of a reverse lookup, with the details of the HTTP response
+    fn reverse_response_with_meta<T>(
+        resp: Response,
+        meta: ResponseMeta,
+        params: &OpenstreetmapReverseParams,
+    ) -> Result<OpenstreetmapResponse<T>, GeocodingError>
+    where
+        T: Float + Debug,
+        for<'de> T: Deserialize<'de>,
+    {
+        let resp = resp.error_for_status()?;
+        let mut res = reverse_response(resp.bytes()?, params.format)?;
+        res.meta = meta;
+        Ok(res)
+    }
+
+    /// The request of a reverse lookup
+    pub(crate) fn reverse_request<T>(
+        &self,
+        point: &Point<T>,
+        params: &OpenstreetmapReverseParams,
+    ) -> RequestBuilder
+    where
+        T: Float + Debug,
+    {
+        let lon = format_coordinate(point.x(), self.precision);
+        let lat = format_coordinate(point.y(), self.precision);
+        self.reverse_request_for(&[("lon", lon), ("lat", lat)], params)
+    }
+
+    /// The request of a reverse lookup of `location`, a point or an OSM object
+    fn reverse_request_for(
+        &self,
+        location: &[(&'static str, String)],
+        params: &OpenstreetmapReverseParams,
+    ) -> RequestBuilder {
+        self.get("reverse").query(&params.query_pairs(location))
+    }
+
+    /// A lookup of OpenStreetMap objects by their ids, returning a full detailed response
+    ///
+    /// Nominatim resolves at most 50 ids per request: longer slices are split into
+    /// several requests, whose features are merged into a single response. An empty slice
+    /// returns an empty response without sending a request.
+    ///
+    /// Please see [the documentation](https://nominatim.org/release-docs/develop/api/Lookup/) for details.
+    ///
+    /// This method passes the `format` parameter to the API.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::Openstreetmap;
+    /// use geocoding::openstreetmap::{OpenstreetmapResponse, OsmId, OsmType};
+    ///
+    /// let osm = Openstreetmap::new();
+    /// let ids = [OsmId::new(OsmType::Relation, 62428)];
+    /// let res: OpenstreetmapResponse<f64> = osm.lookup(&ids).unwrap();
+    /// let result = res.features[0].properties.clone();
+    /// assert!(result.display_name.contains("München"));
+    /// ```
+    pub fn lookup<T>(&self, ids: &[OsmId]) -> Result<OpenstreetmapResponse<T>, GeocodingError>
+    where
+        T: Float + Debug,
+        for<'de> T: Deserialize<'de>,
+    {
+        if ids.is_empty() {
+            return Ok(empty_lookup_response());
+        }
+        let mut chunks = ids.chunks(LOOKUP_MAX_IDS);
+        let mut res = self.lookup_chunk(chunks.next().unwrap_or_default())?;
+        for chunk in chunks {
+            res.features.extend(self.lookup_chunk(chunk)?.features);
+        }
+        Ok(res)
+    }
+
+    /// Query the status of the Nominatim instance
+    ///
+    /// Useful as a health check, and to verify how recently the data of a self-hosted
+    /// instance has been updated.
+    ///
+    /// Please see [the documentation](https://nominatim.org/release-docs/develop/api/Status/) for details.
+    ///
+    /// This method passes the `format` parameter to the API.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::Openstreetmap;
+    ///
+    /// let osm = Openstreetmap::new();
+    /// let status = osm.status().unwrap();
+    /// assert_eq!(status.message, "OK");
+    /// ```
+    pub fn status(&self) -> Result<OpenstreetmapStatus, GeocodingError> {
+        self.status_with_meta().map(|(res, _)| res)
+    }
+
+    /// The version of the Nominatim software of the instance, from its status
+    ///
+    /// This is `None` for instances which don't report it.
+    pub fn api_version(&self) -> Result<Option<String>, GeocodingError> {
+        Ok(self.status()?.software_version)
+    }
+
+    /// The status of the instance, with the details of the response
+    fn status_with_meta(&self) -> Result<(OpenstreetmapStatus, ResponseMeta), GeocodingError> {
+        let (resp, meta) = self
+            .get("status")
+            .query(&[(&"format", &String::from("json"))])
+            .send_with_meta(&self.hooks, &self.endpoint)?;
+        let res: OpenstreetmapStatus = request::json(resp.error_for_status()?)?;
+        Ok((res, meta))
+    }
+
+    fn lookup_chunk<T>(&self, ids: &[OsmId]) -> Result<OpenstreetmapResponse<T>, GeocodingError>
+    where
+        T: Float + Debug,
+        for<'de> T: Deserialize<'de>,
+    {
+        let (resp, meta) = self
+            .get("lookup")
+            .query(&lookup_query(ids))
+            .send_with_meta(&self.hooks, &self.endpoint)?;
+        let mut res: OpenstreetmapResponse<T> = request::json(resp.error_for_status()?)?;
+        res.meta = meta;
+        warn_unknown_fields("nominatim", &res.unknown_fields);
+        Ok(res)
+    }
+}
+
+/// The typed response of a search, from its body in `format`
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn search_response<T>(
+    body: Bytes,
+    format: Format,
+) -> Result<OpenstreetmapResponse<T>, GeocodingError>
+where
+    T: Float + Debug,
+    for<'de> T: Deserialize<'de>,
+{
+    let res = match format {
+        Format::Geojson => from_json_body(body)?,
+        Format::Jsonv2 | Format::Json => {
+            let results: Vec<OpenstreetmapJsonResult<T>> = from_json_body(body)?;
+            OpenstreetmapResponse::from(results)
+        }
+    };
+    warn_unknown_fields("nominatim", &res.unknown_fields);
+    Ok(res)
+}
+
+/// The typed response of a reverse lookup, from its body in `format`
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn reverse_response<T>(
+    body: Bytes,
+    format: Format,
+) -> Result<OpenstreetmapResponse<T>, GeocodingError>
+where
+    T: Float + Debug,
+    for<'de> T: Deserialize<'de>,
+{
+    Ok(match format {
+        Format::Geojson => from_json_body(body)?,
+        // A point without any place nearby is answered with an error object
+        Format::Jsonv2 | Format::Json => match from_json_body(body)? {
+            JsonReverse::Result(result) => OpenstreetmapResponse::from(vec![*result]),
+            JsonReverse::Error { .. } => OpenstreetmapResponse::from(vec![]),
+        },
+    })
+}
+
+/// The query of a lookup of `ids`
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn lookup_query(ids: &[OsmId]) -> [(&'static str, String); 2] {
+    let osm_ids = ids
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    [("osm_ids", osm_ids), ("format", String::from("geojson"))]
+}
+
+/// The response of a lookup without any id, answered without sending a request
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn empty_lookup_response<T>() -> OpenstreetmapResponse<T>
+where
+    T: Float + Debug,
+{
+    OpenstreetmapResponse {
+        r#type: String::from("FeatureCollection"),
+        licence: String::new(),
+        features: vec![],
+        unknown_fields: HashMap::new(),
+        meta: ResponseMeta::default(),
+    }
+}
+
+/// An iterator over successive pages of forward-geocoding results
+///
+/// Created by [`Openstreetmap::forward_paged`](struct.Openstreetmap.html#method.forward_paged).
+#[cfg(feature = "blocking")]
+pub struct OpenstreetmapPages<'a, T>
+where
+    T: Float + Debug,
+{
+    osm: &'a Openstreetmap,
+    params: &'a OpenstreetmapParams<T>,
+    seen: Vec<u64>,
+    done: bool,
+}
+
+#[cfg(feature = "blocking")]
+impl<'a, T> Iterator for OpenstreetmapPages<'a, T>
+where
+    T: Float + Debug,
+    for<'de> T: Deserialize<'de>,
+{
+    type Item = Result<OpenstreetmapResponse<T>, GeocodingError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.osm.search(self.params, &self.seen) {
+            Ok(res) if res.features.is_empty() => {
+                self.done = true;
+                None
+            }
+            Ok(res) => {
+                self.seen
+                    .extend(res.features.iter().map(|f| f.properties.place_id));
+                Some(Ok(res))
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl HealthCheck for Openstreetmap {
+    /// A query of the status of the instance, whose message is reported
+    fn ping(&self) -> Result<ProviderStatus, GeocodingError> {
+        let (res, meta) = self.status_with_meta()?;
+        Ok(ProviderStatus {
+            meta,
+            message: Some(res.message),
+        })
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<T> NearbySearch<T> for Openstreetmap
+where
+    T: Float + Debug,
+    for<'de> T: Deserialize<'de>,
+{
+    /// A search for places of a category around a point, using a Nominatim
+    /// [special phrase](https://wiki.openstreetmap.org/wiki/Nominatim/Special_Phrases)
+    /// such as `"cafe"`, or a `"key=value"` tag such as `"amenity=cafe"`.
+    ///
+    /// The search is bounded to a box around the circle, and results outside the circle
+    /// are then discarded. Nominatim returns at most 50 results.
+    ///
+    /// This method passes the `q`, `viewbox`, `bounded`, `limit` and `format` parameters to the API.
+    fn nearby(
+        &self,
+        point: &Point<T>,
+        radius: f64,
+        category: &str,
+    ) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        let (lon, lat) = (point.x().to_f64().unwrap(), point.y().to_f64().unwrap());
+        let d_lat = radius / 111_320.0;
+        let d_lon = d_lat / lat.to_radians().cos();
+        let viewbox = String::from(InputBounds::new(
+            (lon - d_lon, lat - d_lat),
+            (lon + d_lon, lat + d_lat),
+        ));
+        let q = format!("[{}]", category);
+
+        let resp = self
+            .get("search")
+            .query(&[
+                ("q", q.as_str()),
+                ("viewbox", &viewbox),
+                ("bounded", "1"),
+                ("limit", "50"),
+                ("format", "geojson"),
+            ])
+            .send_hooked(&self.hooks, &self.endpoint)?
+            .error_for_status()?;
+        let res: OpenstreetmapResponse<T> = request::json(resp)?;
+        let mut places: Vec<(f64, Point<T>, String)> = res
+            .features
+            .into_iter()
+            .map(|f| {
+                let p = Point::new(f.geometry.coordinates.0, f.geometry.coordinates.1);
+                (haversine_distance(point, &p), p, f.properties.display_name)
+            })
+            .filter(|(distance, _, _)| *distance <= radius)
+            .collect();
+        places.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(places.into_iter().map(|(_, p, label)| (p, label)).collect())
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl Default for Openstreetmap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<T> Forward<T> for Openstreetmap
+where
+    T: Float + Debug,
+    for<'de> T: Deserialize<'de>,
+{
+    /// A forward-geocoding lookup of an address. Please see [the documentation](https://nominatim.org/release-docs/develop/api/Search/) for details.
+    ///
+    /// This method passes the `format` parameter to the API.
+    fn forward(&self, place: &str) -> Result<Vec<Point<T>>, GeocodingError> {
+        let resp = self
+            .get("search")
+            .query(&[(&"q", place), (&"format", &String::from("geojson"))])
+            .send_hooked(&self.hooks, &self.endpoint)?
+            .error_for_status()?;
+        let res: OpenstreetmapResponse<T> = request::json(resp)?;
+        Ok(res
+            .features
+            .iter()
+            .map(|res| Point::new(res.geometry.coordinates.0, res.geometry.coordinates.1))
+            .collect())
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<T> ForwardLabels<T> for Openstreetmap
+where
+    T: Float + Debug,
+    for<'de> T: Deserialize<'de>,
+{
+    /// A forward-geocoding lookup of an address, returning the `display_name` of each result.
+    ///
+    /// This method passes the `format` parameter to the API.
+    fn forward_with_labels(&self, place: &str) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        let resp = self
+            .get("search")
+            .query(&[(&"q", place), (&"format", &String::from("geojson"))])
+            .send_hooked(&self.hooks, &self.endpoint)?
+            .error_for_status()?;
+        let res: OpenstreetmapResponse<T> = request::json(resp)?;
+        Ok(res
+            .features
+            .iter()
+            .map(|res| {
+                (
+                    Point::new(res.geometry.coordinates.0, res.geometry.coordinates.1),
+                    res.properties.display_name.clone(),
+                )
+            })
+            .collect())
+    }
+
+    /// This method passes the `format`, `addressdetails` and `accept-language` parameters
+    /// to the API.
+    fn forward_localized(
+        &self,
+        place: &str,
+        language: &LanguageTag,
+    ) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        let params = OpenstreetmapParams::new(place)
+            .with_accept_language(language.as_str())
+            .build();
+        let res: OpenstreetmapResponse<T> = self.forward_full(&params)?;
+        Ok(res
+            .features
+            .iter()
+            .map(|res| {
+                (
+                    Point::new(res.geometry.coordinates.0, res.geometry.coordinates.1),
+                    res.properties.display_name.clone(),
+                )
+            })
+            .collect())
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<T> ForwardStructured<T> for Openstreetmap
+where
+    T: Float + Debug,
+    for<'de> T: Deserialize<'de>,
+{
+    /// A structured forward-geocoding lookup of an address, returning the `display_name`
+    /// of each result.
+    ///
+    /// This method passes the `format`, `addressdetails`, `street`, `city`, `state`,
+    /// `country` and `postalcode` parameters to the API.
+    fn forward_structured(
+        &self,
+        address: &Address,
+    ) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        let params = OpenstreetmapParams::new("")
+            .with_structured_address(address.clone())
+            .build();
+        let res: OpenstreetmapResponse<T> = self.forward_full(&params)?;
+        Ok(res
+            .features
+            .iter()
+            .map(|res| {
+                (
+                    Point::new(res.geometry.coordinates.0, res.geometry.coordinates.1),
+                    res.properties.display_name.clone(),
+                )
+            })
+            .collect())
+    }
+
+    /// This method also passes the `addressdetails` parameter to the API, for the address of
+    /// each result.
+    fn forward_structured_results(
+        &self,
+        address: &Address,
+    ) -> Result<Vec<GeocodeResult<T>>, GeocodingError> {
+        let params = OpenstreetmapParams::new("")
+            .with_structured_address(address.clone())
+            .with_addressdetails(true)
+            .build();
+        let res: OpenstreetmapResponse<T> = self.forward_full(&params)?;
+        Ok(res
+            .features
+            .iter()
+            .map(|feature| GeocodeResult {
+                licence: Some(res.licence.clone()),
+                ..GeocodeResult::from(feature)
+            })
+            .collect())
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<T> Reverse<T> for Openstreetmap
+where
+    T: Float + Debug,
+    for<'de> T: Deserialize<'de>,
+{
+    /// A reverse lookup of a point. More detail on the format of the
+    /// returned `String` can be found [here](https://nominatim.org/release-docs/develop/api/Reverse/)
+    ///
+    /// This method passes the `format` parameter to the API.
+    fn reverse(&self, point: &Point<T>) -> Result<Option<String>, GeocodingError> {
+        let resp = self
+            .get("reverse")
+            .query(&[
+                (&"lon", &format_coordinate(point.x(), self.precision)),
+                (&"lat", &format_coordinate(point.y(), self.precision)),
+                (&"format", &String::from("geojson")),
+            ])
+            .send_hooked(&self.hooks, &self.endpoint)?
+            .error_for_status()?;
+        let res: OpenstreetmapResponse<T> = request::json(resp)?;
+        Ok(res
+            .features
+            .first()
+            .map(|address| address.properties.display_name.to_string()))
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<T> ReverseFull<T> for Openstreetmap
+where
+    T: Float + Debug,
+    for<'de> T: Deserialize<'de>,
+{
+    /// A reverse lookup of a point, with its address details.
+    ///
+    /// This method passes the `format` and `addressdetails` parameters to the API.
+    fn reverse_result(&self, point: &Point<T>) -> Result<Option<GeocodeResult<T>>, GeocodingError> {
+        let params = OpenstreetmapReverseParams::new()
+            .with_addressdetails(true)
+            .build();
+        let res: OpenstreetmapResponse<T> = self.reverse_full(point, &params)?;
+        Ok(res.features.first().map(|feature| GeocodeResult {
+            licence: Some(res.licence.clone()),
+            ..GeocodeResult::from(feature)
+        }))
+    }
+
+    /// This method passes the `format`, `addressdetails` and `accept-language` parameters
+    /// to the API.
+    fn reverse_localized(
+        &self,
+        point: &Point<T>,
+        language: &LanguageTag,
+    ) -> Result<Option<GeocodeResult<T>>, GeocodingError> {
+        let params = OpenstreetmapReverseParams::new()
+            .with_addressdetails(true)
+            .with_accept_language(language.as_str())
+            .build();
+        let res: OpenstreetmapResponse<T> = self.reverse_full(point, &params)?;
+        Ok(res.features.first().map(|feature| GeocodeResult {
+            licence: Some(res.licence.clone()),
+            ..GeocodeResult::from(feature)
+        }))
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<T> AdminReverse<T> for Openstreetmap
+where
+    T: Float + Debug,
+    for<'de> T: Deserialize<'de>,
+{
+    /// A reverse lookup of the city, region and country containing a point.
+    ///
+    /// This method passes the `format`, `addressdetails` and `zoom` parameters to the API,
+    /// requesting city-level detail.
+    fn admin_reverse(&self, point: &Point<T>) -> Result<Option<AdminArea>, GeocodingError> {
+        let params = OpenstreetmapReverseParams::new()
+            .with_addressdetails(true)
+            .with_zoom(10)
+            .build();
+        let res: OpenstreetmapResponse<T> = self.reverse_full(point, &params)?;
+        Ok(res
+            .features
+            .into_iter()
+            .next()
+            .and_then(|feature| feature.properties.address)
+            .map(AdminArea::from))
+    }
+}
+
+/// An instance of the Openstreetmap geocoding service, sending its requests without blocking
+///
+/// Enabled by the `async` feature, which doesn't require the `blocking` feature. Requests are
+/// sent with the async `reqwest` client, so they must be awaited within a Tokio runtime. The
+/// throttle, audit, failover and response limits of the blocking
+/// [`Openstreetmap`](struct.Openstreetmap.html) client aren't available: the requests to the
+/// public Nominatim server must be spaced by the application.
+///
+/// # Examples
+///
+/// ```no_run
+/// use geocoding::openstreetmap::{OpenstreetmapParams, OpenstreetmapResponse};
+/// use geocoding::AsyncOpenstreetmap;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let osm = AsyncOpenstreetmap::new();
+/// let params = OpenstreetmapParams::new("Schwabing, München").build();
+/// let res: OpenstreetmapResponse<f64> = osm.forward_full(&params).await.unwrap();
+/// println!("{}", res.features[0].properties.display_name);
+/// # }
+/// ```
+#[cfg(feature = "async")]
+pub struct AsyncOpenstreetmap {
+    client: reqwest::Client,
+    endpoint: String,
+    api_key: Option<(String, SecretString)>,
+    email: Option<String>,
+    precision: Option<u8>,
+}
+
+#[cfg(feature = "async")]
+impl AsyncOpenstreetmap {
+    /// Create a new async Openstreetmap geocoding instance using the default endpoint
+    pub fn new() -> Self {
+        AsyncOpenstreetmap::new_with_endpoint("https://nominatim.openstreetmap.org/".to_string())
+    }
+
+    /// Create a new async Openstreetmap geocoding instance with a custom endpoint.
+    ///
+    /// Endpoint should include a trailing slash (i.e. "https://nominatim.openstreetmap.org/")
+    pub fn new_with_endpoint(endpoint: String) -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(crate::UA_STRING)
+            .build()
+            .expect("Couldn't build a client!");
+        AsyncOpenstreetmap {
+            client,
+            endpoint,
+            api_key: None,
+            email: None,
+            precision: None,
+        }
+    }
+
+    /// The credit OpenStreetMap requires under the ODbL
+    pub fn attribution(&self) -> Attribution {
+        Attribution::new(
+            "© OpenStreetMap contributors",
+            Some("ODbL-1.0"),
+            "https://www.openstreetmap.org/copyright",
+        )
+    }
+
+    /// Set an API key, sent as the `param` query parameter on every request
+    ///
+    /// See [`Openstreetmap::with_api_key`](struct.Openstreetmap.html#method.with_api_key).
+    pub fn with_api_key(mut self, param: &str, key: &str) -> Self {
+        self.api_key = Some((param.to_owned(), SecretString::from(key.to_owned())));
+        self
+    }
+
+    /// Set a contact email address, sent as the `email` query parameter on every request
+    pub fn with_email(mut self, email: &str) -> Self {
+        self.email = Some(email.to_owned());
+        self
+    }
+
+    /// Round the coordinates sent in reverse queries to `precision` decimals
+    pub fn with_precision(mut self, precision: u8) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    /// Send a GET request to `path`, relative to the endpoint, returning the body of a
+    /// successful response with its details
+    async fn get(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<(Bytes, ResponseMeta), GeocodingError> {
+        let started = Instant::now();
+        let mut req = self.client.get(format!("{}{}", self.endpoint, path));
+        if let Some((param, key)) = &self.api_key {
+            req = req.query(&[(param, key.expose_secret())]);
+        }
+        if let Some(email) = &self.email {
+            req = req.query(&[("email", email)]);
+        }
+        let resp = req.query(query).send().await?;
+        let sent = crate::Sent {
+            duration: started.elapsed(),
+            elapsed: started.elapsed(),
+            attempts: 1,
+            endpoint: self.endpoint.clone(),
+        };
+        let meta = ResponseMeta::new(resp.status(), resp.headers(), sent);
+        Ok((resp.error_for_status()?.bytes().await?, meta))
+    }
+
+    /// A forward-geocoding lookup of an address, returning a full detailed response
+    ///
+    /// Accepts the same [`OpenstreetmapParams`](struct.OpenstreetmapParams.html) as
+    /// [`Openstreetmap::forward_full`](struct.Openstreetmap.html#method.forward_full).
+    pub async fn forward_full<T>(
+        &self,
+        params: &OpenstreetmapParams<T>,
+    ) -> Result<OpenstreetmapResponse<T>, GeocodingError>
+    where
+        T: Float + Debug,
+        for<'de> T: Deserialize<'de>,
+    {
+        let (body, meta) = self.get("search", &params.query_pairs(&[])).await?;
+        let mut res = search_response(body, params.format)?;
+        res.meta = meta;
+        Ok(res)
+    }
+
+    /// A forward-geocoding lookup of an address, returning the points of the results
+    pub async fn forward<T>(&self, place: &str) -> Result<Vec<Point<T>>, GeocodingError>
+    where
+        T: Float + Debug,
+        for<'de> T: Deserialize<'de>,
+    {
+        let params = OpenstreetmapParams::new(place).build();
+        let res = self.forward_full(&params).await?;
+        Ok(res
+            .features
+            .iter()
+            .map(|res| Point::new(res.geometry.coordinates.0, res.geometry.coordinates.1))
+            .collect())
+    }
+
+    /// A reverse lookup of a point, returning a full detailed response
+    ///
+    /// Accepts the same [`OpenstreetmapReverseParams`](struct.OpenstreetmapReverseParams.html)
+    /// as [`Openstreetmap::reverse_full`](struct.Openstreetmap.html#method.reverse_full).
+    pub async fn reverse_full<T>(
+        &self,
+        point: &Point<T>,
+        params: &OpenstreetmapReverseParams,
+    ) -> Result<OpenstreetmapResponse<T>, GeocodingError>
+    where
+        T: Float + Debug,
+        for<'de> T: Deserialize<'de>,
+    {
+        let location = [
+            ("lon", format_coordinate(point.x(), self.precision)),
+            ("lat", format_coordinate(point.y(), self.precision)),
+        ];
+        let (body, meta) = self.get("reverse", &params.query_pairs(&location)).await?;
+        let mut res = reverse_response(body, params.format)?;
+        res.meta = meta;
+        Ok(res)
+    }
+
+    /// A reverse lookup of a point, returning the `display_name` of the nearest place, if any
+    pub async fn reverse<T>(&self, point: &Point<T>) -> Result<Option<String>, GeocodingError>
+    where
+        T: Float + Debug,
+        for<'de> T: Deserialize<'de>,
+    {
+        let params = OpenstreetmapReverseParams::new().build();
+        let res: OpenstreetmapResponse<T> = self.reverse_full(point, &params).await?;
+        Ok(res
+            .features
+            .first()
+            .map(|res| res.properties.display_name.clone()))
+    }
+
+    /// A lookup of OpenStreetMap objects by their ids, returning a full detailed response
+    ///
+    /// Like [`Openstreetmap::lookup`](struct.Openstreetmap.html#method.lookup), longer slices
+    /// than the 50 ids Nominatim resolves per request are split into several requests, sent
+    /// one after the other, and an empty slice returns an empty response without sending a
+    /// request.
+    pub async fn lookup<T>(&self, ids: &[OsmId]) -> Result<OpenstreetmapResponse<T>, GeocodingError>
+    where
+        T: Float + Debug,
+        for<'de> T: Deserialize<'de>,
+    {
+        if ids.is_empty() {
+            return Ok(empty_lookup_response());
+        }
+        let mut chunks = ids.chunks(LOOKUP_MAX_IDS);
+        let mut res = self.lookup_chunk(chunks.next().unwrap_or_default()).await?;
+        for chunk in chunks {
+            res.features
+                .extend(self.lookup_chunk(chunk).await?.features);
+        }
+        Ok(res)
+    }
+
+    async fn lookup_chunk<T>(
+        &self,
+        ids: &[OsmId],
+    ) -> Result<OpenstreetmapResponse<T>, GeocodingError>
+    where
+        T: Float + Debug,
+        for<'de> T: Deserialize<'de>,
+    {
+        let (body, meta) = self.get("lookup", &lookup_query(ids)).await?;
+        let mut res: OpenstreetmapResponse<T> = from_json_body(body)?;
+        res.meta = meta;
+        warn_unknown_fields("nominatim", &res.unknown_fields);
+        Ok(res)
+    }
+
+    /// Query the status of the Nominatim instance
+    pub async fn status(&self) -> Result<OpenstreetmapStatus, GeocodingError> {
+        let (body, _) = self
+            .get("status", &[("format", "json".to_string())])
+            .await?;
+        from_json_body(body)
+    }
+}
+
+#[cfg(feature = "async")]
+impl Default for AsyncOpenstreetmap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<AddressDetails> for AdminArea {
+    fn from(address: AddressDetails) -> Self {
+        AdminArea {
+            city: address
+                .city
+                .or(address.town)
+                .or(address.village)
+                .or(address.municipality),
+            region: address.state.or(address.region),
+            region_code: address.iso3166_2_lvl4.or(address.iso3166_2_lvl3),
+            country: address.country,
+            country_code: address.country_code.map(|code| code.to_uppercase()),
+        }
+    }
+}
+
+/// The top-level full GeoJSON response returned by a forward-geocoding request
+///
+/// See [the documentation](https://nominatim.org/release-docs/develop/api/Search/#geojson) for more details
+///
+///```json
+///{
+///  "type": "FeatureCollection",
+///  "licence": "Data © OpenStreetMap contributors, ODbL 1.0. https://osm.org/copyright",
+///  "features": [
+///    {
+///      "type": "Feature",
+///      "properties": {
+///        "place_id": 263681481,
+///        "osm_type": "way",
+///        "osm_id": 355421084,
+///        "display_name": "68, Carrer de Calatrava, les Tres Torres, Sarrià - Sant Gervasi, Barcelona, BCN, Catalonia, 08017, Spain",
+///        "place_rank": 30,
+///        "category": "building",
+///        "type": "apartments",
+///        "importance": 0.7409999999999999,
+///        "address": {
+///          "house_number": "68",
+///          "road": "Carrer de Calatrava",
+///          "suburb": "les Tres Torres",
+///          "city_district": "Sarrià - Sant Gervasi",
+///          "city": "Barcelona",
+///          "county": "BCN",
+///          "state": "Catalonia",
+///          "postcode": "08017",
+///          "country": "Spain",
+///          "country_code": "es"
+///        }
+///      },
+///      "bbox": [
+///        2.1284918,
+///        41.401227,
+///        2.128952,
+///        41.4015815
+///      ],
+///      "geometry": {
+///        "type": "Point",
+///        "coordinates": [
+///          2.12872241167437,
+///          41.40140675
+///        ]
+///      }
+///    }
+///  ]
+///}
+///```
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpenstreetmapResponse<T>
+where
+    T: Float + Debug,
+{
+    pub r#type: String,
+    pub licence: String,
+    pub features: Vec<OpenstreetmapResult<T>>,
+    /// The top-level fields unknown to this crate, a sign that Nominatim changed its schema
+    #[serde(flatten)]
+    pub unknown_fields: HashMap<String, serde_json::Value>,
+    /// Details of the HTTP response, not part of the JSON
+    #[serde(skip)]
+    pub meta: ResponseMeta,
+}
+
+impl<T> OpenstreetmapResponse<T>
+where
+    T: Float + Debug,
+    for<'de> T: Deserialize<'de>,
+{
+    /// Parse a response from its JSON text, e.g. a recorded response
+    pub fn from_json_str(json: &str) -> Result<Self, GeocodingError> {
+        from_json_str(json)
+    }
+
+    /// Parse a response from its JSON bytes, in place with the `simd-json` feature
+    pub fn from_json_slice(json: &mut [u8]) -> Result<Self, GeocodingError> {
+        from_json_slice(json)
+    }
+}
+
+/// A geocoding result
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpenstreetmapResult<T>
+where
+    T: Float + Debug,
+{
+    pub r#type: String,
+    pub properties: ResultProperties,
+    pub bbox: (T, T, T, T),
+    pub geometry: ResultGeometry<T>,
+}
+
+/// Geocoding result properties
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResultProperties {
+    pub place_id: u64,
+    pub osm_type: String,
+    pub osm_id: u64,
+    pub display_name: String,
     pub place_rank: u64,
-    pub category: String,
+    pub category: Category,
+    pub r#type: PlaceType,
+    pub importance: f64,
+    pub addresstype: Option<String>,
+    pub name: Option<String>,
+    pub address: Option<AddressDetails>,
+    pub extratags: Option<HashMap<String, String>>,
+    pub namedetails: Option<HashMap<String, String>>,
+}
+
+impl ResultProperties {
+    /// The typed identifier of the result's OSM object, unless its `osm_type` is unknown
+    pub fn osm(&self) -> Option<OsmId> {
+        let osm_type = match self.osm_type.as_str() {
+            "node" => OsmType::Node,
+            "way" => OsmType::Way,
+            "relation" => OsmType::Relation,
+            _ => return None,
+        };
+        Some(OsmId::new(osm_type, self.osm_id))
+    }
+}
+
+/// Address details in the result object
+///
+/// Address keys without a dedicated field are collected in `other`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AddressDetails {
+    pub borough: Option<String>,
+    pub city: Option<String>,
+    pub city_district: Option<String>,
+    pub construction: Option<String>,
+    pub continent: Option<String>,
+    pub country: Option<String>,
+    pub country_code: Option<String>,
+    pub county: Option<String>,
+    pub hamlet: Option<String>,
+    pub house_number: Option<String>,
+    #[serde(rename = "ISO3166-2-lvl3")]
+    pub iso3166_2_lvl3: Option<String>,
+    #[serde(rename = "ISO3166-2-lvl4")]
+    pub iso3166_2_lvl4: Option<String>,
+    #[serde(rename = "ISO3166-2-lvl5")]
+    pub iso3166_2_lvl5: Option<String>,
+    #[serde(rename = "ISO3166-2-lvl6")]
+    pub iso3166_2_lvl6: Option<String>,
+    pub municipality: Option<String>,
+    pub neighbourhood: Option<String>,
+    pub postcode: Option<String>,
+    pub public_building: Option<String>,
+    pub region: Option<String>,
+    pub road: Option<String>,
+    pub state: Option<String>,
+    pub state_district: Option<String>,
+    pub suburb: Option<String>,
+    pub town: Option<String>,
+    pub village: Option<String>,
+    #[serde(flatten)]
+    pub other: HashMap<String, String>,
+}
+
+impl From<&AddressDetails> for Address {
+    fn from(address: &AddressDetails) -> Self {
+        Address {
+            house_number: address.house_number.clone(),
+            road: address.road.clone(),
+            suburb: address.suburb.clone(),
+            city: address
+                .city
+                .as_ref()
+                .or(address.town.as_ref())
+                .or(address.village.as_ref())
+                .or(address.municipality.as_ref())
+                .cloned(),
+            postcode: address.postcode.clone(),
+            state: address.state.clone(),
+            // The subdivision part of the ISO 3166-2 code, e.g. "CT" for "ES-CT"
+            state_code: address
+                .iso3166_2_lvl4
+                .as_ref()
+                .and_then(|code| code.split('-').nth(1))
+                .map(String::from),
+            country: address.country.clone(),
+            country_code: address.country_code.as_ref().map(|c| c.to_uppercase()),
+        }
+    }
+}
+
+/// The status of a Nominatim instance
+///
+/// See [the documentation](https://nominatim.org/release-docs/develop/api/Status/) for more details
+///
+///```json
+///{
+///  "status": 0,
+///  "message": "OK",
+///  "data_updated": "2020-05-04T14:47:00+00:00",
+///  "software_version": "3.6.0-0",
+///  "database_version": "3.6.0-0"
+///}
+///```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OpenstreetmapStatus {
+    pub status: i32,
+    pub message: String,
+    pub data_updated: Option<DateTime<FixedOffset>>,
+    pub software_version: Option<String>,
+    pub database_version: Option<String>,
+}
+
+impl OpenstreetmapStatus {
+    /// Parse a response from its JSON text, e.g. a recorded response
+    pub fn from_json_str(json: &str) -> Result<Self, GeocodingError> {
+        from_json_str(json)
+    }
+}
+
+impl<T> From<&OpenstreetmapResult<T>> for GeocodeResult<T>
+where
+    T: Float + Debug,
+{
+    fn from(result: &OpenstreetmapResult<T>) -> Self {
+        let (lon, lat) = result.geometry.coordinates;
+        let mut res =
+            GeocodeResult::new(Point::new(lon, lat), result.properties.display_name.clone());
+        let (min_lon, min_lat, max_lon, max_lat) = result.bbox;
+        res.bbox = Some(Rect::new((min_lon, min_lat), (max_lon, max_lat)));
+        res.address = result.properties.address.as_ref().map(Address::from);
+        res
+    }
+}
+
+/// A geocoding result geometry
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResultGeometry<T>
+where
+    T: Float + Debug,
+{
     pub r#type: String,
+    pub coordinates: (T, T),
+}
+
+/// A geocoding result in the `jsonv2` or `json` format
+///
+/// See [the documentation](https://nominatim.org/release-docs/develop/api/Output/#json) for more details.
+/// The `json` format names the category `class`, and lacks `place_rank`, `addresstype`
+/// and `name`.
+///
+///```json
+///{
+///  "place_id": 263681481,
+///  "licence": "Data © OpenStreetMap contributors, ODbL 1.0. https://osm.org/copyright",
+///  "osm_type": "way",
+///  "osm_id": 355421084,
+///  "lat": "41.40140675",
+///  "lon": "2.12872241167437",
+///  "category": "building",
+///  "type": "apartments",
+///  "place_rank": 30,
+///  "importance": 0.7409999999999999,
+///  "addresstype": "building",
+///  "name": "",
+///  "display_name": "68, Carrer de Calatrava, les Tres Torres, Sarrià - Sant Gervasi, Barcelona, BCN, Catalonia, 08017, Spain",
+///  "boundingbox": ["41.401227", "41.4015815", "2.1284918", "2.128952"]
+///}
+///```
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de>"))]
+pub struct OpenstreetmapJsonResult<T>
+where
+    T: Float + Debug,
+{
+    pub place_id: u64,
+    pub licence: String,
+    pub osm_type: String,
+    pub osm_id: u64,
+    #[serde(deserialize_with = "deserialize_number_string")]
+    pub lat: T,
+    #[serde(deserialize_with = "deserialize_number_string")]
+    pub lon: T,
+    #[serde(alias = "class")]
+    pub category: Category,
+    pub r#type: PlaceType,
+    pub place_rank: Option<u64>,
+    #[serde(default)]
     pub importance: f64,
+    pub addresstype: Option<String>,
+    pub name: Option<String>,
+    pub display_name: String,
+    /// The bounds of the result: its minimum and maximum latitudes, then longitudes
+    #[serde(deserialize_with = "deserialize_bounding_box")]
+    pub boundingbox: [T; 4],
     pub address: Option<AddressDetails>,
+    pub extratags: Option<HashMap<String, String>>,
+    pub namedetails: Option<HashMap<String, String>>,
 }
 
-/// Address details in the result object
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct AddressDetails {
-    pub city: Option<String>,
-    pub city_district: Option<String>,
-    pub construction: Option<String>,
-    pub continent: Option<String>,
-    pub country: Option<String>,
-    pub country_code: Option<String>,
-    pub house_number: Option<String>,
-    pub neighbourhood: Option<String>,
-    pub postcode: Option<String>,
-    pub public_building: Option<String>,
-    pub state: Option<String>,
-    pub suburb: Option<String>,
+/// The response to a reverse lookup in the `jsonv2` or `json` format
+#[cfg(any(feature = "blocking", feature = "async"))]
+#[derive(Deserialize)]
+#[serde(untagged, bound(deserialize = "T: Deserialize<'de>"))]
+enum JsonReverse<T>
+where
+    T: Float + Debug,
+{
+    Error {
+        #[allow(dead_code)]
+        error: String,
+    },
+    Result(Box<OpenstreetmapJsonResult<T>>),
 }
 
-/// A geocoding result geometry
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ResultGeometry<T>
+/// A number, or a string containing one, as the `json` formats encode coordinates
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberString<T> {
+    Number(T),
+    String(String),
+}
+
+impl<T> NumberString<T>
+where
+    T: Float,
+{
+    fn value<E: serde::de::Error>(self) -> Result<T, E> {
+        match self {
+            NumberString::Number(value) => Ok(value),
+            NumberString::String(value) => value
+                .parse::<f64>()
+                .ok()
+                .and_then(T::from)
+                .ok_or_else(|| E::custom(format!("invalid number: {}", value))),
+        }
+    }
+}
+
+fn deserialize_number_string<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Float + Deserialize<'de>,
+{
+    NumberString::deserialize(deserializer)?.value()
+}
+
+fn deserialize_bounding_box<'de, D, T>(deserializer: D) -> Result<[T; 4], D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Float + Deserialize<'de>,
+{
+    let [a, b, c, d] = <[NumberString<T>; 4]>::deserialize(deserializer)?;
+    Ok([a.value()?, b.value()?, c.value()?, d.value()?])
+}
+
+impl<T> From<OpenstreetmapJsonResult<T>> for OpenstreetmapResult<T>
 where
     T: Float + Debug,
 {
-    pub r#type: String,
+    fn from(result: OpenstreetmapJsonResult<T>) -> Self {
+        let [min_lat, max_lat, min_lon, max_lon] = result.boundingbox;
+        OpenstreetmapResult {
+            r#type: String::from("Feature"),
+            properties: ResultProperties {
+                place_id: result.place_id,
+                osm_type: result.osm_type,
+                osm_id: result.osm_id,
+                display_name: result.display_name,
+                place_rank: result.place_rank.unwrap_or_default(),
+                category: result.category,
+                r#type: result.r#type,
+                importance: result.importance,
+                addresstype: result.addresstype,
+                name: result.name,
+                address: result.address,
+                extratags: result.extratags,
+                namedetails: result.namedetails,
+            },
+            bbox: (min_lon, min_lat, max_lon, max_lat),
+            geometry: ResultGeometry {
+                r#type: String::from("Point"),
+                coordinates: (result.lon, result.lat),
+            },
+        }
+    }
+}
+
+/// A response of results in the `jsonv2` or `json` format, as a GeoJSON response
+impl<T> From<Vec<OpenstreetmapJsonResult<T>>> for OpenstreetmapResponse<T>
+where
+    T: Float + Debug,
+{
+    fn from(results: Vec<OpenstreetmapJsonResult<T>>) -> Self {
+        OpenstreetmapResponse {
+            r#type: String::from("FeatureCollection"),
+            licence: results
+                .first()
+                .map(|result| result.licence.clone())
+                .unwrap_or_default(),
+            features: results.into_iter().map(OpenstreetmapResult::from).collect(),
+            unknown_fields: HashMap::new(),
+            meta: ResponseMeta::default(),
+        }
+    }
+}
+
+/// A forward-geocoding response whose strings borrow from the response body
+///
+/// Returned by parsing a [`RawResponse`](../struct.RawResponse.html) from
+/// [`forward_full_raw`](struct.Openstreetmap.html#method.forward_full_raw). Strings without
+/// JSON escapes borrow from the body, so that high-throughput pipelines don't allocate a
+/// `String` per field of each result.
+#[derive(Debug, Deserialize)]
+pub struct BorrowedResponse<'a, T>
+where
+    T: Float + Debug,
+{
+    #[serde(borrow)]
+    pub licence: Cow<'a, str>,
+    #[serde(borrow)]
+    pub features: Vec<BorrowedResult<'a, T>>,
+}
+
+/// A geocoding result borrowing from the response body
+#[derive(Debug, Deserialize)]
+pub struct BorrowedResult<'a, T>
+where
+    T: Float + Debug,
+{
+    #[serde(borrow)]
+    pub properties: BorrowedProperties<'a>,
+    pub bbox: (T, T, T, T),
+    pub geometry: BorrowedGeometry<'a, T>,
+}
+
+/// Geocoding result properties borrowing from the response body
+#[derive(Debug, Deserialize)]
+pub struct BorrowedProperties<'a> {
+    pub place_id: u64,
+    #[serde(borrow)]
+    pub osm_type: Cow<'a, str>,
+    pub osm_id: u64,
+    #[serde(borrow)]
+    pub display_name: Cow<'a, str>,
+    pub place_rank: u64,
+    #[serde(borrow)]
+    pub category: Cow<'a, str>,
+    #[serde(borrow)]
+    pub r#type: Cow<'a, str>,
+    pub importance: f64,
+    #[serde(borrow)]
+    pub address: Option<HashMap<Cow<'a, str>, Cow<'a, str>>>,
+}
+
+/// A geocoding result geometry borrowing from the response body
+#[derive(Debug, Deserialize)]
+pub struct BorrowedGeometry<'a, T>
+where
+    T: Float + Debug,
+{
+    #[serde(borrow)]
+    pub r#type: Cow<'a, str>,
     pub coordinates: (T, T),
 }
 
-#[cfg(test)]
+impl<T> From<&BorrowedResult<'_, T>> for GeocodeResult<T>
+where
+    T: Float + Debug,
+{
+    fn from(result: &BorrowedResult<T>) -> Self {
+        let (x, y) = result.geometry.coordinates;
+        GeocodeResult::new(
+            Point::new(x, y),
+            result.properties.display_name.clone().into_owned(),
+        )
+    }
+}
+
+#[cfg(all(test, feature = "blocking"))]
 mod test {
     use super::*;
+    use crate::test_server::{Response, Server};
+
+    #[test]
+    fn tag_enum_test() {
+        let properties: ResultProperties = serde_json::from_str(
+            r#"{"place_id": 1, "osm_type": "node", "osm_id": 1, "display_name": "Club", "place_rank": 30, "category": "club", "type": "cafe", "importance": 0.1}"#,
+        )
+        .unwrap();
+        assert_eq!(properties.category, Category::Other("club".to_string()));
+        assert_eq!(properties.r#type, PlaceType::Cafe);
+        assert_eq!(properties.r#type, "cafe");
+        assert_eq!(Category::from("man_made"), Category::ManMade);
+        assert_eq!(Category::ManMade.to_string(), "man_made");
+        let json = serde_json::to_value(&properties).unwrap();
+        assert_eq!(json["category"], "club");
+        assert_eq!(json["type"], "cafe");
+    }
 
     #[test]
     fn new_with_endpoint_forward_test() {
         let osm =
             Openstreetmap::new_with_endpoint("https://nominatim.openstreetmap.org/".to_string());
         let address = "Schwabing, München";
-        let res = osm.forward(&address);
+        let res = osm.forward(address);
         assert_eq!(res.unwrap(), vec![Point::new(11.5884858, 48.1700887)]);
     }
 
+    #[test]
+    fn forward_with_labels_test() {
+        let osm = Openstreetmap::new();
+        let address = "Schwabing, München";
+        let res = osm.forward_with_labels(address).unwrap();
+        assert_eq!(res[0].0, Point::new(11.5884858, 48.1700887));
+        assert!(res[0].1.contains("Schwabing"));
+    }
+
     #[test]
     fn forward_full_test() {
         let osm = Openstreetmap::new();
@@ -364,9 +2503,9 @@ mod test {
             (-0.13806939125061035, 51.51989264641164),
             (-0.13427138328552246, 51.52319711775629),
         );
-        let params = OpenstreetmapParams::new(&"UCL CASA")
+        let params = OpenstreetmapParams::new("UCL CASA")
             .with_addressdetails(true)
-            .with_viewbox(&viewbox)
+            .with_viewbox(viewbox)
             .build();
         let res: OpenstreetmapResponse<f64> = osm.forward_full(&params).unwrap();
         let result = res.features[0].properties.clone();
@@ -374,14 +2513,662 @@ mod test {
         assert_eq!(result.address.unwrap().city.unwrap(), "London");
     }
 
+    #[test]
+    fn forward_full_feature_type_test() {
+        let osm = Openstreetmap::new();
+        let params = OpenstreetmapParams::<f64>::new("Paris")
+            .with_layers(&[Layer::Address])
+            .with_feature_type(FeatureType::City)
+            .with_addressdetails(true)
+            .build();
+        let res = osm.forward_full(&params).unwrap();
+        assert!(res
+            .features
+            .iter()
+            .all(|f| matches!(f.properties.category, Category::Boundary | Category::Place)));
+    }
+
+    #[test]
+    fn forward_full_structured_test() {
+        let osm = Openstreetmap::new();
+        let address = Address {
+            house_number: Some("68".to_string()),
+            road: Some("Carrer de Calatrava".to_string()),
+            city: Some("Barcelona".to_string()),
+            country: Some("Spain".to_string()),
+            ..Default::default()
+        };
+        let params = OpenstreetmapParams::new("")
+            .with_structured_address(address)
+            .build();
+        let res: OpenstreetmapResponse<f64> = osm.forward_full(&params).unwrap();
+        assert!(res.features[0]
+            .properties
+            .display_name
+            .contains("Carrer de Calatrava"));
+    }
+
+    #[test]
+    fn forward_paged_test() {
+        let osm = Openstreetmap::new();
+        let params = OpenstreetmapParams::<f64>::new("Bahnhofstrasse").build();
+        let pages = osm
+            .forward_paged(&params)
+            .take(2)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(pages.len(), 2);
+        let first: Vec<u64> = pages[0]
+            .features
+            .iter()
+            .map(|f| f.properties.place_id)
+            .collect();
+        assert!(pages[1]
+            .features
+            .iter()
+            .all(|f| !first.contains(&f.properties.place_id)));
+    }
+
+    #[test]
+    fn lookup_test() {
+        let osm = Openstreetmap::new();
+        let ids = [
+            OsmId::new(OsmType::Relation, 62428),
+            OsmId::new(OsmType::Way, 355421084),
+        ];
+        let res: OpenstreetmapResponse<f64> = osm.lookup(&ids).unwrap();
+        assert_eq!(res.features.len(), 2);
+        assert_eq!(res.features[1].properties.osm(), Some(ids[1]));
+        assert!(res.features[1]
+            .properties
+            .display_name
+            .contains("Carrer de Calatrava"));
+    }
+
+    #[test]
+    fn lookup_empty_test() {
+        // No request is sent
+        let osm = Openstreetmap::new_with_endpoint("http://127.0.0.1:9/".to_string());
+        let res: OpenstreetmapResponse<f64> = osm.lookup(&[]).unwrap();
+        assert!(res.features.is_empty());
+        assert_eq!(osm.stats().requests, 0);
+    }
+
+    #[test]
+    fn status_test() {
+        let osm = Openstreetmap::new();
+        let status = osm.status().unwrap();
+        assert_eq!(status.status, 0);
+        assert!(status.data_updated.is_some());
+    }
+
+    #[test]
+    fn with_api_key_test() {
+        let osm = Openstreetmap::new_with_endpoint("https://us1.locationiq.com/v1/".to_string())
+            .with_api_key("key", "pk.0123");
+        let req = osm.get("search").build().unwrap();
+        assert_eq!(
+            req.url().as_str(),
+            "https://us1.locationiq.com/v1/search?key=pk.0123"
+        );
+    }
+
+    #[test]
+    fn response_meta_test() {
+        let server = Server::single(
+            Response::json(r#"{"type": "FeatureCollection", "licence": "ODbL", "features": []}"#)
+                .with_header("X-RateLimit-Remaining", "42")
+                .with_header("X-Other", "1"),
+        );
+        let endpoint = server.endpoint();
+        let osm = Openstreetmap::new_with_endpoint(endpoint.clone());
+        let params = OpenstreetmapParams::new("Calatrava").build();
+        let res: OpenstreetmapResponse<f64> = osm.forward_full(&params).unwrap();
+        server.requests();
+        assert!(res.features.is_empty());
+        assert_eq!(res.meta.status, 200);
+        assert_eq!(res.meta.attempts, 1);
+        assert_eq!(res.meta.endpoint, endpoint);
+        assert_eq!(
+            res.meta.headers_of_interest.get("x-ratelimit-remaining"),
+            Some(&"42".to_string())
+        );
+        assert!(!res.meta.headers_of_interest.contains_key("x-other"));
+        assert!(res.meta.request_duration > std::time::Duration::ZERO);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn async_openstreetmap_test() {
+        let server = Server::new(
+            [
+                r#"{"type": "FeatureCollection", "licence": "ODbL", "features": [{"type": "Feature", "properties": {"place_id": 1, "osm_type": "way", "osm_id": 2, "display_name": "68, Carrer de Calatrava, Barcelona", "place_rank": 30, "category": "building", "type": "yes", "importance": 0.1}, "bbox": [2.1, 41.4, 2.1, 41.4], "geometry": {"type": "Point", "coordinates": [2.1, 41.4]}}]}"#,
+                r#"{"error": "Unable to geocode"}"#,
+            ]
+            .iter()
+            .map(|&body| Response::json(body).with_header("X-RateLimit-Remaining", "42"))
+            .collect(),
+        );
+        let endpoint = server.endpoint();
+        let osm = AsyncOpenstreetmap::new_with_endpoint(endpoint.clone())
+            .with_email("ops@example.com")
+            .with_precision(5);
+        let params = OpenstreetmapParams::new("Calatrava 68").build();
+        let res: OpenstreetmapResponse<f64> = osm.forward_full(&params).await.unwrap();
+        assert_eq!(res.features[0].properties.osm_id, 2);
+        assert_eq!(res.meta.status, 200);
+        assert_eq!(res.meta.endpoint, endpoint);
+        assert_eq!(
+            res.meta.headers_of_interest.get("x-ratelimit-remaining"),
+            Some(&"42".to_string())
+        );
+        let params = OpenstreetmapReverseParams::new()
+            .with_format(Format::Jsonv2)
+            .build();
+        let point = Point::new(2.128701, 41.401392);
+        let res: OpenstreetmapResponse<f64> = osm.reverse_full(&point, &params).await.unwrap();
+        assert!(res.features.is_empty());
+        let lines = server.request_lines();
+        assert!(lines[0].starts_with("GET /search?email=ops%40example.com&format=geojson"));
+        assert!(lines[0].contains("&q=Calatrava+68"));
+        assert!(
+            lines[1].starts_with("GET /reverse?email=ops%40example.com&lon=2.1287&lat=41.40139")
+        );
+        assert!(lines[1].contains("&format=jsonv2"));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn async_lookup_test() {
+        let server = Server::new(
+            [1, 2]
+                .iter()
+                .map(|&osm_id| {
+                    Response::json(format!(
+                        r#"{{"type": "FeatureCollection", "licence": "ODbL", "features": [{{"type": "Feature", "properties": {{"place_id": 1, "osm_type": "node", "osm_id": {}, "display_name": "Bern", "place_rank": 30, "category": "place", "type": "city", "importance": 0.1}}, "bbox": [7.4, 46.9, 7.4, 46.9], "geometry": {{"type": "Point", "coordinates": [7.4, 46.9]}}}}]}}"#,
+                        osm_id
+                    ))
+                })
+                .collect(),
+        );
+        let osm = AsyncOpenstreetmap::new_with_endpoint(server.endpoint());
+        let empty: OpenstreetmapResponse<f64> = osm.lookup(&[]).await.unwrap();
+        assert!(empty.features.is_empty());
+        let ids: Vec<OsmId> = (1..=51).map(|id| OsmId::new(OsmType::Node, id)).collect();
+        let res: OpenstreetmapResponse<f64> = osm.lookup(&ids).await.unwrap();
+        let lines = server.request_lines();
+        assert_eq!(res.features.len(), 2);
+        assert_eq!(res.features[1].properties.osm_id, 2);
+        // The empty lookup sent no request
+        assert!(lines[0].starts_with("GET /lookup?osm_ids=N1%2CN2%2C"));
+        assert!(lines[0].contains("N50&format=geojson"));
+        assert!(lines[1].starts_with("GET /lookup?osm_ids=N51&format=geojson"));
+    }
+
+    #[test]
+    fn lookup_meta_test() {
+        let server = Server::single(Response::json(
+            r#"{"type": "FeatureCollection", "licence": "ODbL", "features": [{"type": "Feature", "properties": {"place_id": 1, "osm_type": "relation", "osm_id": 62428, "display_name": "München", "place_rank": 12, "category": "boundary", "type": "administrative", "importance": 0.8}, "bbox": [11.4, 48.1, 11.7, 48.2], "geometry": {"type": "Point", "coordinates": [11.57, 48.13]}}]}"#,
+        ));
+        let endpoint = server.endpoint();
+        let osm = Openstreetmap::new_with_endpoint(endpoint.clone());
+        let ids = [OsmId::new(OsmType::Relation, 62428)];
+        let res: OpenstreetmapResponse<f64> = osm.lookup(&ids).unwrap();
+        let lines = server.request_lines();
+        assert!(lines[0].starts_with("GET /lookup?osm_ids=R62428&format=geojson"));
+        assert_eq!(res.features[0].properties.osm_id, 62428);
+        assert_eq!(res.meta.status, 200);
+        assert_eq!(res.meta.attempts, 1);
+        assert_eq!(res.meta.endpoint, endpoint);
+    }
+
+    #[test]
+    fn with_extra_params_test() {
+        let server = Server::single(Response::json(
+            r#"{"type": "FeatureCollection", "licence": "ODbL", "features": []}"#,
+        ));
+        let osm = Openstreetmap::new_with_endpoint(server.endpoint());
+        let params = OpenstreetmapParams::<f64>::new("Calatrava")
+            .with_extra_params(&[("polygon_geojson", "1"), ("format", "json")])
+            .build();
+        osm.forward_full(&params).unwrap();
+        let request = server.request();
+        let line = request.lines().next().unwrap();
+        assert!(line.contains("&polygon_geojson=1"));
+        // The crate's format takes precedence
+        assert!(line.contains("format=geojson"));
+        assert!(!line.contains("format=json"));
+    }
+
+    #[test]
+    fn localized_test() {
+        let body = r#"{"type": "FeatureCollection", "licence": "ODbL", "features": []}"#;
+        let server = Server::new(vec![Response::json(body), Response::json(body)]);
+        let osm = Openstreetmap::new_with_endpoint(server.endpoint());
+        let language: LanguageTag = "fr-CH".parse().unwrap();
+        let res: Vec<(Point<f64>, String)> = osm.forward_localized("Genf", &language).unwrap();
+        assert!(res.is_empty());
+        let res: Option<GeocodeResult<f64>> = osm
+            .reverse_localized(&Point::new(6.14, 46.2), &language)
+            .unwrap();
+        assert!(res.is_none());
+        let lines = server.request_lines();
+        assert!(lines[0].starts_with("GET /search?"));
+        assert!(lines[0].contains("&accept-language=fr-CH"));
+        assert!(lines[1].starts_with("GET /reverse?"));
+        assert!(lines[1].contains("&accept-language=fr-CH"));
+    }
+
+    #[test]
+    fn format_test() {
+        let result = r#"{"place_id": 263681481, "licence": "ODbL", "osm_type": "way", "osm_id": 355421084, "lat": "41.40140675", "lon": "2.12872241167437", "category": "building", "type": "apartments", "place_rank": 30, "importance": 0.74, "addresstype": "building", "name": "", "display_name": "68, Carrer de Calatrava, Barcelona", "boundingbox": ["41.401227", "41.4015815", "2.1284918", "2.128952"]}"#;
+        let server = Server::new(vec![
+            Response::json(format!("[{}]", result)),
+            Response::json(r#"{"error": "Unable to geocode"}"#),
+        ]);
+        let osm = Openstreetmap::new_with_endpoint(server.endpoint());
+        let params = OpenstreetmapParams::new("Carrer de Calatrava 68")
+            .with_format(Format::Jsonv2)
+            .build();
+        let res: OpenstreetmapResponse<f64> = osm.forward_full(&params).unwrap();
+        assert_eq!(res.licence, "ODbL");
+        let result = &res.features[0];
+        assert_eq!(result.geometry.coordinates, (2.12872241167437, 41.40140675));
+        assert_eq!(result.bbox, (2.1284918, 41.401227, 2.128952, 41.4015815));
+        assert_eq!(
+            result.properties.osm(),
+            Some(OsmId::new(OsmType::Way, 355421084))
+        );
+        assert_eq!(result.properties.category, Category::Building);
+        assert_eq!(result.properties.addresstype.as_deref(), Some("building"));
+        let params = OpenstreetmapReverseParams::new()
+            .with_format(Format::Json)
+            .build();
+        let res: OpenstreetmapResponse<f64> =
+            osm.reverse_full(&Point::new(0.0, 0.0), &params).unwrap();
+        assert!(res.features.is_empty());
+        let lines = server.request_lines();
+        assert!(lines[0].starts_with("GET /search?format=jsonv2&"));
+        assert!(lines[1].contains("&format=json&"));
+    }
+
+    #[test]
+    fn reverse_empty_test() {
+        let server = Server::single(Response::json(
+            r#"{"type": "FeatureCollection", "licence": "ODbL", "features": []}"#,
+        ));
+        let osm = Openstreetmap::new_with_endpoint(server.endpoint());
+        let res = osm.reverse(&Point::new(0.0, 0.0));
+        server.requests();
+        assert_eq!(res.unwrap(), None);
+    }
+
+    #[test]
+    fn html_error_page_test() {
+        let server = Server::single(
+            Response::new(
+                "200 OK",
+                "<!DOCTYPE html>\n<html><body>Server overloaded</body></html>",
+            )
+            .with_header("Content-Type", "text/html"),
+        );
+        let osm = Openstreetmap::new_with_endpoint(server.endpoint());
+        let res: Result<Vec<Point<f64>>, _> = osm.forward("Calatrava");
+        server.requests();
+        match res {
+            Err(GeocodingError::UnexpectedBody { snippet }) => assert_eq!(
+                snippet,
+                "<!DOCTYPE html> <html><body>Server overloaded</body></html>"
+            ),
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn json_result_test() {
+        // The `json` format names the category `class`, and lacks the `jsonv2` fields
+        let json = r#"{"place_id": 1, "licence": "ODbL", "osm_type": "node", "osm_id": 2, "lat": "46.9", "lon": "7.4", "class": "place", "type": "house", "importance": 0.1, "display_name": "Wabern", "boundingbox": ["46.8", "47.0", "7.3", "7.5"]}"#;
+        let result: OpenstreetmapJsonResult<f32> = serde_json::from_str(json).unwrap();
+        assert_eq!((result.lon, result.lat), (7.4, 46.9));
+        assert_eq!(result.boundingbox, [46.8, 47.0, 7.3, 7.5]);
+        let result = OpenstreetmapResult::from(result);
+        assert_eq!(result.properties.place_rank, 0);
+        assert_eq!(result.properties.name, None);
+        assert_eq!(result.bbox, (7.3, 46.8, 7.5, 47.0));
+    }
+
+    #[test]
+    fn with_snapping_test() {
+        let server = Server::single(Response::json(
+            r#"{"type": "FeatureCollection", "licence": "ODbL", "features": []}"#,
+        ));
+        let osm = Openstreetmap::new_with_endpoint(server.endpoint());
+        let params = OpenstreetmapReverseParams::new()
+            .with_snapping(ReverseSnapping::Road)
+            .build();
+        let res: OpenstreetmapResponse<f64> = osm
+            .reverse_full(&Point::new(2.1287, 41.4014), &params)
+            .unwrap();
+        assert!(res.features.is_empty());
+        let request = server.request();
+        let line = request.lines().next().unwrap();
+        assert!(line.contains("&zoom=17&layer=address"));
+    }
+
+    #[test]
+    fn reverse_structured_test() {
+        let server = Server::single(Response::json(
+            r#"{"type": "FeatureCollection", "licence": "ODbL", "features": [{"type": "Feature", "properties": {"place_id": 1, "osm_type": "way", "osm_id": 2, "display_name": "68, Carrer de Calatrava, Barcelona, 08017, España", "place_rank": 30, "category": "building", "type": "yes", "importance": 0.1, "address": {"house_number": "68", "road": "Carrer de Calatrava", "city": "Barcelona", "postcode": "08017", "country": "España", "country_code": "es"}}, "bbox": [2.1, 41.4, 2.1, 41.4], "geometry": {"type": "Point", "coordinates": [2.1, 41.4]}}]}"#,
+        ));
+        let osm = Openstreetmap::new_with_endpoint(server.endpoint());
+        let address = osm
+            .reverse_structured(&Point::new(2.1, 41.4))
+            .unwrap()
+            .unwrap();
+        let request = server.request();
+        assert!(request.contains("addressdetails=1"));
+        assert_eq!(address.postcode.as_deref(), Some("08017"));
+        assert_eq!(address.city.as_deref(), Some("Barcelona"));
+        assert_eq!(address.country_code.as_deref(), Some("ES"));
+    }
+
+    #[test]
+    fn reverse_object_test() {
+        let server = Server::single(Response::json(
+            r#"{"type": "FeatureCollection", "licence": "ODbL", "features": [{"type": "Feature", "properties": {"place_id": 1, "osm_type": "way", "osm_id": 355421084, "display_name": "68, Carrer de Calatrava, Barcelona", "place_rank": 30, "category": "building", "type": "apartments", "importance": 0.1}, "bbox": [2.1, 41.4, 2.1, 41.4], "geometry": {"type": "Point", "coordinates": [2.1, 41.4]}}]}"#,
+        ));
+        let osm = Openstreetmap::new_with_endpoint(server.endpoint());
+        let id = OsmId::new(OsmType::Way, 355421084);
+        let params = OpenstreetmapReverseParams::new().with_zoom(18).build();
+        let res: OpenstreetmapResponse<f64> = osm.reverse_object(&id, &params).unwrap();
+        assert_eq!(res.features[0].properties.osm(), Some(id));
+        let request = server.request();
+        let line = request.lines().next().unwrap();
+        assert!(line.starts_with("GET /reverse?osm_type=W&osm_id=355421084&format=geojson&"));
+        assert!(line.contains("zoom=18"));
+        assert!(!line.contains("lat="));
+    }
+
+    #[test]
+    fn forward_full_raw_test() {
+        let server = Server::single(Response::json(
+            r#"{"type": "FeatureCollection", "licence": "ODbL", "features": [{"type": "Feature", "properties": {"place_id": 1, "osm_type": "way", "osm_id": 2, "display_name": "Carrer de Calatrava, Barcelona", "place_rank": 26, "category": "highway", "type": "residential", "importance": 0.1, "address": {"road": "Carrer de Calatrava", "city": "Barcelona \u00e0"}}, "bbox": [2.1, 41.4, 2.1, 41.4], "geometry": {"type": "Point", "coordinates": [2.1, 41.4]}}]}"#,
+        ));
+        let osm = Openstreetmap::new_with_endpoint(server.endpoint());
+        let params = OpenstreetmapParams::<f64>::new("Calatrava").build();
+        let raw = osm.forward_full_raw(&params).unwrap();
+        server.requests();
+        assert_eq!(raw.meta.status, 200);
+        let res: BorrowedResponse<f64> = raw.parse().unwrap();
+        let properties = &res.features[0].properties;
+        assert!(matches!(properties.display_name, Cow::Borrowed(_)));
+        assert_eq!(properties.display_name, "Carrer de Calatrava, Barcelona");
+        assert_eq!(properties.category, "highway");
+        let address = properties.address.as_ref().unwrap();
+        // Escaped strings are unescaped into owned strings
+        assert!(matches!(address["city"], Cow::Owned(_)));
+        assert_eq!(address["city"], "Barcelona à");
+        let result = GeocodeResult::from(&res.features[0]);
+        assert_eq!(result.point, Point::new(2.1, 41.4));
+    }
+
+    #[test]
+    fn ping_test() {
+        let server = Server::new(vec![
+            Response::new("200 OK", r#"{"status": 0, "message": "OK"}"#),
+            Response::new("500 Internal Server Error", "Database connection failed"),
+        ]);
+        let osm = Openstreetmap::new_with_endpoint(server.endpoint());
+        let status = osm.ping().unwrap();
+        assert_eq!(status.meta.status, 200);
+        assert_eq!(status.message.as_deref(), Some("OK"));
+        assert!(matches!(osm.ping(), Err(GeocodingError::Request(_))));
+        server.requests();
+    }
+
+    #[test]
+    fn with_throttle_test() {
+        let throttle = Arc::new(Throttle::new(std::time::Duration::from_millis(50)));
+        // Nothing listens on this port, so requests fail right after being throttled
+        let endpoint = "http://127.0.0.1:9/".to_string();
+        let osm =
+            Openstreetmap::new_with_endpoint(endpoint.clone()).with_throttle(throttle.clone());
+        let other = Openstreetmap::new_with_endpoint(endpoint).with_throttle(throttle);
+        let start = std::time::Instant::now();
+        assert!(osm.status().is_err());
+        assert!(other.status().is_err());
+        assert!(osm.status().is_err());
+        assert!(start.elapsed() >= std::time::Duration::from_millis(100));
+    }
+
+    #[test]
+    fn address_details_test() {
+        let address: AddressDetails = serde_json::from_str(
+            r#"{
+                "road": "Rue de Rivoli",
+                "quarter": "Quartier Saint-Germain-l'Auxerrois",
+                "city": "Paris",
+                "ISO3166-2-lvl6": "FR-75",
+                "state": "Île-de-France",
+                "ISO3166-2-lvl4": "FR-IDF",
+                "postcode": "75001",
+                "country": "France",
+                "country_code": "fr"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(address.road.unwrap(), "Rue de Rivoli");
+        assert_eq!(address.iso3166_2_lvl4.unwrap(), "FR-IDF");
+        assert_eq!(address.iso3166_2_lvl6.unwrap(), "FR-75");
+        assert_eq!(
+            address.other["quarter"],
+            "Quartier Saint-Germain-l'Auxerrois"
+        );
+        assert_eq!(address.other.len(), 1);
+    }
+
+    #[test]
+    fn admin_area_from_address_details_test() {
+        let address: AddressDetails = serde_json::from_str(
+            r#"{
+                "town": "Sant Cugat del Vallès",
+                "county": "Vallès Occidental",
+                "state": "Catalunya",
+                "ISO3166-2-lvl4": "ES-CT",
+                "country": "España",
+                "country_code": "es"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            AdminArea::from(address),
+            AdminArea {
+                city: Some("Sant Cugat del Vallès".to_string()),
+                region: Some("Catalunya".to_string()),
+                region_code: Some("ES-CT".to_string()),
+                country: Some("España".to_string()),
+                country_code: Some("ES".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn address_from_address_details_test() {
+        let details: AddressDetails = serde_json::from_str(
+            r#"{
+                "house_number": "68",
+                "road": "Carrer de Calatrava",
+                "suburb": "Sant Gervasi - la Bonanova",
+                "city": "Barcelona",
+                "state": "Catalunya",
+                "ISO3166-2-lvl4": "ES-CT",
+                "postcode": "08017",
+                "country": "España",
+                "country_code": "es"
+            }"#,
+        )
+        .unwrap();
+        let address = Address::from(&details);
+        assert_eq!(address.state_code.as_deref(), Some("CT"));
+        assert_eq!(address.country_code.as_deref(), Some("ES"));
+        assert_eq!(
+            crate::address_format::format_one_line(&address),
+            "Carrer de Calatrava 68, 08017 Barcelona, España"
+        );
+    }
+
+    #[test]
+    fn admin_reverse_test() {
+        let osm = Openstreetmap::new();
+        let p = Point::new(2.12870, 41.40139);
+        let res = osm.admin_reverse(&p).unwrap().unwrap();
+        assert_eq!(res.city.unwrap(), "Barcelona");
+        assert_eq!(res.region_code.unwrap(), "ES-CT");
+        assert_eq!(res.country_code.unwrap(), "ES");
+    }
+
+    #[test]
+    fn with_email_test() {
+        let osm = Openstreetmap::new().with_email("geocoding@example.com");
+        let req = osm.get("status").build().unwrap();
+        assert_eq!(
+            req.url().as_str(),
+            "https://nominatim.openstreetmap.org/status?email=geocoding%40example.com"
+        );
+    }
+
+    #[test]
+    fn geocode_result_test() {
+        let res: OpenstreetmapResponse<f64> = serde_json::from_str(
+            r#"{
+                "type": "FeatureCollection",
+                "licence": "Data © OpenStreetMap contributors, ODbL 1.0. https://osm.org/copyright",
+                "features": [{
+                    "type": "Feature",
+                    "properties": {
+                        "place_id": 263681481,
+                        "osm_type": "way",
+                        "osm_id": 355421084,
+                        "display_name": "68, Carrer de Calatrava, Barcelona, 08017, España",
+                        "place_rank": 30,
+                        "category": "building",
+                        "type": "apartments",
+                        "importance": 0.00001
+                    },
+                    "bbox": [2.1284918, 41.401227, 2.1288787, 41.4015153],
+                    "geometry": { "type": "Point", "coordinates": [2.12870, 41.40139] }
+                }]
+            }"#,
+        )
+        .unwrap();
+        let result = GeocodeResult::from(&res.features[0]);
+        assert_eq!(result.point, Point::new(2.12870, 41.40139));
+        assert_eq!(
+            result.label,
+            "68, Carrer de Calatrava, Barcelona, 08017, España"
+        );
+        assert_eq!(&result.geohash[..8], "sp3e82yh");
+        assert_eq!(
+            result.bbox,
+            Some(Rect::new((2.1284918, 41.401227), (2.1288787, 41.4015153)))
+        );
+    }
+
+    #[test]
+    fn osm_id_display_test() {
+        assert_eq!(
+            OsmId::new(OsmType::Node, 240109189).to_string(),
+            "N240109189"
+        );
+        assert_eq!(
+            OsmId::new(OsmType::Way, 355421084).to_string(),
+            "W355421084"
+        );
+        assert_eq!(OsmId::new(OsmType::Relation, 146656).to_string(), "R146656");
+    }
+
+    #[test]
+    fn osm_id_from_str_test() {
+        assert_eq!(
+            "N240109189".parse::<OsmId>().unwrap(),
+            OsmId::new(OsmType::Node, 240109189)
+        );
+        assert_eq!(
+            "r146656".parse::<OsmId>().unwrap(),
+            OsmId::new(OsmType::Relation, 146656)
+        );
+        assert!("X123".parse::<OsmId>().is_err());
+        assert!("W".parse::<OsmId>().is_err());
+        assert!("W-1".parse::<OsmId>().is_err());
+    }
+
+    #[test]
+    fn result_properties_osm_id_test() {
+        let properties: ResultProperties = serde_json::from_str(
+            r#"{
+                "place_id": 263681481,
+                "osm_type": "way",
+                "osm_id": 355421084,
+                "display_name": "68, Carrer de Calatrava, Barcelona, 08017, Spain",
+                "place_rank": 30,
+                "category": "building",
+                "type": "apartments",
+                "importance": 0.741
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(properties.osm_type, "way");
+        assert_eq!(properties.osm(), Some(OsmId::new(OsmType::Way, 355421084)));
+        let json = serde_json::to_value(&properties).unwrap();
+        assert_eq!(json["osm_type"], "way");
+        assert_eq!(json["osm_id"], 355421084);
+    }
+
     #[test]
     fn forward_test() {
         let osm = Openstreetmap::new();
         let address = "Schwabing, München";
-        let res = osm.forward(&address);
+        let res = osm.forward(address);
         assert_eq!(res.unwrap(), vec![Point::new(11.5884858, 48.1700887)]);
     }
 
+    #[test]
+    fn reverse_full_test() {
+        let osm = Openstreetmap::new();
+        let p = Point::new(2.12870, 41.40139);
+        let params = OpenstreetmapReverseParams::new()
+            .with_addressdetails(true)
+            .with_namedetails(true)
+            .with_zoom(10)
+            .build();
+        let res: OpenstreetmapResponse<f64> = osm.reverse_full(&p, &params).unwrap();
+        let result = res.features[0].properties.clone();
+        assert_eq!(result.address.unwrap().city.unwrap(), "Barcelona");
+        assert!(result.namedetails.is_some());
+    }
+
+    #[test]
+    fn nearby_test() {
+        let osm = Openstreetmap::new();
+        let p = Point::new(2.12870, 41.40139);
+        let res = osm.nearby(&p, 500.0, "amenity=cafe").unwrap();
+        assert!(!res.is_empty());
+        assert!(res
+            .iter()
+            .all(|(place, _)| haversine_distance(&p, place) <= 500.0));
+    }
+
+    #[test]
+    fn reverse_f32_test() {
+        let osm = Openstreetmap::new().with_precision(5);
+        let p = Point::new(2.12870_f32, 41.40139);
+        let res = osm.reverse(&p);
+        assert!(res
+            .unwrap()
+            .unwrap()
+            .contains("Barcelona, Barcelonès, Barcelona, Catalunya"));
+    }
+
     #[test]
     fn reverse_test() {
         let osm = Openstreetmap::new();
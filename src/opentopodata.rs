@@ -0,0 +1,266 @@
+//! The [Open Topo Data](https://www.opentopodata.org/) provider.
+//!
+//! Elevation lookups are implemented on the [`Opentopodata`](struct.Opentopodata.html) struct.
+//! Please see the [API documentation](https://www.opentopodata.org/api/) for details.
+//!
+//! The public Open Topo Data API is free, but limited to 1 request per second and
+//! 1000 requests per day. Each request may look up to 100 locations.
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::{Elevation, Opentopodata, Point};
+//!
+//! let otd = Opentopodata::new_with_dataset("eudem25m");
+//! let p = Point::new(7.451352119445801, 46.92793655395508);
+//! let res = otd.elevation(&p);
+//! assert!(res.unwrap().unwrap() > 500.0);
+//! ```
+use crate::audit::AuditSink;
+use crate::format_coordinate;
+use crate::from_json_str;
+use crate::request::{RequestHooks, SendHooked};
+use crate::stats::UsageStats;
+use crate::throttle::Throttle;
+use crate::Client;
+use crate::ClientSettings;
+use crate::Deserialize;
+use crate::Elevation;
+use crate::GeocodingError;
+use crate::Point;
+use num_traits::Float;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// An instance of the Open Topo Data service
+pub struct Opentopodata {
+    client: Client,
+    client_settings: ClientSettings,
+    endpoint: String,
+    hooks: RequestHooks,
+    dataset: String,
+}
+
+impl Opentopodata {
+    /// Create a new Open Topo Data instance using the default endpoint and the
+    /// global `srtm90m` dataset
+    pub fn new() -> Self {
+        Opentopodata::new_with_dataset("srtm90m")
+    }
+
+    /// Create a new Open Topo Data instance using the default endpoint and the given dataset,
+    /// such as `eudem25m`, `ned10m` or `aster30m`
+    ///
+    /// Several datasets can be given as a comma-separated list, in which case the first
+    /// dataset with data for a location is used.
+    pub fn new_with_dataset(dataset: &str) -> Self {
+        Opentopodata::new_with_endpoint("https://api.opentopodata.org/v1/".to_string(), dataset)
+    }
+
+    /// Create a new Open Topo Data instance with a custom endpoint and dataset.
+    ///
+    /// Endpoint should include a trailing slash (i.e. "https://api.opentopodata.org/v1/")
+    pub fn new_with_endpoint(endpoint: String, dataset: &str) -> Self {
+        let client_settings = ClientSettings::default();
+        Opentopodata {
+            client: client_settings.client(),
+            client_settings,
+            endpoint,
+            dataset: dataset.to_owned(),
+            hooks: RequestHooks::default(),
+        }
+    }
+
+    /// Space the requests to the endpoint's host using `throttle`, which also slows down
+    /// when the server asks to
+    ///
+    /// The public Open Topo Data API allows 1 request per second, so
+    /// [`Throttle::global`](../throttle/struct.Throttle.html#method.global) is a good fit.
+    pub fn with_throttle(mut self, throttle: Arc<Throttle>) -> Self {
+        self.hooks.throttle = Some(throttle);
+        self
+    }
+
+    /// Record every request sent to the endpoint in `sink`
+    ///
+    /// See the [`audit`](../audit/index.html) module for details.
+    pub fn with_audit(mut self, sink: Arc<dyn AuditSink>) -> Self {
+        self.hooks.audit = Some(sink);
+        self
+    }
+
+    /// The requests sent by this instance so far
+    ///
+    /// See the [`stats`](../stats/index.html) module for details.
+    pub fn stats(&self) -> UsageStats {
+        self.hooks.usage.snapshot()
+    }
+
+    /// Accept compressed responses, `true` by default
+    ///
+    /// Only the compressions enabled by the crate's features are negotiated.
+    pub fn with_compression(mut self, compression: bool) -> Self {
+        self.client_settings.compression = compression;
+        self.client = self.client_settings.client();
+        self
+    }
+
+    /// Apply connection pool and compression settings to the HTTP client
+    pub fn with_client_settings(mut self, settings: &ClientSettings) -> Self {
+        self.client_settings = settings.clone();
+        self.client = self.client_settings.client();
+        self
+    }
+
+    /// An elevation lookup of several points in a single request, returning the full response
+    ///
+    /// This method passes the `locations` parameter to the API.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::{Opentopodata, Point};
+    /// use geocoding::opentopodata::OpentopodataResponse;
+    ///
+    /// let otd = Opentopodata::new();
+    /// let points = [
+    ///     Point::new(7.451352119445801, 46.92793655395508),
+    ///     Point::new(2.12870, 41.40139),
+    /// ];
+    /// let res: OpentopodataResponse<f64> = otd.lookup(&points).unwrap();
+    /// assert_eq!(res.status, "OK");
+    /// assert_eq!(res.results.len(), 2);
+    /// ```
+    pub fn lookup<T>(&self, points: &[Point<T>]) -> Result<OpentopodataResponse<T>, GeocodingError>
+    where
+        T: Float + Debug,
+        for<'de> T: Deserialize<'de>,
+    {
+        // Open Topo Data expects lat, lon order
+        let locations = points
+            .iter()
+            .map(|p| {
+                format!(
+                    "{},{}",
+                    format_coordinate(p.y(), None),
+                    format_coordinate(p.x(), None)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("|");
+
+        let resp = self
+            .client
+            .get(format!("{}{}", self.endpoint, self.dataset))
+            .query(&[("locations", &locations)])
+            .send_hooked(&self.hooks, &self.endpoint)?
+            .error_for_status()?;
+        let res: OpentopodataResponse<T> = resp.json()?;
+        Ok(res)
+    }
+}
+
+impl Default for Opentopodata {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Elevation<T> for Opentopodata
+where
+    T: Float + Debug,
+    for<'de> T: Deserialize<'de>,
+{
+    /// An elevation lookup of a point. Please see [the documentation](https://www.opentopodata.org/api/) for details.
+    ///
+    /// This method passes the `locations` parameter to the API.
+    fn elevation(&self, point: &Point<T>) -> Result<Option<f64>, GeocodingError> {
+        let res = self.lookup(std::slice::from_ref(point))?;
+        Ok(res.results.first().and_then(|r| r.elevation))
+    }
+}
+
+/// The top-level full JSON response returned by an elevation lookup
+///
+/// See [the documentation](https://www.opentopodata.org/api/) for more details
+///
+///```json
+/// {
+///     "results": [
+///         {
+///             "dataset": "srtm90m",
+///             "elevation": 557.0,
+///             "location": {
+///                 "lat": 46.92793655395508,
+///                 "lng": 7.451352119445801
+///             }
+///         }
+///     ],
+///     "status": "OK"
+/// }
+///```
+#[derive(Debug, Deserialize)]
+pub struct OpentopodataResponse<T>
+where
+    T: Float + Debug,
+{
+    pub results: Vec<OpentopodataResult<T>>,
+    pub status: String,
+}
+
+impl<T> OpentopodataResponse<T>
+where
+    T: Float + Debug,
+    for<'de> T: Deserialize<'de>,
+{
+    /// Parse a response from its JSON text, e.g. a recorded response
+    pub fn from_json_str(json: &str) -> Result<Self, GeocodingError> {
+        from_json_str(json)
+    }
+}
+
+/// An elevation result
+#[derive(Clone, Debug, Deserialize)]
+pub struct OpentopodataResult<T>
+where
+    T: Float + Debug,
+{
+    pub dataset: Option<String>,
+    pub elevation: Option<f64>,
+    pub location: HashMap<String, T>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn elevation_test() {
+        let otd = Opentopodata::new_with_dataset("eudem25m");
+        let p = Point::new(7.451352119445801, 46.92793655395508);
+        let res = otd.elevation(&p).unwrap().unwrap();
+        assert!(res > 500.0 && res < 600.0);
+    }
+
+    #[test]
+    fn lookup_test() {
+        let otd = Opentopodata::new();
+        let points = [Point::new(7.451352119445801, 46.92793655395508)];
+        let res: OpentopodataResponse<f64> = otd.lookup(&points).unwrap();
+        assert_eq!(res.results[0].dataset.as_deref(), Some("srtm90m"));
+    }
+
+    #[test]
+    fn response_test() {
+        let res: OpentopodataResponse<f64> = serde_json::from_str(
+            r#"{
+                "results": [{"dataset": "etopo1", "elevation": null, "location": {"lat": 0.0, "lng": 0.0}}],
+                "status": "OK"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(res.results[0].elevation, None);
+        assert_eq!(res.results[0].location["lng"], 0.0);
+    }
+}
@@ -0,0 +1,192 @@
+//! A single input type for search boxes.
+//!
+//! Users type free-form addresses, coordinates and sometimes fill in the fields of an
+//! address form into the same search box. [`Query`] represents any of these inputs, and
+//! [`geocode`] sends each to the matching lookup of a provider: a forward lookup for text,
+//! a structured forward lookup for addresses, and a reverse lookup for coordinates.
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::{geocode, GeocodeResult, Openstreetmap, Query};
+//!
+//! let osm = Openstreetmap::new();
+//! let query: Query<f64> = Query::parse("46.9480, 7.4474");
+//! let res: Vec<GeocodeResult<f64>> = geocode(&osm, &query).unwrap();
+//! assert!(res[0].label.contains("Bern"));
+//! ```
+use crate::coords;
+use crate::{Address, ForwardStructured, GeocodeResult, GeocodingError, Point, ReverseFull};
+use num_traits::Float;
+use std::fmt::Debug;
+
+/// A structured address query, with the components of the address known separately
+pub type StructuredQuery = Address;
+
+/// A query typed into a search box
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query<T>
+where
+    T: Float + Debug,
+{
+    /// A free-form address or place name
+    Text(String),
+    /// An address whose components are known
+    Structured(StructuredQuery),
+    /// A coordinate, in lon, lat (x, y) order
+    Coords(Point<T>),
+}
+
+impl<T> Query<T>
+where
+    T: Float + Debug,
+{
+    /// Interpret the input of a search box
+    ///
+    /// Returns `Coords` if `input` is a coordinate or a geohash, as recognised by
+    /// [`coords::parse`](../coords/fn.parse.html), and `Text` otherwise.
+    pub fn parse(input: &str) -> Self {
+        match coords::parse(input) {
+            Some(point) => Query::Coords(point),
+            None => Query::Text(input.trim().to_string()),
+        }
+    }
+}
+
+impl<T> From<&str> for Query<T>
+where
+    T: Float + Debug,
+{
+    fn from(input: &str) -> Self {
+        Query::Text(input.to_string())
+    }
+}
+
+impl<T> From<String> for Query<T>
+where
+    T: Float + Debug,
+{
+    fn from(input: String) -> Self {
+        Query::Text(input)
+    }
+}
+
+impl<T> From<StructuredQuery> for Query<T>
+where
+    T: Float + Debug,
+{
+    fn from(address: StructuredQuery) -> Self {
+        Query::Structured(address)
+    }
+}
+
+impl<T> From<Point<T>> for Query<T>
+where
+    T: Float + Debug,
+{
+    fn from(point: Point<T>) -> Self {
+        Query::Coords(point)
+    }
+}
+
+/// Geocode a query with the lookup of `provider` matching its kind
+///
+/// `Text` queries are forward-geocoded, `Structured` queries are forward-geocoded with
+/// [`ForwardStructured`](../trait.ForwardStructured.html), and `Coords` queries are
+/// reverse-geocoded, returning at most one result.
+pub fn geocode<P, T>(
+    provider: &P,
+    query: &Query<T>,
+) -> Result<Vec<GeocodeResult<T>>, GeocodingError>
+where
+    P: ForwardStructured<T> + ReverseFull<T> + ?Sized,
+    T: Float + Debug,
+{
+    let labelled = match query {
+        Query::Text(text) => provider.forward_with_labels(text)?,
+        Query::Structured(address) => provider.forward_structured(address)?,
+        Query::Coords(point) => return Ok(provider.reverse_result(point)?.into_iter().collect()),
+    };
+    Ok(labelled
+        .into_iter()
+        .map(|(point, label)| GeocodeResult::new(point, label))
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ForwardLabels, Reverse};
+
+    /// Answers each lookup with a label naming the lookup
+    struct Echo;
+
+    impl ForwardLabels<f64> for Echo {
+        fn forward_with_labels(
+            &self,
+            address: &str,
+        ) -> Result<Vec<(Point<f64>, String)>, GeocodingError> {
+            Ok(vec![(
+                Point::new(7.4474, 46.948),
+                format!("forward {}", address),
+            )])
+        }
+    }
+
+    impl ForwardStructured<f64> for Echo {}
+
+    impl Reverse<f64> for Echo {
+        fn reverse(&self, point: &Point<f64>) -> Result<Option<String>, GeocodingError> {
+            Ok(Some(format!("reverse {} {}", point.x(), point.y())))
+        }
+    }
+
+    impl ReverseFull<f64> for Echo {
+        fn reverse_result(
+            &self,
+            point: &Point<f64>,
+        ) -> Result<Option<GeocodeResult<f64>>, GeocodingError> {
+            Ok(self
+                .reverse(point)?
+                .map(|label| GeocodeResult::new(*point, label)))
+        }
+    }
+
+    #[test]
+    fn parse_test() {
+        assert_eq!(
+            Query::<f64>::parse(" Bundesplatz 3, Bern "),
+            Query::Text("Bundesplatz 3, Bern".to_string())
+        );
+        assert_eq!(
+            Query::<f64>::parse("46.948, 7.4474"),
+            Query::Coords(Point::new(7.4474, 46.948))
+        );
+    }
+
+    #[test]
+    fn geocode_test() {
+        let res = geocode(&Echo, &Query::from("Bern")).unwrap();
+        assert_eq!(res[0].label, "forward Bern");
+
+        let address = Address {
+            road: Some("Bundesplatz".to_string()),
+            house_number: Some("3".to_string()),
+            city: Some("Bern".to_string()),
+            ..Default::default()
+        };
+        let res = geocode(&Echo, &Query::from(address.clone())).unwrap();
+        assert_eq!(
+            res[0].label,
+            format!(
+                "forward {}",
+                crate::address_format::format_one_line(&address)
+            )
+        );
+
+        let res = geocode(&Echo, &Query::from(Point::new(7.5, 47.0))).unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].label, "reverse 7.5 47");
+        assert_eq!(res[0].point, Point::new(7.5, 47.0));
+    }
+}
@@ -0,0 +1,382 @@
+//! The URLs of provider requests, built without sending them.
+//!
+//! Each function returns the URL of the request sent by the corresponding lookup method,
+//! built by the same code, so that applications and tests can check exactly what will be
+//! sent without network access. Requests are sent to these URLs unless a
+//! [failover](../failover/index.html) endpoint is used, or the query is sent as a form by
+//! `with_max_query_length`.
+//!
+//! Like the lookup methods, the functions fail with an `InvalidConfiguration` error if the
+//! provider is configured in a way its API rejects.
+//!
+//! The URLs contain the API keys of the providers: redact them with
+//! [`redact_url`](../audit/fn.redact_url.html) before logging them.
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::openstreetmap::OpenstreetmapParams;
+//! use geocoding::{raw, Openstreetmap};
+//!
+//! let osm = Openstreetmap::new();
+//! let params = OpenstreetmapParams::<f64>::new("Carrer de Calatrava").build();
+//! let url = raw::openstreetmap::build_forward_request(&osm, &params).unwrap();
+//! assert_eq!(
+//!     url.as_str(),
+//!     "https://nominatim.openstreetmap.org/search?format=geojson&addressdetails=0&q=Carrer+de+Calatrava"
+//! );
+//! ```
+use crate::GeocodingError;
+use reqwest::blocking::RequestBuilder;
+use reqwest::Url;
+
+/// The URLs of [`Openstreetmap`](../../struct.Openstreetmap.html) requests
+#[cfg(feature = "osm")]
+pub mod openstreetmap {
+    use super::url;
+    use crate::openstreetmap::{OpenstreetmapParams, OpenstreetmapReverseParams};
+    use crate::{GeocodingError, Openstreetmap, Point};
+    use num_traits::Float;
+    use reqwest::Url;
+    use std::fmt::Debug;
+
+    /// The URL of [`Openstreetmap::forward_full`](../../struct.Openstreetmap.html#method.forward_full)
+    pub fn build_forward_request<T>(
+        osm: &Openstreetmap,
+        params: &OpenstreetmapParams<T>,
+    ) -> Result<Url, GeocodingError>
+    where
+        T: Float + Debug,
+    {
+        url(osm.search_request(params, &[]))
+    }
+
+    /// The URL of [`Openstreetmap::reverse_full`](../../struct.Openstreetmap.html#method.reverse_full)
+    pub fn build_reverse_request<T>(
+        osm: &Openstreetmap,
+        point: &Point<T>,
+        params: &OpenstreetmapReverseParams,
+    ) -> Result<Url, GeocodingError>
+    where
+        T: Float + Debug,
+    {
+        url(osm.reverse_request(point, params))
+    }
+}
+
+/// The URLs of [`Opencage`](../../struct.Opencage.html) requests
+#[cfg(feature = "opencage")]
+pub mod opencage {
+    use super::url;
+    use crate::opencage::Parameters;
+    use crate::{GeocodingError, InputBounds, Opencage, Point};
+    use num_traits::Float;
+    use reqwest::Url;
+    use std::fmt::Debug;
+
+    /// The URL of [`Opencage::forward_full_with`](../../struct.Opencage.html#method.forward_full_with),
+    /// or of `forward_full` with the instance's parameters
+    pub fn build_forward_request<T, U>(
+        oc: &Opencage,
+        place: &str,
+        bounds: U,
+        parameters: &Parameters,
+    ) -> Result<Url, GeocodingError>
+    where
+        T: Float + Debug,
+        U: Into<Option<InputBounds<T>>>,
+    {
+        oc.validate()?;
+        url(oc.search_request(place, bounds, parameters))
+    }
+
+    /// The URL of [`Opencage::reverse_full_with`](../../struct.Opencage.html#method.reverse_full_with),
+    /// or of `reverse_full` with the instance's parameters
+    pub fn build_reverse_request<T>(
+        oc: &Opencage,
+        point: &Point<T>,
+        parameters: &Parameters,
+    ) -> Result<Url, GeocodingError>
+    where
+        T: Float + Debug,
+    {
+        oc.validate()?;
+        url(oc.reverse_request(point, parameters))
+    }
+}
+
+/// The URLs of [`GeoAdmin`](../../struct.GeoAdmin.html) requests
+#[cfg(feature = "geoadmin")]
+pub mod geoadmin {
+    use super::url;
+    use crate::geoadmin::{GeoAdminParams, GeoAdminReverseParams};
+    use crate::{GeoAdmin, GeocodingError, Point};
+    use num_traits::Float;
+    use reqwest::Url;
+    use std::fmt::Debug;
+
+    /// The URL of [`GeoAdmin::forward_full`](../../struct.GeoAdmin.html#method.forward_full)
+    pub fn build_forward_request<T>(
+        geoadmin: &GeoAdmin,
+        params: &GeoAdminParams<T>,
+    ) -> Result<Url, GeocodingError>
+    where
+        T: Float + Debug,
+    {
+        geoadmin.validate()?;
+        url(geoadmin.search_request(params))
+    }
+
+    /// The URL of [`GeoAdmin::reverse_full`](../../struct.GeoAdmin.html#method.reverse_full)
+    pub fn build_reverse_request<T>(
+        geoadmin: &GeoAdmin,
+        point: &Point<T>,
+        params: &GeoAdminReverseParams<T>,
+    ) -> Result<Url, GeocodingError>
+    where
+        T: Float + Debug,
+    {
+        geoadmin.validate()?;
+        url(geoadmin.identify_request(point, params))
+    }
+}
+
+/// The URL of a request, failing if it's invalid, e.g. with a malformed endpoint
+fn url(req: RequestBuilder) -> Result<Url, GeocodingError> {
+    Ok(req.build()?.url().clone())
+}
+
+#[cfg(all(test, feature = "opencage", feature = "osm", feature = "geoadmin"))]
+mod test {
+    use super::*;
+    use crate::geoadmin::{GeoAdminParams, GeoAdminReverseParams, Origin};
+    use crate::opencage::{Parameters, NOBOX};
+    use crate::openstreetmap::{
+        FeatureType, Layer, OpenstreetmapParams, OpenstreetmapReverseParams,
+    };
+    use crate::{
+        Address, CountryCode, GeoAdmin, InputBounds, Opencage, Openstreetmap, Point,
+        PrivacyControl, PrivacyMode, ReverseSnapping,
+    };
+
+    /// The query of `url`, as pairs
+    fn query(url: &Url) -> Vec<(String, String)> {
+        url.query_pairs().into_owned().collect()
+    }
+
+    fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn openstreetmap_forward_test() {
+        let osm = Openstreetmap::new_with_endpoint("https://nominatim.example.com/".to_string())
+            .with_email("ops@example.com");
+        let params = OpenstreetmapParams::new("Calatrava")
+            .with_addressdetails(true)
+            .with_viewbox(InputBounds::new((2.1, 41.3), (2.2, 41.5)))
+            .with_bounded(true)
+            .with_exclude_place_ids(&[1, 2])
+            .with_layers(&[Layer::Address, Layer::Poi])
+            .with_feature_type(FeatureType::City)
+            .with_countrycodes(&["ES".parse::<CountryCode>().unwrap()])
+            .with_accept_language("ca,es")
+            .with_extra_params(&[("dedupe", "0"), ("format", "json")])
+            .build();
+        let url = openstreetmap::build_forward_request(&osm, &params).unwrap();
+        assert_eq!(url.path(), "/search");
+        assert_eq!(
+            query(&url),
+            pairs(&[
+                ("email", "ops@example.com"),
+                ("format", "geojson"),
+                ("addressdetails", "1"),
+                ("q", "Calatrava"),
+                ("viewbox", "2.1,41.3,2.2,41.5"),
+                ("bounded", "1"),
+                ("exclude_place_ids", "1,2"),
+                ("layer", "address,poi"),
+                ("featureType", "city"),
+                ("countrycodes", "es"),
+                ("accept-language", "ca,es"),
+                ("dedupe", "0"),
+            ])
+        );
+
+        let address = Address {
+            house_number: Some("68".to_string()),
+            road: Some("Carrer de Calatrava".to_string()),
+            city: Some("Barcelona".to_string()),
+            postcode: Some("08017".to_string()),
+            ..Address::default()
+        };
+        let params = OpenstreetmapParams::<f64>::new("")
+            .with_structured_address(address)
+            .build();
+        let url = openstreetmap::build_forward_request(&osm, &params).unwrap();
+        assert_eq!(
+            query(&url)[3..],
+            pairs(&[
+                ("street", "68 Carrer de Calatrava"),
+                ("city", "Barcelona"),
+                ("postalcode", "08017"),
+            ])[..]
+        );
+    }
+
+    #[test]
+    fn openstreetmap_reverse_test() {
+        let osm = Openstreetmap::new().with_precision(4);
+        let params = OpenstreetmapReverseParams::new()
+            .with_addressdetails(true)
+            .with_namedetails(true)
+            .with_snapping(ReverseSnapping::Locality)
+            .with_extra_params(&[("polygon_text", "1")])
+            .build();
+        let url =
+            openstreetmap::build_reverse_request(&osm, &Point::new(2.128701, 41.401392), &params)
+                .unwrap();
+        assert_eq!(url.path(), "/reverse");
+        assert_eq!(
+            query(&url),
+            pairs(&[
+                ("lon", "2.1287"),
+                ("lat", "41.4014"),
+                ("format", "geojson"),
+                ("addressdetails", "1"),
+                ("extratags", "0"),
+                ("namedetails", "1"),
+                ("zoom", "10"),
+                ("layer", "address"),
+                ("polygon_text", "1"),
+            ])
+        );
+    }
+
+    #[test]
+    fn opencage_test() {
+        let oc = Opencage::new("0123".to_string());
+        let parameters = Parameters {
+            language: Some("fr"),
+            countrycode: Some("es"),
+            limit: Some(2),
+            snapping: Some(ReverseSnapping::Road),
+            extra_params: &[("roadinfo", "0"), ("abbrv", "1")],
+            ..Parameters::default()
+        };
+        let bounds = InputBounds::new((2.1, 41.3), (2.2, 41.5));
+        let url = opencage::build_forward_request(&oc, "Calatrava", bounds, &parameters).unwrap();
+        assert_eq!(url.path(), "/geocode/v1/json");
+        assert_eq!(
+            query(&url),
+            pairs(&[
+                ("q", "Calatrava"),
+                ("key", "0123"),
+                ("no_annotations", "0"),
+                ("bounds", "2.1,41.3,2.2,41.5"),
+                ("language", "fr"),
+                ("countrycode", "es"),
+                ("limit", "2"),
+                ("no_record", "1"),
+                // Snapping only applies to reverse lookups
+                ("roadinfo", "0"),
+                ("abbrv", "1"),
+            ])
+        );
+
+        let oc = oc
+            .with_privacy_mode(PrivacyMode::Standard)
+            .with_precision(3);
+        let url =
+            opencage::build_reverse_request(&oc, &Point::new(2.128701, 41.401392), &parameters)
+                .unwrap();
+        assert_eq!(
+            query(&url),
+            pairs(&[
+                ("q", "41.401, 2.129"),
+                ("key", "0123"),
+                ("no_annotations", "0"),
+                ("language", "fr"),
+                ("countrycode", "es"),
+                ("limit", "2"),
+                ("roadinfo", "1"),
+                ("abbrv", "1"),
+            ])
+        );
+        let url =
+            opencage::build_forward_request(&oc, "Calatrava", NOBOX, oc.parameters()).unwrap();
+        assert_eq!(
+            query(&url),
+            pairs(&[("q", "Calatrava"), ("key", "0123"), ("no_annotations", "0")])
+        );
+    }
+
+    #[test]
+    fn geoadmin_forward_test() {
+        let geoadmin = GeoAdmin::builder().with_sr("2056").build();
+        let params = GeoAdminParams::new("Seftigenstrasse Bern")
+            .with_origins(&[Origin::Address, Origin::Zipcode])
+            .with_bbox(InputBounds::new(
+                (2_600_967.75, 1_197_426.0),
+                (2_600_969.75, 1_197_428.0),
+            ))
+            .with_limit(5)
+            .with_language("fr")
+            .with_extra_params(&[("timeEnabled", "false")])
+            .build();
+        let url = geoadmin::build_forward_request(&geoadmin, &params).unwrap();
+        assert_eq!(url.path(), "/rest/services/api/SearchServer");
+        assert_eq!(
+            query(&url),
+            pairs(&[
+                ("searchText", "Seftigenstrasse Bern"),
+                ("type", "locations"),
+                ("origins", "address,zipcode"),
+                ("sr", "2056"),
+                ("geometryFormat", "geojson"),
+                ("bbox", "2600967.75,1197426,2600969.75,1197428"),
+                ("limit", "5"),
+                ("lang", "fr"),
+                ("timeEnabled", "false"),
+            ])
+        );
+    }
+
+    #[test]
+    fn geoadmin_reverse_test() {
+        let geoadmin = GeoAdmin::new();
+        let params = GeoAdminReverseParams::new()
+            .with_snapping(ReverseSnapping::Road)
+            .with_tolerance(10)
+            .with_return_geometry(true)
+            .build();
+        let url = geoadmin::build_reverse_request(&geoadmin, &Point::new(7.4513, 46.9279), &params)
+            .unwrap();
+        assert_eq!(url.path(), "/rest/services/api/MapServer/identify");
+        assert_eq!(
+            query(&url),
+            pairs(&[
+                ("geometry", "7.4513,46.9279"),
+                ("geometryType", "esriGeometryPoint"),
+                ("layers", "all:ch.swisstopo.amtliches-strassenverzeichnis"),
+                ("mapExtent", "0,0,100,100"),
+                ("imageDisplay", "100,100,100"),
+                ("tolerance", "10"),
+                ("returnGeometry", "true"),
+                ("geometryFormat", "geojson"),
+                ("sr", "4326"),
+                ("lang", "en"),
+            ])
+        );
+    }
+
+    #[test]
+    fn invalid_endpoint_test() {
+        let osm = Openstreetmap::new_with_endpoint("not a url/".to_string());
+        let params = OpenstreetmapParams::<f64>::new("Calatrava").build();
+        assert!(openstreetmap::build_forward_request(&osm, &params).is_err());
+    }
+}
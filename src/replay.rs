@@ -0,0 +1,307 @@
+//! Replay of recorded provider responses.
+//!
+//! With the `replay` feature, requests sent by the providers are first looked up in a set of
+//! [`Cassette`]s, and answered with the recorded response when one matches their method and
+//! URL. Requests without a recorded response are sent as usual.
+//!
+//! Cassettes are JSON files holding a list of interactions. The cassettes of the directory
+//! named by the `GEOCODING_CASSETTES` environment variable are loaded on the first request,
+//! and more can be added with [`insert`]. When `GEOCODING_RECORD` is set as well, the
+//! responses to requests without a recorded response are appended to the `recorded.json`
+//! cassette of that directory.
+//!
+//! The crate's own tests, doctests and examples run through the cassettes in `tests/cassettes`,
+//! so that `cargo test` works offline and doesn't use the quota of the providers. The
+//! `online-tests` feature skips loading them, sending the requests to the providers instead;
+//! run `GEOCODING_RECORD=1 cargo test --features online-tests` to record fresh responses.
+//!
+//! URLs are matched after redacting their credentials, as in the [audit log](../audit/index.html),
+//! so cassettes never contain API keys and match whichever key is used.
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::replay::{self, Cassette};
+//! use geocoding::{Forward, GeoAdmin, Point};
+//!
+//! let cassette = Cassette::from_json_str(r#"{
+//!   "interactions": [{
+//!     "method": "GET",
+//!     "url": "https://api3.geo.admin.ch/rest/services/api/SearchServer?searchText=Bundesplatz+3&type=locations&origins=address&limit=1&sr=4326&geometryFormat=geojson",
+//!     "status": 200,
+//!     "headers": { "content-type": "application/json" },
+//!     "body": { "type": "FeatureCollection", "features": [] }
+//!   }]
+//! }"#).unwrap();
+//! replay::insert(cassette);
+//!
+//! let geoadmin = GeoAdmin::new();
+//! let res: Vec<Point<f64>> = geoadmin.forward("Bundesplatz 3").unwrap();
+//! assert!(res.is_empty());
+//! ```
+use crate::audit;
+use crate::GeocodingError;
+use reqwest::blocking::{RequestBuilder, Response};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{ResponseBuilderExt, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// The environment variable naming the directory of the cassettes loaded on the first request
+pub const CASSETTES_ENV: &str = "GEOCODING_CASSETTES";
+
+/// The environment variable enabling the recording of missing responses
+pub const RECORD_ENV: &str = "GEOCODING_RECORD";
+
+/// The cassette of the cassette directory to which responses are recorded
+pub const RECORDED_CASSETTE: &str = "recorded.json";
+
+static INTERACTIONS: OnceLock<Mutex<Vec<Interaction>>> = OnceLock::new();
+
+/// A request and its recorded response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    /// The HTTP method of the request
+    pub method: String,
+    /// The URL of the request, with credentials redacted
+    pub url: String,
+    /// The HTTP status of the response
+    pub status: u16,
+    /// The headers of the response
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// The body of the response: strings are sent as they are, other values as JSON
+    pub body: serde_json::Value,
+}
+
+impl Interaction {
+    /// Whether this interaction records a request with `method` to `url`
+    pub fn matches(&self, method: &str, url: &Url) -> bool {
+        self.method.eq_ignore_ascii_case(method) && self.url == audit::redact(url, &[])
+    }
+
+    /// The body of the recorded response
+    pub fn body_bytes(&self) -> Vec<u8> {
+        match &self.body {
+            serde_json::Value::String(body) => body.clone().into_bytes(),
+            body => body.to_string().into_bytes(),
+        }
+    }
+
+    /// The recorded response, as a response to a request to `url`
+    fn response(&self, url: Url) -> Response {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                headers.insert(name, value);
+            }
+        }
+        let mut response = hyper::Response::builder()
+            .status(StatusCode::from_u16(self.status).unwrap_or(StatusCode::OK))
+            .url(url)
+            .body(self.body_bytes())
+            .expect("a response with a valid status is valid");
+        *response.headers_mut() = headers;
+        Response::from(response)
+    }
+}
+
+/// A list of recorded interactions with providers
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cassette {
+    /// The recorded interactions
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    /// Parse a cassette from its JSON representation
+    pub fn from_json_str(json: &str) -> Result<Self, GeocodingError> {
+        serde_json::from_str(json).map_err(GeocodingError::Json)
+    }
+
+    /// Read a cassette from a JSON file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, GeocodingError> {
+        Self::from_json_str(&fs::read_to_string(path)?)
+    }
+
+    /// Read all the cassettes of a directory, i.e. its files with the `json` extension
+    pub fn load_dir<P: AsRef<Path>>(dir: P) -> Result<Vec<Self>, GeocodingError> {
+        let mut paths = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.retain(|path| path.extension().is_some_and(|ext| ext == "json"));
+        paths.sort();
+        paths.iter().map(Self::load).collect()
+    }
+
+    /// Write the cassette to a JSON file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), GeocodingError> {
+        let json = serde_json::to_string_pretty(self).map_err(GeocodingError::Json)?;
+        fs::write(path, json + "\n")?;
+        Ok(())
+    }
+
+    /// The interaction recording a request with `method` to `url`
+    pub fn find(&self, method: &str, url: &Url) -> Option<&Interaction> {
+        self.interactions
+            .iter()
+            .find(|interaction| interaction.matches(method, url))
+    }
+}
+
+/// Add the interactions of a cassette to the responses replayed to all providers
+///
+/// Interactions added later take precedence over earlier ones for the same request.
+pub fn insert(cassette: Cassette) {
+    interactions().lock().unwrap().extend(cassette.interactions);
+}
+
+/// The directory of the cassettes named by `GEOCODING_CASSETTES`
+fn cassette_dir() -> Option<PathBuf> {
+    std::env::var_os(CASSETTES_ENV).map(PathBuf::from)
+}
+
+fn interactions() -> &'static Mutex<Vec<Interaction>> {
+    INTERACTIONS.get_or_init(|| {
+        let cassettes = match cassette_dir() {
+            Some(_) if cfg!(feature = "online-tests") => Vec::new(),
+            Some(dir) => Cassette::load_dir(&dir).unwrap_or_else(|e| {
+                panic!("failed to load the cassettes of {}: {}", dir.display(), e)
+            }),
+            None => Vec::new(),
+        };
+        Mutex::new(
+            cassettes
+                .into_iter()
+                .flat_map(|cassette| cassette.interactions)
+                .collect(),
+        )
+    })
+}
+
+/// Answer a request from the cassettes, or send it and record the response when recording
+pub(crate) fn send(req: RequestBuilder) -> Result<Response, reqwest::Error> {
+    // Requests with streaming bodies can't be cloned, and are always sent
+    let request = match req.try_clone().and_then(|req| req.build().ok()) {
+        Some(request) => request,
+        None => return req.send(),
+    };
+    let method = request.method().as_str();
+    let url = request.url();
+    let replayed = interactions()
+        .lock()
+        .unwrap()
+        .iter()
+        .rev()
+        .find(|interaction| interaction.matches(method, url))
+        .map(|interaction| interaction.response(url.clone()));
+    if let Some(resp) = replayed {
+        return Ok(resp);
+    }
+
+    let resp = req.send()?;
+    let dir = match cassette_dir() {
+        Some(dir) if std::env::var_os(RECORD_ENV).is_some() => dir,
+        _ => return Ok(resp),
+    };
+    let headers = resp
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+    let status = resp.status().as_u16();
+    let (resp, body) = crate::request::buffer(resp)?;
+    let body = match serde_json::from_slice(&body) {
+        Ok(json) => json,
+        Err(_) => serde_json::Value::String(String::from_utf8_lossy(&body).into_owned()),
+    };
+    let interaction = Interaction {
+        method: method.to_string(),
+        url: audit::redact(url, &[]),
+        status,
+        headers,
+        body,
+    };
+    record(&dir.join(RECORDED_CASSETTE), interaction.clone());
+    interactions().lock().unwrap().push(interaction);
+    Ok(resp)
+}
+
+/// Append an interaction to the cassette at `path`
+fn record(path: &Path, interaction: Interaction) {
+    let mut cassette = Cassette::load(path).unwrap_or_default();
+    cassette.interactions.push(interaction);
+    if let Err(e) = cassette.save(path) {
+        panic!("failed to record to {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use reqwest::blocking::Client;
+
+    fn cassette(url: &str, body: serde_json::Value) -> Cassette {
+        Cassette {
+            interactions: vec![Interaction {
+                method: "GET".to_string(),
+                url: url.to_string(),
+                status: 200,
+                headers: BTreeMap::from([(
+                    "content-type".to_string(),
+                    "application/json".to_string(),
+                )]),
+                body,
+            }],
+        }
+    }
+
+    #[test]
+    fn matches_redacted_url_test() {
+        let cassette = cassette(
+            "https://api.opencagedata.com/geocode/v1/json?q=Berlin&key=REDACTED",
+            serde_json::json!({}),
+        );
+        let url =
+            Url::parse("https://api.opencagedata.com/geocode/v1/json?q=Berlin&key=secret").unwrap();
+        assert!(cassette.find("GET", &url).is_some());
+        assert!(cassette.find("POST", &url).is_none());
+        let other =
+            Url::parse("https://api.opencagedata.com/geocode/v1/json?q=Bern&key=secret").unwrap();
+        assert!(cassette.find("GET", &other).is_none());
+    }
+
+    #[test]
+    fn replay_test() {
+        let url = "http://replay.invalid/search?q=Wabern";
+        insert(cassette(url, serde_json::json!({ "label": "Wabern" })));
+        let resp = send(Client::new().get(url)).unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.url().as_str(), url);
+        assert_eq!(resp.headers()["content-type"], "application/json");
+        assert_eq!(resp.text().unwrap(), r#"{"label":"Wabern"}"#);
+    }
+
+    #[test]
+    fn text_body_test() {
+        let cassette = cassette("http://replay.invalid/", serde_json::json!("plain text"));
+        assert_eq!(cassette.interactions[0].body_bytes(), b"plain text");
+    }
+
+    #[test]
+    fn bundled_cassettes_test() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/cassettes");
+        let cassettes = Cassette::load_dir(dir).unwrap();
+        assert!(!cassettes.is_empty());
+        for interaction in cassettes.iter().flat_map(|c| &c.interactions) {
+            let url = Url::parse(&interaction.url).unwrap();
+            assert_eq!(audit::redact(&url, &[]), interaction.url);
+        }
+    }
+}
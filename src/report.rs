@@ -0,0 +1,259 @@
+//! Human-readable tables of results.
+//!
+//! A [`Report`] renders results as a compact table with their rank, formatted address,
+//! coordinates and confidence, for quick inspection in a terminal or a log. Results and the
+//! full responses of the providers implement [`DisplayReport`], whose
+//! [`report`](trait.DisplayReport.html#tymethod.report) builds the table.
+//!
+//! The confidence of [`GeocodeResult`](../struct.GeocodeResult.html)s is their match score,
+//! while the confidence of full responses is the provider's own: the `confidence` of OpenCage
+//! results, from 1 to 10, and the `importance` of Nominatim results.
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::openstreetmap::{OpenstreetmapParams, OpenstreetmapResponse};
+//! use geocoding::report::DisplayReport;
+//! use geocoding::Openstreetmap;
+//!
+//! let osm = Openstreetmap::new();
+//! let params = OpenstreetmapParams::new("Carrer de Calatrava, Barcelona").build();
+//! let res: OpenstreetmapResponse<f64> = osm.forward_full(&params).unwrap();
+//! println!("{}", res.report());
+//! ```
+use crate::GeocodeResult;
+use num_traits::Float;
+use std::fmt::{self, Debug};
+
+/// The maximum length in characters of the formatted addresses of a report
+const FORMATTED_LENGTH: usize = 48;
+
+/// A result in a [`Report`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReportRow {
+    /// The rank of the result, from 1
+    pub rank: usize,
+    pub formatted: String,
+    pub lon: f64,
+    pub lat: f64,
+    pub confidence: Option<f64>,
+}
+
+/// A table of results, rendered by its `Display` implementation
+///
+/// Formatted addresses longer than 48 characters are cut, ending with `…`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    pub rows: Vec<ReportRow>,
+}
+
+impl Report {
+    /// Create a report of results, ranked in their order, given their formatted address,
+    /// point and confidence
+    pub fn new<T, I>(results: I) -> Self
+    where
+        T: Float,
+        I: IntoIterator<Item = (String, T, T, Option<f64>)>,
+    {
+        let rows = results
+            .into_iter()
+            .enumerate()
+            .map(|(index, (formatted, lon, lat, confidence))| ReportRow {
+                rank: index + 1,
+                formatted,
+                lon: lon.to_f64().unwrap_or(f64::NAN),
+                lat: lat.to_f64().unwrap_or(f64::NAN),
+                confidence,
+            })
+            .collect();
+        Report { rows }
+    }
+}
+
+/// `formatted`, cut to `FORMATTED_LENGTH` characters
+fn cut(formatted: &str) -> String {
+    if formatted.chars().count() <= FORMATTED_LENGTH {
+        return formatted.to_string();
+    }
+    let mut cut: String = formatted.chars().take(FORMATTED_LENGTH - 1).collect();
+    cut.push('…');
+    cut
+}
+
+impl fmt::Display for Report {
+    /// One line per result after a header line, without a trailing newline
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rows: Vec<(String, String, String, String, String)> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let confidence = match row.confidence {
+                    Some(confidence) => format!("{:.2}", confidence),
+                    None => "-".to_string(),
+                };
+                (
+                    row.rank.to_string(),
+                    cut(&row.formatted),
+                    format!("{:.6}", row.lon),
+                    format!("{:.6}", row.lat),
+                    confidence,
+                )
+            })
+            .collect();
+        let header = (
+            "#".to_string(),
+            "formatted".to_string(),
+            "lon".to_string(),
+            "lat".to_string(),
+            "confidence".to_string(),
+        );
+        let width = |column: fn(&(String, String, String, String, String)) -> &String| {
+            rows.iter()
+                .chain(Some(&header))
+                .map(|row| column(row).chars().count())
+                .max()
+                .unwrap_or(0)
+        };
+        let widths = (
+            width(|row| &row.0),
+            width(|row| &row.1),
+            width(|row| &row.2),
+            width(|row| &row.3),
+            width(|row| &row.4),
+        );
+        for (index, row) in Some(&header).into_iter().chain(&rows).enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            // The formatted address is padded by hand, as `…` is wider than a byte
+            let padding = widths.1 - row.1.chars().count();
+            write!(
+                f,
+                "{:<w0$}  {}{:padding$}  {:>w2$}  {:>w3$}  {:>w4$}",
+                row.0,
+                row.1,
+                "",
+                row.2,
+                row.3,
+                row.4,
+                w0 = widths.0,
+                padding = padding,
+                w2 = widths.2,
+                w3 = widths.3,
+                w4 = widths.4,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Results which can be rendered as a [`Report`]
+pub trait DisplayReport {
+    /// A table of the results
+    fn report(&self) -> Report;
+}
+
+impl<T> DisplayReport for [GeocodeResult<T>]
+where
+    T: Float + Debug,
+{
+    fn report(&self) -> Report {
+        Report::new(self.iter().map(|res| {
+            (
+                res.label.clone(),
+                res.point.x(),
+                res.point.y(),
+                res.match_score,
+            )
+        }))
+    }
+}
+
+impl<T> DisplayReport for Vec<GeocodeResult<T>>
+where
+    T: Float + Debug,
+{
+    fn report(&self) -> Report {
+        self.as_slice().report()
+    }
+}
+
+#[cfg(feature = "opencage")]
+impl<T> DisplayReport for crate::opencage::OpencageResponse<T>
+where
+    T: Float + Debug,
+{
+    /// Results without a point are skipped
+    fn report(&self) -> Report {
+        Report::new(self.results.iter().filter_map(|res| {
+            let point = res.point().ok()?;
+            let confidence = Some(f64::from(res.confidence));
+            Some((res.formatted.clone(), point.x(), point.y(), confidence))
+        }))
+    }
+}
+
+#[cfg(feature = "osm")]
+impl<T> DisplayReport for crate::openstreetmap::OpenstreetmapResponse<T>
+where
+    T: Float + Debug,
+{
+    fn report(&self) -> Report {
+        Report::new(self.features.iter().map(|res| {
+            let point = GeocodeResult::from(res).point;
+            let confidence = Some(res.properties.importance);
+            (
+                res.properties.display_name.clone(),
+                point.x(),
+                point.y(),
+                confidence,
+            )
+        }))
+    }
+}
+
+#[cfg(feature = "geoadmin")]
+impl<T> DisplayReport for crate::geoadmin::GeoAdminForwardResponse<T>
+where
+    T: Float + Debug,
+{
+    /// GeoAdmin has no confidence, and ranks its results by their order. The coordinates are
+    /// the WGS84 `lon` and `lat` of the results, whatever the sr of the request.
+    fn report(&self) -> Report {
+        Report::new(self.features.iter().map(|location| {
+            let res = GeocodeResult::from(location);
+            let properties = &location.properties;
+            (res.label, properties.lon, properties.lat, None)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Point;
+
+    #[test]
+    fn report_test() {
+        let mut first = GeocodeResult::new(
+            Point::new(2.1287, 41.40139),
+            "68, Carrer de Calatrava, les Tres Torres, Sarrià - Sant Gervasi, Barcelona"
+                .to_string(),
+        );
+        first.match_score = Some(0.9);
+        let second = GeocodeResult::new(Point::new(-3.7038, 40.4168), "Madrid".to_string());
+        let expected = [
+            "#  formatted                                               lon        lat  confidence",
+            "1  68, Carrer de Calatrava, les Tres Torres, Sarri…   2.128700  41.401390        0.90",
+            "2  Madrid                                            -3.703800  40.416800           -",
+        ];
+        assert_eq!(
+            vec![first, second].report().to_string(),
+            expected.join("\n")
+        );
+        assert_eq!(
+            Vec::<GeocodeResult<f64>>::new().report().to_string(),
+            "#  formatted  lon  lat  confidence"
+        );
+    }
+}
@@ -0,0 +1,538 @@
+//! Sending of provider requests through the throttle, audit, failover and POST hooks,
+//! counting them in the usage statistics of the provider and capturing their responses for
+//! lineage records.
+use crate::audit::{self, AuditRecord, AuditSink};
+use crate::failover::Failover;
+use crate::lineage::LineageResponse;
+use crate::stats::UsageCounters;
+use crate::throttle::{host, Throttle};
+#[cfg(any(feature = "opencage", feature = "osm", feature = "geoadmin"))]
+use crate::ResponseMeta;
+use crate::{GeocodingError, Sent};
+use chrono::Utc;
+use hyper::body::Bytes;
+use reqwest::blocking::{Client, Request, RequestBuilder, Response};
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use reqwest::{Method, ResponseBuilderExt, StatusCode, Url, Version};
+#[cfg(any(feature = "opencage", feature = "osm"))]
+use serde::de::DeserializeOwned;
+use std::cell::RefCell;
+use std::io::Read;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The header carrying the request id set with [`with_request_id`](fn.with_request_id.html)
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+thread_local! {
+    static REQUEST_ID: RefCell<Option<String>> = const { RefCell::new(None) };
+    static CAPTURED: RefCell<Option<Vec<LineageResponse>>> = const { RefCell::new(None) };
+}
+
+/// Run `f`, attaching `id` to every request it sends on the current thread
+///
+/// The id is sent in the [`REQUEST_ID_HEADER`](constant.REQUEST_ID_HEADER.html) header and
+/// recorded in the `request_id` of [`AuditRecord`](audit/struct.AuditRecord.html)s, so that the
+/// requests of an operation, including its retries, can be correlated across logs. Requests
+/// sent on other threads, e.g. by a parallel batch, don't carry the id. Calls can be nested,
+/// the innermost id applying.
+///
+/// # Examples
+///
+/// ```
+/// use geocoding::{with_request_id, Forward, Openstreetmap, Point};
+///
+/// let osm = Openstreetmap::new();
+/// let res: Vec<Point<f64>> =
+///     with_request_id("order-1234", || osm.forward("Schwabing, München")).unwrap();
+/// ```
+pub fn with_request_id<R>(id: &str, f: impl FnOnce() -> R) -> R {
+    /// Restores the previous id, even if `f` panics
+    struct Reset(Option<String>);
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            REQUEST_ID.with(|current| *current.borrow_mut() = self.0.take());
+        }
+    }
+
+    let _reset = Reset(REQUEST_ID.with(|current| current.replace(Some(id.to_string()))));
+    f()
+}
+
+/// The request id attached to the requests sent on the current thread, if any
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.with(|current| current.borrow().clone())
+}
+
+/// Run `f`, also returning the responses received by the requests it sends on the current
+/// thread
+///
+/// Calls can be nested, the responses of inner calls being returned by the outer ones too.
+pub(crate) fn capture<R>(f: impl FnOnce() -> R) -> (R, Vec<LineageResponse>) {
+    /// Restores the previous capture, even if `f` panics
+    struct Reset(Option<Vec<LineageResponse>>);
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            CAPTURED.with(|captured| *captured.borrow_mut() = self.0.take());
+        }
+    }
+
+    let mut reset = Reset(CAPTURED.with(|captured| captured.replace(Some(Vec::new()))));
+    let res = f();
+    let responses = CAPTURED
+        .with(|captured| captured.borrow_mut().take())
+        .unwrap_or_default();
+    if let Some(outer) = &mut reset.0 {
+        outer.extend(responses.iter().cloned());
+    }
+    (res, responses)
+}
+
+/// The hooks applied to each request sent by a provider
+#[derive(Clone, Default)]
+pub(crate) struct RequestHooks {
+    /// The throttle spacing the requests to the endpoint's host
+    pub(crate) throttle: Option<Arc<Throttle>>,
+    /// The sink recording each request
+    pub(crate) audit: Option<Arc<dyn AuditSink>>,
+    /// Query parameters redacted from audited URLs in addition to the usual credentials
+    pub(crate) redacted_params: Vec<String>,
+    /// The endpoints requests fail over to
+    pub(crate) failover: Option<Arc<Failover>>,
+    /// The length of the encoded query above which GET requests are sent as POST requests
+    pub(crate) max_query_length: Option<usize>,
+    /// The size in bytes above which reading a response body fails
+    pub(crate) max_response_size: Option<usize>,
+    /// The number of results above which a response fails
+    pub(crate) max_results: Option<usize>,
+    /// The client of the provider, sending the requests rebuilt by the hooks
+    pub(crate) client: Option<Client>,
+    /// The counters of the requests sent
+    pub(crate) usage: Arc<UsageCounters>,
+}
+
+impl RequestHooks {
+    /// Create hooks rebuilding requests with `client`
+    #[cfg(any(feature = "opencage", feature = "osm"))]
+    pub(crate) fn with_client(client: &Client) -> Self {
+        RequestHooks {
+            client: Some(client.clone()),
+            ..RequestHooks::default()
+        }
+    }
+
+    /// Rebuild requests with `client`, after the provider's client changed
+    #[cfg(any(feature = "opencage", feature = "osm", feature = "geoadmin"))]
+    pub(crate) fn set_client(&mut self, client: &Client) {
+        self.client = Some(client.clone());
+    }
+}
+
+/// Sending of requests through a provider's hooks
+pub(crate) trait SendHooked {
+    /// Send the request once the throttle allows a request to the host of `endpoint`,
+    /// adapt the throttle to the response, and audit the request
+    fn send_hooked(self, hooks: &RequestHooks, endpoint: &str) -> Result<Response, GeocodingError>;
+
+    /// Send the request like `send_hooked`, also returning the details of the response
+    #[cfg(any(feature = "opencage", feature = "osm", feature = "geoadmin"))]
+    fn send_with_meta(
+        self,
+        hooks: &RequestHooks,
+        endpoint: &str,
+    ) -> Result<(Response, ResponseMeta), GeocodingError>;
+}
+
+impl SendHooked for RequestBuilder {
+    fn send_hooked(self, hooks: &RequestHooks, endpoint: &str) -> Result<Response, GeocodingError> {
+        send(self, hooks, endpoint).map(|(resp, _)| resp)
+    }
+
+    #[cfg(any(feature = "opencage", feature = "osm", feature = "geoadmin"))]
+    fn send_with_meta(
+        self,
+        hooks: &RequestHooks,
+        endpoint: &str,
+    ) -> Result<(Response, ResponseMeta), GeocodingError> {
+        let (resp, sent) = send(self, hooks, endpoint)?;
+        let meta = ResponseMeta::new(resp.status(), resp.headers(), sent);
+        Ok((resp, meta))
+    }
+}
+
+/// Parse the JSON body of a response like `from_json_body`
+#[cfg(any(feature = "opencage", feature = "osm"))]
+pub(crate) fn json<R>(resp: Response) -> Result<R, GeocodingError>
+where
+    R: DeserializeOwned,
+{
+    crate::from_json_body(resp.bytes()?)
+}
+
+/// Send a request through `hooks`, as a POST request if its query is too long, failing over
+/// to the next endpoint when it fails, and returning the response and how it was sent
+fn send(
+    req: RequestBuilder,
+    hooks: &RequestHooks,
+    endpoint: &str,
+) -> Result<(Response, Sent), GeocodingError> {
+    let started = Instant::now();
+    let sent = |attempts: u32, endpoint: &str, (resp, duration): (Response, Duration)| {
+        let sent = Sent {
+            duration,
+            elapsed: started.elapsed(),
+            attempts,
+            endpoint: endpoint.to_string(),
+        };
+        (resp, sent)
+    };
+    let client = match &hooks.client {
+        Some(client) => client,
+        None => return send_once(req, hooks, endpoint).map(|resp| sent(1, endpoint, resp)),
+    };
+    let req = match hooks.max_query_length {
+        Some(max) => post_long_query(client, req, max),
+        None => req,
+    };
+    let failover = match &hooks.failover {
+        Some(failover) => failover,
+        None => return send_once(req, hooks, endpoint).map(|resp| sent(1, endpoint, resp)),
+    };
+    // Requests with streaming bodies can't be sent twice
+    let request = match req.try_clone().and_then(|req| req.build().ok()) {
+        Some(request) => request,
+        None => return send_once(req, hooks, endpoint).map(|resp| sent(1, endpoint, resp)),
+    };
+    let path = match request.url().as_str().strip_prefix(endpoint) {
+        Some(path) => path.to_string(),
+        None => return send_once(req, hooks, endpoint).map(|resp| sent(1, endpoint, resp)),
+    };
+    let candidates = failover.candidates();
+    let mut attempts = 0;
+    let mut result = None;
+    for candidate in &candidates {
+        let url = match Url::parse(&format!("{}{}", candidate, path)) {
+            Ok(url) => url,
+            Err(_) => continue,
+        };
+        let req = rebuild(client, &request, request.method().clone(), url);
+        attempts += 1;
+        let attempt = send_once(req, hooks, candidate);
+        // Responses over the limits were sent by a working endpoint
+        let success = match &attempt {
+            Ok((resp, _)) => !resp.status().is_server_error(),
+            Err(GeocodingError::ResponseTooLarge { .. })
+            | Err(GeocodingError::TooManyResults { .. }) => true,
+            Err(_) => false,
+        };
+        failover.record(candidate, success);
+        if success {
+            return attempt.map(|resp| sent(attempts, candidate, resp));
+        }
+        result = Some(attempt.map(|resp| sent(attempts, candidate, resp)));
+    }
+    // Without a valid endpoint, the request is sent to the provider's own
+    result.unwrap_or_else(|| send_once(req, hooks, endpoint).map(|resp| sent(1, endpoint, resp)))
+}
+
+/// Convert a GET request whose encoded query is longer than `max` into a POST request,
+/// sending the query as a form
+fn post_long_query(client: &Client, req: RequestBuilder, max: usize) -> RequestBuilder {
+    let request = match req.try_clone().and_then(|req| req.build().ok()) {
+        Some(request) => request,
+        None => return req,
+    };
+    let query = match request.url().query() {
+        Some(query) if request.method() == Method::GET && query.len() > max => query.to_string(),
+        _ => return req,
+    };
+    let mut url = request.url().clone();
+    url.set_query(None);
+    rebuild(client, &request, Method::POST, url)
+        .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(query)
+}
+
+/// A request like `request`, with another method and URL, to be sent with `client`
+pub(crate) fn rebuild(
+    client: &Client,
+    request: &Request,
+    method: Method,
+    url: Url,
+) -> RequestBuilder {
+    let mut req = client
+        .request(method, url)
+        .headers(request.headers().clone())
+        .version(request.version());
+    if let Some(timeout) = request.timeout() {
+        req = req.timeout(*timeout);
+    }
+    if let Some(body) = request
+        .try_clone()
+        .and_then(|mut request| request.body_mut().take())
+    {
+        req = req.body(body);
+    }
+    req
+}
+
+/// Send a request to a single endpoint through `hooks`
+fn send_once(
+    req: RequestBuilder,
+    hooks: &RequestHooks,
+    endpoint: &str,
+) -> Result<(Response, Duration), GeocodingError> {
+    let request_id = current_request_id();
+    let req = match &request_id {
+        Some(id) => req.header(REQUEST_ID_HEADER, id),
+        None => req,
+    };
+    let host = host(endpoint);
+    if let Some(throttle) = &hooks.throttle {
+        throttle.wait(&host);
+    }
+    // Requests with streaming bodies can't be cloned, and are audited by their endpoint
+    let record = hooks.audit.as_ref().map(|_| {
+        let (method, url) = match req.try_clone().and_then(|req| req.build().ok()) {
+            Some(req) => (
+                req.method().to_string(),
+                audit::redact(req.url(), &hooks.redacted_params),
+            ),
+            None => (String::new(), audit::redact_url(endpoint)),
+        };
+        AuditRecord {
+            timestamp: Utc::now(),
+            method,
+            url,
+            status: None,
+            result_count: None,
+            error: None,
+            request_id,
+        }
+    });
+    let started = Instant::now();
+    let mut duration = Duration::ZERO;
+    let mut status = None;
+    // The body is read here to count its bytes, capture it for the lineage of the lookup,
+    // and count the results it holds when audited
+    let result = transmit(req)
+        .map_err(GeocodingError::from)
+        .and_then(|resp| {
+            duration = started.elapsed();
+            if let Some(throttle) = &hooks.throttle {
+                throttle.record(&host, resp.status(), resp.headers());
+            }
+            status = Some(resp.status().as_u16());
+            buffer_limited(resp, hooks)
+        });
+    hooks.usage.record(&result);
+    if let Ok((resp, body)) = &result {
+        CAPTURED.with(|captured| {
+            if let Some(responses) = captured.borrow_mut().as_mut() {
+                responses.push(LineageResponse {
+                    url: audit::redact(resp.url(), &hooks.redacted_params),
+                    status: resp.status().as_u16(),
+                    body: String::from_utf8_lossy(body).into_owned(),
+                });
+            }
+        });
+    }
+    if let (Some(sink), Some(mut record)) = (&hooks.audit, record) {
+        record.status = status;
+        match &result {
+            Ok((_, body)) => record.result_count = audit::result_count(body),
+            Err(e) => record.error = Some(e.to_string()),
+        }
+        sink.record(&record);
+    }
+    result.map(|(resp, _)| (resp, duration))
+}
+
+/// Send a request, or answer it from the cassettes of the `replay` feature
+fn transmit(req: RequestBuilder) -> Result<Response, reqwest::Error> {
+    #[cfg(feature = "replay")]
+    return crate::replay::send(req);
+    #[cfg(not(feature = "replay"))]
+    req.send()
+}
+
+/// Read the body of a response, returning an equivalent response along with the body
+///
+/// The response and the returned body share the same buffer.
+pub(crate) fn buffer(resp: Response) -> Result<(Response, Bytes), reqwest::Error> {
+    let parts = Parts::of(&resp);
+    let body = resp.bytes()?;
+    Ok(parts.rebuild(body))
+}
+
+/// Read the body of a response like `buffer`, within the maximum size and result count of
+/// `hooks`
+///
+/// Reading stops once the body exceeds the maximum size, so that a huge body, such as the
+/// polygon of a large boundary, is never held in memory in full.
+fn buffer_limited(
+    resp: Response,
+    hooks: &RequestHooks,
+) -> Result<(Response, Bytes), GeocodingError> {
+    let limit = match hooks.max_response_size {
+        Some(limit) => limit,
+        None => return check_results(buffer(resp)?, hooks),
+    };
+    if resp
+        .content_length()
+        .is_some_and(|length| length > limit as u64)
+    {
+        return Err(GeocodingError::ResponseTooLarge { limit });
+    }
+    let parts = Parts::of(&resp);
+    let mut body = Vec::new();
+    resp.take(limit as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| match e.into_inner() {
+            // Transport failures while reading keep their classification
+            Some(inner) => match inner.downcast::<reqwest::Error>() {
+                Ok(e) => GeocodingError::from(*e),
+                Err(inner) => GeocodingError::Io(std::io::Error::other(inner)),
+            },
+            None => GeocodingError::Io(std::io::ErrorKind::Other.into()),
+        })?;
+    if body.len() > limit {
+        return Err(GeocodingError::ResponseTooLarge { limit });
+    }
+    check_results(parts.rebuild(Bytes::from(body)), hooks)
+}
+
+/// Fail if a buffered response has more results than the maximum count of `hooks`
+fn check_results(
+    (resp, body): (Response, Bytes),
+    hooks: &RequestHooks,
+) -> Result<(Response, Bytes), GeocodingError> {
+    if let Some(limit) = hooks.max_results {
+        if let Some(count) = audit::result_count(&body).filter(|count| *count > limit) {
+            return Err(GeocodingError::TooManyResults { count, limit });
+        }
+    }
+    Ok((resp, body))
+}
+
+/// The parts of a response other than its body
+struct Parts {
+    status: StatusCode,
+    version: Version,
+    url: Url,
+    headers: HeaderMap,
+}
+
+impl Parts {
+    fn of(resp: &Response) -> Self {
+        Parts {
+            status: resp.status(),
+            version: resp.version(),
+            url: resp.url().clone(),
+            headers: resp.headers().clone(),
+        }
+    }
+
+    /// A response with these parts and `body`, along with the body
+    ///
+    /// Cloning `Bytes` only counts a reference: the body isn't copied.
+    fn rebuild(self, body: Bytes) -> (Response, Bytes) {
+        let Parts {
+            status,
+            version,
+            url,
+            headers,
+        } = self;
+        let mut rebuilt = hyper::Response::builder()
+            .status(status)
+            .version(version)
+            .url(url)
+            .body(body.clone())
+            .expect("a response with a valid status is valid");
+        *rebuilt.headers_mut() = headers;
+        (Response::from(rebuilt), body)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn with_request_id_test() {
+        assert_eq!(current_request_id(), None);
+        with_request_id("outer", || {
+            assert_eq!(current_request_id().as_deref(), Some("outer"));
+            with_request_id("inner", || {
+                assert_eq!(current_request_id().as_deref(), Some("inner"));
+            });
+            // Other threads don't see the id
+            let other = std::thread::spawn(current_request_id).join().unwrap();
+            assert_eq!(other, None);
+            assert_eq!(current_request_id().as_deref(), Some("outer"));
+        });
+        let res = std::panic::catch_unwind(|| with_request_id("panic", || panic!("failed")));
+        assert!(res.is_err());
+        assert_eq!(current_request_id(), None);
+    }
+
+    #[cfg(feature = "osm")]
+    #[test]
+    fn post_long_query_test() {
+        use crate::test_server::{Response, Server};
+        use crate::{Forward, Openstreetmap, Point};
+        let body = r#"{"type": "FeatureCollection", "licence": "ODbL", "features": []}"#;
+        let server = Server::new(vec![Response::json(body), Response::json(body)]);
+        let osm = Openstreetmap::new_with_endpoint(server.endpoint()).with_max_query_length(40);
+        let _: Vec<Point<f64>> = osm.forward("Calatrava").unwrap();
+        let _: Vec<Point<f64>> = osm
+            .forward("Carrer de Calatrava, 68, 08017 Barcelona, Spain")
+            .unwrap();
+        let requests = server.requests();
+        assert!(requests[0].starts_with("GET /search?q=Calatrava&"));
+        assert!(requests[1].starts_with("POST /search HTTP/1.1\r\n"));
+        assert!(requests[1]
+            .to_lowercase()
+            .contains("content-type: application/x-www-form-urlencoded\r\n"));
+        assert!(requests[1].ends_with("format=geojson"));
+        assert!(requests[1].contains("\r\n\r\nq=Carrer+de+Calatrava"));
+    }
+
+    #[cfg(feature = "osm")]
+    #[test]
+    fn response_limits_test() {
+        use crate::test_server::{Response, Server};
+        use crate::{Forward, Openstreetmap, Point};
+        let body = r#"{"type": "FeatureCollection", "licence": "ODbL", "features": [{}, {}]}"#;
+        let server = Server::new(vec![
+            Response::json(body),
+            // Without a length, the body is only found to be too large while reading it
+            Response::Raw(format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+                body.len(),
+                body
+            )),
+            Response::json(body),
+        ]);
+        let endpoint = server.endpoint();
+        let osm = Openstreetmap::new_with_endpoint(endpoint.clone()).with_max_response_size(40);
+        for _ in 0..2 {
+            let res: Result<Vec<Point<f64>>, _> = osm.forward("Calatrava");
+            assert!(matches!(
+                res,
+                Err(GeocodingError::ResponseTooLarge { limit: 40 })
+            ));
+        }
+        let osm = Openstreetmap::new_with_endpoint(endpoint)
+            .with_max_response_size(body.len())
+            .with_max_results(1);
+        let res: Result<Vec<Point<f64>>, _> = osm.forward("Calatrava");
+        assert!(matches!(
+            res,
+            Err(GeocodingError::TooManyResults { count: 2, limit: 1 })
+        ));
+        server.requests();
+    }
+}
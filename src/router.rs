@@ -0,0 +1,218 @@
+//! Routing of queries to national geocoders.
+//!
+//! National geocoders, such as GeoAdmin in Switzerland, are usually much better inside their
+//! own borders than global ones. A [`GeoRouter`] sends the queries of each country to the
+//! provider declared for it with [`with_route`](struct.GeoRouter.html#method.with_route),
+//! and all other queries to a fallback provider.
+//!
+//! The country of a forward query is detected from its last comma-separated part, a name
+//! declared with [`with_country_names`](struct.GeoRouter.html#method.with_country_names), or
+//! an ISO 3166-1 alpha-2 code such as `CH` once enabled with
+//! [`with_country_codes`](struct.GeoRouter.html#method.with_country_codes). The country of a
+//! reverse query is the country containing the point, when the router has a
+//! [`CountryReverse`](../struct.CountryReverse.html). Applications which already know the
+//! country of a query can pick its provider with
+//! [`provider_for`](struct.GeoRouter.html#method.provider_for).
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::router::GeoRouter;
+//! use geocoding::{Forward, GeoAdmin, Openstreetmap, Point};
+//!
+//! let router = GeoRouter::new(Box::new(Openstreetmap::new()))
+//!     .with_route("CH".parse().unwrap(), Box::new(GeoAdmin::new()))
+//!     .with_country_names("CH".parse().unwrap(), &["Schweiz", "Suisse", "Switzerland"]);
+//! // Sent to GeoAdmin
+//! let res: Vec<Point<f64>> = router.forward("Bundesplatz 3, Bern, Schweiz").unwrap();
+//! ```
+use crate::{CountryCode, CountryReverse, Geocoder, GeocodingError, LanguageTag, Point};
+use crate::{Forward, ForwardLabels, Reverse};
+use std::collections::HashMap;
+
+/// A geocoder sending the queries of each country to the provider declared for it
+pub struct GeoRouter {
+    fallback: Box<dyn Geocoder>,
+    routes: HashMap<CountryCode, Box<dyn Geocoder>>,
+    /// Country names recognised in queries, in lower case
+    names: HashMap<String, CountryCode>,
+    /// Whether ISO 3166-1 alpha-2 codes are recognised in queries
+    country_codes: bool,
+    countries: Option<CountryReverse>,
+}
+
+impl GeoRouter {
+    /// Create a router sending all queries to `fallback`, until routes are declared
+    pub fn new(fallback: Box<dyn Geocoder>) -> Self {
+        GeoRouter {
+            fallback,
+            routes: HashMap::new(),
+            names: HashMap::new(),
+            country_codes: false,
+            countries: None,
+        }
+    }
+
+    /// Send the queries of `country` to `provider`
+    pub fn with_route(mut self, country: CountryCode, provider: Box<dyn Geocoder>) -> Self {
+        self.routes.insert(country, provider);
+        self
+    }
+
+    /// Recognise `names` at the end of forward queries as `country`, case-insensitively
+    pub fn with_country_names(mut self, country: CountryCode, names: &[&str]) -> Self {
+        for name in names {
+            self.names.insert(name.trim().to_lowercase(), country);
+        }
+        self
+    }
+
+    /// Recognise ISO 3166-1 alpha-2 codes at the end of forward queries, case-insensitively
+    ///
+    /// This is disabled by default, as codes are ambiguous with the abbreviations of states
+    /// and provinces: "Sacramento, CA" would be routed to Canada, and "Wilmington, DE" to
+    /// Germany. Declared names take precedence over codes.
+    pub fn with_country_codes(mut self, country_codes: bool) -> Self {
+        self.country_codes = country_codes;
+        self
+    }
+
+    /// Route reverse queries by the country containing their point
+    pub fn with_countries(mut self, countries: CountryReverse) -> Self {
+        self.countries = Some(countries);
+        self
+    }
+
+    /// The country of a forward query, from its last comma-separated part
+    ///
+    /// Returns `None` if the last part is neither a declared name nor, when enabled, a country
+    /// code, and for queries without a comma, whose single part is rarely a country.
+    pub fn detect_country(&self, address: &str) -> Option<CountryCode> {
+        let (_, last) = address.rsplit_once(',')?;
+        let last = last.trim();
+        self.names
+            .get(&last.to_lowercase())
+            .copied()
+            .or_else(|| last.parse().ok().filter(|_| self.country_codes))
+    }
+
+    /// The provider of the queries of `country`, the fallback if it has no route
+    pub fn provider_for(&self, country: Option<CountryCode>) -> &dyn Geocoder {
+        country
+            .and_then(|country| self.routes.get(&country))
+            .unwrap_or(&self.fallback)
+            .as_ref()
+    }
+
+    /// The provider of a forward query
+    fn forward_provider(&self, address: &str) -> &dyn Geocoder {
+        self.provider_for(self.detect_country(address))
+    }
+}
+
+impl Forward<f64> for GeoRouter {
+    fn forward(&self, address: &str) -> Result<Vec<Point<f64>>, GeocodingError> {
+        self.forward_provider(address).forward(address)
+    }
+}
+
+impl ForwardLabels<f64> for GeoRouter {
+    fn forward_with_labels(
+        &self,
+        address: &str,
+    ) -> Result<Vec<(Point<f64>, String)>, GeocodingError> {
+        self.forward_provider(address).forward_with_labels(address)
+    }
+
+    fn forward_localized(
+        &self,
+        address: &str,
+        language: &LanguageTag,
+    ) -> Result<Vec<(Point<f64>, String)>, GeocodingError> {
+        self.forward_provider(address)
+            .forward_localized(address, language)
+    }
+}
+
+impl Reverse<f64> for GeoRouter {
+    fn reverse(&self, point: &Point<f64>) -> Result<Option<String>, GeocodingError> {
+        let country = self
+            .countries
+            .as_ref()
+            .and_then(|countries| countries.country_code(point))
+            .and_then(|code| code.parse().ok());
+        self.provider_for(country).reverse(point)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Answers each lookup with its own name
+    struct Named(&'static str);
+
+    impl Forward<f64> for Named {
+        fn forward(&self, _: &str) -> Result<Vec<Point<f64>>, GeocodingError> {
+            Ok(vec![])
+        }
+    }
+
+    impl ForwardLabels<f64> for Named {
+        fn forward_with_labels(
+            &self,
+            _: &str,
+        ) -> Result<Vec<(Point<f64>, String)>, GeocodingError> {
+            Ok(vec![(Point::new(0.0, 0.0), self.0.to_string())])
+        }
+    }
+
+    impl Reverse<f64> for Named {
+        fn reverse(&self, _: &Point<f64>) -> Result<Option<String>, GeocodingError> {
+            Ok(Some(self.0.to_string()))
+        }
+    }
+
+    fn router() -> GeoRouter {
+        let ch = "CH".parse().unwrap();
+        GeoRouter::new(Box::new(Named("fallback")))
+            .with_route(ch, Box::new(Named("geoadmin")))
+            .with_country_names(ch, &["Schweiz", "Suisse"])
+    }
+
+    #[test]
+    fn detect_country_test() {
+        let router = router();
+        let ch = "CH".parse().ok();
+        assert_eq!(router.detect_country("Bundesplatz 3, Bern, schweiz"), ch);
+        assert_eq!(router.detect_country("Rue de Rivoli, Paris"), None);
+        assert_eq!(router.detect_country("CH"), None);
+        // Codes are only recognised once enabled, as they are ambiguous with US states
+        assert_eq!(router.detect_country("Bundesplatz 3, Bern, CH"), None);
+        assert_eq!(router.detect_country("Sacramento, CA"), None);
+        let router = router.with_country_codes(true);
+        assert_eq!(router.detect_country("Bundesplatz 3, Bern, CH"), ch);
+        assert_eq!(
+            router.detect_country("Rue de Rivoli, Paris, FR"),
+            "FR".parse().ok()
+        );
+    }
+
+    #[test]
+    fn route_test() {
+        let countries = CountryReverse::from_geojson(
+            r#"{"type": "FeatureCollection", "features": [{"type": "Feature", "properties": {"ISO_A2": "CH"}, "geometry": {"type": "Polygon", "coordinates": [[[6.0, 45.8], [10.5, 45.8], [10.5, 47.8], [6.0, 47.8], [6.0, 45.8]]]}}]}"#,
+        )
+        .unwrap();
+        let router = router().with_countries(countries);
+        let label = |address: &str| router.forward_with_labels(address).unwrap()[0].1.clone();
+        assert_eq!(label("Bundesplatz 3, Bern, Suisse"), "geoadmin");
+        // Countries without a route, and queries without a country, go to the fallback
+        assert_eq!(label("Rue de Rivoli, Paris, FR"), "fallback");
+        assert_eq!(label("Bundesplatz 3"), "fallback");
+        let bern = Point::new(7.4474, 46.948);
+        assert_eq!(router.reverse(&bern).unwrap().unwrap(), "geoadmin");
+        let paris = Point::new(2.3522, 48.8566);
+        assert_eq!(router.reverse(&paris).unwrap().unwrap(), "fallback");
+    }
+}
@@ -0,0 +1,158 @@
+//! [S2](https://s2geometry.io/) cell ids, enabled by the `s2` feature.
+//!
+//! S2 projects the six faces of a cube onto the sphere, and divides each face into
+//! quadrilateral cells along a Hilbert curve, at 31 levels from 0 (a whole face) to 30
+//! (about 1 cm across). Cell ids are returned as their `u64` value, and can be converted
+//! to the token form used by most S2 tools with [`token`](fn.token.html).
+//! They can be added to a [`GeocodeResult`](../struct.GeocodeResult.html) using
+//! [`with_s2_cell`](../struct.GeocodeResult.html#method.with_s2_cell).
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::{s2, Point};
+//!
+//! let p = Point::new(-74.0060, 40.7128);
+//! let id = s2::cell_id(&p, 12).unwrap();
+//! assert!(s2::token(id).starts_with("89c25"));
+//! ```
+use crate::Point;
+use num_traits::Float;
+use std::fmt::Debug;
+
+/// The finest S2 level
+pub const MAX_LEVEL: u8 = 30;
+
+const POS_BITS: u32 = 2 * MAX_LEVEL as u32 + 1;
+const SWAP_MASK: usize = 1;
+const INVERT_MASK: usize = 2;
+/// The position of each (i, j) quadrant along the Hilbert curve, for each orientation
+const IJ_TO_POS: [[u64; 4]; 4] = [[0, 1, 3, 2], [0, 3, 1, 2], [2, 3, 1, 0], [2, 1, 3, 0]];
+/// The change of orientation of the Hilbert curve within each quadrant
+const POS_TO_ORIENTATION: [usize; 4] = [SWAP_MASK, 0, 0, INVERT_MASK | SWAP_MASK];
+
+/// The id of the S2 cell containing a point, at `level`
+///
+/// Returns `None` if the point is out of range, or if `level` is greater than
+/// [`MAX_LEVEL`](constant.MAX_LEVEL.html).
+pub fn cell_id<T>(point: &Point<T>, level: u8) -> Option<u64>
+where
+    T: Float + Debug,
+{
+    let lat = point.y().to_f64()?;
+    let lon = point.x().to_f64()?;
+    if level > MAX_LEVEL || !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+        return None;
+    }
+    let (lat, lon) = (lat.to_radians(), lon.to_radians());
+    let xyz = [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()];
+    let (face, u, v) = face_uv(xyz);
+    let leaf = leaf_id(face, st_to_ij(uv_to_st(u)), st_to_ij(uv_to_st(v)));
+    // Keep the position bits above the level, and set the marker bit of the level
+    let lsb = 1u64 << (2 * (MAX_LEVEL - level));
+    Some((leaf & lsb.wrapping_neg()) | lsb)
+}
+
+/// The token of a cell id: its hexadecimal form, without trailing zeros
+pub fn token(id: u64) -> String {
+    if id == 0 {
+        return "X".to_string();
+    }
+    let hex = format!("{:016x}", id);
+    hex.trim_end_matches('0').to_string()
+}
+
+/// Project a unit vector onto a face of the cube
+fn face_uv(xyz: [f64; 3]) -> (u64, f64, f64) {
+    let [x, y, z] = xyz;
+    let axis = if x.abs() >= y.abs() && x.abs() >= z.abs() {
+        0
+    } else if y.abs() >= z.abs() {
+        1
+    } else {
+        2
+    };
+    let face = if xyz[axis] < 0.0 { axis + 3 } else { axis };
+    let (u, v) = match face {
+        0 => (y / x, z / x),
+        1 => (-x / y, z / y),
+        2 => (-x / z, -y / z),
+        3 => (z / x, y / x),
+        4 => (z / y, -x / y),
+        _ => (-y / z, -x / z),
+    };
+    (face as u64, u, v)
+}
+
+/// The quadratic transform from cube face to cell coordinates, which evens out cell areas
+fn uv_to_st(u: f64) -> f64 {
+    if u >= 0.0 {
+        0.5 * (1.0 + 3.0 * u).sqrt()
+    } else {
+        1.0 - 0.5 * (1.0 - 3.0 * u).sqrt()
+    }
+}
+
+fn st_to_ij(s: f64) -> u64 {
+    let max = 1u64 << MAX_LEVEL;
+    ((s * max as f64).floor().max(0.0) as u64).min(max - 1)
+}
+
+/// The id of the leaf cell at (i, j) on a face, following the Hilbert curve
+fn leaf_id(face: u64, i: u64, j: u64) -> u64 {
+    let mut id = face << POS_BITS;
+    let mut orientation = face as usize & SWAP_MASK;
+    for k in (0..MAX_LEVEL as u32).rev() {
+        let quadrant = (((i >> k) & 1) << 1 | ((j >> k) & 1)) as usize;
+        let pos = IJ_TO_POS[orientation][quadrant];
+        id |= pos << (2 * k + 1);
+        orientation ^= POS_TO_ORIENTATION[pos as usize];
+    }
+    id | 1
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn face_cells_test() {
+        // The centre of each face lies within its level 0 cell
+        let centres = [
+            (0.0, 0.0),
+            (90.0, 0.0),
+            (0.0, 90.0),
+            (180.0, 0.0),
+            (-90.0, 0.0),
+            (0.0, -90.0),
+        ];
+        for (face, (lon, lat)) in centres.iter().enumerate() {
+            let id = cell_id(&Point::new(*lon, *lat), 0).unwrap();
+            assert_eq!(id, (face as u64) << POS_BITS | 1 << 60);
+        }
+    }
+
+    #[test]
+    fn cell_id_test() {
+        let p = Point::new(-74.0060, 40.7128);
+        assert!(token(cell_id(&p, 12).unwrap()).starts_with("89c25"));
+        let leaf = cell_id(&p, MAX_LEVEL).unwrap();
+        assert_eq!(leaf & 1, 1);
+        // Each level is the parent of the next
+        for level in 0..MAX_LEVEL {
+            let parent = cell_id(&p, level).unwrap();
+            let lsb = parent & parent.wrapping_neg();
+            assert_eq!(lsb, 1 << (2 * (MAX_LEVEL - level)));
+            assert_eq!(leaf & !(2 * lsb - 1), parent & !(2 * lsb - 1));
+        }
+        assert_eq!(cell_id(&p, 31), None);
+        assert_eq!(cell_id(&Point::new(181.0, 0.0), 5), None);
+    }
+
+    #[test]
+    fn token_test() {
+        assert_eq!(token(0x89c2590000000000), "89c259");
+        assert_eq!(token(0x1000000000000000), "1");
+        assert_eq!(token(0), "X");
+    }
+}
@@ -0,0 +1,151 @@
+//! Scoring of results against the query which produced them.
+//!
+//! Providers return their best candidates even when none of them matches the query well.
+//! [`match_score`](fn.match_score.html) estimates how well a candidate matches, between
+//! `0.0` and `1.0`, so that batch pipelines can accept good matches automatically and flag
+//! the others for review. Scores can be added to a
+//! [`GeocodeResult`](../struct.GeocodeResult.html) using
+//! [`with_match_score`](../struct.GeocodeResult.html#method.with_match_score).
+//!
+//! The score is the share of the query's words found in the candidate's label and address
+//! components. It is then halved if the first word of the query with 4 or more characters
+//! including a digit, taken as its postcode, differs from the candidate's postcode, and reduced
+//! by a third if the query contains other numbers but not the candidate's house number.
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::score::match_score;
+//!
+//! let label = "Carrer de Calatrava, 68, 08017 Barcelona, Spain";
+//! assert_eq!(match_score("Calatrava 68 Barcelona", label, None), 1.0);
+//! assert!(match_score("Calatrava 12 Madrid", label, None) < 0.5);
+//! ```
+use crate::Address;
+use std::collections::HashSet;
+
+/// Split a string into lower-case alphanumeric words
+fn tokens(s: &str) -> Vec<String> {
+    s.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Estimate how well a candidate, given by its label and optionally its address,
+/// matches `query`, between `0.0` (no match) and `1.0` (all words match)
+pub fn match_score(query: &str, label: &str, address: Option<&Address>) -> f64 {
+    let query_tokens = tokens(query);
+    if query_tokens.is_empty() {
+        return 0.0;
+    }
+    let mut candidate_tokens: HashSet<String> = tokens(label).into_iter().collect();
+    if let Some(address) = address {
+        let components = [
+            &address.house_number,
+            &address.road,
+            &address.suburb,
+            &address.city,
+            &address.postcode,
+            &address.state,
+            &address.state_code,
+            &address.country,
+            &address.country_code,
+        ];
+        for component in components.iter().filter_map(|c| c.as_deref()) {
+            candidate_tokens.extend(tokens(component));
+        }
+    }
+
+    let query_set: HashSet<&String> = query_tokens.iter().collect();
+    let found = query_set
+        .iter()
+        .filter(|token| candidate_tokens.contains(**token))
+        .count();
+    let mut score = found as f64 / query_set.len() as f64;
+
+    // In query order, so that the first long number is the same on every run
+    let mut numbers: Vec<&String> = Vec::new();
+    for token in &query_tokens {
+        if token.chars().any(|c| c.is_ascii_digit()) && !numbers.contains(&token) {
+            numbers.push(token);
+        }
+    }
+    if let Some(address) = address {
+        if let Some(postcode) = address.postcode.as_deref() {
+            let postcode = tokens(postcode).concat();
+            if let Some(i) = numbers.iter().position(|token| token.len() >= 4) {
+                // The postcode isn't a house number
+                if *numbers.remove(i) != postcode {
+                    score *= 0.5;
+                }
+            }
+        }
+        if let Some(house_number) = address.house_number.as_deref() {
+            let house_number = house_number.to_lowercase();
+            if !numbers.is_empty() && !numbers.iter().any(|token| **token == house_number) {
+                score *= 2.0 / 3.0;
+            }
+        }
+    }
+    score
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn address() -> Address {
+        Address {
+            house_number: Some("68".to_string()),
+            road: Some("Carrer de Calatrava".to_string()),
+            city: Some("Barcelona".to_string()),
+            postcode: Some("08017".to_string()),
+            country: Some("Spain".to_string()),
+            country_code: Some("ES".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn token_similarity_test() {
+        let label = "Carrer de Calatrava, 68, 08017 Barcelona, Spain";
+        assert_eq!(match_score("carrer de calatrava 68", label, None), 1.0);
+        assert_eq!(match_score("Calatrava, Madrid", label, None), 0.5);
+        assert_eq!(match_score("", label, None), 0.0);
+        assert_eq!(match_score("Sevilla", label, None), 0.0);
+        // Address components count as well as the label
+        assert_eq!(
+            match_score("Calatrava ES", "Calatrava", Some(&address())),
+            1.0
+        );
+    }
+
+    #[test]
+    fn postcode_test() {
+        let label = "Carrer de Calatrava, Barcelona";
+        let score = match_score("Calatrava 68 08017", label, Some(&address()));
+        assert_eq!(score, 1.0);
+        let score = match_score("Calatrava 08017", label, Some(&address()));
+        assert_eq!(score, 1.0);
+        let score = match_score("Calatrava 68 08018", label, Some(&address()));
+        assert_eq!(score, 2.0 / 3.0 * 0.5);
+        // The postcode is the first long number of the query, on every run
+        let mut address = address();
+        address.house_number = Some("1234".to_string());
+        for _ in 0..20 {
+            let score = match_score("08017 Calatrava 1234", label, Some(&address));
+            assert_eq!(score, 1.0);
+        }
+    }
+
+    #[test]
+    fn house_number_test() {
+        let label = "Carrer de Calatrava, Barcelona";
+        let score = match_score("Calatrava 12 Barcelona", label, Some(&address()));
+        assert_eq!(score, (2.0 / 3.0) * (2.0 / 3.0));
+        // Queries without numbers aren't penalised
+        let score = match_score("Calatrava Barcelona", label, Some(&address()));
+        assert_eq!(score, 1.0);
+    }
+}
@@ -0,0 +1,1140 @@
+//! Selection of a provider at runtime.
+//!
+//! [`GeocodingService`] wraps the built-in geocoding providers, and implements `Forward`,
+//! `ForwardLabels`, `Reverse` and `HealthCheck` by delegating to the wrapped provider. It can
+//! be created from a provider name such as `"nominatim"`, or from a [`ServiceConfig`] read
+//! from a configuration file, so that applications can switch providers without matching on
+//! them.
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::service::{GeocodingService, ServiceConfig, ServiceKind};
+//!
+//! // e.g. from an environment variable
+//! let osm: GeocodingService = "nominatim".parse().unwrap();
+//! assert_eq!(osm.kind(), ServiceKind::Openstreetmap);
+//!
+//! // e.g. from a configuration file
+//! let config: ServiceConfig = serde_json::from_str(
+//!     r#"{"provider": "opencage", "api_key": "dcdbf0d783374909b3debee728c7cc10"}"#,
+//! )
+//! .unwrap();
+//! let oc = GeocodingService::new(&config).unwrap();
+//! assert_eq!(oc.kind(), ServiceKind::Opencage);
+//! ```
+use crate::geoadmin::{GeoAdminBuilder, GeoAdminParams, Origin};
+use crate::opencage::Parameters;
+use crate::openstreetmap::{
+    Category, OpenstreetmapParams, OpenstreetmapResponse, OpenstreetmapReverseParams, PlaceType,
+};
+use crate::{
+    Address, Attribution, CountryCode, GeocodeResult, InputBounds, LanguageTag, Paged, SearchArea,
+};
+use crate::{ExposeSecret, SecretString};
+use crate::{
+    Forward, ForwardLabels, ForwardStructured, GeoAdmin, GeocodingError, HealthCheck, Opencage,
+    Openstreetmap, Point, ProviderStatus, Reverse, ReverseFull, ReverseSnapping,
+};
+use num_traits::Float;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::{self, Debug};
+use std::str::FromStr;
+
+/// The built-in geocoding providers
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceKind {
+    /// [`Opencage`](../opencage/struct.Opencage.html), named `opencage`
+    Opencage,
+    /// [`Openstreetmap`](../openstreetmap/struct.Openstreetmap.html), named `nominatim`,
+    /// `openstreetmap` or `osm`
+    #[serde(rename = "nominatim", alias = "openstreetmap", alias = "osm")]
+    Openstreetmap,
+    /// [`GeoAdmin`](../geoadmin/struct.GeoAdmin.html), named `geoadmin`
+    GeoAdmin,
+}
+
+impl ServiceKind {
+    /// The name of the provider
+    pub fn name(&self) -> &'static str {
+        match self {
+            ServiceKind::Opencage => "opencage",
+            ServiceKind::Openstreetmap => "nominatim",
+            ServiceKind::GeoAdmin => "geoadmin",
+        }
+    }
+}
+
+impl fmt::Display for ServiceKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ServiceKind {
+    type Err = GeocodingError;
+
+    /// Parse a provider name, ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "opencage" => Ok(ServiceKind::Opencage),
+            "nominatim" | "openstreetmap" | "osm" => Ok(ServiceKind::Openstreetmap),
+            "geoadmin" => Ok(ServiceKind::GeoAdmin),
+            _ => Err(GeocodingError::Config(format!("unknown provider {:?}", s))),
+        }
+    }
+}
+
+/// The configuration of a [`GeocodingService`](struct.GeocodingService.html)
+///
+/// The API key isn't serialized, so that configurations can be written without revealing it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceConfig {
+    /// The provider to use
+    pub provider: ServiceKind,
+    /// The API key, required by OpenCage and sent as the `key` parameter to Nominatim
+    #[serde(default, skip_serializing)]
+    pub api_key: Option<SecretString>,
+    /// A custom endpoint, instead of the default endpoint of the provider
+    #[serde(default)]
+    pub endpoint: Option<String>,
+}
+
+impl ServiceConfig {
+    /// Create a configuration of `provider` with its default endpoint and no API key
+    pub fn new(provider: ServiceKind) -> Self {
+        ServiceConfig {
+            provider,
+            api_key: None,
+            endpoint: None,
+        }
+    }
+}
+
+/// Options of a [`GeocodingService::forward_with_options`](enum.GeocodingService.html#method.forward_with_options)
+/// search, mapped to the closest parameters of each provider
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ForwardOptions {
+    region_bias: Option<CountryCode>,
+    language: Option<LanguageTag>,
+    search_area: Option<SearchArea<f64>>,
+    area_filter: bool,
+}
+
+impl ForwardOptions {
+    /// Create a new options builder, with no options set
+    /// # Example:
+    ///
+    /// ```
+    /// use geocoding::service::ForwardOptions;
+    ///
+    /// let options = ForwardOptions::new()
+    ///     .with_region_bias("CH".parse().unwrap())
+    ///     .build();
+    /// ```
+    pub fn new() -> ForwardOptions {
+        ForwardOptions::default()
+    }
+
+    /// Prefer results in the country of `country`, e.g. the country of the calling user
+    ///
+    /// This is the `countrycode` parameter of OpenCage and the `countrycodes` parameter of
+    /// Nominatim, which both restrict the results to the country. GeoAdmin only covers
+    /// Switzerland and Liechtenstein, and ignores it.
+    pub fn with_region_bias(&mut self, country: CountryCode) -> &mut Self {
+        self.region_bias = Some(country);
+        self
+    }
+
+    /// Request the labels of the results in `language`
+    ///
+    /// This is the `language` parameter of OpenCage, the `accept-language` parameter of
+    /// Nominatim, and the `lang` parameter of GeoAdmin, which only uses the primary language
+    /// subtag.
+    pub fn with_language(&mut self, language: LanguageTag) -> &mut Self {
+        self.language = Some(language);
+        self
+    }
+
+    /// Restrict the search to `area`, in WGS84 coordinates
+    ///
+    /// Providers are restricted to the bounding box of the area: the `bounds` parameter of
+    /// OpenCage, the `viewbox` and `bounded` parameters of Nominatim, and the `bbox` parameter
+    /// of GeoAdmin. Results may thus lie outside of circles and polygons, unless they are
+    /// filtered with [`with_area_filter`](#method.with_area_filter).
+    pub fn with_search_area(&mut self, area: SearchArea<f64>) -> &mut Self {
+        self.search_area = Some(area);
+        self
+    }
+
+    /// Discard the results outside of the search area, `false` by default
+    ///
+    /// Providers only honor the bounding box of the area, and Nominatim may return results
+    /// slightly outside of it. Filtering the results on the client ensures that all
+    /// providers return results within the exact area.
+    pub fn with_area_filter(&mut self, area_filter: bool) -> &mut Self {
+        self.area_filter = area_filter;
+        self
+    }
+
+    /// Build and return an instance of ForwardOptions
+    pub fn build(&self) -> ForwardOptions {
+        self.clone()
+    }
+}
+
+/// A result of [`GeocodingService::forward_localized`](enum.GeocodingService.html#method.forward_localized),
+/// with its label in each language
+#[derive(Clone, Debug, PartialEq)]
+pub struct LocalizedResult<T>
+where
+    T: Float + Debug,
+{
+    /// The result in the first language
+    pub result: GeocodeResult<T>,
+    /// The label of the result in each language
+    pub labels: HashMap<LanguageTag, String>,
+}
+
+/// The centroid of a postcode, as returned by
+/// [`GeocodingService::geocode_postcode`](enum.GeocodingService.html#method.geocode_postcode)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PostcodeCentroid<T>
+where
+    T: Float + Debug,
+{
+    /// The centroid of the postcode area
+    pub point: Point<T>,
+    /// Whether the provider identified the result as the postcode, rather than another place
+    /// matching the query
+    pub exact: bool,
+}
+
+/// One of the built-in geocoding providers, selected at runtime
+#[derive(Deserialize)]
+#[serde(try_from = "ServiceConfig")]
+pub enum GeocodingService {
+    /// An OpenCage provider
+    Opencage(Opencage<'static>),
+    /// An OpenStreetMap Nominatim provider
+    Openstreetmap(Openstreetmap),
+    /// A GeoAdmin provider
+    GeoAdmin(GeoAdmin),
+}
+
+impl GeocodingService {
+    /// Create the provider described by `config`
+    ///
+    /// Fails if the provider requires an API key and `config` has none.
+    pub fn new(config: &ServiceConfig) -> Result<Self, GeocodingError> {
+        let service = match config.provider {
+            ServiceKind::Opencage => {
+                let api_key = config.api_key.clone().ok_or_else(|| {
+                    GeocodingError::Config("opencage requires an api_key".to_string())
+                })?;
+                match &config.endpoint {
+                    Some(endpoint) => Opencage::new_with_endpoint(api_key, endpoint.clone()),
+                    None => Opencage::new(api_key),
+                }
+                .into()
+            }
+            ServiceKind::Openstreetmap => {
+                let osm = match &config.endpoint {
+                    Some(endpoint) => Openstreetmap::new_with_endpoint(endpoint.clone()),
+                    None => Openstreetmap::new(),
+                };
+                match &config.api_key {
+                    Some(api_key) => osm.with_api_key("key", api_key.expose_secret()),
+                    None => osm,
+                }
+                .into()
+            }
+            ServiceKind::GeoAdmin => {
+                let mut builder = GeoAdminBuilder::new();
+                if let Some(endpoint) = &config.endpoint {
+                    builder.with_endpoint(endpoint);
+                }
+                builder.build().into()
+            }
+        };
+        Ok(service)
+    }
+
+    /// A forward-geocoding lookup of an address with `options`, returning provider-independent
+    /// results
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::service::{ForwardOptions, GeocodingService};
+    /// use geocoding::GeocodeResult;
+    ///
+    /// let osm: GeocodingService = "nominatim".parse().unwrap();
+    /// let options = ForwardOptions::new()
+    ///     .with_region_bias("ES".parse().unwrap())
+    ///     .build();
+    /// let res: Vec<GeocodeResult<f64>> = osm.forward_with_options("Calatrava", &options).unwrap();
+    /// assert!(res[0].label.contains("Spain") || res[0].label.contains("España"));
+    /// ```
+    pub fn forward_with_options<T>(
+        &self,
+        address: &str,
+        options: &ForwardOptions,
+    ) -> Result<Vec<GeocodeResult<T>>, GeocodingError>
+    where
+        T: Float + DeserializeOwned + Debug,
+    {
+        self.forward_page(address, options, &mut vec![])
+    }
+
+    /// A forward-geocoding lookup of an address with `options`, returning the first page of
+    /// results, from which the following pages can be requested
+    ///
+    /// Only Nominatim paginates its results: each further page excludes the `place_id`s of
+    /// the previous results. The results of the other providers are a single page.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::service::{ForwardOptions, GeocodingService};
+    ///
+    /// let osm: GeocodingService = "nominatim".parse().unwrap();
+    /// let mut pages = osm
+    ///     .forward_paged::<f64>("Bahnhofstrasse", &ForwardOptions::new())
+    ///     .unwrap();
+    /// let first = pages.items().to_vec();
+    /// let second = pages.next_page().unwrap().unwrap();
+    /// assert_ne!(first[0], second[0]);
+    /// ```
+    pub fn forward_paged<T>(
+        &self,
+        address: &str,
+        options: &ForwardOptions,
+    ) -> Result<Paged<'_, GeocodeResult<T>>, GeocodingError>
+    where
+        T: Float + DeserializeOwned + Debug,
+    {
+        let mut seen = vec![];
+        let first = self.forward_page(address, options, &mut seen)?;
+        if !matches!(self, GeocodingService::Openstreetmap(_)) {
+            return Ok(Paged::single(first));
+        }
+        let (address, options) = (address.to_string(), options.clone());
+        Ok(Paged::new(first, move || {
+            let excluded = seen.len();
+            let page = self.forward_page(&address, &options, &mut seen)?;
+            // The page is filtered by area, so only its place ids tell if it was empty
+            Ok(if seen.len() > excluded {
+                Some(page)
+            } else {
+                None
+            })
+        }))
+    }
+
+    /// A forward-geocoding lookup excluding the Nominatim results whose `place_id` was `seen`,
+    /// and adding those of the results to `seen`
+    fn forward_page<T>(
+        &self,
+        address: &str,
+        options: &ForwardOptions,
+        seen: &mut Vec<u64>,
+    ) -> Result<Vec<GeocodeResult<T>>, GeocodingError>
+    where
+        T: Float + DeserializeOwned + Debug,
+    {
+        let countrycode = options.region_bias.map(|code| code.as_str().to_lowercase());
+        let language = options.language.as_ref().map(LanguageTag::as_str);
+        let bounds: Option<InputBounds<T>> = options.search_area.as_ref().map(|area| {
+            let bounds = area.bounds();
+            let convert =
+                |p: Point<f64>| Point::new(T::from(p.x()).unwrap(), T::from(p.y()).unwrap());
+            InputBounds::new(
+                convert(bounds.minimum_lonlat),
+                convert(bounds.maximum_lonlat),
+            )
+        });
+        let mut results: Vec<GeocodeResult<T>> = match self {
+            GeocodingService::Opencage(provider) => {
+                let mut parameters = *provider.parameters();
+                if let Some(countrycode) = &countrycode {
+                    parameters.countrycode = Some(countrycode);
+                }
+                if language.is_some() {
+                    parameters.language = language;
+                }
+                let res = provider.forward_full_with(address, bounds, &parameters)?;
+                let licence = res.licence();
+                res.results
+                    .iter()
+                    .map(|result| {
+                        Ok(GeocodeResult {
+                            licence: licence.clone(),
+                            ..GeocodeResult::try_from(result)?
+                        })
+                    })
+                    .collect::<Result<_, GeocodingError>>()?
+            }
+            GeocodingService::Openstreetmap(provider) => {
+                let countrycodes: Vec<CountryCode> = options.region_bias.into_iter().collect();
+                let mut params = OpenstreetmapParams::new(address)
+                    .with_addressdetails(true)
+                    .with_countrycodes(&countrycodes)
+                    .with_exclude_place_ids(seen);
+                if let Some(language) = language {
+                    params = params.with_accept_language(language);
+                }
+                if let Some(bounds) = bounds {
+                    params = params.with_viewbox(bounds).with_bounded(true);
+                }
+                let res = provider.forward_full(&params.build())?;
+                seen.extend(res.features.iter().map(|f| f.properties.place_id));
+                res.features
+                    .iter()
+                    .map(|feature| GeocodeResult {
+                        licence: Some(res.licence.clone()),
+                        ..GeocodeResult::from(feature)
+                    })
+                    .collect()
+            }
+            GeocodingService::GeoAdmin(provider) => {
+                let mut params = GeoAdminParams::new(address);
+                let primary = language.map(|language| {
+                    language
+                        .split('-')
+                        .next()
+                        .unwrap_or(language)
+                        .to_lowercase()
+                });
+                if let Some(primary) = primary {
+                    params = params.with_language(primary);
+                }
+                // The bbox is in the instance's sr
+                let bbox = bounds.map(|bounds| {
+                    InputBounds::new(
+                        provider.wgs84_to_sr(&bounds.minimum_lonlat),
+                        provider.wgs84_to_sr(&bounds.maximum_lonlat),
+                    )
+                });
+                if let Some(bbox) = bbox {
+                    params = params.with_bbox(bbox);
+                }
+                let res = provider.forward_full(&params.build())?;
+                res.features
+                    .iter()
+                    .map(|location| provider.geocode_result(location))
+                    .collect()
+            }
+        };
+        if options.area_filter {
+            if let Some(area) = &options.search_area {
+                results.retain(|result| {
+                    let point = Point::new(
+                        result.point.x().to_f64().unwrap(),
+                        result.point.y().to_f64().unwrap(),
+                    );
+                    area.contains(&point)
+                });
+            }
+        }
+        Ok(results)
+    }
+
+    /// A forward-geocoding lookup of an address in each of `languages`, returning the results
+    /// of the first language with their labels in every language
+    ///
+    /// A request is sent per language, with the `options` of the search and the language. The
+    /// results of the other languages are matched to those of the first language by their
+    /// coordinates, so a label is missing when a provider returns different results.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::service::{ForwardOptions, GeocodingService};
+    ///
+    /// let osm: GeocodingService = "nominatim".parse().unwrap();
+    /// let languages = ["de".parse().unwrap(), "fr".parse().unwrap()];
+    /// let res = osm
+    ///     .forward_localized::<f64>("Genf", &languages, &ForwardOptions::new())
+    ///     .unwrap();
+    /// assert!(res[0].labels[&languages[0]].starts_with("Genf"));
+    /// assert!(res[0].labels[&languages[1]].starts_with("Genève"));
+    /// ```
+    pub fn forward_localized<T>(
+        &self,
+        address: &str,
+        languages: &[LanguageTag],
+        options: &ForwardOptions,
+    ) -> Result<Vec<LocalizedResult<T>>, GeocodingError>
+    where
+        T: Float + DeserializeOwned + Debug,
+    {
+        let mut options = options.clone();
+        let mut localized: Vec<LocalizedResult<T>> = vec![];
+        for (i, language) in languages.iter().enumerate() {
+            options.language = Some(language.clone());
+            let results = self.forward_with_options(address, &options)?;
+            if i == 0 {
+                localized = results
+                    .iter()
+                    .map(|result| LocalizedResult {
+                        result: result.clone(),
+                        labels: HashMap::new(),
+                    })
+                    .collect();
+            }
+            for result in localized.iter_mut() {
+                let point = result.result.point;
+                if let Some(other) = results.iter().find(|other| other.point == point) {
+                    result.labels.insert(language.clone(), other.label.clone());
+                }
+            }
+        }
+        Ok(localized)
+    }
+
+    /// The centroid of a postcode of `country`
+    ///
+    /// Sends the most precise query of each provider: a structured `postalcode` search on
+    /// Nominatim, a search restricted to the country on OpenCage, and a search of the zipcode
+    /// origin on GeoAdmin, which only covers Switzerland and Liechtenstein and returns `None`
+    /// for other countries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::service::GeocodingService;
+    ///
+    /// let osm: GeocodingService = "nominatim".parse().unwrap();
+    /// let res = osm
+    ///     .geocode_postcode::<f64>("CH".parse().unwrap(), "3084")
+    ///     .unwrap()
+    ///     .unwrap();
+    /// assert!(res.exact);
+    /// ```
+    pub fn geocode_postcode<T>(
+        &self,
+        country: CountryCode,
+        postcode: &str,
+    ) -> Result<Option<PostcodeCentroid<T>>, GeocodingError>
+    where
+        T: Float + DeserializeOwned + Debug,
+    {
+        let postcode = postcode.trim();
+        let centroid = match self {
+            GeocodingService::Opencage(provider) => {
+                let countrycode = country.as_str().to_lowercase();
+                let parameters = Parameters {
+                    countrycode: Some(&countrycode),
+                    limit: Some(1),
+                    ..*provider.parameters()
+                };
+                let res = provider.forward_full_with(postcode, None, &parameters)?;
+                match res.results.first() {
+                    Some(result) => Some(PostcodeCentroid {
+                        point: result.point()?,
+                        exact: result.components.get("_type").and_then(|t| t.as_str())
+                            == Some("postcode"),
+                    }),
+                    None => None,
+                }
+            }
+            GeocodingService::Openstreetmap(provider) => {
+                let address = Address {
+                    postcode: Some(postcode.to_string()),
+                    ..Address::default()
+                };
+                let countrycodes = [country];
+                let params = OpenstreetmapParams::new(postcode)
+                    .with_structured_address(address)
+                    .with_countrycodes(&countrycodes)
+                    .build();
+                let res = provider.forward_full(&params)?;
+                res.features.first().map(|result| PostcodeCentroid {
+                    point: GeocodeResult::from(result).point,
+                    exact: result.properties.category == Category::Place
+                        && result.properties.r#type == PlaceType::Postcode,
+                })
+            }
+            GeocodingService::GeoAdmin(provider) => {
+                if !["CH", "LI"].contains(&country.as_str()) {
+                    return Ok(None);
+                }
+                let params = GeoAdminParams::new(postcode)
+                    .with_origins(&[Origin::Zipcode])
+                    .with_limit(1)
+                    .build();
+                let res = provider.forward_full(&params)?;
+                res.features.first().map(|result| PostcodeCentroid {
+                    point: provider.geocode_result(result).point,
+                    exact: result.properties.detail.starts_with(postcode),
+                })
+            }
+        };
+        Ok(centroid)
+    }
+
+    /// A forward-geocoding lookup of the intersection of two streets in `locality`
+    ///
+    /// None of the built-in providers geocode intersections natively, so the streets are
+    /// combined into a single query by [`intersection_query`](fn.intersection_query.html).
+    /// Providers may return the nearest match to that query, such as one of the streets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::service::GeocodingService;
+    /// use geocoding::GeocodeResult;
+    ///
+    /// let osm: GeocodingService = "nominatim".parse().unwrap();
+    /// let res: Vec<GeocodeResult<f64>> = osm
+    ///     .forward_intersection("Carrer de Calatrava", "Via Augusta", "Barcelona")
+    ///     .unwrap();
+    /// ```
+    pub fn forward_intersection<T>(
+        &self,
+        street1: &str,
+        street2: &str,
+        locality: &str,
+    ) -> Result<Vec<GeocodeResult<T>>, GeocodingError>
+    where
+        T: Float + DeserializeOwned + Debug,
+    {
+        let query = intersection_query(street1, street2, locality);
+        self.forward_with_options(&query, &ForwardOptions::new())
+    }
+
+    /// A reverse lookup of a point, snapped to the nearest address, the nearest road or the
+    /// containing locality
+    ///
+    /// See [`ReverseSnapping`](../enum.ReverseSnapping.html) for the parameters each provider
+    /// is sent. GeoAdmin results are always the nearest building, since the features of the
+    /// other layers aren't addresses.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geocoding::service::GeocodingService;
+    /// use geocoding::{GeocodeResult, Point, ReverseSnapping};
+    ///
+    /// let osm: GeocodingService = "nominatim".parse().unwrap();
+    /// let res: Option<GeocodeResult<f64>> = osm
+    ///     .reverse_snapped(&Point::new(2.12870, 41.40139), ReverseSnapping::Road)
+    ///     .unwrap();
+    /// let address = res.unwrap().address.unwrap();
+    /// assert_eq!(address.road.as_deref(), Some("Carrer de Calatrava"));
+    /// ```
+    pub fn reverse_snapped<T>(
+        &self,
+        point: &Point<T>,
+        snapping: ReverseSnapping,
+    ) -> Result<Option<GeocodeResult<T>>, GeocodingError>
+    where
+        T: Float + DeserializeOwned + Debug,
+    {
+        match self {
+            GeocodingService::Opencage(provider) => {
+                let parameters = Parameters {
+                    snapping: Some(snapping),
+                    ..*provider.parameters()
+                };
+                let res = provider.reverse_full_with(point, &parameters)?;
+                let licence = res.licence();
+                res.results
+                    .first()
+                    .map(|result| {
+                        Ok(GeocodeResult {
+                            licence,
+                            ..GeocodeResult::try_from(result)?
+                        })
+                    })
+                    .transpose()
+            }
+            GeocodingService::Openstreetmap(provider) => {
+                let params = OpenstreetmapReverseParams::new()
+                    .with_addressdetails(true)
+                    .with_snapping(snapping)
+                    .build();
+                let res: OpenstreetmapResponse<T> = provider.reverse_full(point, &params)?;
+                Ok(res.features.first().map(|feature| GeocodeResult {
+                    licence: Some(res.licence.clone()),
+                    ..GeocodeResult::from(feature)
+                }))
+            }
+            GeocodingService::GeoAdmin(provider) => provider.reverse_result(point),
+        }
+    }
+
+    /// The kind of the wrapped provider
+    pub fn kind(&self) -> ServiceKind {
+        match self {
+            GeocodingService::Opencage(_) => ServiceKind::Opencage,
+            GeocodingService::Openstreetmap(_) => ServiceKind::Openstreetmap,
+            GeocodingService::GeoAdmin(_) => ServiceKind::GeoAdmin,
+        }
+    }
+
+    /// The credit the wrapped provider requires
+    pub fn attribution(&self) -> Attribution {
+        match self {
+            GeocodingService::Opencage(provider) => provider.attribution(),
+            GeocodingService::Openstreetmap(provider) => provider.attribution(),
+            GeocodingService::GeoAdmin(provider) => provider.attribution(),
+        }
+    }
+}
+
+/// The query of the intersection of two streets in `locality`, such as
+/// `"Main St & 5th Ave, Springfield"`
+///
+/// Empty parts are left out.
+///
+/// ```
+/// use geocoding::service::intersection_query;
+///
+/// assert_eq!(
+///     intersection_query("Main St", "5th Ave", "Springfield"),
+///     "Main St & 5th Ave, Springfield"
+/// );
+/// assert_eq!(intersection_query("Main St", "5th Ave", ""), "Main St & 5th Ave");
+/// ```
+pub fn intersection_query(street1: &str, street2: &str, locality: &str) -> String {
+    let streets = [street1.trim(), street2.trim()]
+        .iter()
+        .filter(|street| !street.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" & ");
+    [streets.as_str(), locality.trim()]
+        .iter()
+        .filter(|part| !part.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl From<Opencage<'static>> for GeocodingService {
+    fn from(provider: Opencage<'static>) -> Self {
+        GeocodingService::Opencage(provider)
+    }
+}
+
+impl From<Openstreetmap> for GeocodingService {
+    fn from(provider: Openstreetmap) -> Self {
+        GeocodingService::Openstreetmap(provider)
+    }
+}
+
+impl From<GeoAdmin> for GeocodingService {
+    fn from(provider: GeoAdmin) -> Self {
+        GeocodingService::GeoAdmin(provider)
+    }
+}
+
+impl TryFrom<ServiceConfig> for GeocodingService {
+    type Error = GeocodingError;
+
+    fn try_from(config: ServiceConfig) -> Result<Self, Self::Error> {
+        GeocodingService::new(&config)
+    }
+}
+
+impl FromStr for GeocodingService {
+    type Err = GeocodingError;
+
+    /// Create a provider with its default configuration from its name
+    ///
+    /// Providers requiring an API key, such as OpenCage, must be created from a
+    /// [`ServiceConfig`](struct.ServiceConfig.html) instead.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        GeocodingService::new(&ServiceConfig::new(s.parse()?))
+    }
+}
+
+impl<T> Forward<T> for GeocodingService
+where
+    T: Float + DeserializeOwned + Debug,
+{
+    fn forward(&self, address: &str) -> Result<Vec<Point<T>>, GeocodingError> {
+        match self {
+            GeocodingService::Opencage(provider) => provider.forward(address),
+            GeocodingService::Openstreetmap(provider) => provider.forward(address),
+            GeocodingService::GeoAdmin(provider) => provider.forward(address),
+        }
+    }
+}
+
+impl<T> ForwardLabels<T> for GeocodingService
+where
+    T: Float + DeserializeOwned + Debug,
+{
+    fn forward_with_labels(
+        &self,
+        address: &str,
+    ) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        match self {
+            GeocodingService::Opencage(provider) => provider.forward_with_labels(address),
+            GeocodingService::Openstreetmap(provider) => provider.forward_with_labels(address),
+            GeocodingService::GeoAdmin(provider) => provider.forward_with_labels(address),
+        }
+    }
+
+    fn forward_localized(
+        &self,
+        address: &str,
+        language: &LanguageTag,
+    ) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        match self {
+            GeocodingService::Opencage(provider) => {
+                ForwardLabels::forward_localized(provider, address, language)
+            }
+            GeocodingService::Openstreetmap(provider) => {
+                ForwardLabels::forward_localized(provider, address, language)
+            }
+            GeocodingService::GeoAdmin(provider) => {
+                ForwardLabels::forward_localized(provider, address, language)
+            }
+        }
+    }
+}
+
+impl<T> ForwardStructured<T> for GeocodingService
+where
+    T: Float + DeserializeOwned + Debug,
+{
+    fn forward_structured(
+        &self,
+        address: &Address,
+    ) -> Result<Vec<(Point<T>, String)>, GeocodingError> {
+        match self {
+            GeocodingService::Opencage(provider) => provider.forward_structured(address),
+            GeocodingService::Openstreetmap(provider) => provider.forward_structured(address),
+            GeocodingService::GeoAdmin(provider) => provider.forward_structured(address),
+        }
+    }
+
+    fn forward_structured_results(
+        &self,
+        address: &Address,
+    ) -> Result<Vec<GeocodeResult<T>>, GeocodingError> {
+        match self {
+            GeocodingService::Opencage(provider) => provider.forward_structured_results(address),
+            GeocodingService::Openstreetmap(provider) => {
+                provider.forward_structured_results(address)
+            }
+            GeocodingService::GeoAdmin(provider) => provider.forward_structured_results(address),
+        }
+    }
+}
+
+impl<T> Reverse<T> for GeocodingService
+where
+    T: Float + DeserializeOwned + Debug,
+{
+    fn reverse(&self, point: &Point<T>) -> Result<Option<String>, GeocodingError> {
+        match self {
+            GeocodingService::Opencage(provider) => provider.reverse(point),
+            GeocodingService::Openstreetmap(provider) => provider.reverse(point),
+            GeocodingService::GeoAdmin(provider) => provider.reverse(point),
+        }
+    }
+}
+
+impl<T> ReverseFull<T> for GeocodingService
+where
+    T: Float + DeserializeOwned + Debug,
+{
+    fn reverse_result(&self, point: &Point<T>) -> Result<Option<GeocodeResult<T>>, GeocodingError> {
+        match self {
+            GeocodingService::Opencage(provider) => provider.reverse_result(point),
+            GeocodingService::Openstreetmap(provider) => provider.reverse_result(point),
+            GeocodingService::GeoAdmin(provider) => provider.reverse_result(point),
+        }
+    }
+
+    fn reverse_localized(
+        &self,
+        point: &Point<T>,
+        language: &LanguageTag,
+    ) -> Result<Option<GeocodeResult<T>>, GeocodingError> {
+        match self {
+            GeocodingService::Opencage(provider) => provider.reverse_localized(point, language),
+            GeocodingService::Openstreetmap(provider) => {
+                provider.reverse_localized(point, language)
+            }
+            GeocodingService::GeoAdmin(provider) => provider.reverse_localized(point, language),
+        }
+    }
+}
+
+impl HealthCheck for GeocodingService {
+    fn ping(&self) -> Result<ProviderStatus, GeocodingError> {
+        match self {
+            GeocodingService::Opencage(provider) => provider.ping(),
+            GeocodingService::Openstreetmap(provider) => provider.ping(),
+            GeocodingService::GeoAdmin(provider) => provider.ping(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_server::{Response, Server};
+
+    #[test]
+    fn service_kind_test() {
+        assert_eq!(
+            "opencage".parse::<ServiceKind>().unwrap(),
+            ServiceKind::Opencage
+        );
+        assert_eq!(
+            " Nominatim".parse::<ServiceKind>().unwrap(),
+            ServiceKind::Openstreetmap
+        );
+        assert_eq!(
+            "osm".parse::<ServiceKind>().unwrap(),
+            ServiceKind::Openstreetmap
+        );
+        assert_eq!(
+            "GEOADMIN".parse::<ServiceKind>().unwrap(),
+            ServiceKind::GeoAdmin
+        );
+        assert!(matches!(
+            "google".parse::<ServiceKind>(),
+            Err(GeocodingError::Config(_))
+        ));
+        assert_eq!(ServiceKind::Openstreetmap.to_string(), "nominatim");
+        assert_eq!(
+            serde_json::to_string(&ServiceKind::GeoAdmin).unwrap(),
+            r#""geoadmin""#
+        );
+    }
+
+    #[test]
+    fn service_config_test() {
+        let service: GeocodingService = serde_json::from_str(
+            r#"{"provider": "openstreetmap", "endpoint": "https://nominatim.example.com/"}"#,
+        )
+        .unwrap();
+        assert_eq!(service.kind(), ServiceKind::Openstreetmap);
+        let service: GeocodingService = "geoadmin".parse().unwrap();
+        assert_eq!(service.kind(), ServiceKind::GeoAdmin);
+
+        // OpenCage can't be created without an API key
+        assert!(matches!(
+            "opencage".parse::<GeocodingService>(),
+            Err(GeocodingError::Config(_))
+        ));
+        let res: Result<GeocodingService, _> = serde_json::from_str(r#"{"provider": "opencage"}"#);
+        assert!(res.is_err());
+
+        // The API key isn't revealed
+        let config: ServiceConfig =
+            serde_json::from_str(r#"{"provider": "opencage", "api_key": "0123abcd"}"#).unwrap();
+        assert_eq!(config.api_key.as_ref().unwrap().expose_secret(), "0123abcd");
+        assert!(!format!("{:?}", config).contains("0123abcd"));
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(json, r#"{"provider":"opencage","endpoint":null}"#);
+    }
+
+    #[test]
+    fn intersection_query_test() {
+        assert_eq!(
+            intersection_query(" Main St ", "5th Ave", "Springfield, IL"),
+            "Main St & 5th Ave, Springfield, IL"
+        );
+        assert_eq!(
+            intersection_query("Main St", "", "Springfield"),
+            "Main St, Springfield"
+        );
+        assert_eq!(intersection_query("", "", ""), "");
+    }
+
+    #[test]
+    fn forward_test() {
+        let server = Server::single(Response::json(
+            r#"{"type": "FeatureCollection", "licence": "ODbL", "features": [{"type": "Feature", "properties": {"place_id": 1, "osm_type": "node", "osm_id": 1, "display_name": "Carrer de Calatrava", "place_rank": 26, "category": "highway", "type": "residential", "importance": 0.1}, "bbox": [2.1, 41.4, 2.1, 41.4], "geometry": {"type": "Point", "coordinates": [2.1, 41.4]}}]}"#,
+        ));
+        let config = ServiceConfig {
+            provider: ServiceKind::Openstreetmap,
+            api_key: Some("pk.0123".to_string().into()),
+            endpoint: Some(server.endpoint()),
+        };
+        let service = GeocodingService::new(&config).unwrap();
+        let res: Vec<Point<f64>> = service.forward("Calatrava").unwrap();
+        let request = server.request();
+        assert_eq!(res, vec![Point::new(2.1, 41.4)]);
+        assert!(request.contains("key=pk.0123"));
+    }
+
+    #[test]
+    fn forward_with_options_test() {
+        let server = Server::single(Response::json(
+            r#"{"type": "FeatureCollection", "licence": "ODbL", "features": [{"type": "Feature", "properties": {"place_id": 1, "osm_type": "node", "osm_id": 1, "display_name": "Carrer de Calatrava, Barcelona", "place_rank": 26, "category": "highway", "type": "residential", "importance": 0.1, "address": {"road": "Carrer de Calatrava", "city": "Barcelona", "country_code": "es"}}, "bbox": [2.1, 41.4, 2.1, 41.4], "geometry": {"type": "Point", "coordinates": [2.1, 41.4]}}]}"#,
+        ));
+        let service = GeocodingService::from(Openstreetmap::new_with_endpoint(server.endpoint()));
+        let options = ForwardOptions::new()
+            .with_region_bias("ES".parse().unwrap())
+            .with_search_area(SearchArea::Bbox(InputBounds::new((2.0, 41.0), (2.5, 41.5))))
+            .build();
+        let res: Vec<GeocodeResult<f64>> =
+            service.forward_with_options("Calatrava", &options).unwrap();
+        let request = server.request();
+        assert!(request.contains("countrycodes=es"));
+        assert!(request.contains("viewbox=2%2C41%2C2.5%2C41.5&bounded=1"));
+        assert!(request.contains("addressdetails=1"));
+        assert_eq!(res[0].point, Point::new(2.1, 41.4));
+        assert_eq!(
+            res[0].address.as_ref().unwrap().city.as_deref(),
+            Some("Barcelona")
+        );
+        assert_eq!(res[0].licence.as_deref(), Some("ODbL"));
+        assert_eq!(service.attribution().licence.as_deref(), Some("ODbL-1.0"));
+    }
+
+    #[test]
+    fn forward_localized_test() {
+        let feature = |name: &str, lon: f64| {
+            format!(
+                r#"{{"type": "Feature", "properties": {{"place_id": 1, "osm_type": "relation", "osm_id": 1, "display_name": "{}", "place_rank": 16, "category": "boundary", "type": "administrative", "importance": 0.7}}, "bbox": [{lon}, 46.2, {lon}, 46.2], "geometry": {{"type": "Point", "coordinates": [{lon}, 46.2]}}}}"#,
+                name,
+                lon = lon
+            )
+        };
+        // The German and French results, in the order the languages are requested
+        let server = Server::new(
+            [("Genf", 1.0), ("Genève", 2.0)]
+                .iter()
+                .map(|&(name, other)| {
+                    Response::json(format!(
+                        r#"{{"type": "FeatureCollection", "licence": "ODbL", "features": [{}, {}]}}"#,
+                        feature(name, 6.1),
+                        feature("Other", other)
+                    ))
+                })
+                .collect(),
+        );
+        let service = GeocodingService::from(Openstreetmap::new_with_endpoint(server.endpoint()));
+        let languages: Vec<LanguageTag> = vec!["de".parse().unwrap(), "fr".parse().unwrap()];
+        let res: Vec<LocalizedResult<f64>> = service
+            .forward_localized("Genf", &languages, &ForwardOptions::new())
+            .unwrap();
+        let requests = server.requests();
+        assert!(requests[0].contains("accept-language=de"));
+        assert!(requests[1].contains("accept-language=fr"));
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0].result.label, "Genf");
+        assert_eq!(res[0].labels[&languages[0]], "Genf");
+        assert_eq!(res[0].labels[&languages[1]], "Genève");
+        // The second result isn't returned in French
+        assert_eq!(res[1].labels.len(), 1);
+    }
+
+    #[test]
+    fn geocode_postcode_test() {
+        let server = Server::single(Response::json(
+            r#"{"type": "FeatureCollection", "licence": "ODbL", "features": [{"type": "Feature", "properties": {"place_id": 1, "osm_type": "relation", "osm_id": 1, "display_name": "3084, Köniz, Schweiz", "place_rank": 21, "category": "place", "type": "postcode", "importance": 0.1}, "bbox": [7.4, 46.9, 7.5, 47.0], "geometry": {"type": "Point", "coordinates": [7.45, 46.93]}}]}"#,
+        ));
+        let service = GeocodingService::from(Openstreetmap::new_with_endpoint(server.endpoint()));
+        let res = service
+            .geocode_postcode::<f64>("ch".parse().unwrap(), " 3084 ")
+            .unwrap();
+        let request = server.request();
+        assert!(request.contains("postalcode=3084"));
+        assert!(request.contains("countrycodes=ch"));
+        assert!(!request.contains("q="));
+        assert_eq!(
+            res,
+            Some(PostcodeCentroid {
+                point: Point::new(7.45, 46.93),
+                exact: true
+            })
+        );
+
+        // GeoAdmin doesn't send requests for other countries
+        let service = GeocodingService::from(
+            GeoAdminBuilder::new()
+                .with_endpoint("http://127.0.0.1:9/")
+                .build(),
+        );
+        let res = service.geocode_postcode::<f64>("DE".parse().unwrap(), "10115");
+        assert_eq!(res.unwrap(), None);
+    }
+
+    #[test]
+    fn area_filter_test() {
+        // The second cafe is within the bounding box, but not the circle
+        let feature = |name: &str, lon: f64, lat: f64| {
+            format!(
+                r#"{{"type": "Feature", "properties": {{"place_id": 1, "osm_type": "node", "osm_id": 1, "display_name": "{name}", "place_rank": 30, "category": "amenity", "type": "cafe", "importance": 0.1}}, "bbox": [{lon}, {lat}, {lon}, {lat}], "geometry": {{"type": "Point", "coordinates": [{lon}, {lat}]}}}}"#,
+            )
+        };
+        let body = format!(
+            r#"{{"type": "FeatureCollection", "licence": "ODbL", "features": [{}, {}]}}"#,
+            feature("Centre", 7.4391, 46.9488),
+            feature("Corner", 7.4520, 46.9575)
+        );
+        let server = Server::new(vec![Response::json(body.clone()), Response::json(body)]);
+        let service = GeocodingService::from(Openstreetmap::new_with_endpoint(server.endpoint()));
+        let mut options = ForwardOptions::new();
+        options.with_search_area(SearchArea::Circle {
+            center: Point::new(7.439122, 46.948825),
+            radius: 1_000.0,
+        });
+        let res: Vec<GeocodeResult<f64>> = service.forward_with_options("cafe", &options).unwrap();
+        assert_eq!(res.len(), 2);
+        options.with_area_filter(true);
+        let res: Vec<GeocodeResult<f64>> = service.forward_with_options("cafe", &options).unwrap();
+        server.requests();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].label, "Centre");
+    }
+
+    #[test]
+    fn forward_paged_test() {
+        let page = |ids: &[u64]| {
+            let features = ids
+                .iter()
+                .map(|id| {
+                    format!(
+                        r#"{{"type": "Feature", "properties": {{"place_id": {id}, "osm_type": "way", "osm_id": {id}, "display_name": "Bahnhofstrasse {id}", "place_rank": 26, "category": "highway", "type": "residential", "importance": 0.1}}, "bbox": [8.5, 47.3, 8.5, 47.3], "geometry": {{"type": "Point", "coordinates": [8.5, 47.3]}}}}"#,
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            Response::json(format!(
+                r#"{{"type": "FeatureCollection", "licence": "ODbL", "features": [{}]}}"#,
+                features
+            ))
+        };
+        let server = Server::new(vec![page(&[1, 2]), page(&[3]), page(&[])]);
+        let service = GeocodingService::from(Openstreetmap::new_with_endpoint(server.endpoint()));
+        let mut pages = service
+            .forward_paged::<f64>("Bahnhofstrasse", &ForwardOptions::new())
+            .unwrap();
+        assert_eq!(pages.items().len(), 2);
+        let page = pages.next_page().unwrap().unwrap();
+        assert_eq!(page[0].label, "Bahnhofstrasse 3");
+        assert_eq!(pages.next_page().unwrap(), None);
+        assert!(!pages.has_next_page());
+        let requests = server.requests();
+        assert!(!requests[0].contains("exclude_place_ids"));
+        assert!(requests[1].contains("exclude_place_ids=1%2C2"));
+        assert!(requests[2].contains("exclude_place_ids=1%2C2%2C3"));
+
+        // Other providers return a single page
+        let service = GeocodingService::from(
+            GeoAdminBuilder::new()
+                .with_endpoint("http://127.0.0.1:9/")
+                .build(),
+        );
+        assert!(service
+            .forward_paged::<f64>("Bahnhofstrasse", &ForwardOptions::new())
+            .is_err());
+    }
+}
@@ -0,0 +1,164 @@
+//! Usage statistics of provider instances.
+//!
+//! Each provider counts the requests it sends, their failures by class and the bytes it
+//! receives, returning them with its `stats` method. The counters help with capacity
+//! planning, e.g. deciding when a bigger OpenCage plan is needed. They cover every request
+//! sent, including retries and requests to [failover](../failover/index.html) endpoints.
+//!
+//! Lookups served by a [`Cached`](../cache/struct.Cached.html) provider don't send any
+//! request: its [`stats`](../cache/struct.Cached.html#method.stats) count the cache hits and
+//! misses, the misses matching the lookups counted by the wrapped provider.
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::{Forward, Openstreetmap, Point};
+//!
+//! let osm = Openstreetmap::new();
+//! let _: Vec<Point<f64>> = osm.forward("Schwabing, München").unwrap();
+//! let stats = osm.stats();
+//! assert_eq!(stats.requests, 1);
+//! assert_eq!(stats.failures(), 0);
+//! ```
+use crate::GeocodingError;
+use hyper::body::Bytes;
+use reqwest::blocking::Response;
+use reqwest::StatusCode;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The requests sent by a provider instance so far
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UsageStats {
+    /// The requests sent, including those which failed
+    pub requests: u64,
+    /// Requests which failed without a complete response, e.g. on connection errors or timeouts
+    pub transport_errors: u64,
+    /// Requests answered with a 4xx status other than 429
+    pub client_errors: u64,
+    /// Requests answered with a 429 (Too Many Requests) status
+    pub rate_limited: u64,
+    /// Requests answered with a 5xx status
+    pub server_errors: u64,
+    /// Responses refused for exceeding the maximum response size or result count of the
+    /// provider
+    pub limit_exceeded: u64,
+    /// The bytes of the response bodies received, after decompression
+    pub bytes_received: u64,
+}
+
+impl UsageStats {
+    /// The requests which failed, whatever their class
+    pub fn failures(&self) -> u64 {
+        self.transport_errors
+            + self.client_errors
+            + self.rate_limited
+            + self.server_errors
+            + self.limit_exceeded
+    }
+}
+
+/// The counters of the requests sent by a provider
+#[derive(Debug, Default)]
+pub(crate) struct UsageCounters {
+    requests: AtomicU64,
+    transport_errors: AtomicU64,
+    client_errors: AtomicU64,
+    rate_limited: AtomicU64,
+    server_errors: AtomicU64,
+    limit_exceeded: AtomicU64,
+    bytes_received: AtomicU64,
+}
+
+impl UsageCounters {
+    /// Count a request, and its response with its body if it was received
+    pub(crate) fn record(&self, result: &Result<(Response, Bytes), GeocodingError>) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        let (resp, body) = match result {
+            Ok(received) => received,
+            Err(GeocodingError::ResponseTooLarge { .. })
+            | Err(GeocodingError::TooManyResults { .. }) => {
+                self.limit_exceeded.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+            Err(_) => {
+                self.transport_errors.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+        };
+        self.bytes_received
+            .fetch_add(body.len() as u64, Ordering::Relaxed);
+        let status = resp.status();
+        let class = if status == StatusCode::TOO_MANY_REQUESTS {
+            &self.rate_limited
+        } else if status.is_client_error() {
+            &self.client_errors
+        } else if status.is_server_error() {
+            &self.server_errors
+        } else {
+            return;
+        };
+        class.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The counts so far
+    pub(crate) fn snapshot(&self) -> UsageStats {
+        UsageStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            transport_errors: self.transport_errors.load(Ordering::Relaxed),
+            client_errors: self.client_errors.load(Ordering::Relaxed),
+            rate_limited: self.rate_limited.load(Ordering::Relaxed),
+            server_errors: self.server_errors.load(Ordering::Relaxed),
+            limit_exceeded: self.limit_exceeded.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "osm"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn usage_stats_test() {
+        use crate::test_server::{Response, Server};
+        use crate::{Forward, Openstreetmap, Point};
+        let body = r#"{"type": "FeatureCollection", "licence": "ODbL", "features": []}"#;
+        let server = Server::new(
+            [
+                "200 OK",
+                "429 Too Many Requests",
+                "503 Service Unavailable",
+                "200 OK",
+            ]
+            .iter()
+            .map(|&status| Response::json(body).with_status(status))
+            .collect(),
+        );
+        let endpoint = server.endpoint();
+        let osm = Openstreetmap::new_with_endpoint(endpoint.clone());
+        let _: Vec<Point<f64>> = osm.forward("Calatrava").unwrap();
+        assert!(Forward::<f64>::forward(&osm, "Calatrava").is_err());
+        assert!(Forward::<f64>::forward(&osm, "Calatrava").is_err());
+        let limited = Openstreetmap::new_with_endpoint(endpoint).with_max_response_size(16);
+        assert!(Forward::<f64>::forward(&limited, "Calatrava").is_err());
+        assert_eq!(limited.stats().limit_exceeded, 1);
+        assert_eq!(limited.stats().failures(), 1);
+        server.requests();
+        // The server is gone
+        assert!(Forward::<f64>::forward(&osm, "Calatrava").is_err());
+        let stats = osm.stats();
+        assert_eq!(
+            stats,
+            UsageStats {
+                requests: 4,
+                transport_errors: 1,
+                client_errors: 0,
+                rate_limited: 1,
+                server_errors: 1,
+                limit_exceeded: 0,
+                bytes_received: 3 * body.len() as u64,
+            }
+        );
+        assert_eq!(stats.failures(), 3);
+    }
+}
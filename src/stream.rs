@@ -0,0 +1,265 @@
+//! Geocoding of asynchronous streams of addresses, enabled by the `async` feature or its
+//! `stream` alias.
+//!
+//! [`GeocodeStreamExt::geocode`](trait.GeocodeStreamExt.html#tymethod.geocode) pipes any
+//! `Stream` of addresses through a provider, yielding a
+//! [`BatchRecord`](../batch/struct.BatchRecord.html) for each address as soon as it is geocoded.
+//! Requests are sent on Tokio's blocking thread pool, so the stream must be polled within a
+//! Tokio runtime. Up to `concurrency` requests are in flight at once, and records are yielded
+//! in completion order: use their `index` to restore the input order if needed.
+//!
+//! ### Example
+//!
+//! ```no_run
+//! use futures_util::stream::{self, StreamExt};
+//! use geocoding::batch::BatchRecord;
+//! use geocoding::stream::GeocodeStreamExt;
+//! use geocoding::Openstreetmap;
+//! use std::sync::Arc;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let osm = Arc::new(Openstreetmap::new());
+//! let addresses = stream::iter(vec!["Schwabing, München".to_string()]);
+//! let mut records = addresses.geocode::<f64, _>(osm, 4);
+//! while let Some(record) = records.next().await {
+//!     println!("{:?}", record);
+//! }
+//! # }
+//! ```
+//!
+//! ### Search as you type
+//!
+//! A [`DebouncedSuggester`](struct.DebouncedSuggester.html) looks up the input of a search
+//! box once it has stopped changing for a delay, and drops the lookups superseded by newer
+//! input, so that only the suggestions for the latest input are shown:
+//!
+//! ```no_run
+//! use geocoding::stream::DebouncedSuggester;
+//! use geocoding::Openstreetmap;
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let suggester = DebouncedSuggester::new(
+//!     Arc::new(Openstreetmap::new()),
+//!     Duration::from_millis(300),
+//! );
+//! // Called on each keystroke, `None` when superseded by a later keystroke
+//! if let Some(suggestions) = suggester.suggest::<f64>("Schwabing, Mün").await {
+//!     println!("{:?}", suggestions.unwrap());
+//! }
+//! # }
+//! ```
+use crate::batch::{type_name, BatchForward, BatchRecord};
+use crate::{ForwardLabels, GeocodingError, Point};
+use futures_util::future::{self, Either};
+use futures_util::stream::{Stream, StreamExt};
+use num_traits::Float;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// An extension trait geocoding the addresses of a `Stream`
+pub trait GeocodeStreamExt: Stream<Item = String> + Sized {
+    /// Forward-geocode each address of the stream with `provider`, with up to `concurrency`
+    /// requests in flight
+    ///
+    /// Failed requests and panics yield records with an `error`, and don't end the stream.
+    fn geocode<T, P>(self, provider: Arc<P>, concurrency: usize) -> GeocodeStream<T>
+    where
+        T: Float + Debug + Send + 'static,
+        P: ForwardLabels<T> + Send + Sync + 'static;
+}
+
+/// A stream of the records of geocoded addresses
+pub type GeocodeStream<T> = futures_util::stream::BoxStream<'static, BatchRecord<T>>;
+
+impl<S> GeocodeStreamExt for S
+where
+    S: Stream<Item = String> + Send + Sized + 'static,
+{
+    fn geocode<T, P>(self, provider: Arc<P>, concurrency: usize) -> GeocodeStream<T>
+    where
+        T: Float + Debug + Send + 'static,
+        P: ForwardLabels<T> + Send + Sync + 'static,
+    {
+        self.enumerate()
+            .map(move |(index, input)| {
+                let provider = provider.clone();
+                async move {
+                    let task_input = input.clone();
+                    let task = tokio::task::spawn_blocking(move || {
+                        BatchForward::new(&*provider).record(index, task_input)
+                    });
+                    task.await.unwrap_or_else(|e| BatchRecord {
+                        index,
+                        input,
+                        lon: None,
+                        lat: None,
+                        formatted: None,
+                        confidence: None,
+                        provider: type_name::<P>().to_string(),
+                        error: Some(e.to_string()),
+                        validation: None,
+                    })
+                }
+            })
+            .buffer_unordered(concurrency.max(1))
+            .boxed()
+    }
+}
+
+/// Forward lookups of the successive inputs of a search box, coalescing rapid keystrokes
+///
+/// Each call to [`suggest`](#method.suggest) supersedes the pending ones: a lookup is only
+/// sent once the input hasn't changed for the delay, and the calls superseded by a newer
+/// input return `None`, including while their request is in flight. Blocking requests
+/// can't be interrupted, so their responses are discarded instead.
+pub struct DebouncedSuggester<P> {
+    provider: Arc<P>,
+    delay: Duration,
+    // The generation of the latest input
+    latest: watch::Sender<u64>,
+}
+
+impl<P> DebouncedSuggester<P> {
+    /// Look up inputs with `provider` once they haven't changed for `delay`
+    pub fn new(provider: Arc<P>, delay: Duration) -> Self {
+        DebouncedSuggester {
+            provider,
+            delay,
+            latest: watch::Sender::new(0),
+        }
+    }
+
+    /// The suggestions for `input`, or `None` if a later call superseded it
+    ///
+    /// Blank inputs have no suggestions, and aren't looked up. The request is sent on Tokio's
+    /// blocking thread pool, so the future must be polled within a Tokio runtime.
+    pub async fn suggest<T>(
+        &self,
+        input: &str,
+    ) -> Option<Result<Vec<(Point<T>, String)>, GeocodingError>>
+    where
+        T: Float + Debug + Send + 'static,
+        P: ForwardLabels<T> + Send + Sync + 'static,
+    {
+        let mut latest = self.latest.subscribe();
+        self.latest.send_modify(|generation| *generation += 1);
+        latest.borrow_and_update();
+        let input = input.trim().to_string();
+        if input.is_empty() {
+            return Some(Ok(vec![]));
+        }
+        let provider = self.provider.clone();
+        let delay = self.delay;
+        let lookup = Box::pin(async move {
+            tokio::time::sleep(delay).await;
+            tokio::task::spawn_blocking(move || provider.forward_with_labels(&input)).await
+        });
+        // Any later call changes the generation
+        let superseded = Box::pin(latest.changed());
+        let result = future::select(lookup, superseded).await;
+        match result {
+            Either::Left((Ok(suggestions), _)) => Some(suggestions),
+            Either::Left((Err(e), _)) => match e.try_into_panic() {
+                Ok(panic) => std::panic::resume_unwind(panic),
+                // The runtime is shutting down
+                Err(_) => None,
+            },
+            Either::Right(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures_util::stream;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Stub;
+
+    impl ForwardLabels<f64> for Stub {
+        fn forward_with_labels(
+            &self,
+            address: &str,
+        ) -> Result<Vec<(Point<f64>, String)>, GeocodingError> {
+            match address {
+                "error" => Err(GeocodingError::Forward),
+                "panic" => panic!("provider panicked"),
+                _ => Ok(vec![(Point::new(2.5, 41.5), address.to_string())]),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn geocode_test() {
+        let inputs: Vec<String> = vec!["Barcelona", "error", "panic", "Girona"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let mut records: Vec<BatchRecord<f64>> = stream::iter(inputs)
+            .geocode(Arc::new(Stub), 2)
+            .collect()
+            .await;
+        records.sort_by_key(|record| record.index);
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0].formatted.as_deref(), Some("Barcelona"));
+        assert_eq!(
+            records[1].error.as_deref(),
+            Some("Forward geocoding failed")
+        );
+        assert_eq!(records[2].input, "panic");
+        assert_eq!(records[2].provider, "Stub");
+        assert!(records[2].error.as_deref().unwrap().contains("panicked"));
+        assert_eq!(records[3].lon, Some(2.5));
+    }
+
+    /// Counts its lookups, and takes 100 ms to answer "slow"
+    #[derive(Default)]
+    struct Counter {
+        calls: AtomicUsize,
+    }
+
+    impl ForwardLabels<f64> for Counter {
+        fn forward_with_labels(
+            &self,
+            address: &str,
+        ) -> Result<Vec<(Point<f64>, String)>, GeocodingError> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            if address == "slow" {
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            Ok(vec![(Point::new(2.5, 41.5), address.to_string())])
+        }
+    }
+
+    #[tokio::test]
+    async fn debounced_suggester_test() {
+        let suggester =
+            DebouncedSuggester::new(Arc::new(Counter::default()), Duration::from_millis(20));
+        // Keystrokes within the delay are coalesced
+        let (first, second) = future::join(suggester.suggest::<f64>("Bar"), async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            suggester.suggest::<f64>("Barcelona").await
+        })
+        .await;
+        assert!(first.is_none());
+        assert_eq!(second.unwrap().unwrap()[0].1, "Barcelona");
+        assert_eq!(suggester.provider.calls.load(Ordering::Relaxed), 1);
+
+        // A request in flight is superseded
+        let (slow, next) = future::join(suggester.suggest::<f64>("slow"), async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            suggester.suggest::<f64>(" ").await
+        })
+        .await;
+        assert!(slow.is_none());
+        assert!(next.unwrap().unwrap().is_empty());
+        assert_eq!(suggester.provider.calls.load(Ordering::Relaxed), 2);
+    }
+}
@@ -0,0 +1,166 @@
+//! A local HTTP server answering the requests of tests with canned responses.
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// A canned response of a [`Server`]
+pub(crate) enum Response {
+    Http {
+        status: String,
+        headers: Vec<(String, String)>,
+        body: String,
+    },
+    /// Written as is, e.g. a chunked response
+    Raw(String),
+    /// No answer, keeping the connection open for a while
+    Silent(Duration),
+}
+
+impl Response {
+    /// A `200 OK` JSON response
+    pub(crate) fn json(body: impl Into<String>) -> Self {
+        Response::new("200 OK", body).with_header("Content-Type", "application/json")
+    }
+
+    /// A response without a `Content-Type`
+    pub(crate) fn new(status: &str, body: impl Into<String>) -> Self {
+        Response::Http {
+            status: status.to_string(),
+            headers: vec![],
+            body: body.into(),
+        }
+    }
+
+    /// Set the status of the response
+    pub(crate) fn with_status(mut self, status: &str) -> Self {
+        if let Response::Http { status: old, .. } = &mut self {
+            *old = status.to_string();
+        }
+        self
+    }
+
+    /// Add a header to the response
+    pub(crate) fn with_header(mut self, name: &str, value: &str) -> Self {
+        if let Response::Http { headers, .. } = &mut self {
+            headers.push((name.to_string(), value.to_string()));
+        }
+        self
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Response::Http {
+                status,
+                headers,
+                body,
+            } => {
+                let mut response = format!("HTTP/1.1 {}\r\n", status);
+                for (name, value) in headers {
+                    response.push_str(&format!("{}: {}\r\n", name, value));
+                }
+                response.push_str(&format!("Content-Length: {}\r\n\r\n{}", body.len(), body));
+                response.into_bytes()
+            }
+            Response::Raw(response) => response.clone().into_bytes(),
+            Response::Silent(_) => vec![],
+        }
+    }
+}
+
+/// A server answering one connection with each response, in order
+///
+/// The listener is closed once every response was sent, failing any further request.
+pub(crate) struct Server {
+    addr: SocketAddr,
+    handle: JoinHandle<Vec<String>>,
+}
+
+impl Server {
+    pub(crate) fn new(responses: Vec<Response>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = std::thread::spawn(move || {
+            let mut requests = vec![];
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                requests.push(read_request(&mut stream));
+                match response {
+                    Response::Silent(duration) => std::thread::sleep(duration),
+                    // The client may hang up before reading the response
+                    response => {
+                        let _ = stream.write_all(&response.to_bytes());
+                    }
+                }
+            }
+            requests
+        });
+        Server { addr, handle }
+    }
+
+    /// A server answering a single request
+    pub(crate) fn single(response: Response) -> Self {
+        Server::new(vec![response])
+    }
+
+    pub(crate) fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The URL of `path` on the server
+    pub(crate) fn url(&self, path: &str) -> String {
+        format!("http://{}/{}", self.addr, path)
+    }
+
+    /// The URL of the root of the server
+    pub(crate) fn endpoint(&self) -> String {
+        self.url("")
+    }
+
+    /// Wait for every response to be sent, and return the requests received
+    pub(crate) fn requests(self) -> Vec<String> {
+        self.handle.join().unwrap()
+    }
+
+    /// Wait for every response to be sent, and return the request lines received
+    pub(crate) fn request_lines(self) -> Vec<String> {
+        self.requests()
+            .iter()
+            .map(|request| request.lines().next().unwrap_or_default().to_string())
+            .collect()
+    }
+
+    /// Wait for the single response to be sent, and return the request received
+    pub(crate) fn request(self) -> String {
+        self.requests().remove(0)
+    }
+}
+
+/// Read the head of a request, and its body when it has a `Content-Length`
+///
+/// Anything which isn't an HTTP request, e.g. a TLS handshake, is returned after the first read.
+fn read_request(stream: &mut impl Read) -> String {
+    let mut request = vec![];
+    let mut buf = [0; 4096];
+    loop {
+        let len = stream.read(&mut buf).unwrap_or(0);
+        request.extend_from_slice(&buf[..len]);
+        if len == 0 || !request[0].is_ascii_uppercase() {
+            break;
+        }
+        let text = String::from_utf8_lossy(&request);
+        if let Some(end) = text.find("\r\n\r\n") {
+            let length = text[..end]
+                .lines()
+                .filter_map(|line| line.split_once(':'))
+                .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            if request.len() >= end + 4 + length {
+                break;
+            }
+        }
+    }
+    String::from_utf8_lossy(&request).into_owned()
+}
@@ -0,0 +1,275 @@
+//! Rate limiting of requests, shared across provider instances.
+//!
+//! Public services such as the OpenStreetMap Nominatim server allow a limited number of
+//! requests per second per application, e.g. [1 request per second for
+//! Nominatim](https://operations.osmfoundation.org/policies/nominatim/). A [`Throttle`]
+//! spaces the requests sent to each host by a minimum interval, regardless of which
+//! provider instance or thread sends them, so that an application creating several
+//! instances still respects the limit as a whole.
+//!
+//! [`Throttle::global`] returns a process-wide throttle allowing 1 request per second to
+//! each host, which is shared by all the instances it is passed to.
+//!
+//! Throttles adapt to the responses of the hosts: when a host answers `429 Too Many Requests`,
+//! or reports that its rate limit is exhausted, the interval between requests to it is
+//! doubled, and the `Retry-After` delay is respected. The interval then recovers gradually
+//! as requests succeed. The current rate is available from [`Throttle::effective_rate`], so
+//! that batch tools can report it.
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::throttle::Throttle;
+//! use geocoding::Openstreetmap;
+//!
+//! // Both instances together send at most 1 request per second to nominatim.openstreetmap.org
+//! let osm = Openstreetmap::new().with_throttle(Throttle::global());
+//! let other = Openstreetmap::new().with_throttle(Throttle::global());
+//! ```
+use reqwest::header::HeaderMap;
+use reqwest::{StatusCode, Url};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The interval between requests to a host of the global throttle
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The shortest interval between requests to a host after it has asked to slow down
+pub const MIN_BACKOFF_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The longest interval between requests to a host
+pub const MAX_INTERVAL: Duration = Duration::from_secs(60);
+
+static GLOBAL: OnceLock<Arc<Throttle>> = OnceLock::new();
+
+/// A scheduler spacing the requests sent to each host by a minimum interval
+///
+/// Requests are scheduled in the order in which they wait, and requests to different
+/// hosts don't delay each other.
+#[derive(Debug)]
+pub struct Throttle {
+    interval: Duration,
+    hosts: Mutex<HashMap<String, HostState>>,
+}
+
+#[derive(Debug)]
+struct HostState {
+    // The earliest time at which the next request may be sent
+    next: Instant,
+    // The current interval, longer than the throttle's after the host asked to slow down
+    interval: Duration,
+}
+
+impl Throttle {
+    /// Create a throttle allowing one request to each host every `interval`
+    pub fn new(interval: Duration) -> Self {
+        Throttle {
+            interval,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The process-wide throttle, allowing one request to each host every second
+    pub fn global() -> Arc<Throttle> {
+        GLOBAL
+            .get_or_init(|| Arc::new(Throttle::new(DEFAULT_INTERVAL)))
+            .clone()
+    }
+
+    /// The minimum interval between requests to a host
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// The current interval between requests to `host`
+    ///
+    /// This is longer than [`interval`](#method.interval) while the host is asking to slow down.
+    pub fn effective_interval(&self, host: &str) -> Duration {
+        self.lock()
+            .get(host)
+            .map_or(self.interval, |state| state.interval)
+    }
+
+    /// The current maximum number of requests per second to `host`
+    ///
+    /// This is infinite if requests to the host aren't spaced.
+    pub fn effective_rate(&self, host: &str) -> f64 {
+        1.0 / self.effective_interval(host).as_secs_f64()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, HostState>> {
+        self.hosts.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Block until a request may be sent to `host`, and reserve its slot
+    pub fn wait(&self, host: &str) {
+        let now = Instant::now();
+        let slot = {
+            let mut hosts = self.lock();
+            let state = hosts.entry(host.to_string()).or_insert(HostState {
+                next: now,
+                interval: self.interval,
+            });
+            let slot = state.next.max(now);
+            state.next = slot + state.interval;
+            slot
+        };
+        if slot > now {
+            thread::sleep(slot - now);
+        }
+    }
+
+    /// Adapt the interval between requests to `host` to the response to a request
+    ///
+    /// A `429 Too Many Requests` status, or a rate limit header with no remaining requests,
+    /// doubles the interval, up to [`MAX_INTERVAL`](constant.MAX_INTERVAL.html), and a
+    /// `Retry-After` header in seconds delays the next request. Other responses bring the
+    /// interval 10% closer to the throttle's interval.
+    pub fn record(&self, host: &str, status: StatusCode, headers: &HeaderMap) {
+        let now = Instant::now();
+        let mut hosts = self.lock();
+        let state = hosts.entry(host.to_string()).or_insert(HostState {
+            next: now,
+            interval: self.interval,
+        });
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u64>().ok())
+        };
+        let exhausted = ["x-ratelimit-remaining", "ratelimit-remaining"]
+            .iter()
+            .any(|name| header(name) == Some(0));
+        if status == StatusCode::TOO_MANY_REQUESTS || exhausted {
+            state.interval = (state.interval * 2)
+                .max(MIN_BACKOFF_INTERVAL)
+                .min(MAX_INTERVAL);
+            if let Some(seconds) = header("retry-after") {
+                let retry = now + Duration::from_secs(seconds).min(MAX_INTERVAL);
+                state.next = state.next.max(retry);
+            }
+        } else if state.interval > self.interval {
+            state.interval = self.interval + (state.interval - self.interval) * 9 / 10;
+        }
+    }
+
+    /// Block until a request may be sent to the host of `url`
+    ///
+    /// URLs which can't be parsed, or which have no host, are throttled together.
+    pub fn wait_for_url(&self, url: &str) {
+        self.wait(&host(url));
+    }
+}
+
+/// The host of a URL, including its port if it is not the default
+pub(crate) fn host(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| {
+            let host = url.host_str()?.to_string();
+            Some(match url.port() {
+                Some(port) => format!("{}:{}", host, port),
+                None => host,
+            })
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn host_test() {
+        assert_eq!(
+            host("https://nominatim.openstreetmap.org/"),
+            "nominatim.openstreetmap.org"
+        );
+        assert_eq!(host("http://localhost:8080/search"), "localhost:8080");
+        assert_eq!(host("https://example.com:443/"), "example.com");
+        assert_eq!(host("not a url"), "");
+    }
+
+    #[test]
+    fn wait_test() {
+        let throttle = Throttle::new(Duration::from_millis(50));
+        let start = Instant::now();
+        for _ in 0..3 {
+            throttle.wait("example.com");
+        }
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        // Other hosts aren't delayed
+        let start = Instant::now();
+        throttle.wait("example.org");
+        assert!(start.elapsed() < Duration::from_millis(50));
+    }
+
+    #[test]
+    fn shared_test() {
+        let throttle = Arc::new(Throttle::new(Duration::from_millis(50)));
+        let start = Instant::now();
+        let threads: Vec<_> = (0..3)
+            .map(|_| {
+                let throttle = throttle.clone();
+                thread::spawn(move || throttle.wait_for_url("https://example.com/search"))
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        assert!(Arc::ptr_eq(&Throttle::global(), &Throttle::global()));
+    }
+
+    #[test]
+    fn adaptive_test() {
+        let throttle = Throttle::new(Duration::from_millis(500));
+        let ok = HeaderMap::new();
+        assert_eq!(throttle.effective_rate("example.com"), 2.0);
+        throttle.record("example.com", StatusCode::TOO_MANY_REQUESTS, &ok);
+        assert_eq!(
+            throttle.effective_interval("example.com"),
+            Duration::from_secs(1)
+        );
+        throttle.record("example.com", StatusCode::TOO_MANY_REQUESTS, &ok);
+        assert_eq!(throttle.effective_rate("example.com"), 0.5);
+        // Other hosts aren't affected
+        assert_eq!(
+            throttle.effective_interval("example.org"),
+            Duration::from_millis(500)
+        );
+        // Successful requests bring the interval back
+        for _ in 0..100 {
+            throttle.record("example.com", StatusCode::OK, &ok);
+        }
+        assert!(throttle.effective_interval("example.com") < Duration::from_millis(501));
+        assert!(throttle.effective_interval("example.com") >= Duration::from_millis(500));
+
+        // An exhausted rate limit slows down as well
+        let mut exhausted = HeaderMap::new();
+        exhausted.insert("x-ratelimit-remaining", "0".parse().unwrap());
+        throttle.record("example.net", StatusCode::OK, &exhausted);
+        assert_eq!(
+            throttle.effective_interval("example.net"),
+            Duration::from_secs(1)
+        );
+        for _ in 0..20 {
+            throttle.record("example.net", StatusCode::TOO_MANY_REQUESTS, &ok);
+        }
+        assert_eq!(throttle.effective_interval("example.net"), MAX_INTERVAL);
+    }
+
+    #[test]
+    fn retry_after_test() {
+        let throttle = Throttle::new(Duration::ZERO);
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", "1".parse().unwrap());
+        throttle.record("example.com", StatusCode::TOO_MANY_REQUESTS, &headers);
+        let start = Instant::now();
+        throttle.wait("example.com");
+        assert!(start.elapsed() >= Duration::from_millis(900));
+    }
+}
@@ -0,0 +1,206 @@
+//! Validation of batch results by rules, as in address-cleaning pipelines.
+//!
+//! Validators set with [`with_validator`](../batch/struct.BatchForward.html#method.with_validator)
+//! check the best result of each address of a batch, e.g. that it is close enough to the
+//! expected region, and mark it as [`Accepted`](enum.Validation.html#variant.Accepted),
+//! [`NeedsReview`](enum.Validation.html#variant.NeedsReview) or
+//! [`Rejected`](enum.Validation.html#variant.Rejected). The strictest outcome of all validators
+//! is stored in the `validation` of the [`BatchRecord`](../batch/struct.BatchRecord.html), and
+//! counted in the [`BatchSummary`](../batch/struct.BatchSummary.html).
+//!
+//! Any `Fn(&Candidate) -> Validation` closure is a validator, and the module provides
+//! [`HasPostcode`], [`MinConfidence`] and [`WithinDistance`].
+//!
+//! ### Example
+//!
+//! ```
+//! use geocoding::batch::BatchForward;
+//! use geocoding::validate::{Candidate, MinConfidence, Validation, WithinDistance};
+//! use geocoding::{Openstreetmap, Point};
+//!
+//! let osm = Openstreetmap::new();
+//! let near_barcelona = WithinDistance::new(Point::new(2.17, 41.39), 30.0);
+//! let not_a_country = |candidate: &Candidate| match candidate.formatted.contains(',') {
+//!     true => Validation::Accepted,
+//!     false => Validation::Rejected,
+//! };
+//! let batch = BatchForward::new(&osm)
+//!     .with_validator(&near_barcelona)
+//!     .with_validator(&MinConfidence(0.8))
+//!     .with_validator(&not_a_country)
+//!     .build();
+//! ```
+use crate::haversine_distance;
+use crate::Point;
+use crate::{Deserialize, Serialize};
+
+/// The outcome of validating a result
+///
+/// Outcomes are ordered from the most lenient to the strictest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Validation {
+    Accepted,
+    NeedsReview,
+    Rejected,
+}
+
+/// The best result of an address, as seen by validators
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Candidate<'r> {
+    /// The address which was geocoded
+    pub input: &'r str,
+    pub point: Point<f64>,
+    /// The label of the result
+    pub formatted: &'r str,
+    /// The [match score](../score/fn.match_score.html) of the result against the input
+    pub confidence: f64,
+}
+
+/// A rule validating the results of a batch
+///
+/// Validators are called on the thread geocoding the address, so parallel batches call them
+/// concurrently.
+pub trait Validator: Send + Sync {
+    /// Validate the best result of an address
+    fn validate(&self, candidate: &Candidate) -> Validation;
+}
+
+impl<F> Validator for F
+where
+    F: Fn(&Candidate) -> Validation + Send + Sync,
+{
+    fn validate(&self, candidate: &Candidate) -> Validation {
+        self(candidate)
+    }
+}
+
+/// Rejects results whose label has no postcode
+///
+/// Like [`match_score`](../score/fn.match_score.html), a word of 4 or more characters
+/// including a digit is taken as a postcode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HasPostcode;
+
+impl Validator for HasPostcode {
+    fn validate(&self, candidate: &Candidate) -> Validation {
+        let has_postcode = candidate
+            .formatted
+            .split(|c: char| !c.is_alphanumeric())
+            .any(|word| word.chars().count() >= 4 && word.chars().any(|c| c.is_ascii_digit()));
+        if has_postcode {
+            Validation::Accepted
+        } else {
+            Validation::Rejected
+        }
+    }
+}
+
+/// Sends results whose confidence is below a minimum for review
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MinConfidence(pub f64);
+
+impl Validator for MinConfidence {
+    fn validate(&self, candidate: &Candidate) -> Validation {
+        if candidate.confidence >= self.0 {
+            Validation::Accepted
+        } else {
+            Validation::NeedsReview
+        }
+    }
+}
+
+/// Rejects results further than a distance from an expected point
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WithinDistance {
+    center: Point<f64>,
+    // In meters
+    radius: f64,
+}
+
+impl WithinDistance {
+    /// Accept results at most `radius_km` kilometers from `center`
+    pub fn new(center: Point<f64>, radius_km: f64) -> Self {
+        WithinDistance {
+            center,
+            radius: radius_km * 1000.0,
+        }
+    }
+}
+
+impl Validator for WithinDistance {
+    fn validate(&self, candidate: &Candidate) -> Validation {
+        if haversine_distance(&self.center, &candidate.point) <= self.radius {
+            Validation::Accepted
+        } else {
+            Validation::Rejected
+        }
+    }
+}
+
+/// The strictest outcome of `validators` for `candidate`, accepted if there are none
+pub(crate) fn validate(validators: &[&dyn Validator], candidate: &Candidate) -> Validation {
+    validators
+        .iter()
+        .map(|validator| validator.validate(candidate))
+        .max()
+        .unwrap_or(Validation::Accepted)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn candidate(formatted: &str) -> Candidate<'_> {
+        Candidate {
+            input: "Carrer de Calatrava 68",
+            point: Point::new(2.12870, 41.40139),
+            formatted,
+            confidence: 0.75,
+        }
+    }
+
+    #[test]
+    fn validators_test() {
+        let with_postcode = candidate("Carrer de Calatrava, 68, 08017 Barcelona, Spain");
+        let without_postcode = candidate("Carrer de Calatrava, 68, Barcelona, Spain");
+        assert_eq!(HasPostcode.validate(&with_postcode), Validation::Accepted);
+        assert_eq!(
+            HasPostcode.validate(&without_postcode),
+            Validation::Rejected
+        );
+        assert_eq!(
+            HasPostcode.validate(&candidate("10 Downing Street, London SW1A 2AA")),
+            Validation::Accepted
+        );
+
+        assert_eq!(
+            MinConfidence(0.5).validate(&with_postcode),
+            Validation::Accepted
+        );
+        assert_eq!(
+            MinConfidence(0.8).validate(&with_postcode),
+            Validation::NeedsReview
+        );
+
+        let barcelona = WithinDistance::new(Point::new(2.17, 41.39), 10.0);
+        let madrid = WithinDistance::new(Point::new(-3.70, 40.42), 10.0);
+        assert_eq!(barcelona.validate(&with_postcode), Validation::Accepted);
+        assert_eq!(madrid.validate(&with_postcode), Validation::Rejected);
+    }
+
+    #[test]
+    fn validate_test() {
+        let candidate = candidate("Carrer de Calatrava, 68, Barcelona, Spain");
+        assert_eq!(validate(&[], &candidate), Validation::Accepted);
+        let review = |_: &Candidate| Validation::NeedsReview;
+        assert_eq!(
+            validate(&[&MinConfidence(0.5), &review], &candidate),
+            Validation::NeedsReview
+        );
+        assert_eq!(
+            validate(&[&review, &HasPostcode, &MinConfidence(0.5)], &candidate),
+            Validation::Rejected
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Deserialization of recorded provider responses.
+//!
+//! Each fixture is a response of a provider API. Update them when a provider changes its
+//! responses, to check that they still parse.
+use geocoding::geoadmin::{
+    GeoAdminFeatureResponse, GeoAdminFeatureSearchResponse, GeoAdminForwardResponse,
+    GeoAdminHeightResponse, GeoAdminLayerSearchResponse, GeoAdminReverseResponse,
+};
+use geocoding::opencage::OpencageResponse;
+use geocoding::openelevation::OpenelevationResponse;
+use geocoding::openstreetmap::{OpenstreetmapResponse, OpenstreetmapStatus};
+use geocoding::opentopodata::OpentopodataResponse;
+use geocoding::{Address, GeocodingError};
+
+#[test]
+fn opencage_forward_test() {
+    let res =
+        OpencageResponse::<f64>::from_json_str(include_str!("fixtures/opencage_forward.json"))
+            .unwrap();
+    assert_eq!(res.total_results, 1);
+    assert_eq!(
+        res.results[0].formatted,
+        "Carrer de Calatrava, 68, 08017 Barcelona, Spain"
+    );
+    assert_eq!(res.results[0].geometry["lng"], 2.1287224);
+    assert_eq!(res.licence().as_deref(), Some("CC-BY-SA, ODbL"));
+    let rate = res.rate.unwrap();
+    assert_eq!(rate.remaining, 2499);
+    assert_eq!(rate.reset.timestamp(), 1523318400);
+    assert_eq!(
+        res.timestamp.unwrap().created_unix.and_utc().timestamp(),
+        1523277181
+    );
+}
+
+#[test]
+fn opencage_forward_paid_test() {
+    // Paid plans don't report a daily quota
+    let res =
+        OpencageResponse::<f64>::from_json_str(include_str!("fixtures/opencage_forward_paid.json"))
+            .unwrap();
+    assert!(res.rate.is_none());
+    assert_eq!(res.status.code, 200);
+    assert_eq!(res.results.len(), 1);
+}
+
+#[test]
+fn opencage_error_test() {
+    let res = OpencageResponse::<f64>::from_json_str(include_str!("fixtures/opencage_error.json"))
+        .unwrap();
+    assert_eq!(res.status.code, 401);
+    assert_eq!(res.status.message, "invalid API key");
+    assert!(res.timestamp.is_none());
+    assert!(res.results.is_empty());
+    let res = OpencageResponse::<f64>::from_json_str(r#"{"results": []}"#).unwrap();
+    assert_eq!(res.total_results, 0);
+}
+
+#[test]
+fn nominatim_search_test() {
+    let res =
+        OpenstreetmapResponse::<f64>::from_json_str(include_str!("fixtures/nominatim_search.json"))
+            .unwrap();
+    assert_eq!(res.features.len(), 1);
+    assert!(res.features[0]
+        .properties
+        .display_name
+        .contains("Carrer de Calatrava"));
+}
+
+#[test]
+fn schema_change_test() {
+    let res =
+        OpencageResponse::<f64>::from_json_str(include_str!("fixtures/opencage_forward.json"))
+            .unwrap();
+    assert!(res.unknown_fields.is_empty());
+    assert!(!res.schema_changed());
+    let res = OpencageResponse::<f64>::from_json_str(
+        r#"{"documentation": "https://opencagedata.com/api/v2", "results": []}"#,
+    )
+    .unwrap();
+    assert!(res.schema_changed());
+
+    let res =
+        OpenstreetmapResponse::<f64>::from_json_str(include_str!("fixtures/nominatim_search.json"))
+            .unwrap();
+    assert!(res.unknown_fields.is_empty());
+    let res = OpenstreetmapResponse::<f64>::from_json_str(
+        r#"{"type": "FeatureCollection", "licence": "ODbL", "features": [], "query": "Berlin"}"#,
+    )
+    .unwrap();
+    assert_eq!(res.unknown_fields["query"], "Berlin");
+}
+
+#[test]
+fn nominatim_search_slice_test() {
+    let mut json = include_bytes!("fixtures/nominatim_search.json").to_vec();
+    let res = OpenstreetmapResponse::<f64>::from_json_slice(&mut json).unwrap();
+    assert_eq!(res.features.len(), 1);
+    let mut invalid = br#"{"type": "FeatureCollection"}"#.to_vec();
+    let err = OpenstreetmapResponse::<f64>::from_json_slice(&mut invalid).unwrap_err();
+    assert!(matches!(err, GeocodingError::Json(_)));
+}
+
+#[test]
+fn nominatim_status_test() {
+    let res =
+        OpenstreetmapStatus::from_json_str(include_str!("fixtures/nominatim_status.json")).unwrap();
+    assert_eq!(res.status, 0);
+    assert!(res.data_updated.is_some());
+}
+
+#[test]
+fn geoadmin_search_test() {
+    let res = GeoAdminForwardResponse::<f64>::from_json_str(include_str!(
+        "fixtures/geoadmin_search.json"
+    ))
+    .unwrap();
+    // The id is private, only shown by `Debug`
+    assert!(format!("{:?}", res.features[0]).contains("id: Some(1420809)"));
+    assert_eq!(res.features[0].properties.num, Some(264));
+}
+
+#[test]
+fn geoadmin_identify_test() {
+    let res: GeoAdminReverseResponse =
+        GeoAdminReverseResponse::from_json_str(include_str!("fixtures/geoadmin_identify.json"))
+            .unwrap();
+    assert_eq!(res.results[0].properties.dplzname, "Wabern");
+    assert_eq!(
+        res.results[0].properties.strname_deinr,
+        "Seftigenstrasse 264"
+    );
+    let address = Address::from(&res.results[0].properties);
+    assert_eq!(address.house_number.as_deref(), Some("264"));
+    assert_eq!(address.postcode.as_deref(), Some("3084"));
+    assert_eq!(address.state_code.as_deref(), Some("BE"));
+}
+
+#[test]
+fn geoadmin_feature_test() {
+    let res: GeoAdminFeatureResponse =
+        GeoAdminFeatureResponse::from_json_str(include_str!("fixtures/geoadmin_feature.json"))
+            .unwrap();
+    assert_eq!(
+        res.feature.layer_bod_id,
+        "ch.bfs.gebaeude_wohnungs_register"
+    );
+}
+
+#[test]
+fn geoadmin_height_test() {
+    let res = GeoAdminHeightResponse::from_json_str(include_str!("fixtures/geoadmin_height.json"))
+        .unwrap();
+    assert!(res.height > 500.0);
+}
+
+#[test]
+fn geoadmin_search_layers_test() {
+    let res = GeoAdminLayerSearchResponse::from_json_str(include_str!(
+        "fixtures/geoadmin_search_layers.json"
+    ))
+    .unwrap();
+    assert!(!res.results.is_empty());
+}
+
+#[test]
+fn geoadmin_search_features_test() {
+    let res = GeoAdminFeatureSearchResponse::<f64>::from_json_str(include_str!(
+        "fixtures/geoadmin_search_features.json"
+    ))
+    .unwrap();
+    assert!(!res.results.is_empty());
+}
+
+#[test]
+fn openelevation_lookup_test() {
+    let res = OpenelevationResponse::<f64>::from_json_str(include_str!(
+        "fixtures/openelevation_lookup.json"
+    ))
+    .unwrap();
+    assert_eq!(res.results[0].elevation, Some(557.0));
+}
+
+#[test]
+fn opentopodata_lookup_test() {
+    let res = OpentopodataResponse::<f64>::from_json_str(include_str!(
+        "fixtures/opentopodata_lookup.json"
+    ))
+    .unwrap();
+    assert_eq!(res.status, "OK");
+    assert_eq!(res.results[0].elevation, Some(557.0));
+}
+
+#[test]
+fn invalid_json_test() {
+    let err = OpenstreetmapStatus::from_json_str(r#"{"status": "OK"}"#).unwrap_err();
+    assert!(matches!(err, GeocodingError::Json(_)));
+}